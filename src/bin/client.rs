@@ -22,6 +22,7 @@ use theta_proto::protocol_types::{
     CoinRequest, DecryptRequest, KeyRequest, SignRequest, StatusRequest,
 };
 
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
 use utils::client::cli::ClientCli;
 use utils::client::types::ClientConfig;
 
@@ -355,12 +356,32 @@ async fn connect_to_all_local(
     for peer in config.peers.iter() {
         let ip = peer.ip.clone();
         let port = peer.rpc_port;
-        let addr = format!("http://[{ip}]:{port}");
-        connections.push(
-            ThresholdCryptoLibraryClient::connect(addr.clone())
-                .await
-                .unwrap(),
-        );
+        let channel = match &config.tls {
+            Some(tls) => {
+                let addr = format!("https://[{ip}]:{port}");
+                let ca = std::fs::read(&tls.ca_file).expect("Unable to read CA certificate");
+                let mut tls_config = ClientTlsConfig::new()
+                    .ca_certificate(Certificate::from_pem(ca))
+                    .domain_name(tls.domain_name.clone().unwrap_or(ip.clone()));
+                if let (Some(cert_file), Some(key_file)) = (&tls.cert_file, &tls.key_file) {
+                    let cert = std::fs::read(cert_file).expect("Unable to read client certificate");
+                    let key = std::fs::read(key_file).expect("Unable to read client key");
+                    tls_config = tls_config.identity(Identity::from_pem(cert, key));
+                }
+                Channel::from_shared(addr)
+                    .unwrap()
+                    .tls_config(tls_config)
+                    .unwrap()
+                    .connect()
+                    .await
+                    .unwrap()
+            }
+            None => {
+                let addr = format!("http://[{ip}]:{port}");
+                Channel::from_shared(addr).unwrap().connect().await.unwrap()
+            }
+        };
+        connections.push(ThresholdCryptoLibraryClient::new(channel));
     }
     println!("Established connection to network.");
     connections
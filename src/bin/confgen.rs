@@ -1,25 +1,76 @@
 use std::ops::Add;
-use std::{convert::TryFrom, fs, net::IpAddr, path::PathBuf, process::exit, str::FromStr};
+use std::{convert::TryFrom, fs, net::IpAddr, path::{Path, PathBuf}, process::exit, str::FromStr};
 
+use base64::{engine::general_purpose, Engine};
 use clap::{Parser, Error};
 use rand::seq::SliceRandom;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyUsagePurpose, SanType,
+};
+use sha2::{Digest, Sha256};
+use theta_network::p2p::identity::generate_identity_file;
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_schemes::{
+    keys::{
+        key_generator::KeyGenerator,
+        key_store::{KeyMetadata, KeyStore},
+    },
+    rand::{RngAlgorithm, RNG},
+};
+use theta_service::access_control::{AccessPolicy, ClientPolicy, Operation, ANY_KEY};
 use utils::confgen::cli::{ConfgenCli, PortStrategy};
+use utils::confgen::spec::{DeploymentSpec, KeySpec, TlsSpec};
 
 use log::{error, info};
-use utils::client::types::{ClientConfig, PeerPublicInfo};
+use utils::client::types::{ClientConfig, ClientTlsConfig, PeerPublicInfo};
 use utils::server::{
     dirutil,
-    types::{Peer, ProxyNode, ServerConfig},
+    types::{Peer, ProxyNode, RpcTlsConfig, ServerConfig},
 };
 use thetacrypt_blockchain_stub::cli::types::{P2PConfig, PeerP2PInfo};
 
 use serde::{Deserialize, Serialize};
 
+#[cfg(test)]
+mod confgen_tests;
+
+// Image built from the repository's Dockerfile.
+const DEFAULT_IMAGE: &str = "rust-threshold-library";
+const DEFAULT_WORKING_DIR: &str = "/opt/thetacrypt";
+
 fn main() {
     env_logger::init();
 
     let confgen_cli = ConfgenCli::parse();
-    let ips = match ips_from_file(&confgen_cli.ip_file) {
+
+    if let Some(spec_file) = &confgen_cli.spec {
+        let spec = match DeploymentSpec::from_file(spec_file) {
+            Ok(spec) => spec,
+            Err(e) => {
+                error!("{}", e);
+                exit(1);
+            }
+        };
+
+        if let Err(e) = dirutil::ensure_sane_output_directory(&confgen_cli.outdir, false) {
+            error!("Invalid output directory: {}, aborting...", e);
+            exit(1);
+        }
+
+        match generate_deployment(&spec, &confgen_cli.outdir) {
+            Ok(_) => info!("Deployment generated, all files saved to {}", confgen_cli.outdir.display()),
+            Err(e) => {
+                error!("Deployment generation failed: {}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    // clap requires the IP file if no spec is given
+    let ip_file = confgen_cli.ip_file.clone().unwrap();
+    let ips = match ips_from_file(&ip_file) {
         Ok(ips) => ips,
         Err(e) => {
             error!("{}", e);
@@ -162,6 +213,7 @@ fn generate_configs(
                 id: u32::try_from(i+1).unwrap(),
                 ip: String::from(ip),
                 p2p_port,
+                identity: None,
             }
         })
         .collect();
@@ -275,3 +327,369 @@ fn generate_configs(
 
     Ok(())
 }
+
+// Assigns the port of the server at `index` (starting at 0). Consecutive ports start one above the
+// given port, like the ports of the IP file based configs.
+fn assign_port(port_strategy: &PortStrategy, port: u16, index: usize) -> Result<u16, String> {
+    match port_strategy {
+        PortStrategy::Consecutive => u16::try_from(index + 1)
+            .ok()
+            .and_then(|offset| port.checked_add(offset))
+            .ok_or_else(|| format!("No port left for server {}", index + 1)),
+        PortStrategy::Static => Ok(port),
+    }
+}
+
+// Generates a complete deployment from a spec: the configs, identities, certificates, keystores
+// and service definitions of all servers, and the configs of a client.
+fn generate_deployment(spec: &DeploymentSpec, outdir: &PathBuf) -> Result<(), String> {
+    // the keys are generated first, so an invalid subject does not leave a partial deployment
+    let keystores = match &spec.keys {
+        Some(keys) => Some(generate_keystores(keys, spec.nodes.len())?),
+        None => None,
+    };
+
+    info!("Generating node identities");
+    let identity_dir = outdir.join("identity");
+    dirutil::ensure_directory_exists(&identity_dir)?;
+    let mut peers = Vec::new();
+    let mut rpc_ports = Vec::new();
+    for (i, node) in spec.nodes.iter().enumerate() {
+        let id = u32::try_from(i + 1).unwrap();
+        let identity = generate_identity_file(&identity_dir.join(format!("node{}.key", id)))?;
+        let p2p_port = match node.p2p_port {
+            Some(port) => port,
+            None => assign_port(&spec.port_strategy, spec.p2p_port, i)?,
+        };
+        rpc_ports.push(match node.rpc_port {
+            Some(port) => port,
+            None => assign_port(&spec.port_strategy, spec.rpc_port, i)?,
+        });
+        peers.push(Peer {
+            id,
+            ip: node.ip.clone(),
+            p2p_port,
+            identity: Some(identity.to_string()),
+        });
+    }
+
+    let client_fingerprint = match &spec.tls {
+        Some(tls) => generate_certificates(tls, &peers, &outdir.join("tls"))?,
+        None => None,
+    };
+
+    info!("Writing server configurations to disk");
+    let config_dir = &spec.config_dir;
+    for (i, peer) in peers.iter().enumerate() {
+        let proxy = match &spec.integration {
+            // with the stub, a central node acts as the proxy of all servers
+            Some(integration) => Some(ProxyNode {
+                ip: integration.proxy_ips[i].clone(),
+                port: if integration.stub {
+                    integration.proxy_port
+                } else {
+                    assign_port(&spec.port_strategy, integration.proxy_port, i)?
+                },
+            }),
+            None => None,
+        };
+
+        let mut config = ServerConfig::new(
+            peer.id,
+            spec.listen_address.clone(),
+            rpc_ports[i],
+            peers.clone(),
+            proxy,
+            spec.event_file.clone(),
+        )?;
+        config.identity_file = Some(config_dir.join(format!("identity/node{}.key", peer.id)));
+        if let Some(tls) = &spec.tls {
+            config.rpc_tls = Some(RpcTlsConfig {
+                cert_file: config_dir.join(format!("tls/node{}.pem", peer.id)),
+                key_file: config_dir.join(format!("tls/node{}.key", peer.id)),
+                client_ca_file: tls.mutual.then(|| config_dir.join("tls/ca.pem")),
+            });
+            if tls.mutual {
+                config.access_policy_file = Some(config_dir.join("access_policy.json"));
+            }
+        }
+        save_config_on_file(outdir.clone(), &config, format!("server_{}.json", peer.id))?;
+    }
+
+    if let Some(fingerprint) = client_fingerprint {
+        let mut operations = vec![
+            Operation::Decrypt,
+            Operation::Sign,
+            Operation::Coin,
+            Operation::Monitor,
+        ];
+        // key management has to be granted explicitly
+        if spec.tls.as_ref().map_or(false, |tls| tls.client_admin) {
+            operations.push(Operation::Admin);
+        }
+        let policy = AccessPolicy {
+            clients: vec![ClientPolicy {
+                name: String::from("client"),
+                cert_fingerprint: fingerprint,
+                operations,
+                key_ids: vec![String::from(ANY_KEY)],
+            }],
+        };
+        save_config_on_file(outdir.clone(), &policy, String::from("access_policy.json"))?;
+    }
+
+    if spec.integration.as_ref().map_or(false, |integration| integration.stub) {
+        let p2p_peers = peers
+            .iter()
+            .map(|peer| PeerP2PInfo {
+                id: peer.id,
+                ip: peer.ip.clone(),
+                p2p_port: peer.p2p_port,
+            })
+            .collect();
+        create_and_save_stub_config(outdir.clone(), p2p_peers)?;
+    }
+
+    let public_peers = peers
+        .iter()
+        .zip(rpc_ports.iter())
+        .map(|(peer, rpc_port)| PeerPublicInfo {
+            id: peer.id,
+            ip: peer.ip.clone(),
+            rpc_port: *rpc_port,
+        })
+        .collect();
+    let mut client_config = ClientConfig::new(public_peers)?;
+    client_config.tls = spec.tls.as_ref().map(|tls| ClientTlsConfig {
+        ca_file: config_dir.join("tls/ca.pem"),
+        cert_file: tls.mutual.then(|| config_dir.join("tls/client.pem")),
+        key_file: tls.mutual.then(|| config_dir.join("tls/client.key")),
+        domain_name: tls.domain_name.clone(),
+    });
+    save_config_on_file(outdir.clone(), &client_config, String::from("client.json"))?;
+
+    if let Some((node_keystores, client_keystore)) = keystores {
+        info!("Writing keystores to disk");
+        for (i, keystore) in node_keystores.iter().enumerate() {
+            let keyfile = outdir.join(format!("node{}.keystore", i + 1));
+            keystore
+                .to_file(&keyfile.to_string_lossy())
+                .map_err(|e| format!("Failed to write {}: {}", keyfile.display(), e))?;
+        }
+        let keyfile = outdir.join("client.keystore");
+        client_keystore
+            .to_file(&keyfile.to_string_lossy())
+            .map_err(|e| format!("Failed to write {}: {}", keyfile.display(), e))?;
+    }
+
+    if spec.layout.docker_compose {
+        write_docker_compose(spec, &peers, outdir)?;
+    }
+    if spec.layout.systemd {
+        write_systemd_units(spec, &peers, &outdir.join("systemd"))?;
+    }
+
+    Ok(())
+}
+
+// Parses a subject of the form 'scheme-group' or 'scheme-group:alias'.
+fn parse_subject(subject: &str) -> Result<(ThresholdScheme, Group, Option<String>), String> {
+    let (pair, alias) = match subject.split_once(':') {
+        Some((pair, alias)) => (pair, Some(String::from(alias))),
+        None => (subject, None),
+    };
+    let (scheme, group) = pair
+        .split_once('-')
+        .ok_or_else(|| format!("Invalid subject '{}', expected 'scheme-group'", subject))?;
+    let scheme = ThresholdScheme::from_str_name(scheme)
+        .ok_or_else(|| format!("Invalid scheme '{}' in subject '{}'", scheme, subject))?;
+    let group = Group::from_str_name(group)
+        .ok_or_else(|| format!("Invalid group '{}' in subject '{}'", group, subject))?;
+    Ok((scheme, group, alias))
+}
+
+// Generates the keys of all subjects. Returns the keystore of every server, holding its shares,
+// and the keystore of the client, holding the public keys.
+fn generate_keystores(keys: &KeySpec, n: usize) -> Result<(Vec<KeyStore>, KeyStore), String> {
+    let mut rng = RNG::new(RngAlgorithm::OsRng);
+    let mut node_keystores: Vec<KeyStore> = (0..n).map(|_| KeyStore::new()).collect();
+    let mut client_keystore = KeyStore::new();
+
+    for subject in keys.subjects.iter() {
+        let (scheme, group, alias) = parse_subject(subject)?;
+        info!("Generating {}...", subject);
+        let shares = KeyGenerator::generate_keys(
+            keys.threshold as usize,
+            n,
+            &mut rng,
+            &scheme,
+            &group,
+            &Option::None,
+        )
+        .map_err(|e| format!("Failed to generate {}: {}", subject, e))?;
+
+        let metadata = KeyMetadata {
+            alias,
+            ..Default::default()
+        };
+        let id = client_keystore
+            .insert_public_key(shares[0].get_public_key())
+            .map_err(|e| e.to_string())?;
+        client_keystore
+            .set_metadata(&id, metadata.clone())
+            .map_err(|e| format!("Invalid alias for {}: {}", subject, e))?;
+        for (keystore, share) in node_keystores.iter_mut().zip(shares.into_iter()) {
+            let id = keystore.insert_private_key(share).map_err(|e| e.to_string())?;
+            keystore.set_metadata(&id, metadata.clone()).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok((node_keystores, client_keystore))
+}
+
+// Creates a CA and the RPC certificates of the servers and, for mutual TLS, of a client, and
+// writes them to `dir`. Returns the fingerprint of the client certificate, as used in access
+// policies.
+fn generate_certificates(tls: &TlsSpec, peers: &[Peer], dir: &PathBuf) -> Result<Option<String>, String> {
+    info!("Generating TLS certificates");
+    dirutil::ensure_directory_exists(dir)?;
+
+    let mut params = CertificateParams::default();
+    params.distinguished_name.push(DnType::CommonName, "Thetacrypt CA");
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    let ca = Certificate::from_params(params).map_err(|e| format!("Failed to create the CA: {}", e))?;
+    let ca_der = ca.serialize_der().map_err(|e| format!("Failed to create the CA: {}", e))?;
+    write_file(&dir.join("ca.pem"), &pem_certificate(&ca_der), false)?;
+    write_file(&dir.join("ca.key"), &ca.serialize_private_key_pem(), true)?;
+
+    for peer in peers {
+        let mut names = vec![String::from("localhost")];
+        names.extend(tls.dns_names.iter().cloned());
+        let mut params = CertificateParams::new(names);
+        params
+            .distinguished_name
+            .push(DnType::CommonName, format!("Thetacrypt node {}", peer.id));
+        let ip = IpAddr::from_str(&peer.ip).map_err(|e| format!("Invalid IP for peer {}: {}", peer.id, e))?;
+        params.subject_alt_names.push(SanType::IpAddress(ip));
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        issue_certificate(params, &ca, dir, &format!("node{}", peer.id))?;
+    }
+
+    if !tls.mutual {
+        return Ok(None);
+    }
+    let mut params = CertificateParams::default();
+    params.distinguished_name.push(DnType::CommonName, "Thetacrypt client");
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let der = issue_certificate(params, &ca, dir, "client")?;
+    Ok(Some(hex::encode(Sha256::digest(&der))))
+}
+
+// Signs a certificate with the CA and writes it to `<name>.pem` and its key to `<name>.key`.
+// Returns the DER encoding of the certificate.
+fn issue_certificate(
+    params: CertificateParams,
+    ca: &Certificate,
+    dir: &PathBuf,
+    name: &str,
+) -> Result<Vec<u8>, String> {
+    let cert = Certificate::from_params(params)
+        .map_err(|e| format!("Failed to create certificate {}: {}", name, e))?;
+    let der = cert
+        .serialize_der_with_signer(ca)
+        .map_err(|e| format!("Failed to create certificate {}: {}", name, e))?;
+    write_file(&dir.join(format!("{}.pem", name)), &pem_certificate(&der), false)?;
+    write_file(&dir.join(format!("{}.key", name)), &cert.serialize_private_key_pem(), true)?;
+    Ok(der)
+}
+
+// The certificate is encoded once, as the signature differs every time it is serialized.
+fn pem_certificate(der: &[u8]) -> String {
+    let encoded = general_purpose::STANDARD.encode(der);
+    let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+    for line in encoded.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push('\n');
+    }
+    pem.push_str("-----END CERTIFICATE-----\n");
+    pem
+}
+
+// Writes `data` to `path`. Secret files are only readable by the owner.
+fn write_file(path: &Path, data: &str, secret: bool) -> Result<(), String> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        if secret {
+            options.mode(0o600);
+        }
+    }
+
+    options
+        .open(path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, data.as_bytes()))
+        .map_err(|e| format!("Failed to write to file: {}, {}", e, path.display()))
+}
+
+// Arguments of the server binary of a node, with paths inside the config directory.
+fn server_args(spec: &DeploymentSpec, id: u32) -> String {
+    let mut args = format!(
+        "--config-file {}",
+        spec.config_dir.join(format!("server_{}.json", id)).display()
+    );
+    if spec.keys.is_some() {
+        args.push_str(&format!(
+            " --key-file {}",
+            spec.config_dir.join(format!("node{}.keystore", id)).display()
+        ));
+    }
+    args
+}
+
+// Writes a docker-compose.yml that runs every server in a container of the image built from the
+// repository's Dockerfile. The containers use the host network, so the addresses in the configs
+// apply unchanged, and the output directory is mounted as the config directory.
+fn write_docker_compose(spec: &DeploymentSpec, peers: &[Peer], outdir: &PathBuf) -> Result<(), String> {
+    info!("Writing docker-compose.yml to disk");
+    let image = spec.layout.image.clone().unwrap_or(String::from(DEFAULT_IMAGE));
+    // the Dockerfile runs the server in /app
+    let mount = Path::new("/app").join(&spec.config_dir);
+
+    let mut compose = String::from("services:\n");
+    for peer in peers {
+        compose.push_str(&format!(
+            "  thetacrypt{id}:\n    container_name: thetacrypt{id}\n    image: {image}\n    command: {args}\n    network_mode: host\n    volumes:\n      - ./:{mount}:Z\n    restart: always\n",
+            id = peer.id,
+            image = image,
+            args = server_args(spec, peer.id),
+            mount = mount.display(),
+        ));
+    }
+    write_file(&outdir.join("docker-compose.yml"), &compose, false)
+}
+
+// Writes a systemd unit per server to `dir`. The units expect the server binary in
+// /usr/local/bin and the config directory in the working directory.
+fn write_systemd_units(spec: &DeploymentSpec, peers: &[Peer], dir: &PathBuf) -> Result<(), String> {
+    info!("Writing systemd units to disk");
+    dirutil::ensure_directory_exists(dir)?;
+    let working_dir = spec
+        .layout
+        .working_dir
+        .clone()
+        .unwrap_or(PathBuf::from(DEFAULT_WORKING_DIR));
+
+    for peer in peers {
+        let unit = format!(
+            "[Unit]\nDescription=Thetacrypt node {id}\nWants=network-online.target\nAfter=network-online.target\n\n[Service]\nWorkingDirectory={dir}\nExecStart=/usr/local/bin/server {args}\nRestart=always\n\n[Install]\nWantedBy=multi-user.target\n",
+            id = peer.id,
+            dir = working_dir.display(),
+            args = server_args(spec, peer.id),
+        );
+        write_file(&dir.join(format!("thetacrypt-node{}.service", peer.id)), &unit, false)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::PathBuf;
+
+use theta_service::access_control::{AccessPolicy, Operation};
+use utils::client::types::ClientConfig;
+use utils::confgen::spec::DeploymentSpec;
+use utils::server::types::ServerConfig;
+
+use super::generate_deployment;
+
+const NODES: &str = r#"
+port_strategy = "consecutive"
+
+[[nodes]]
+ip = "127.0.0.1"
+[[nodes]]
+ip = "127.0.0.1"
+[[nodes]]
+ip = "127.0.0.1"
+"#;
+
+// Generates the deployment of `spec` into a fresh temporary directory.
+fn generate(name: &str, spec: &str) -> PathBuf {
+    let outdir = std::env::temp_dir().join(format!(
+        "thetacrypt-confgen-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&outdir);
+    fs::create_dir_all(&outdir).unwrap();
+
+    let spec = DeploymentSpec::from_toml(spec).unwrap();
+    generate_deployment(&spec, &outdir).unwrap();
+    outdir
+}
+
+fn server_config(outdir: &PathBuf, id: u32) -> ServerConfig {
+    let data = fs::read_to_string(outdir.join(format!("server_{}.json", id))).unwrap();
+    serde_json::from_str(&data).unwrap()
+}
+
+fn access_policy(outdir: &PathBuf) -> AccessPolicy {
+    AccessPolicy::from_file(&outdir.join("access_policy.json")).unwrap()
+}
+
+#[test]
+fn test_servers_and_client() {
+    let outdir = generate("servers", &format!("{}\n[tls]\nmutual = true", NODES));
+
+    for id in 1..=3 {
+        let config = server_config(&outdir, id);
+        assert_eq!(config.id, id);
+        // consecutive ports start one above the base port
+        assert_eq!(config.rpc_port, 51000 + id as u16);
+        assert_eq!(config.peers.len(), 3);
+        assert!(config.peers.iter().all(|peer| peer.identity.is_some()));
+        assert_eq!(
+            config.identity_file,
+            Some(PathBuf::from(format!("conf/identity/node{}.key", id)))
+        );
+        assert!(outdir.join(format!("identity/node{}.key", id)).exists());
+        assert!(outdir.join(format!("tls/node{}.pem", id)).exists());
+
+        let tls = config.rpc_tls.unwrap();
+        assert_eq!(tls.client_ca_file, Some(PathBuf::from("conf/tls/ca.pem")));
+        assert_eq!(
+            config.access_policy_file,
+            Some(PathBuf::from("conf/access_policy.json"))
+        );
+    }
+
+    let client = ClientConfig::from_file(&outdir.join("client.json")).unwrap();
+    assert_eq!(client.peers.len(), 3);
+    let tls = client.tls.unwrap();
+    assert_eq!(tls.cert_file, Some(PathBuf::from("conf/tls/client.pem")));
+    assert!(outdir.join("tls/client.key").exists());
+
+    fs::remove_dir_all(&outdir).unwrap();
+}
+
+#[test]
+fn test_client_is_no_admin_by_default() {
+    let outdir = generate("policy", &format!("{}\n[tls]\nmutual = true", NODES));
+
+    let policy = access_policy(&outdir);
+    assert_eq!(policy.clients.len(), 1);
+    let operations = &policy.clients[0].operations;
+    assert!(operations.contains(&Operation::Decrypt));
+    assert!(operations.contains(&Operation::Monitor));
+    assert!(!operations.contains(&Operation::Admin));
+
+    fs::remove_dir_all(&outdir).unwrap();
+}
+
+#[test]
+fn test_client_admin() {
+    let outdir = generate(
+        "admin",
+        &format!("{}\n[tls]\nmutual = true\nclient_admin = true", NODES),
+    );
+
+    let policy = access_policy(&outdir);
+    assert!(policy.clients[0].operations.contains(&Operation::Admin));
+
+    fs::remove_dir_all(&outdir).unwrap();
+}
+
+#[test]
+fn test_without_tls() {
+    let outdir = generate("plaintext", NODES);
+
+    let config = server_config(&outdir, 1);
+    assert!(config.rpc_tls.is_none());
+    assert!(config.access_policy_file.is_none());
+    assert!(!outdir.join("access_policy.json").exists());
+    assert!(!outdir.join("tls").exists());
+
+    fs::remove_dir_all(&outdir).unwrap();
+}
@@ -4,18 +4,32 @@ use log::{debug, error, info, warn};
 use log4rs;
 use sha2::{Digest, Sha256};
 use tokio::{runtime::Handle, sync::Notify, task::JoinHandle};
-use std::{future, path::PathBuf, process::exit, result, sync::Arc, vec};
+use std::{env, fs, future, path::PathBuf, process::exit, result, sync::Arc, vec};
 use theta_events::event::emitter::{self, start_null_emitter};
 use theta_orchestration::{
-    instance_manager::instance_manager::{InstanceManager, InstanceManagerCommand},
-    key_manager::key_manager::{KeyManager, KeyManagerCommand},
+    instance_manager::{
+        admission::AdmissionLimits,
+        instance_manager::{InstanceManager, InstanceManagerCommand},
+    },
+    key_manager::{
+        key_manager::{KeyManager, KeyManagerCommand},
+        nonce_pool::{FrostNoncePool, NoncePoolSettings},
+    },
+    signing_policy::signing_policy::SigningPolicy,
+};
+use theta_schemes::dl_schemes::signatures::frost::FrostOptions;
+use theta_schemes::keys::key_store::{KeyEntry, KeyStore};
+use theta_service::{
+    access_control::AccessPolicy,
+    membership,
+    rpc_request_handler::{self, RpcRequestHandler},
+    tls::server_tls_config,
 };
-use theta_service::rpc_request_handler::{self, RpcRequestHandler};
 
-use utils::server::{cli::ServerCli, types::ServerConfig};
+use utils::server::{cli::ServerCli, types::{ServerConfig, ENV_OVERRIDES}};
 
 use theta_network::{
-    network_manager::{network_director::NetworkDirector, network_manager_builder::NetworkManagerBuilder}, types::{config::NetworkConfig, message::NetMessage}
+    network_manager::{network_director::NetworkDirector, network_manager::NetworkCommand, network_manager_builder::NetworkManagerBuilder}, p2p::identity::load_identity_file, tob::cometbft::DEFAULT_POLL_INTERVAL_MS, types::{config::{NetworkConfig, NetworkPeer, NetworkProxy}, message::NetMessage}
 };
 
 
@@ -23,6 +37,10 @@ use theta_network::{
 async fn main() {
     let server_cli = ServerCli::parse();
 
+    if server_cli.check_config {
+        exit(check_config(&server_cli.config_file, server_cli.key_file.as_ref()));
+    }
+
     log4rs::init_file(server_cli.log4rs_config, Default::default())
         .expect("Unable to access supplied log4rs configuration file");
 
@@ -53,14 +71,12 @@ async fn main() {
 
     info!("Keychain location: {}", keychain_path.display());
 
-    //TODO: Move cjhecking existance of emmitter file path also here and return error if it does not exist
-
     //Logic for handling correctly the shutdown of the server
     let shutdown_notify = Arc::new(Notify::new());
     let mut handles: Vec<JoinHandle<Result<(), String>>> = vec![];
 
     // Starting all the component of the server. Here we want to return a list of handles for every component
-    let result = start_server(&cfg, keychain_path, shutdown_notify.clone());
+    let result = start_server(&cfg, server_cli.config_file.clone(), keychain_path, shutdown_notify.clone());
     match result {
         Ok(h) => handles = h,
         Err(e) => {
@@ -118,8 +134,110 @@ async fn monitor_for_task_failure(handles: &mut Vec<JoinHandle<Result<(), String
     }
 }
 
+/// Checks the config file, the files it refers to and, if given, the keystore, and prints all
+/// problems found. Returns the exit code of the check.
+fn check_config(config_file: &PathBuf, key_file: Option<&PathBuf>) -> i32 {
+    let mut problems = Vec::new();
+
+    let config = match ServerConfig::load(config_file) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            problems.push(e);
+            None
+        }
+    };
+
+    if let Some(config) = &config {
+        problems.extend(config.validate());
+
+        // accepted at startup, but clients cannot be told apart and key management is disabled
+        if config.access_policy_file.is_none() {
+            problems.push(String::from(
+                "No access policy configured (access_policy_file): every client may use every key and the admin RPCs are disabled",
+            ));
+        }
+
+        // files that exist are also parsed, missing ones were reported by validate()
+        if let Some(file) = config.access_policy_file.as_ref().filter(|f| f.is_file()) {
+            if let Err(e) = AccessPolicy::from_file(file) {
+                problems.push(e);
+            }
+        }
+        if let Some(file) = config.signing_policy_file.as_ref().filter(|f| f.is_file()) {
+            if let Err(e) = SigningPolicy::from_file(file) {
+                problems.push(e);
+            }
+        }
+        if let Some(file) = config.identity_file.as_ref().filter(|f| f.is_file()) {
+            if let Err(e) = load_identity_file(file) {
+                problems.push(e);
+            }
+        }
+        for peer in config.peers.iter() {
+            if let Err(e) = NetworkPeer::new(peer).get_identity() {
+                problems.push(e);
+            }
+        }
+    }
+
+    match key_file {
+        Some(key_file) if key_file.exists() => match KeyStore::from_file(key_file) {
+            Ok(keystore) => {
+                let keys = keystore.list_keys();
+                for entry in keys.iter() {
+                    let share = match &entry.sk {
+                        Some(share) => share,
+                        None => continue,
+                    };
+                    if let Err(e) = share.validate() {
+                        problems.push(format!("Invalid key share {}: {}", entry.id, e));
+                    }
+                    if let Some(config) = config.as_ref().filter(|c| c.id != share.get_share_id() as u32) {
+                        problems.push(format!(
+                            "The share of key {} belongs to server {}, not to server {}",
+                            entry.id,
+                            share.get_share_id(),
+                            config.id
+                        ));
+                    }
+                }
+
+                if let Some(config) = &config {
+                    let peers: Vec<NetworkPeer> = config.peers.iter().map(NetworkPeer::new).collect();
+                    let keys: Vec<Arc<KeyEntry>> = keys.into_iter().map(Arc::new).collect();
+                    if let Err(e) = membership::validate_committee(&peers, &keys) {
+                        problems.push(e);
+                    }
+                }
+            }
+            Err(e) => problems.push(format!("Error loading keystore {}: {}", key_file.display(), e)),
+        },
+        Some(key_file) => println!("Keystore {} does not exist, the server starts without keys", key_file.display()),
+        None => problems.push(String::from("No keystore given (--key-file)")),
+    }
+
+    let overrides: Vec<&str> = ENV_OVERRIDES
+        .iter()
+        .map(|(name, _)| *name)
+        .filter(|name| env::var(name).is_ok())
+        .collect();
+    if !overrides.is_empty() {
+        println!("Applied environment overrides: {}", overrides.join(", "));
+    }
+
+    if problems.is_empty() {
+        println!("{}: no problems found", config_file.display());
+        return 0;
+    }
+    println!("{}: {} problem(s) found", config_file.display(), problems.len());
+    for problem in problems {
+        println!("- {}", problem);
+    }
+    1
+}
+
 /// Start main event loop of server.
-pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_notify: Arc<Notify>) -> Result<Vec<JoinHandle<Result<(), String>>>, String >{
+pub fn start_server(config: &ServerConfig, config_file: PathBuf, keychain_path: PathBuf, shutdown_notify: Arc<Notify>) -> Result<Vec<JoinHandle<Result<(), String>>>, String >{
 
     let mut handles = vec![];
 
@@ -155,12 +273,25 @@ pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_noti
     network_builder.set_config(net_cfg.clone());
     network_builder.set_id(my_id);
 
+    // Channel to send commands to the NetworkManager, used by the RpcRequestHandler
+    let (network_command_sender, network_command_receiver) =
+        tokio::sync::mpsc::channel::<NetworkCommand>(32);
+    network_builder.set_command_receiver(network_command_receiver);
+
     //Here introduce code to differenciate between standalone and blockchain integration
     if config.proxy_node.is_some(){
         info!("Starting network with a proxy node");
         NetworkDirector::construct_proxy_network(&mut network_builder, net_cfg.clone(), my_id);
     }else{
-        NetworkDirector::construct_standalone_network(&mut network_builder, net_cfg.clone(), my_id);
+        if let Err(e) = NetworkDirector::construct_standalone_network(&mut network_builder, net_cfg.clone(), my_id) {
+            panic!("{}", e);
+        }
+    }
+
+    if let Some(cometbft) = config.cometbft.as_ref() {
+        info!("Ordering TOB messages through the CometBFT node at {}", cometbft.rpc_url);
+        let poll_interval = tokio::time::Duration::from_millis(cometbft.poll_interval_ms.unwrap_or(DEFAULT_POLL_INTERVAL_MS));
+        NetworkDirector::add_cometbft_tob(&mut network_builder, cometbft.rpc_url.clone(), poll_interval);
     }
 
     // Instantiate the NetworkManager
@@ -178,10 +309,26 @@ pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_noti
     let (key_manager_command_sender, key_manager_command_receiver) =
         tokio::sync::mpsc::channel::<KeyManagerCommand>(32);
 
+    let nonce_pool_config = config.frost_nonce_pool.clone().unwrap_or_default();
+    if nonce_pool_config.file.is_none() {
+        info!("FROST nonce pool is kept in memory only");
+    }
+    let nonce_pool = FrostNoncePool::load(NoncePoolSettings {
+        path: nonce_pool_config.file.clone(),
+        target_size: nonce_pool_config.target_size,
+        low_watermark: nonce_pool_config.low_watermark,
+    })?;
+
     info!("Initiating the key manager.");
     let shutdown_key_manager = shutdown_notify.clone();
+    let key_manager_network_sender = network_command_sender.clone();
+    let mut sm = KeyManager::new(
+        keychain_path,
+        nonce_pool,
+        key_manager_command_receiver,
+        Some(key_manager_network_sender),
+    )?;
     let key_manager_handle = tokio::spawn(async move {
-        let mut sm = KeyManager::new(keychain_path, key_manager_command_receiver);
         return sm.run(shutdown_key_manager).await;
     });
 
@@ -224,9 +371,35 @@ pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_noti
 
     handles.push(emitter_handle);
 
+    let signing_policy = match &config.signing_policy_file {
+        Some(file) => {
+            info!("Loading signing policy from {}", file.display());
+            SigningPolicy::from_file(file)?
+        }
+        None => SigningPolicy::new(),
+    };
+
+    let admission_limits = match &config.admission {
+        Some(admission) => AdmissionLimits {
+            max_running_instances: admission.max_running_instances,
+            max_running_per_client: admission.max_running_per_client,
+            max_running_per_key: admission.max_running_per_key,
+            max_queued_requests: admission.max_queued_requests,
+            instance_timeout: Some(tokio::time::Duration::from_secs(admission.instance_timeout_secs)),
+        },
+        None => AdmissionLimits::default(),
+    };
+
     let inst_cmd_sender = instance_manager_sender.clone();
     let key_mgr_sender = key_manager_command_sender.clone();
 
+    let frost_options = if config.robust_frost {
+        FrostOptions::Robust
+    } else {
+        info!("FROST signatures use the plain protocol without signer re-selection");
+        FrostOptions::NoPrecomputation
+    };
+
     let emitter_tx2 = emitter_tx.clone();
 
     let shutdown_instance_manager = shutdown_notify.clone();
@@ -238,6 +411,10 @@ pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_noti
             prot_to_net_sender,
             net_to_prot_receiver,
             emitter_tx,
+            signing_policy,
+            admission_limits,
+            tokio::time::Duration::from_secs(nonce_pool_config.check_interval_secs),
+            frost_options,
         );
         return mfw.run(shutdown_instance_manager).await;
     });
@@ -250,14 +427,109 @@ pub fn start_server(config: &ServerConfig, keychain_path: PathBuf, shutdown_noti
         "Starting RPC server on {}:{}",
         my_listen_address, my_rpc_port
     );
+    let tls_config = match &config.rpc_tls {
+        Some(tls) => {
+            if tls.client_ca_file.is_none() {
+                warn!("RPC endpoint uses TLS without client authentication");
+            }
+            Some(server_tls_config(
+                &tls.cert_file,
+                &tls.key_file,
+                tls.client_ca_file.as_ref(),
+            )?)
+        }
+        None => None,
+    };
+
+    let access_policy = match &config.access_policy_file {
+        Some(file) => {
+            if config.rpc_tls.as_ref().map_or(true, |tls| tls.client_ca_file.is_none()) {
+                return Err("An access policy requires mutual TLS on the RPC endpoint (rpc_tls.client_ca_file)".to_string());
+            }
+            info!("Loading access policy from {}", file.display());
+            Some(AccessPolicy::from_file(file)?)
+        }
+        None => None,
+    };
+
+    if let Some(interval_secs) = config.peer_watch_interval_secs {
+        info!("Watching {} for changes of the peers", config_file.display());
+        let key_mgr_sender = key_manager_command_sender.clone();
+        let network_sender = network_command_sender.clone();
+        let shutdown_watcher = shutdown_notify.clone();
+        let my_id = config.id;
+        let watcher_handle = tokio::spawn(async move {
+            watch_peers(
+                config_file,
+                my_id,
+                tokio::time::Duration::from_secs(interval_secs),
+                key_mgr_sender,
+                network_sender,
+                shutdown_watcher,
+            )
+            .await
+        });
+        handles.push(watcher_handle);
+    }
+
     let shutdown_rpc_handler = shutdown_notify.clone();
-    let rpc_request_handler = RpcRequestHandler::new(key_manager_command_sender, instance_manager_sender, emitter_tx2);
+    let rpc_request_handler = RpcRequestHandler::new(key_manager_command_sender, instance_manager_sender, network_command_sender, emitter_tx2, access_policy);
     let rpc_addr = format!("{}:{}", my_listen_address, my_rpc_port);
     let rpc_handle = tokio::spawn(async move {
-        RpcRequestHandler::run(rpc_addr, shutdown_rpc_handler, rpc_request_handler).await
+        RpcRequestHandler::run(rpc_addr, shutdown_rpc_handler, rpc_request_handler, tls_config).await
     });
 
     handles.push(rpc_handle);
 
     return Ok(handles);
 }
+
+/// Applies the peers and proxy of the config file whenever the file changes. Invalid changes are
+/// logged and ignored, the node keeps running with its current peers.
+async fn watch_peers(
+    config_file: PathBuf,
+    my_id: u32,
+    interval: tokio::time::Duration,
+    key_manager_command_sender: tokio::sync::mpsc::Sender<KeyManagerCommand>,
+    network_command_sender: tokio::sync::mpsc::Sender<NetworkCommand>,
+    shutdown_notify: Arc<Notify>,
+) -> Result<(), String> {
+    let modified = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_modified = modified(&config_file);
+    let mut check = tokio::time::interval(interval);
+
+    loop {
+        tokio::select! {
+            _ = check.tick() => {
+                let current = modified(&config_file);
+                if current.is_none() || current == last_modified {
+                    continue;
+                }
+                last_modified = current;
+
+                info!("Config file changed, reloading peers");
+                let cfg = match ServerConfig::from_file(&config_file) {
+                    Ok(cfg) => cfg,
+                    Err(e) => {
+                        warn!("Ignoring changed config file: {}", e);
+                        continue;
+                    }
+                };
+                if cfg.id != my_id {
+                    warn!("Ignoring changed config file: the server id cannot change at runtime");
+                    continue;
+                }
+
+                let peers = cfg.peers.iter().map(NetworkPeer::new).collect();
+                let proxy = cfg.get_proxy_node().map(NetworkProxy::new);
+                if let Err(e) = membership::update_peers(&key_manager_command_sender, &network_command_sender, peers, proxy).await {
+                    warn!("Ignoring peers of changed config file: {}", e);
+                }
+            }
+            _ = shutdown_notify.notified() => {
+                info!("Stopping the config file watcher");
+                return Ok(());
+            }
+        }
+    }
+}
@@ -1,37 +1,47 @@
 use std::{
-    collections::HashMap,
     fmt::Debug,
     fs::File,
-    io::{Read, Write},
+    io::{BufReader, BufWriter, Read, Write},
     path::PathBuf,
 };
 
 use clap::Parser;
 use hex::FromHex;
 use log::{error, info, debug};
+use rand::{distributions::Alphanumeric, Rng};
+use std::time::Duration;
 use terminal_menu::{button, label, menu, mut_menu, run, TerminalMenuItem};
 
 use std::fs;
 use theta_proto::{
     protocol_types::{threshold_crypto_library_client::ThresholdCryptoLibraryClient, KeyRequest},
-    scheme_types::{Group, ThresholdOperation, ThresholdScheme},
+    scheme_types::{Group, KeyStatus, ThresholdOperation, ThresholdScheme},
 };
 use theta_schemes::{
     interface::{
-        SchemeError, Serializable, Signature, ThresholdCipher, ThresholdCipherParams,
+        Ciphertext, SchemeError, Serializable, Signature, ThresholdCipher, ThresholdCipherParams,
         ThresholdSignature,
     },
     keys::{
+        ceremony::{self, KeyManifest, NodeBundle, SignedManifest},
         key_generator::KeyGenerator,
-        key_store::KeyStore,
+        key_store::{KeyMetadata, KeyStore},
         keys::{PrivateKeyShare, PublicKey},
     },
     rand::{RngAlgorithm, RNG},
     scheme_types_impl::SchemeDetails,
+    stream::{self, StreamHeader},
+};
+use theta_client::{
+    client::{ClientOptions, ThetacryptClient},
+    error::ClientError,
 };
 use thiserror::Error;
 use utils::thetacli::cli::*;
 
+#[cfg(test)]
+mod thetacli_tests;
+
 #[derive(Error)]
 enum Error {
     #[error("file error: {0}")]
@@ -67,6 +77,20 @@ async fn main() -> Result<(), Error> {
                 &key_gen_args.subjects,
                 &key_gen_args.output,
                 key_gen_args.new,
+                key_gen_args.ceremony.then(|| Ceremony {
+                    signing_key: key_gen_args.signing_key,
+                    recipients: key_gen_args.recipients,
+                }),
+            );
+        }
+        Commands::Enc(enc_args) if enc_args.stream => {
+            return encrypt_stream(
+                &enc_args.infile,
+                enc_args.label.as_bytes(),
+                &enc_args.output,
+                enc_args.pubkey,
+                enc_args.keystore,
+                enc_args.key_id,
             );
         }
         Commands::Enc(enc_args) => {
@@ -88,22 +112,76 @@ async fn main() -> Result<(), Error> {
                 verify_args.key_id,
             );
         }
-        Commands::Keystore(keystore_args) => {
-            return keystore(
-                &keystore_args.action,
-                &keystore_args.keystore,
-                keystore_args.address,
-                keystore_args.new,
-                keystore_args.input,
+        Commands::Keystore(keystore_args) => match keystore_args.action.as_str() {
+            "check" => return validate_keystore(&keystore_args.keystore),
+            "label" => return label_key(&keystore_args),
+            "verify" => return verify_keystore(&keystore_args),
+            "unpack" => return unpack_bundle(&keystore_args),
+            "bundle-key" => return bundle_key(&keystore_args.keystore),
+            _ => {
+                return keystore(
+                    &keystore_args.action,
+                    &keystore_args.keystore,
+                    keystore_args.address,
+                    keystore_args.new,
+                    keystore_args.input,
+                )
+                .await;
+            }
+        },
+        Commands::Decrypt(decrypt_args) if decrypt_args.stream => {
+            return decrypt_stream(
+                &decrypt_args.servers,
+                &decrypt_args.infile,
+                decrypt_args.key_id,
+                &decrypt_args.output,
             )
             .await;
         }
+        Commands::Decrypt(decrypt_args) => {
+            return decrypt(
+                &decrypt_args.servers,
+                &decrypt_args.infile,
+                decrypt_args.key_id,
+                &decrypt_args.output,
+            )
+            .await;
+        }
+        Commands::Sign(sign_args) => {
+            return sign(
+                &sign_args.servers,
+                &sign_args.infile,
+                &sign_args.key_id,
+                sign_args.label,
+                &sign_args.output,
+            )
+            .await;
+        }
+        Commands::Coin(coin_args) => {
+            return coin(
+                &coin_args.servers,
+                &coin_args.name,
+                &coin_args.key_id,
+                &coin_args.output,
+            )
+            .await;
+        }
+        Commands::Rotate(rotate_args) => {
+            return rotate(&rotate_args.key_id, &rotate_args.output, rotate_args.k);
+        }
     }
 }
 
-fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
+fn keygen(
+    k: u16,
+    n: u16,
+    a: &str,
+    dir: &str,
+    new: bool,
+    ceremony: Option<Ceremony>,
+) -> Result<(), Error> {
     let mut parts = a.split(',');
-    let mut keys = HashMap::new();
+    let mut keys: Vec<(Option<String>, Vec<PrivateKeyShare>)> = Vec::new();
     let mut rng = RNG::new(RngAlgorithm::OsRng);
 
     if k > n {
@@ -112,6 +190,15 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
         ))));
     }
 
+    if let Some(options) = &ceremony {
+        if !options.recipients.is_empty() && options.recipients.len() != n as usize {
+            return Err(Error::String(format!(
+                "There must be one recipient for each of the {} parties",
+                n
+            )));
+        }
+    }
+
     if fs::create_dir_all(dir).is_err() {
         error!("Error: could not create directory");
         return Err(Error::Threshold(SchemeError::IOError));
@@ -138,6 +225,11 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
     }
 
     for part in parts {
+        // an alias can be given for the key as 'scheme-group:alias'
+        let (part, alias) = match part.split_once(':') {
+            Some((subject, alias)) => (subject, Some(String::from(alias))),
+            None => (part, None),
+        };
         let mut s = part.split('-');
 
         let scheme_str = s.next();
@@ -166,11 +258,6 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
 
         info!("Generating {}...", part);
 
-        // Creation of the id (name) given to a certain key. For now the name is based on scheme_group info.
-        let mut name = String::from(group_str.unwrap());
-        name.insert_str(0, "-");
-        name.insert_str(0, scheme_str.unwrap());
-
         let key = KeyGenerator::generate_keys(
             k as usize,
             n as usize,
@@ -189,7 +276,11 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
             return Err(Error::Threshold(SchemeError::IOError));
         }
 
-        keys.insert(name.clone(), key);
+        keys.push((alias, key));
+    }
+
+    if let Some(options) = ceremony {
+        return write_ceremony(dir, n, &keys, options);
     }
 
     for node_id in 0..n {
@@ -202,9 +293,7 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
         }
 
         // each value in keys is a vector of secret key share (related to the same pk) that needs to be distributed among the right key file (parties)
-        for k in keys.clone() {
-            let _ = kc.insert_private_key(k.1[node_id as usize].clone());
-        }
+        insert_shares(&mut kc, &keys, node_id as usize)?;
 
         // Here the information about the keys of a specific party are actually being written on file
         // TODO: eventually here there could be a protocol for an online phase to send the information to the Thetacrypt instances.
@@ -217,6 +306,158 @@ fn keygen(k: u16, n: u16, a: &str, dir: &str, new: bool) -> Result<(), Error> {
     return Ok(());
 }
 
+// Generates the next version of a key and adds its shares to the keystores of all nodes. In
+// every keystore, the new version takes over the alias and default flag of the previous one,
+// which can then only be used for decryption.
+fn rotate(key_id: &str, dir: &str, k: Option<u16>) -> Result<(), Error> {
+    let keystore_file = |node_id: u16| format!("{}/node{}.keystore", dir, node_id);
+    let previous = KeyStore::from_file(&PathBuf::from(keystore_file(1)))?
+        .get_key_by_id(key_id)
+        .map_err(|e| Error::String(e.to_string()))?;
+    let scheme = previous.pk.get_scheme();
+    let group = *previous.pk.get_group();
+    let n = previous.pk.get_n();
+    let k = k.unwrap_or(previous.pk.get_threshold());
+    if k > n {
+        return Err(Error::Threshold(SchemeError::InvalidParams(Some(
+            "Threshold parameter must not exceed number of parties".into(),
+        ))));
+    }
+
+    info!("Generating the next version of {}...", previous.id);
+    let keys = KeyGenerator::generate_keys(
+        k as usize,
+        n as usize,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &group,
+        &Option::None,
+    )?;
+
+    // all keystores are checked before any of them is changed
+    let mut keystores = Vec::new();
+    for node_id in 1..=n {
+        let mut keystore = KeyStore::from_file(&PathBuf::from(keystore_file(node_id)))?;
+        keystore
+            .rotate_key(&previous.id, keys[(node_id - 1) as usize].clone())
+            .map_err(|e| Error::String(format!("node{}.keystore: {}", node_id, e)))?;
+        keystores.push(keystore);
+    }
+    for (i, keystore) in keystores.iter().enumerate() {
+        keystore.to_file(&keystore_file(i as u16 + 1))?;
+    }
+
+    let pubkey = keys[0].get_public_key().to_bytes()?;
+    fs::create_dir_all(format!("{}/pub", dir))?;
+    fs::write(
+        format!(
+            "{}/pub/{}-{}_{}.pub",
+            dir,
+            scheme.as_str_name(),
+            group.as_str_name(),
+            keys[0].get_key_id()
+        ),
+        pubkey,
+    )?;
+
+    let version = keystores[0]
+        .get_key_by_id(keys[0].get_key_id())
+        .map_err(|e| Error::String(e.to_string()))?
+        .metadata
+        .version;
+    println!(
+        "Created version {} of key {}: {}",
+        version,
+        previous.lineage(),
+        keys[0].get_key_id()
+    );
+    Ok(())
+}
+
+// Adds the share of party `index` (starting at 0) of every generated key to the keystore.
+fn insert_shares(
+    keystore: &mut KeyStore,
+    keys: &[(Option<String>, Vec<PrivateKeyShare>)],
+    index: usize,
+) -> Result<(), Error> {
+    for (alias, shares) in keys.iter() {
+        let id = keystore
+            .insert_private_key(shares[index].clone())
+            .map_err(|e| Error::String(e.to_string()))?;
+        if alias.is_some() {
+            let metadata = KeyMetadata {
+                alias: alias.clone(),
+                ..Default::default()
+            };
+            keystore
+                .set_metadata(&id, metadata)
+                .map_err(|e| Error::String(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+// Output options of a key ceremony.
+struct Ceremony {
+    // the key that signs the manifest, a fresh key is used if not set
+    signing_key: Option<String>,
+    // the bundle keys of the nodes, the bundles are not encrypted if empty
+    recipients: Vec<String>,
+}
+
+// Writes one bundle per node and the signed manifest of the public keys.
+fn write_ceremony(
+    dir: &str,
+    n: u16,
+    keys: &[(Option<String>, Vec<PrivateKeyShare>)],
+    options: Ceremony,
+) -> Result<(), Error> {
+    let signing_key = match options.signing_key {
+        Some(path) if PathBuf::from(&path).exists() => {
+            ceremony::decode_signing_key(&fs::read_to_string(&path)?).map_err(Error::String)?
+        }
+        Some(path) => {
+            let signing_key = ceremony::generate_signing_key();
+            fs::write(&path, ceremony::encode_key(&signing_key.to_bytes()))?;
+            info!("Created signing key {}", path);
+            signing_key
+        }
+        None => ceremony::generate_signing_key(),
+    };
+
+    let public_keys: Vec<PublicKey> = keys
+        .iter()
+        .map(|(_, shares)| shares[0].get_public_key())
+        .collect();
+    let manifest = KeyManifest::new(n, &public_keys).map_err(Error::String)?;
+
+    for node_id in 1..=n {
+        let mut keystore = KeyStore::new();
+        insert_shares(&mut keystore, keys, (node_id - 1) as usize)?;
+
+        let recipient = match options.recipients.get((node_id - 1) as usize) {
+            Some(path) => Some(ceremony::decode_bundle_key(&fs::read_to_string(path)?).map_err(Error::String)?),
+            None => None,
+        };
+        let bundle = NodeBundle::seal(node_id, &keystore, &manifest, recipient.as_ref())
+            .map_err(Error::String)?;
+
+        let bundle_file = format!("{}/node{}.bundle", dir, node_id);
+        bundle.to_file(&bundle_file)?;
+        info!("Created {}", bundle_file);
+    }
+
+    let manifest_file = format!("{}/manifest.json", dir);
+    manifest.sign(&signing_key).to_file(&manifest_file)?;
+    println!(
+        "Created {} signed by {}",
+        manifest_file,
+        ceremony::encode_key(signing_key.verifying_key().as_bytes())
+    );
+
+    Ok(())
+}
+
 fn encrypt(
     infile: &Option<String>,
     label: &[u8],
@@ -300,6 +541,37 @@ fn encrypt(
     return Ok(());
 }
 
+fn encrypt_stream(
+    infile: &Option<String>,
+    label: &[u8],
+    outfile: &str,
+    key_path: Option<String>,
+    keystore_path: Option<String>,
+    key_id: Option<String>,
+) -> Result<(), Error> {
+    let key = load_key(
+        key_path,
+        keystore_path,
+        key_id,
+        ThresholdOperation::Encryption,
+    )?;
+
+    let mut reader = open_input(infile)?;
+    let mut writer = open_output(outfile)?;
+    let mut params = ThresholdCipherParams::new();
+    match stream::encrypt_stream(&mut reader, &mut writer, label, &key, &mut params) {
+        Ok(length) => {
+            info!("Encrypted {} bytes", length);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Error encrypting stream: {}", e);
+            discard_output(outfile);
+            Err(Error::Threshold(e))
+        }
+    }
+}
+
 fn verify(
     message_path: &str,
     signature_path: &str,
@@ -435,7 +707,12 @@ fn load_key(
                     entries = keystore.get_coin_keys();
                 }
                 ThresholdOperation::Encryption => {
-                    entries = keystore.get_encryption_keys();
+                    // new data is only encrypted under active key versions
+                    entries = keystore
+                        .get_encryption_keys()
+                        .into_iter()
+                        .filter(|entry| entry.status() == KeyStatus::Active)
+                        .collect();
                 }
                 ThresholdOperation::Signature => {
                     entries = keystore.get_signing_keys();
@@ -462,7 +739,11 @@ fn load_key(
                 key = tmp.unwrap().pk.clone();
             }
         } else {
-            let tmp = keystore.get_key_by_id(&key_id.unwrap());
+            // encrypting under any version of a key uses its active version
+            let tmp = match operation {
+                ThresholdOperation::Encryption => keystore.get_active_version(&key_id.unwrap()),
+                _ => keystore.get_key_by_id(&key_id.unwrap()),
+            };
 
             if let Err(e) = tmp {
                 error!("Error loading public key: {}", e.to_string());
@@ -596,9 +877,345 @@ async fn keystore(
             println!("Successfully added {} key to keystore", key_type);
         }
         _ => {
-            println!("Invalid action. Valid actions are: ls, add, fetch");
+            println!("Invalid action. Valid actions are: ls, add, fetch, check, label, verify, unpack, bundle-key");
         }
     }
 
     Ok(())
 }
+
+// Reads the manifest of a key ceremony and checks its signature.
+fn load_manifest(manifest: &Option<String>, signer: &Option<String>) -> Result<KeyManifest, Error> {
+    let path = manifest
+        .as_ref()
+        .ok_or_else(|| Error::String(String::from("No manifest specified")))?;
+    let signed = SignedManifest::from_file(&PathBuf::from(path)).map_err(Error::String)?;
+
+    let trusted = match signer {
+        Some(signer) => Some(ceremony::decode_verifying_key(signer).map_err(Error::String)?),
+        None => {
+            println!(
+                "No --signer given, check that the manifest was signed by {}",
+                signed.signer
+            );
+            None
+        }
+    };
+
+    Ok(signed.verify(trusted.as_ref()).map_err(Error::String)?.clone())
+}
+
+fn check_keystore(keystore: &KeyStore, manifest: &KeyManifest, node_id: Option<u16>) -> Result<(), Error> {
+    let checks = ceremony::verify_keystore(keystore, manifest, node_id);
+    let mut failed = 0;
+    for check in checks.iter() {
+        match &check.error {
+            None => println!("{}: ok", check.key_id),
+            Some(e) => {
+                failed += 1;
+                println!("{}: FAILED ({})", check.key_id, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::String(format!(
+            "{} of {} keys are not consistent with the manifest",
+            failed,
+            checks.len()
+        )));
+    }
+
+    println!("All {} keys are consistent with the manifest", checks.len());
+    Ok(())
+}
+
+// Sets the alias, description and tags of a key in the keystore.
+fn label_key(args: &KeystoreArgs) -> Result<(), Error> {
+    let key_id = args
+        .key_id
+        .as_ref()
+        .ok_or_else(|| Error::String(String::from("No key id provided")))?;
+    let path = PathBuf::from(&args.keystore);
+    let mut keystore = KeyStore::from_file(&path)
+        .map_err(|e| Error::String(format!("Error reading key store: {}", e)))?;
+
+    let metadata = KeyMetadata {
+        alias: args.alias.clone().filter(|alias| !alias.is_empty()),
+        description: args.description.clone(),
+        tags: args.tags.clone(),
+        ..Default::default()
+    };
+    keystore
+        .set_metadata(key_id, metadata)
+        .map_err(|e| Error::String(e.to_string()))?;
+    keystore.to_file(&args.keystore)?;
+
+    let entry = keystore
+        .get_key_by_id(key_id)
+        .map_err(|e| Error::String(e.to_string()))?;
+    println!("{}", entry.to_string());
+    Ok(())
+}
+
+// Checks every private key share in the keystore against the verification keys of its public key.
+fn validate_keystore(path: &str) -> Result<(), Error> {
+    let keystore = KeyStore::from_file(&PathBuf::from(path))
+        .map_err(|e| Error::String(format!("Error reading key store: {}", e)))?;
+
+    let mut checked = 0;
+    let mut failed = 0;
+    for entry in keystore.list_keys().iter() {
+        if let Some(sk) = &entry.sk {
+            checked += 1;
+            match sk.validate() {
+                Ok(()) => println!("{}: ok", entry.id),
+                Err(e) => {
+                    failed += 1;
+                    println!("{}: FAILED ({})", entry.id, e);
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(Error::String(format!(
+            "{} of {} key shares are invalid",
+            failed, checked
+        )));
+    }
+
+    println!("All {} key shares are valid", checked);
+    Ok(())
+}
+
+fn verify_keystore(args: &KeystoreArgs) -> Result<(), Error> {
+    let manifest = load_manifest(&args.manifest, &args.signer)?;
+    let keystore = KeyStore::from_file(&PathBuf::from(&args.keystore))
+        .map_err(|e| Error::String(format!("Error reading key store: {}", e)))?;
+    check_keystore(&keystore, &manifest, args.node_id)
+}
+
+// Opens the bundle of a node, checks it against the manifest and writes the keystore.
+fn unpack_bundle(args: &KeystoreArgs) -> Result<(), Error> {
+    let manifest = load_manifest(&args.manifest, &args.signer)?;
+    let input = args
+        .input
+        .as_ref()
+        .ok_or_else(|| Error::String(String::from("No input bundle provided")))?;
+    let bundle = NodeBundle::from_file(&PathBuf::from(input)).map_err(Error::String)?;
+
+    if let Some(node_id) = args.node_id {
+        if node_id != bundle.node_id {
+            return Err(Error::String(format!(
+                "The bundle belongs to node {}, not to node {}",
+                bundle.node_id, node_id
+            )));
+        }
+    }
+
+    let secret = match &args.bundle_key {
+        Some(path) => Some(ceremony::decode_bundle_secret(&fs::read_to_string(path)?).map_err(Error::String)?),
+        None => None,
+    };
+    let keystore = bundle.open(&manifest, secret.as_ref()).map_err(Error::String)?;
+    check_keystore(&keystore, &manifest, Some(bundle.node_id))?;
+
+    keystore.to_file(&args.keystore)?;
+    println!("Unpacked the keystore of node {} to {}", bundle.node_id, args.keystore);
+    Ok(())
+}
+
+// Creates the X25519 key a node receives its bundle with. The public key is written to
+// `<path>.pub`.
+fn bundle_key(path: &str) -> Result<(), Error> {
+    let (secret, public) = ceremony::generate_bundle_key();
+    fs::write(path, ceremony::encode_key(&secret.to_bytes()))?;
+    let public_path = format!("{}.pub", path);
+    fs::write(&public_path, ceremony::encode_key(public.as_bytes()))?;
+    println!("Created bundle key {}, its public key is in {}", path, public_path);
+    Ok(())
+}
+
+async fn decrypt(
+    servers: &ServerArgs,
+    infile: &Option<String>,
+    key_id: Option<String>,
+    outfile: &str,
+) -> Result<(), Error> {
+    let ciphertext = Ciphertext::from_bytes(&read_input(infile)?)?;
+
+    if let Some(key_id) = key_id {
+        if key_id != ciphertext.get_key_id() {
+            return Err(Error::String(format!(
+                "The ciphertext was encrypted with key {}, not {}",
+                ciphertext.get_key_id(),
+                key_id
+            )));
+        }
+    }
+
+    let client = connect_to_committee(servers).await?;
+    let plaintext = client.decrypt(&ciphertext).await.map_err(client_error)?;
+
+    write_output(outfile, &plaintext)
+}
+
+async fn decrypt_stream(
+    servers: &ServerArgs,
+    infile: &Option<String>,
+    key_id: Option<String>,
+    outfile: &str,
+) -> Result<(), Error> {
+    let mut reader = open_input(infile)?;
+    let header = StreamHeader::read_from(&mut reader)?;
+    let ciphertext = header.get_ciphertext();
+
+    if let Some(key_id) = key_id {
+        if key_id != ciphertext.get_key_id() {
+            return Err(Error::String(format!(
+                "The stream was encrypted with key {}, not {}",
+                ciphertext.get_key_id(),
+                key_id
+            )));
+        }
+    }
+
+    // only the data key is decrypted by the committee
+    let client = connect_to_committee(servers).await?;
+    let data_key = client.decrypt(ciphertext).await.map_err(client_error)?;
+
+    let mut writer = open_output(outfile)?;
+    match stream::decrypt_stream(&header, &mut reader, &mut writer, &data_key) {
+        Ok(length) => {
+            info!("Decrypted {} bytes", length);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Error decrypting stream: {}", e);
+            discard_output(outfile);
+            Err(Error::Threshold(e))
+        }
+    }
+}
+
+async fn sign(
+    servers: &ServerArgs,
+    infile: &Option<String>,
+    key_id: &str,
+    label: Option<String>,
+    outfile: &str,
+) -> Result<(), Error> {
+    let message = read_input(infile)?;
+    let client = connect_to_committee(servers).await?;
+
+    // the label distinguishes signing requests for the same message
+    let label = label.unwrap_or_else(random_label);
+    let signature = client
+        .sign(key_id, &message, label.as_bytes())
+        .await
+        .map_err(client_error)?;
+
+    write_output(outfile, hex::encode(signature.to_bytes()?).as_bytes())
+}
+
+async fn coin(servers: &ServerArgs, name: &str, key_id: &str, outfile: &str) -> Result<(), Error> {
+    let client = connect_to_committee(servers).await?;
+    let value = client
+        .flip_coin(key_id, name.as_bytes())
+        .await
+        .map_err(client_error)?;
+
+    write_output(outfile, hex::encode(value).as_bytes())
+}
+
+fn random_label() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect()
+}
+
+async fn connect_to_committee(servers: &ServerArgs) -> Result<ThetacryptClient, Error> {
+    let options = ClientOptions {
+        result_timeout: Duration::from_secs(servers.timeout),
+        combine_shares: servers.combine_shares,
+        ..Default::default()
+    };
+
+    let client = match &servers.config {
+        Some(config_path) => ThetacryptClient::from_file(&PathBuf::from(config_path), options),
+        None if !servers.servers.is_empty() => ThetacryptClient::from_addresses(&servers.servers, options),
+        None => {
+            return Err(Error::String(String::from(
+                "Either servers or a client config need to be specified",
+            )))
+        }
+    };
+    let client = client.map_err(client_error)?;
+
+    // keys of a trusted keystore are used as they are
+    if let Some(keystore_path) = &servers.keystore {
+        let keystore = KeyStore::from_file(&PathBuf::from(keystore_path))?;
+        for entry in keystore.list_keys() {
+            client.add_public_key(entry.pk).await;
+        }
+    }
+
+    Ok(client)
+}
+
+fn client_error(e: ClientError) -> Error {
+    match e {
+        ClientError::SchemeError(e) => Error::Threshold(e),
+        e => Error::String(e.to_string()),
+    }
+}
+
+fn read_input(infile: &Option<String>) -> Result<Vec<u8>, Error> {
+    match infile {
+        Some(path) => Ok(fs::read(path)?),
+        None => {
+            let mut buf = Vec::new();
+            if atty::isnt(atty::Stream::Stdin) {
+                std::io::stdin().read_to_end(&mut buf)?;
+            }
+            if buf.is_empty() {
+                return Err(Error::String(String::from("No input specified")));
+            }
+            Ok(buf)
+        }
+    }
+}
+
+fn write_output(outfile: &str, data: &[u8]) -> Result<(), Error> {
+    if outfile == "-" {
+        std::io::stdout().write_all(data)?;
+    } else {
+        fs::write(outfile, data)?;
+    }
+    Ok(())
+}
+
+fn open_input(infile: &Option<String>) -> Result<Box<dyn Read>, Error> {
+    match infile {
+        Some(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+        None => Ok(Box::new(BufReader::new(std::io::stdin()))),
+    }
+}
+
+fn open_output(outfile: &str) -> Result<Box<dyn Write>, Error> {
+    if outfile == "-" {
+        Ok(Box::new(BufWriter::new(std::io::stdout())))
+    } else {
+        Ok(Box::new(BufWriter::new(File::create(outfile)?)))
+    }
+}
+
+// Removes an incomplete or unauthenticated output file.
+fn discard_output(outfile: &str) {
+    if outfile != "-" {
+        let _ = fs::remove_file(outfile);
+    }
+}
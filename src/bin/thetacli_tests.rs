@@ -0,0 +1,268 @@
+use std::fs;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::time::Duration;
+
+use theta_events::event::emitter::start_null_emitter;
+use theta_network::simulation::simulated_network::SimulationConfig;
+use theta_orchestration::test_harness::committee::{
+    add_shares, keystores_from_shares, SimulatedCommittee,
+};
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_schemes::{
+    interface::{
+        Serializable, Signature, ThresholdCipher, ThresholdCipherParams, ThresholdSignature,
+    },
+    keys::{key_generator::KeyGenerator, key_store::KeyStore, keys::PrivateKeyShare},
+    rand::{RngAlgorithm, RNG},
+};
+use theta_service::rpc_request_handler::RpcRequestHandler;
+use tokio::sync::Notify;
+use utils::thetacli::cli::ServerArgs;
+
+use super::{coin, connect_to_committee, decrypt, sign, Error};
+
+const N: usize = 5;
+const K: usize = 3;
+
+fn generate_shares(scheme: ThresholdScheme) -> Vec<PrivateKeyShare> {
+    KeyGenerator::generate_keys(
+        K,
+        N,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap()
+}
+
+fn temp_path(name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!("thetacrypt-cli-{}-{}", std::process::id(), name))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn free_port() -> u16 {
+    TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+fn server_args(servers: Vec<String>) -> ServerArgs {
+    ServerArgs {
+        servers,
+        config: None,
+        keystore: None,
+        timeout: 30,
+        combine_shares: false,
+    }
+}
+
+// A simulated committee whose nodes serve the RPC endpoint on localhost, like servers started with
+// `server`.
+struct RpcCommittee {
+    committee: SimulatedCommittee,
+    servers: Vec<String>,
+    shutdown: Arc<Notify>,
+}
+
+impl RpcCommittee {
+    fn start(shares: Vec<Vec<PrivateKeyShare>>) -> Self {
+        let mut shares = shares.into_iter();
+        let mut keystores = keystores_from_shares(shares.next().unwrap());
+        for shares in shares {
+            add_shares(&mut keystores, shares);
+        }
+        let committee = SimulatedCommittee::start(
+            keystores,
+            SimulationConfig {
+                seed: 1,
+                min_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(10),
+                drop_probability: 0.0,
+            },
+        );
+
+        let shutdown = Arc::new(Notify::new());
+        let mut servers = Vec::new();
+        for node in committee.nodes() {
+            let (emitter, _) = start_null_emitter(shutdown.clone());
+            let handler = RpcRequestHandler::new(
+                node.key_manager.clone(),
+                node.instance_manager.clone(),
+                node.network.clone(),
+                emitter,
+                None,
+            );
+            let port = free_port();
+            let shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                RpcRequestHandler::run(format!("127.0.0.1:{}", port), shutdown, handler, None).await
+            });
+            servers.push(format!("http://127.0.0.1:{}", port));
+        }
+
+        Self {
+            committee,
+            servers,
+            shutdown,
+        }
+    }
+
+    fn args(&self) -> ServerArgs {
+        server_args(self.servers.clone())
+    }
+
+    fn stop(&self) {
+        self.shutdown.notify_waiters();
+        self.committee.shutdown();
+    }
+}
+
+#[tokio::test]
+async fn test_decrypt() {
+    let shares = generate_shares(ThresholdScheme::Sg02);
+    let public_key = shares[0].get_public_key();
+    let committee = RpcCommittee::start(vec![shares]);
+
+    let plaintext = b"decrypted by the committee".to_vec();
+    let mut params = ThresholdCipherParams::new();
+    let ciphertext =
+        ThresholdCipher::encrypt(&plaintext, b"label", &public_key, &mut params).unwrap();
+    let infile = temp_path("ciphertext");
+    fs::write(&infile, ciphertext.to_bytes().unwrap()).unwrap();
+
+    // by an instance of the nodes, and combined from the shares of the nodes
+    for combine_shares in [false, true] {
+        let outfile = temp_path(&format!("plaintext-{}", combine_shares));
+        let args = ServerArgs {
+            combine_shares,
+            ..committee.args()
+        };
+        decrypt(&args, &Some(infile.clone()), None, &outfile)
+            .await
+            .unwrap();
+        assert_eq!(fs::read(&outfile).unwrap(), plaintext);
+        fs::remove_file(&outfile).unwrap();
+    }
+
+    fs::remove_file(&infile).unwrap();
+    committee.stop();
+}
+
+#[tokio::test]
+async fn test_sign() {
+    let shares = generate_shares(ThresholdScheme::Bls04);
+    let public_key = shares[0].get_public_key();
+    let key_id = shares[0].get_key_id().to_string();
+    let committee = RpcCommittee::start(vec![shares]);
+
+    let message = b"signed by the committee".to_vec();
+    let infile = temp_path("message");
+    fs::write(&infile, &message).unwrap();
+    let outfile = temp_path("signature");
+
+    sign(
+        &committee.args(),
+        &Some(infile.clone()),
+        &key_id,
+        None,
+        &outfile,
+    )
+    .await
+    .unwrap();
+
+    let signature = hex::decode(fs::read_to_string(&outfile).unwrap()).unwrap();
+    let signature = Signature::from_bytes(&signature).unwrap();
+    assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+
+    fs::remove_file(&infile).unwrap();
+    fs::remove_file(&outfile).unwrap();
+    committee.stop();
+}
+
+#[tokio::test]
+async fn test_coin() {
+    let shares = generate_shares(ThresholdScheme::Cks05);
+    let key_id = shares[0].get_key_id().to_string();
+    let committee = RpcCommittee::start(vec![shares, generate_shares(ThresholdScheme::Bls04)]);
+
+    // the same coin has the same value every time it is flipped
+    let mut values = Vec::new();
+    for i in 0..2 {
+        let outfile = temp_path(&format!("coin-{}", i));
+        coin(&committee.args(), "coin", &key_id, &outfile)
+            .await
+            .unwrap();
+        values.push(hex::decode(fs::read_to_string(&outfile).unwrap()).unwrap());
+        fs::remove_file(&outfile).unwrap();
+    }
+    assert_eq!(values[0].len(), 1);
+    assert_eq!(values[0], values[1]);
+
+    committee.stop();
+}
+
+#[tokio::test]
+async fn test_decrypt_rejects_other_key() {
+    let shares = generate_shares(ThresholdScheme::Sg02);
+    let mut params = ThresholdCipherParams::new();
+    let ciphertext = ThresholdCipher::encrypt(
+        b"plaintext",
+        b"label",
+        &shares[0].get_public_key(),
+        &mut params,
+    )
+    .unwrap();
+    let infile = temp_path("other-key-ciphertext");
+    fs::write(&infile, ciphertext.to_bytes().unwrap()).unwrap();
+
+    // the ciphertext is checked before the committee is contacted
+    let args = server_args(vec![String::from("http://127.0.0.1:1")]);
+    let result = decrypt(
+        &args,
+        &Some(infile.clone()),
+        Some(String::from("other")),
+        "-",
+    )
+    .await;
+    match result {
+        Err(Error::String(e)) => assert!(e.ends_with("not other"), "{}", e),
+        r => panic!("Unexpected result {:?}", r),
+    }
+
+    fs::remove_file(&infile).unwrap();
+}
+
+#[tokio::test]
+async fn test_connect_to_committee() {
+    match connect_to_committee(&server_args(Vec::new())).await {
+        Err(Error::String(e)) => {
+            assert_eq!(e, "Either servers or a client config need to be specified")
+        }
+        _ => panic!("Connected without servers"),
+    }
+
+    // keys of the keystore are used without asking the servers, which are not running here
+    let shares = generate_shares(ThresholdScheme::Bls04);
+    let mut keystore = KeyStore::new();
+    let key_id = keystore
+        .insert_public_key(shares[0].get_public_key())
+        .unwrap();
+    let keystore_path = temp_path("keystore.json");
+    keystore.to_file(&keystore_path).unwrap();
+
+    let args = ServerArgs {
+        keystore: Some(keystore_path.clone()),
+        ..server_args(vec![String::from("http://127.0.0.1:1")])
+    };
+    let client = connect_to_committee(&args).await.unwrap();
+    let key = client.public_key(&key_id).await.unwrap();
+    assert_eq!(key.get_key_id(), key_id);
+
+    fs::remove_file(&keystore_path).unwrap();
+}
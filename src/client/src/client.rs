@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::future::join_all;
+use log::{debug, info, warn};
+use theta_proto::protocol_types::{
+    threshold_crypto_library_client::ThresholdCryptoLibraryClient, CoinRequest, DecryptRequest,
+    KeyRequest, ShareResponse, SignRequest, StatusRequest, StatusResponse,
+};
+use theta_proto::scheme_types::ThresholdOperation;
+use theta_schemes::interface::{
+    Ciphertext, CoinShare, DecryptionShare, Serializable, Signature, SignatureShare,
+    ThresholdCipher, ThresholdCipherParams, ThresholdCoin, ThresholdSignature,
+};
+use theta_schemes::keys::keys::PublicKey;
+use theta_schemes::stream::{self, StreamHeader};
+use tokio::sync::Mutex;
+use tokio::time::{sleep, timeout, Instant};
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Endpoint, Identity};
+use tonic::{Code, Status};
+use utils::client::types::ClientConfig;
+
+use crate::error::ClientError;
+use crate::quorum::{agreed_key, ResultCollector};
+
+/// Settings of a `ThetacryptClient`.
+#[derive(Debug, Clone)]
+pub struct ClientOptions {
+    /// Number of nodes that must accept a request. The request is sent to this many nodes, and to
+    /// further nodes in place of those that are down or reject it. If not set, the request is sent
+    /// to every node and one accepting node suffices.
+    pub quorum: Option<usize>,
+    /// Number of nodes that must return the same plaintext before it is accepted. Defaults to the
+    /// threshold of the key (t+1), so that one of the agreeing nodes is honest. Only applies to
+    /// plaintexts, the only results that cannot be verified locally.
+    pub min_agreement: Option<usize>,
+    /// Timeout of a single RPC to a node.
+    pub request_timeout: Duration,
+    /// Time to wait for the result of an instance.
+    pub result_timeout: Duration,
+    /// Interval in which the nodes are asked for the status of an instance.
+    pub poll_interval: Duration,
+    /// Number of times a node that is down (or overloaded) is asked again to accept a request.
+    pub retries: u32,
+    /// Wait time before the first retry, doubled for every further retry.
+    pub retry_backoff: Duration,
+    /// Collect the shares of the nodes and assemble the result locally instead of letting the
+    /// nodes run an instance. Every share is verified, so `quorum` and `min_agreement` do not
+    /// apply. Only supported for non-interactive schemes (SG02, BZ03, BLS04, CKS05, SH00). Coins
+    /// are always assembled from verified shares.
+    pub combine_shares: bool,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            quorum: None,
+            min_agreement: None,
+            request_timeout: Duration::from_secs(5),
+            result_timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_millis(100),
+            retries: 3,
+            retry_backoff: Duration::from_millis(500),
+            combine_shares: false,
+        }
+    }
+}
+
+struct Node {
+    id: u32,
+    address: String,
+    rpc: ThresholdCryptoLibraryClient<Channel>,
+}
+
+// A request to start an instance, sent to several nodes.
+enum OperationRequest {
+    Decrypt(DecryptRequest),
+    Sign(SignRequest),
+    Coin(CoinRequest),
+}
+
+/// Client of a thetacrypt committee. Requests are sent to several nodes concurrently, and the
+/// first result that verifies against the public key (signatures) or that enough nodes agree on
+/// (plaintexts) is returned. Coins are assembled from verified coin shares. Connections are
+/// established lazily, so nodes that are down when the client is created are used once they are
+/// back.
+///
+/// Public keys are fetched from the nodes and only used if as many nodes as the threshold of the
+/// key returned the same key, unless they were added with `add_public_key` (e.g. from a trusted
+/// keystore) before.
+pub struct ThetacryptClient {
+    nodes: Vec<Node>,
+    options: ClientOptions,
+    keys: Mutex<HashMap<String, PublicKey>>,
+}
+
+impl ThetacryptClient {
+    /// Creates a client for the nodes of `config`. Must be called within a Tokio runtime.
+    pub fn new(config: &ClientConfig, options: ClientOptions) -> Result<Self, ClientError> {
+        let mut nodes = Vec::new();
+        for peer in config.peers.iter() {
+            let (address, endpoint) = node_endpoint(config, &peer.ip, peer.rpc_port)?;
+            nodes.push((peer.id, address, endpoint));
+        }
+        Self::with_endpoints(nodes, options)
+    }
+
+    /// Creates a client for the nodes listed in the client config file at `path`.
+    pub fn from_file(path: &PathBuf, options: ClientOptions) -> Result<Self, ClientError> {
+        let config = ClientConfig::from_file(path).map_err(ClientError::Config)?;
+        Self::new(&config, options)
+    }
+
+    /// Creates a client for the nodes at the given RPC addresses (e.g. `http://127.0.0.1:51000`),
+    /// numbered from 1 in the given order.
+    pub fn from_addresses(addresses: &[String], options: ClientOptions) -> Result<Self, ClientError> {
+        let mut nodes = Vec::new();
+        for (i, address) in addresses.iter().enumerate() {
+            let endpoint = Endpoint::from_shared(address.clone()).map_err(|e| {
+                ClientError::Config(format!("Invalid node address {}: {}", address, e))
+            })?;
+            nodes.push((i as u32 + 1, address.clone(), endpoint));
+        }
+        Self::with_endpoints(nodes, options)
+    }
+
+    fn with_endpoints(
+        endpoints: Vec<(u32, String, Endpoint)>,
+        options: ClientOptions,
+    ) -> Result<Self, ClientError> {
+        if endpoints.is_empty() {
+            return Err(ClientError::Config(String::from("No nodes configured")));
+        }
+        if let Some(quorum) = options.quorum {
+            if quorum == 0 || quorum > endpoints.len() {
+                return Err(ClientError::Config(format!(
+                    "Quorum of {} with {} nodes",
+                    quorum,
+                    endpoints.len()
+                )));
+            }
+        }
+
+        let nodes = endpoints
+            .into_iter()
+            .map(|(id, address, endpoint)| Node {
+                id,
+                address,
+                rpc: ThresholdCryptoLibraryClient::new(
+                    endpoint.connect_timeout(options.request_timeout).connect_lazy(),
+                ),
+            })
+            .collect();
+
+        Ok(Self {
+            nodes,
+            options,
+            keys: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Adds a public key that is trusted without asking the nodes.
+    pub async fn add_public_key(&self, key: PublicKey) {
+        self.keys
+            .lock()
+            .await
+            .insert(key.get_key_id().to_string(), key);
+    }
+
+    /// Returns the public key with the given id. A key that was not added with `add_public_key` is
+    /// fetched from every node and only used if as many nodes as its threshold returned it.
+    pub async fn public_key(&self, key_id: &str) -> Result<PublicKey, ClientError> {
+        if let Some(key) = self.keys.lock().await.get(key_id) {
+            return Ok(key.clone());
+        }
+
+        let responses = join_all(self.nodes.iter().map(|node| {
+            let mut rpc = node.rpc.clone();
+            timeout(self.options.request_timeout, async move {
+                rpc.get_public_keys(KeyRequest {}).await
+            })
+        }))
+        .await;
+
+        let mut encodings = Vec::new();
+        for (node, response) in self.nodes.iter().zip(responses) {
+            match response {
+                Ok(Ok(response)) => {
+                    let keys = response.into_inner().keys;
+                    if let Some(entry) = keys.into_iter().find(|e| e.id == key_id) {
+                        encodings.push(entry.key);
+                    }
+                }
+                Ok(Err(e)) => warn!("Error fetching public keys from node {}: {}", node.id, e.message()),
+                Err(_) => warn!("Node {} did not respond in time", node.id),
+            }
+        }
+        if encodings.is_empty() {
+            return Err(ClientError::Key(format!(
+                "Key {} is not known to any reachable node",
+                key_id
+            )));
+        }
+
+        // only keys that enough nodes agree on are cached
+        let key = agreed_key(key_id, &encodings)?;
+        self.keys
+            .lock()
+            .await
+            .insert(key_id.to_string(), key.clone());
+        Ok(key)
+    }
+
+    /// Encrypts `message` locally with the key `key_id`.
+    pub async fn encrypt(
+        &self,
+        key_id: &str,
+        message: &[u8],
+        label: &[u8],
+    ) -> Result<Ciphertext, ClientError> {
+        let key = self.key_for(key_id, ThresholdOperation::Encryption).await?;
+        let mut params = ThresholdCipherParams::new();
+        Ok(ThresholdCipher::encrypt(message, label, &key, &mut params)?)
+    }
+
+    /// Decrypts `ciphertext` with the committee holding its key.
+    pub async fn decrypt(&self, ciphertext: &Ciphertext) -> Result<Vec<u8>, ClientError> {
+        let request = OperationRequest::Decrypt(DecryptRequest {
+            ciphertext: ciphertext.to_bytes()?,
+            key_id: Some(ciphertext.get_key_id().to_string()),
+        });
+
+        let key = self
+            .key_for(ciphertext.get_key_id(), ThresholdOperation::Encryption)
+            .await?;
+        if self.options.combine_shares {
+            let shares = self
+                .collect_shares(&request, key.get_threshold() as usize, |bytes| {
+                    let share = DecryptionShare::from_bytes(&bytes.to_vec()).ok()?;
+                    ThresholdCipher::verify_share(&share, ciphertext, &key)
+                        .unwrap_or(false)
+                        .then(|| (share.get_id(), share))
+                })
+                .await?;
+            return Ok(ThresholdCipher::assemble(&shares, ciphertext)?);
+        }
+
+        let min_agreement = self
+            .options
+            .min_agreement
+            .unwrap_or(key.get_threshold() as usize);
+        self.run(request, min_agreement, |_| true).await
+    }
+
+    /// Encrypts everything `reader` returns into `writer` in the streaming format of
+    /// `theta_schemes::stream`, for data too large to be held in memory. Returns the number of
+    /// plaintext bytes.
+    pub async fn encrypt_stream<R: Read, W: Write>(
+        &self,
+        key_id: &str,
+        label: &[u8],
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<u64, ClientError> {
+        let key = self.key_for(key_id, ThresholdOperation::Encryption).await?;
+        let mut params = ThresholdCipherParams::new();
+        Ok(stream::encrypt_stream(reader, writer, label, &key, &mut params)?)
+    }
+
+    /// Decrypts a stream written by `encrypt_stream`. Only the data key in the header is decrypted
+    /// by the committee, the segments are decrypted locally. Returns the number of plaintext bytes.
+    pub async fn decrypt_stream<R: Read, W: Write>(
+        &self,
+        reader: &mut R,
+        writer: &mut W,
+    ) -> Result<u64, ClientError> {
+        let header = StreamHeader::read_from(reader)?;
+        let data_key = self.decrypt(header.get_ciphertext()).await?;
+        Ok(stream::decrypt_stream(&header, reader, writer, &data_key)?)
+    }
+
+    /// Signs `message` with the key `key_id`. The signature is verified before it is returned.
+    pub async fn sign(
+        &self,
+        key_id: &str,
+        message: &[u8],
+        label: &[u8],
+    ) -> Result<Signature, ClientError> {
+        let key = self.key_for(key_id, ThresholdOperation::Signature).await?;
+        let request = OperationRequest::Sign(SignRequest {
+            message: message.to_vec(),
+            label: label.to_vec(),
+            key_id: Some(key_id.to_string()),
+            scheme: key.get_scheme() as i32,
+            group: *key.get_group() as i32,
+        });
+
+        let verify = |bytes: &[u8]| match Signature::from_bytes(&bytes.to_vec()) {
+            Ok(signature) => ThresholdSignature::verify(&signature, &key, message).unwrap_or(false),
+            Err(_) => false,
+        };
+        if self.options.combine_shares {
+            let shares = self
+                .collect_shares(&request, key.get_threshold() as usize, |bytes| {
+                    let share = SignatureShare::from_bytes(&bytes.to_vec()).ok()?;
+                    ThresholdSignature::verify_share(&share, message, &key)
+                        .unwrap_or(false)
+                        .then(|| (share.get_id(), share))
+                })
+                .await?;
+            let signature = ThresholdSignature::assemble(&shares, message, &key)?;
+            if !ThresholdSignature::verify(&signature, &key, message)? {
+                return Err(ClientError::Failed(String::from(
+                    "The assembled signature is invalid",
+                )));
+            }
+            return Ok(signature);
+        }
+
+        // a verified signature needs no agreement
+        let signature = self.run(request, 1, verify).await?;
+        Ok(Signature::from_bytes(&signature)?)
+    }
+
+    /// Flips the coin `name` with the key `key_id`. A coin value cannot be verified on its own, so
+    /// the value is assembled from the coin shares of the nodes, each verified with the public key.
+    pub async fn flip_coin(&self, key_id: &str, name: &[u8]) -> Result<Vec<u8>, ClientError> {
+        let key = self.key_for(key_id, ThresholdOperation::Coin).await?;
+        let request = OperationRequest::Coin(CoinRequest {
+            name: name.to_vec(),
+            key_id: Some(key_id.to_string()),
+            scheme: key.get_scheme() as i32,
+            group: *key.get_group() as i32,
+        });
+
+        let shares = self
+            .collect_shares(&request, key.get_threshold() as usize, |bytes| {
+                let share = CoinShare::from_bytes(&bytes.to_vec()).ok()?;
+                ThresholdCoin::verify_share(&share, name, &key)
+                    .unwrap_or(false)
+                    .then(|| (share.get_id(), share))
+            })
+            .await?;
+        Ok(vec![ThresholdCoin::assemble(&shares)?])
+    }
+
+    async fn key_for(
+        &self,
+        key_id: &str,
+        operation: ThresholdOperation,
+    ) -> Result<PublicKey, ClientError> {
+        let key = self.public_key(key_id).await?;
+        if key.get_operation() != operation {
+            return Err(ClientError::Key(format!(
+                "Key {} cannot be used for {}",
+                key_id,
+                operation.as_str_name()
+            )));
+        }
+        Ok(key)
+    }
+
+    async fn run<V: Fn(&[u8]) -> bool>(
+        &self,
+        request: OperationRequest,
+        min_agreement: usize,
+        verify: V,
+    ) -> Result<Vec<u8>, ClientError> {
+        let (instance_id, accepted) = self.submit(&request).await?;
+        info!(
+            "Instance {} was started by {} nodes",
+            instance_id,
+            accepted.len()
+        );
+        self.await_result(&instance_id, &accepted, min_agreement, verify)
+            .await
+    }
+
+    // Sends the request until enough nodes accepted it. Returns the instance id and the indices of
+    // the accepting nodes.
+    async fn submit(&self, request: &OperationRequest) -> Result<(String, Vec<usize>), ClientError> {
+        let (target, required) = match self.options.quorum {
+            Some(quorum) => (quorum, quorum),
+            None => (self.nodes.len(), 1),
+        };
+
+        let mut instance_id: Option<String> = None;
+        let mut accepted = Vec::new();
+        let mut untried: Vec<usize> = (0..self.nodes.len()).collect();
+        let mut unavailable = Vec::new();
+        let mut retries = 0;
+        let mut backoff = self.options.retry_backoff;
+
+        while accepted.len() < target {
+            let missing = (target - accepted.len()).min(untried.len());
+            let batch: Vec<usize> = untried.drain(..missing).collect();
+
+            if batch.is_empty() {
+                if unavailable.is_empty() || retries >= self.options.retries {
+                    break;
+                }
+                retries += 1;
+                debug!("Retrying {} nodes in {:?}", unavailable.len(), backoff);
+                sleep(backoff).await;
+                backoff *= 2;
+                untried = std::mem::take(&mut unavailable);
+                continue;
+            }
+
+            let results = join_all(batch.iter().map(|&i| self.submit_to(i, request))).await;
+            for (i, result) in batch.into_iter().zip(results) {
+                let node = &self.nodes[i];
+                match result {
+                    Ok(id) => {
+                        match &instance_id {
+                            Some(existing) if *existing != id => warn!(
+                                "Node {} started instance {} instead of {}",
+                                node.id, id, existing
+                            ),
+                            Some(_) => {}
+                            None => instance_id = Some(id),
+                        }
+                        accepted.push(i);
+                    }
+                    Err(e) if is_transient(&e) => {
+                        debug!("Node {} ({}) is unavailable: {}", node.id, node.address, e.message());
+                        unavailable.push(i);
+                    }
+                    Err(e) => warn!("Node {} rejected the request: {}", node.id, e.message()),
+                }
+            }
+        }
+
+        match instance_id {
+            Some(id) if accepted.len() >= required => Ok((id, accepted)),
+            _ => Err(ClientError::NoQuorum(format!(
+                "{} of the required {} nodes accepted the request",
+                accepted.len(),
+                required
+            ))),
+        }
+    }
+
+    async fn submit_to(&self, node: usize, request: &OperationRequest) -> Result<String, Status> {
+        let mut rpc = self.nodes[node].rpc.clone();
+        let call = async move {
+            match request {
+                OperationRequest::Decrypt(r) => rpc.decrypt(r.clone()).await.map(|r| r.into_inner().instance_id),
+                OperationRequest::Sign(r) => rpc.sign(r.clone()).await.map(|r| r.into_inner().instance_id),
+                OperationRequest::Coin(r) => rpc.flip_coin(r.clone()).await.map(|r| r.into_inner().instance_id),
+            }
+        };
+        match timeout(self.options.request_timeout, call).await {
+            Ok(result) => result,
+            Err(_) => Err(Status::deadline_exceeded("The node did not respond in time")),
+        }
+    }
+
+    // Asks every node for its share until `threshold` valid shares of distinct nodes are collected.
+    // `parse` returns the id and the share if the share is valid. Nodes that are down are asked again, like in
+    // `submit`.
+    async fn collect_shares<S, P: Fn(&[u8]) -> Option<(u16, S)>>(
+        &self,
+        request: &OperationRequest,
+        threshold: usize,
+        parse: P,
+    ) -> Result<Vec<S>, ClientError> {
+        let mut shares: HashMap<u16, S> = HashMap::new();
+        let mut pending: Vec<usize> = (0..self.nodes.len()).collect();
+        let mut retries = 0;
+        let mut backoff = self.options.retry_backoff;
+
+        loop {
+            let results = join_all(pending.iter().map(|&i| self.share_of(i, request))).await;
+            let mut unavailable = Vec::new();
+            for (i, result) in pending.into_iter().zip(results) {
+                let node = &self.nodes[i];
+                match result {
+                    Ok(response) => match parse(&response.share) {
+                        // shares are counted by their id, a node cannot contribute twice
+                        Some((id, share)) => {
+                            shares.entry(id).or_insert(share);
+                        }
+                        None => warn!("Node {} returned an invalid share", node.id),
+                    },
+                    Err(e) if is_transient(&e) => {
+                        debug!("Node {} ({}) is unavailable: {}", node.id, node.address, e.message());
+                        unavailable.push(i);
+                    }
+                    Err(e) => warn!("Node {} returned no share: {}", node.id, e.message()),
+                }
+            }
+
+            if shares.len() >= threshold {
+                info!("Collected {} shares", shares.len());
+                return Ok(shares.into_values().take(threshold).collect());
+            }
+            if shares.len() + unavailable.len() < threshold || retries >= self.options.retries {
+                return Err(ClientError::NoQuorum(format!(
+                    "{} of the required {} nodes returned a valid share",
+                    shares.len(),
+                    threshold
+                )));
+            }
+
+            retries += 1;
+            debug!("Retrying {} nodes in {:?}", unavailable.len(), backoff);
+            sleep(backoff).await;
+            backoff *= 2;
+            pending = unavailable;
+        }
+    }
+
+    async fn share_of(&self, node: usize, request: &OperationRequest) -> Result<ShareResponse, Status> {
+        let mut rpc = self.nodes[node].rpc.clone();
+        let call = async move {
+            match request {
+                OperationRequest::Decrypt(r) => rpc.get_decryption_share(r.clone()).await,
+                OperationRequest::Sign(r) => rpc.get_signature_share(r.clone()).await,
+                OperationRequest::Coin(r) => rpc.get_coin_share(r.clone()).await,
+            }
+        };
+        match timeout(self.options.request_timeout, call).await {
+            Ok(result) => result.map(|r| r.into_inner()),
+            Err(_) => Err(Status::deadline_exceeded("The node did not respond in time")),
+        }
+    }
+
+    async fn status_of(&self, node: usize, request: &StatusRequest) -> Result<StatusResponse, Status> {
+        let mut rpc = self.nodes[node].rpc.clone();
+        match timeout(self.options.request_timeout, rpc.get_status(request.clone())).await {
+            Ok(result) => result.map(|r| r.into_inner()),
+            Err(_) => Err(Status::deadline_exceeded("The node did not respond in time")),
+        }
+    }
+
+    // Polls the accepting nodes until a result is accepted, cannot be accepted anymore, or the
+    // result timeout expired.
+    async fn await_result<V: Fn(&[u8]) -> bool>(
+        &self,
+        instance_id: &str,
+        accepted: &[usize],
+        min_agreement: usize,
+        verify: V,
+    ) -> Result<Vec<u8>, ClientError> {
+        let mut collector = ResultCollector::new(min_agreement);
+        let deadline = Instant::now() + self.options.result_timeout;
+        let request = StatusRequest {
+            instance_id: instance_id.to_string(),
+        };
+
+        loop {
+            let pending: Vec<usize> = accepted
+                .iter()
+                .copied()
+                .filter(|&i| !collector.has_reported(self.nodes[i].id))
+                .collect();
+            let statuses = join_all(pending.iter().map(|&i| self.status_of(i, &request))).await;
+
+            for (i, status) in pending.into_iter().zip(statuses) {
+                let node = &self.nodes[i];
+                match status {
+                    Ok(status) if status.is_finished => match status.result {
+                        Some(result) if verify(&result) => {
+                            if let Some(result) = collector.add_result(node.id, result) {
+                                return Ok(result);
+                            }
+                        }
+                        Some(_) => {
+                            warn!("Node {} returned an invalid result for instance {}", node.id, instance_id);
+                            collector.add_failure(node.id);
+                        }
+                        None => {
+                            debug!(
+                                "Instance {} failed on node {}: {}",
+                                instance_id,
+                                node.id,
+                                status.error.unwrap_or_default()
+                            );
+                            collector.add_failure(node.id);
+                        }
+                    },
+                    Ok(_) => {}
+                    Err(e) => debug!(
+                        "Error getting the status of instance {} from node {}: {}",
+                        instance_id,
+                        node.id,
+                        e.message()
+                    ),
+                }
+            }
+
+            if collector.is_hopeless(accepted.len()) {
+                return Err(ClientError::Failed(format!(
+                    "No {} nodes returned the same valid result for instance {}",
+                    min_agreement, instance_id
+                )));
+            }
+
+            if Instant::now() >= deadline {
+                return Err(ClientError::Timeout(format!(
+                    "Instance {} did not finish within {:?}",
+                    instance_id, self.options.result_timeout
+                )));
+            }
+
+            sleep(self.options.poll_interval).await;
+        }
+    }
+}
+
+// Errors after which the node is asked again, as it may accept the request later.
+fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted | Code::Unknown
+    )
+}
+
+fn node_endpoint(config: &ClientConfig, ip: &str, port: u16) -> Result<(String, Endpoint), ClientError> {
+    match &config.tls {
+        Some(tls) => {
+            let address = format!("https://[{ip}]:{port}");
+            let read = |path: &PathBuf| {
+                fs::read(path).map_err(|e| {
+                    ClientError::Config(format!("Error reading {}: {}", path.display(), e))
+                })
+            };
+            let mut tls_config = ClientTlsConfig::new()
+                .ca_certificate(Certificate::from_pem(read(&tls.ca_file)?))
+                .domain_name(tls.domain_name.clone().unwrap_or(ip.to_string()));
+            if let (Some(cert_file), Some(key_file)) = (&tls.cert_file, &tls.key_file) {
+                tls_config = tls_config.identity(Identity::from_pem(read(cert_file)?, read(key_file)?));
+            }
+            let endpoint = Endpoint::from_shared(address.clone())
+                .and_then(|endpoint| endpoint.tls_config(tls_config))
+                .map_err(|e| ClientError::Config(format!("Invalid node address {}: {}", address, e)))?;
+            Ok((address, endpoint))
+        }
+        None => {
+            let address = format!("http://[{ip}]:{port}");
+            let endpoint = Endpoint::from_shared(address.clone())
+                .map_err(|e| ClientError::Config(format!("Invalid node address {}: {}", address, e)))?;
+            Ok((address, endpoint))
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use std::error::Error;
+use std::fmt;
+
+use theta_schemes::interface::SchemeError;
+
+/// Errors returned by `ThetacryptClient`.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The committee configuration is invalid.
+    Config(String),
+    /// The key is not known to any reachable node, or cannot be used for the operation.
+    Key(String),
+    /// Fewer nodes than required accepted the request.
+    NoQuorum(String),
+    /// Every node that ran the instance finished without a (valid) result.
+    Failed(String),
+    /// No result was available in time.
+    Timeout(String),
+    SchemeError(SchemeError),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Config(e) => write!(f, "Invalid configuration: {}", e),
+            ClientError::Key(e) => write!(f, "Key error: {}", e),
+            ClientError::NoQuorum(e) => write!(f, "No quorum: {}", e),
+            ClientError::Failed(e) => write!(f, "Operation failed: {}", e),
+            ClientError::Timeout(e) => write!(f, "Timeout: {}", e),
+            ClientError::SchemeError(e) => write!(f, "Scheme error: {}", e),
+        }
+    }
+}
+
+impl Error for ClientError {}
+
+impl From<SchemeError> for ClientError {
+    fn from(e: SchemeError) -> Self {
+        ClientError::SchemeError(e)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod client;
+pub mod error;
+pub mod quorum;
+#[cfg(test)]
+mod quorum_tests;
@@ -0,0 +1,98 @@
+use std::collections::{HashMap, HashSet};
+
+use theta_schemes::interface::Serializable;
+use theta_schemes::keys::keys::{key2id, PublicKey};
+
+use crate::error::ClientError;
+
+/// Collects the results that the nodes of a committee report for one instance. A result is
+/// accepted once `min_agreement` different nodes reported the same value. Every node is counted
+/// once, later reports of the same node are ignored.
+pub struct ResultCollector {
+    min_agreement: usize,
+    votes: HashMap<Vec<u8>, HashSet<u32>>,
+    reported: HashSet<u32>,
+    failed: HashSet<u32>,
+}
+
+impl ResultCollector {
+    pub fn new(min_agreement: usize) -> Self {
+        Self {
+            min_agreement: min_agreement.max(1),
+            votes: HashMap::new(),
+            reported: HashSet::new(),
+            failed: HashSet::new(),
+        }
+    }
+
+    /// Records the result of `node`. Returns the accepted result, if there is one now.
+    pub fn add_result(&mut self, node: u32, result: Vec<u8>) -> Option<Vec<u8>> {
+        if !self.reported.insert(node) {
+            return self.accepted();
+        }
+        self.votes.entry(result).or_default().insert(node);
+        self.accepted()
+    }
+
+    /// Records that `node` finished without a (valid) result.
+    pub fn add_failure(&mut self, node: u32) {
+        if self.reported.insert(node) {
+            self.failed.insert(node);
+        }
+    }
+
+    /// Whether `node` already reported a result or a failure.
+    pub fn has_reported(&self, node: u32) -> bool {
+        self.reported.contains(&node)
+    }
+
+    /// Returns true if no result can be accepted anymore, given that at most `nodes` nodes report.
+    pub fn is_hopeless(&self, nodes: usize) -> bool {
+        let missing = nodes.saturating_sub(self.reported.len());
+        let best = self.votes.values().map(|v| v.len()).max().unwrap_or(0);
+        best + missing < self.min_agreement
+    }
+
+    pub fn accepted(&self) -> Option<Vec<u8>> {
+        self.votes
+            .iter()
+            .find(|(_, nodes)| nodes.len() >= self.min_agreement)
+            .map(|(result, _)| result.clone())
+    }
+}
+
+/// Returns the public key `key_id` that the nodes agree on, given the encodings of the key that the
+/// nodes returned, one per node. A key is only accepted if its id matches its content and if at least
+/// as many nodes as its threshold (t+1) returned it, so that one of them is honest.
+pub fn agreed_key(key_id: &str, encodings: &[Vec<u8>]) -> Result<PublicKey, ClientError> {
+    let mut votes: HashMap<&Vec<u8>, usize> = HashMap::new();
+    for encoding in encodings.iter() {
+        *votes.entry(encoding).or_default() += 1;
+    }
+
+    let mut agreed = Vec::new();
+    for (encoding, count) in votes {
+        let key = match PublicKey::from_bytes(encoding) {
+            Ok(key) => key,
+            Err(_) => continue,
+        };
+        if key.get_key_id() == key_id
+            && key2id(&key) == key_id
+            && count >= key.get_threshold() as usize
+        {
+            agreed.push(key);
+        }
+    }
+
+    match agreed.len() {
+        1 => Ok(agreed.remove(0)),
+        0 => Err(ClientError::Key(format!(
+            "Fewer nodes than the threshold returned the same key {}",
+            key_id
+        ))),
+        _ => Err(ClientError::Key(format!(
+            "The nodes returned conflicting keys {}",
+            key_id
+        ))),
+    }
+}
@@ -0,0 +1,72 @@
+use theta_schemes::interface::{Group, Serializable, ThresholdScheme};
+use theta_schemes::keys::key_generator::KeyGenerator;
+use theta_schemes::keys::keys::PublicKey;
+use theta_schemes::rand::{RngAlgorithm, RNG};
+
+use crate::error::ClientError;
+use crate::quorum::{agreed_key, ResultCollector};
+
+fn public_key(threshold: u16) -> PublicKey {
+    let keys = KeyGenerator::generate_keys(
+        threshold as usize,
+        4,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &ThresholdScheme::Sg02,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap();
+    keys[0].get_public_key()
+}
+
+#[test]
+fn test_single_result_is_accepted() {
+    let mut collector = ResultCollector::new(1);
+    assert_eq!(collector.add_result(1, vec![1, 2, 3]), Some(vec![1, 2, 3]));
+}
+
+#[test]
+fn test_agreement_of_distinct_nodes() {
+    let mut collector = ResultCollector::new(2);
+    assert_eq!(collector.add_result(1, vec![1]), None);
+    // a node is only counted once
+    assert_eq!(collector.add_result(1, vec![1]), None);
+    assert_eq!(collector.add_result(2, vec![2]), None);
+    assert_eq!(collector.add_result(3, vec![1]), Some(vec![1]));
+}
+
+#[test]
+fn test_hopeless_when_results_disagree() {
+    let mut collector = ResultCollector::new(2);
+    collector.add_result(1, vec![1]);
+    collector.add_failure(2);
+    assert!(!collector.is_hopeless(3));
+    assert!(collector.has_reported(2));
+
+    collector.add_result(3, vec![3]);
+    assert!(collector.is_hopeless(3));
+    assert_eq!(collector.accepted(), None);
+}
+
+#[test]
+fn test_key_needs_threshold_nodes() {
+    let key = public_key(2);
+    let encoding = key.to_bytes().unwrap();
+
+    assert!(matches!(
+        agreed_key(key.get_key_id(), &[encoding.clone()]),
+        Err(ClientError::Key(_))
+    ));
+    let agreed = agreed_key(key.get_key_id(), &[encoding.clone(), encoding]).unwrap();
+    assert_eq!(agreed, key);
+}
+
+#[test]
+fn test_key_of_other_id_is_rejected() {
+    let key = public_key(2);
+    // a node cannot pass off a key of threshold 1 as the requested key
+    let forged = public_key(1).to_bytes().unwrap();
+    let encodings = vec![forged.clone(), forged, vec![1, 2, 3]];
+
+    assert!(agreed_key(key.get_key_id(), &encodings).is_err());
+}
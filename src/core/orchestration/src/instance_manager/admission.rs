@@ -0,0 +1,286 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use theta_protocols::interface::ProtocolError;
+
+use crate::instance_manager::instance_manager::StartInstanceRequest;
+use crate::instance_manager::share::NodeShare;
+
+/// Time after which a running instance is aborted and its slot freed, unless configured otherwise.
+pub const DEFAULT_INSTANCE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Limits applied by the instance manager before it starts a new instance. A limit set to None
+/// is not enforced.
+#[derive(Debug, Clone)]
+pub struct AdmissionLimits {
+    /// Maximum number of instances running at the same time.
+    pub max_running_instances: Option<usize>,
+    /// Maximum number of running instances requested by the same client.
+    pub max_running_per_client: Option<usize>,
+    /// Maximum number of running instances using the same key.
+    pub max_running_per_key: Option<usize>,
+    /// Maximum number of requests waiting for admission. Requests arriving while the queue is full
+    /// are rejected.
+    pub max_queued_requests: usize,
+    /// Maximum time an instance or share computation may hold its slot. Instances running longer
+    /// are aborted, so instances waiting for shares that never arrive do not occupy slots forever.
+    pub instance_timeout: Option<Duration>,
+}
+
+impl Default for AdmissionLimits {
+    fn default() -> Self {
+        Self {
+            max_running_instances: None,
+            max_running_per_client: None,
+            max_running_per_key: None,
+            max_queued_requests: 0,
+            instance_timeout: Some(DEFAULT_INSTANCE_TIMEOUT),
+        }
+    }
+}
+
+/// Snapshot of the admission state, exposed to operators.
+#[derive(Debug, Clone, Default)]
+pub struct AdmissionMetrics {
+    pub running_instances: usize,
+    pub queued_requests: usize,
+    pub admitted_requests: u64,
+    pub rejected_requests: u64,
+    pub max_running_instances: Option<usize>,
+    pub max_queued_requests: usize,
+}
+
+/// The requester of a pending request, which is either waiting for a new instance or for the
+/// share of this node.
+pub enum PendingResponder {
+    Instance(tokio::sync::oneshot::Sender<Result<String, ProtocolError>>),
+    Share(tokio::sync::oneshot::Sender<Result<NodeShare, ProtocolError>>),
+}
+
+impl PendingResponder {
+    /// Whether the requester stopped waiting, e.g., because the client went away.
+    pub fn is_closed(&self) -> bool {
+        match self {
+            PendingResponder::Instance(responder) => responder.is_closed(),
+            PendingResponder::Share(responder) => responder.is_closed(),
+        }
+    }
+
+    /// Answers the requester with an error instead of admitting the request.
+    pub fn reject(self, error: ProtocolError) {
+        match self {
+            PendingResponder::Instance(responder) => {
+                let _ = responder.send(Err(error));
+            }
+            PendingResponder::Share(responder) => {
+                let _ = responder.send(Err(error));
+            }
+        }
+    }
+}
+
+/// A request that could not be admitted immediately. The requester keeps waiting on `responder`
+/// until the request gets admitted, which provides backpressure to the RPC layer.
+pub struct PendingRequest {
+    pub request: StartInstanceRequest,
+    pub client_id: Option<String>,
+    pub responder: PendingResponder,
+    /// Id of the key the request uses, see `requested_key_id`.
+    pub key: String,
+    enqueued_at: Instant,
+}
+
+impl PendingRequest {
+    pub fn new(
+        request: StartInstanceRequest,
+        client_id: Option<String>,
+        key: String,
+        responder: PendingResponder,
+    ) -> Self {
+        Self {
+            request,
+            client_id,
+            responder,
+            key,
+            enqueued_at: Instant::now(),
+        }
+    }
+
+    /// Time the request spent waiting for admission so far.
+    pub fn waiting_time_ms(&self) -> u128 {
+        self.enqueued_at.elapsed().as_millis()
+    }
+}
+
+struct RunningSlot {
+    client_id: Option<String>,
+    key: String,
+    admitted_at: Instant,
+}
+
+/// AdmissionController keeps track of the running instances and the queue of waiting requests.
+/// Shares computed for clients that combine them locally occupy a slot as well, until the share
+/// has been computed.
+pub struct AdmissionController {
+    limits: AdmissionLimits,
+    running: HashMap<String, RunningSlot>,
+    running_per_client: HashMap<String, usize>,
+    running_per_key: HashMap<String, usize>,
+    queue: VecDeque<PendingRequest>,
+    admitted_requests: u64,
+    rejected_requests: u64,
+}
+
+impl AdmissionController {
+    pub fn new(limits: AdmissionLimits) -> Self {
+        Self {
+            limits,
+            running: HashMap::new(),
+            running_per_client: HashMap::new(),
+            running_per_key: HashMap::new(),
+            queue: VecDeque::new(),
+            admitted_requests: 0,
+            rejected_requests: 0,
+        }
+    }
+
+    /// Returns true if a new instance for the given client and key fits within all limits.
+    pub fn can_admit(&self, client_id: &Option<String>, key: &str) -> bool {
+        if let Some(max) = self.limits.max_running_instances {
+            if self.running.len() >= max {
+                return false;
+            }
+        }
+
+        if let (Some(max), Some(client)) = (self.limits.max_running_per_client, client_id) {
+            if self.running_per_client.get(client).copied().unwrap_or(0) >= max {
+                return false;
+            }
+        }
+
+        if let Some(max) = self.limits.max_running_per_key {
+            if self.running_per_key.get(key).copied().unwrap_or(0) >= max {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Accounts for a newly started instance.
+    pub fn admit(&mut self, instance_id: &str, client_id: Option<String>, request_key: String) {
+        if self.running.contains_key(instance_id) {
+            return;
+        }
+
+        if let Some(client) = &client_id {
+            *self.running_per_client.entry(client.clone()).or_insert(0) += 1;
+        }
+        *self.running_per_key.entry(request_key.clone()).or_insert(0) += 1;
+
+        self.running.insert(
+            instance_id.to_string(),
+            RunningSlot {
+                client_id,
+                key: request_key,
+                admitted_at: Instant::now(),
+            },
+        );
+        self.admitted_requests += 1;
+    }
+
+    /// Frees the slot of a terminated instance. Returns false if the instance was not accounted for.
+    pub fn release(&mut self, instance_id: &str) -> bool {
+        let slot = match self.running.remove(instance_id) {
+            Some(slot) => slot,
+            None => return false,
+        };
+
+        if let Some(client) = &slot.client_id {
+            decrement(&mut self.running_per_client, client);
+        }
+        decrement(&mut self.running_per_key, &slot.key);
+
+        true
+    }
+
+    /// Returns the ids of the slots held for longer than the instance timeout, in case their
+    /// instance or share computation never reported back. The slots stay occupied until they are
+    /// released, so the caller can stop the instance first.
+    pub fn expired(&self, now: Instant) -> Vec<String> {
+        let timeout = match self.limits.instance_timeout {
+            Some(timeout) => timeout,
+            None => return Vec::new(),
+        };
+
+        self.running
+            .iter()
+            .filter(|(_, slot)| now.saturating_duration_since(slot.admitted_at) > timeout)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    pub fn instance_timeout(&self) -> Option<Duration> {
+        self.limits.instance_timeout
+    }
+
+    /// Adds a request to the queue. If the queue is full, the request is handed back.
+    pub fn enqueue(&mut self, pending: PendingRequest) -> Result<usize, PendingRequest> {
+        self.drop_abandoned();
+        if self.queue.len() >= self.limits.max_queued_requests {
+            self.rejected_requests += 1;
+            return Err(pending);
+        }
+        self.queue.push_back(pending);
+        Ok(self.queue.len())
+    }
+
+    /// Removes and returns the oldest queued request that fits within the limits, if any.
+    pub fn pop_admissible(&mut self) -> Option<PendingRequest> {
+        self.drop_abandoned();
+        let position = self
+            .queue
+            .iter()
+            .position(|p| self.can_admit(&p.client_id, &p.key))?;
+        self.queue.remove(position)
+    }
+
+    // Removes the queued requests whose requester stopped waiting, so they neither occupy the
+    // queue nor get started for nobody.
+    fn drop_abandoned(&mut self) {
+        self.queue.retain(|p| !p.responder.is_closed());
+    }
+
+    pub fn metrics(&self) -> AdmissionMetrics {
+        AdmissionMetrics {
+            running_instances: self.running.len(),
+            queued_requests: self.queue.len(),
+            admitted_requests: self.admitted_requests,
+            rejected_requests: self.rejected_requests,
+            max_running_instances: self.limits.max_running_instances,
+            max_queued_requests: self.limits.max_queued_requests,
+        }
+    }
+}
+
+/// Returns the key id given in a request, which identifies the key for accounting per-key limits.
+/// Requests without an explicit key id use the default key of their scheme and group, whose id
+/// the instance manager looks up before admitting them.
+pub fn requested_key_id(request: &StartInstanceRequest) -> Option<String> {
+    match request {
+        StartInstanceRequest::Decryption { ciphertext } => {
+            Some(ciphertext.get_key_id().to_string())
+        }
+        StartInstanceRequest::Signature { key_id, .. }
+        | StartInstanceRequest::Coin { key_id, .. } => key_id.clone(),
+    }
+}
+
+fn decrement(counters: &mut HashMap<String, usize>, key: &str) {
+    if let Some(count) = counters.get_mut(key) {
+        *count -= 1;
+        if *count == 0 {
+            counters.remove(key);
+        }
+    }
+}
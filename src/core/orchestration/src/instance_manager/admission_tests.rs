@@ -0,0 +1,319 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use theta_events::event::emitter::start_null_emitter;
+use theta_network::types::message::NetMessage;
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_protocols::interface::ProtocolError;
+use theta_schemes::{
+    dl_schemes::signatures::frost::FrostOptions,
+    keys::{key_generator::KeyGenerator, key_store::KeyStore},
+    rand::{RngAlgorithm, RNG},
+};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+use crate::instance_manager::admission::{
+    requested_key_id, AdmissionController, AdmissionLimits, PendingRequest, PendingResponder,
+};
+use crate::instance_manager::instance_manager::{
+    InstanceManager, InstanceManagerCommand, StartInstanceRequest,
+};
+use crate::key_manager::key_manager::KeyManager;
+use crate::key_manager::nonce_pool::{FrostNoncePool, NoncePoolSettings};
+use crate::signing_policy::signing_policy::SigningPolicy;
+
+fn limits() -> AdmissionLimits {
+    AdmissionLimits {
+        max_running_instances: Some(3),
+        max_running_per_client: Some(2),
+        max_running_per_key: Some(2),
+        max_queued_requests: 2,
+        instance_timeout: Some(Duration::from_secs(60)),
+    }
+}
+
+fn coin(name: &str, key_id: Option<&str>) -> StartInstanceRequest {
+    StartInstanceRequest::Coin {
+        name: name.as_bytes().to_vec(),
+        scheme: ThresholdScheme::Cks05,
+        group: Group::Bls12381,
+        key_id: key_id.map(String::from),
+    }
+}
+
+// A queued request together with the receiver its requester waits on.
+fn pending(
+    name: &str,
+    client: &str,
+    key: &str,
+) -> (
+    PendingRequest,
+    oneshot::Receiver<Result<String, ProtocolError>>,
+) {
+    let (responder, receiver) = oneshot::channel();
+    let pending = PendingRequest::new(
+        coin(name, Some(key)),
+        Some(String::from(client)),
+        String::from(key),
+        PendingResponder::Instance(responder),
+    );
+    (pending, receiver)
+}
+
+fn client(name: &str) -> Option<String> {
+    Some(String::from(name))
+}
+
+#[test]
+fn test_running_limits() {
+    let mut admission = AdmissionController::new(limits());
+
+    admission.admit("i1", client("a"), String::from("k1"));
+    admission.admit("i2", client("a"), String::from("k2"));
+    // client a reached its limit, other clients and anonymous requests are still admitted
+    assert!(!admission.can_admit(&client("a"), "k3"));
+    assert!(admission.can_admit(&client("b"), "k3"));
+    assert!(admission.can_admit(&None, "k3"));
+
+    admission.admit("i3", None, String::from("k1"));
+    // key k1 reached its limit
+    assert!(!admission.can_admit(&client("b"), "k1"));
+    // and the node its limit of running instances
+    assert!(!admission.can_admit(&client("b"), "k3"));
+    assert_eq!(admission.metrics().running_instances, 3);
+    assert_eq!(admission.metrics().admitted_requests, 3);
+}
+
+#[test]
+fn test_release_frees_slot() {
+    let mut admission = AdmissionController::new(limits());
+
+    admission.admit("i1", client("a"), String::from("k1"));
+    admission.admit("i2", client("a"), String::from("k1"));
+    assert!(!admission.can_admit(&client("b"), "k1"));
+    assert!(!admission.can_admit(&client("a"), "k2"));
+
+    assert!(admission.release("i1"));
+    assert!(admission.can_admit(&client("b"), "k1"));
+    assert!(admission.can_admit(&client("a"), "k2"));
+
+    // a slot is released only once
+    assert!(!admission.release("i1"));
+    assert_eq!(admission.metrics().running_instances, 1);
+}
+
+#[test]
+fn test_queue_admits_oldest_fitting_request() {
+    let mut admission = AdmissionController::new(limits());
+    admission.admit("i1", client("a"), String::from("k1"));
+    admission.admit("i2", client("b"), String::from("k1"));
+
+    let (q1, _r1) = pending("q1", "c", "k1");
+    let (q2, _r2) = pending("q2", "c", "k2");
+    let (q3, _r3) = pending("q3", "c", "k2");
+    assert!(admission.enqueue(q1).is_ok());
+    assert!(admission.enqueue(q2).is_ok());
+    assert!(admission.enqueue(q3).is_err());
+    assert_eq!(admission.metrics().queued_requests, 2);
+    assert_eq!(admission.metrics().rejected_requests, 1);
+
+    // q1 still waits for a slot of key k1, q2 fits
+    let next = admission.pop_admissible().unwrap();
+    assert_eq!(next.key, "k2");
+    assert!(admission.pop_admissible().is_none());
+
+    admission.release("i1");
+    let next = admission.pop_admissible().unwrap();
+    assert_eq!(next.key, "k1");
+    assert_eq!(admission.metrics().queued_requests, 0);
+}
+
+#[test]
+fn test_queue_drops_abandoned_requests() {
+    let mut admission = AdmissionController::new(limits());
+    admission.admit("i1", client("a"), String::from("k1"));
+    admission.admit("i2", client("b"), String::from("k1"));
+
+    // the requesters of q1 and q2 stop waiting, which frees their places in the queue
+    let (q1, r1) = pending("q1", "c", "k1");
+    let (q2, r2) = pending("q2", "c", "k2");
+    assert!(admission.enqueue(q1).is_ok());
+    assert!(admission.enqueue(q2).is_ok());
+    drop(r1);
+    drop(r2);
+    let (q3, _r3) = pending("q3", "d", "k1");
+    assert!(admission.enqueue(q3).is_ok());
+    assert_eq!(admission.metrics().queued_requests, 1);
+
+    // and they are never started
+    assert!(admission.pop_admissible().is_none());
+    admission.release("i1");
+    assert_eq!(admission.pop_admissible().unwrap().client_id, client("d"));
+    assert!(admission.pop_admissible().is_none());
+}
+
+#[test]
+fn test_expired_slots() {
+    let mut admission = AdmissionController::new(limits());
+    admission.admit("i1", client("a"), String::from("k1"));
+    admission.admit("i2", client("a"), String::from("k1"));
+
+    assert!(admission.expired(Instant::now()).is_empty());
+
+    let mut expired = admission.expired(Instant::now() + Duration::from_secs(61));
+    expired.sort();
+    assert_eq!(expired, vec![String::from("i1"), String::from("i2")]);
+    // the slots stay occupied until they are released
+    assert_eq!(admission.metrics().running_instances, 2);
+    for id in expired {
+        assert!(admission.release(&id));
+    }
+    assert!(admission.can_admit(&client("a"), "k1"));
+
+    // without a timeout, slots are only freed by their instance
+    let mut admission = AdmissionController::new(AdmissionLimits {
+        instance_timeout: None,
+        ..limits()
+    });
+    admission.admit("i1", client("a"), String::from("k1"));
+    assert!(admission
+        .expired(Instant::now() + Duration::from_secs(3600))
+        .is_empty());
+}
+
+#[test]
+fn test_requested_key_id() {
+    assert_eq!(
+        requested_key_id(&coin("c", Some("k1"))).as_deref(),
+        Some("k1")
+    );
+    assert_eq!(requested_key_id(&coin("c", None)), None);
+}
+
+// A node that holds share 1 of a CKS05 key, with the given admission limits. Its outgoing
+// messages are not delivered, so its instances never terminate.
+struct LonelyNode {
+    key_id: String,
+    instance_manager: InstanceManager,
+    commands: mpsc::Sender<InstanceManagerCommand>,
+    _outgoing: mpsc::Receiver<NetMessage>,
+    _incoming: mpsc::Sender<NetMessage>,
+}
+
+fn start_lonely_node(name: &str, limits: AdmissionLimits) -> LonelyNode {
+    let shares = KeyGenerator::generate_keys(
+        3,
+        5,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &ThresholdScheme::Cks05,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap();
+    let mut keystore = KeyStore::new();
+    let key_id = keystore
+        .insert_private_key(shares.into_iter().next().unwrap())
+        .unwrap();
+
+    let shutdown = Arc::new(Notify::new());
+    let keychain_path =
+        std::env::temp_dir().join(format!("thetacrypt-{}-{}.json", name, std::process::id()));
+    let (key_manager_sender, key_manager_receiver) = mpsc::channel(32);
+    let mut key_manager = KeyManager::from_keystore(
+        keystore,
+        keychain_path,
+        FrostNoncePool::new(NoncePoolSettings::default()),
+        key_manager_receiver,
+        None,
+    );
+    let shutdown_key_manager = shutdown.clone();
+    tokio::spawn(async move { key_manager.run(shutdown_key_manager).await });
+
+    let (emitter_sender, _) = start_null_emitter(shutdown);
+    let (outgoing_sender, outgoing_receiver) = mpsc::channel(32);
+    let (incoming_sender, incoming_receiver) = mpsc::channel(32);
+    let (instance_manager_sender, instance_manager_receiver) = mpsc::channel(32);
+    let instance_manager = InstanceManager::new(
+        key_manager_sender,
+        instance_manager_receiver,
+        instance_manager_sender.clone(),
+        outgoing_sender,
+        incoming_receiver,
+        emitter_sender,
+        SigningPolicy::new(),
+        limits,
+        Duration::from_secs(10),
+        FrostOptions::Robust,
+    );
+
+    LonelyNode {
+        key_id,
+        instance_manager,
+        commands: instance_manager_sender,
+        _outgoing: outgoing_receiver,
+        _incoming: incoming_sender,
+    }
+}
+
+#[tokio::test]
+async fn test_default_key_is_accounted_under_its_id() {
+    let node = start_lonely_node("admission-default-key", AdmissionLimits::default());
+
+    let key = node.instance_manager.admission_key(&coin("c", None)).await;
+    assert_eq!(key, node.key_id);
+
+    let explicit = coin("c", Some(&node.key_id));
+    assert_eq!(
+        node.instance_manager.admission_key(&explicit).await,
+        node.key_id
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_instance_timeout_frees_slot() {
+    let node = start_lonely_node(
+        "admission-timeout",
+        AdmissionLimits {
+            max_running_instances: Some(1),
+            max_queued_requests: 1,
+            instance_timeout: Some(Duration::from_secs(30)),
+            ..AdmissionLimits::default()
+        },
+    );
+    let commands = node.commands;
+    let mut instance_manager = node.instance_manager;
+    tokio::spawn(async move { instance_manager.run(Arc::new(Notify::new())).await });
+
+    let (responder, response) = oneshot::channel();
+    let create = InstanceManagerCommand::CreateInstance {
+        request: coin("first", None),
+        client_id: None,
+        responder,
+    };
+    commands.send(create).await.unwrap();
+    let instance_id = response.await.unwrap().unwrap();
+
+    // the second request waits for the slot of the first instance, which never terminates
+    let (responder, second) = oneshot::channel();
+    let create = InstanceManagerCommand::CreateInstance {
+        request: coin("second", None),
+        client_id: None,
+        responder,
+    };
+    commands.send(create).await.unwrap();
+
+    let second = tokio::time::timeout(Duration::from_secs(60), second).await;
+    assert!(second.unwrap().unwrap().is_ok());
+
+    let (responder, status) = oneshot::channel();
+    commands
+        .send(InstanceManagerCommand::GetInstanceStatus {
+            instance_id,
+            responder,
+        })
+        .await
+        .unwrap();
+    let status = status.await.unwrap().unwrap();
+    assert!(status.finished);
+    assert!(matches!(status.result, Some(Err(ProtocolError::Timeout))));
+}
@@ -1,7 +1,7 @@
 use core::fmt;
 use clap::error;
 use theta_proto::scheme_types::{Group, ThresholdScheme};
-use theta_protocols::interface::ProtocolError;
+use theta_protocols::interface::{Misbehavior, ProtocolError};
 use tokio::sync::mpsc::error::SendError;
 use theta_network::types::message::NetMessage;
 use log::error;
@@ -10,10 +10,12 @@ pub struct Instance {
     id: String,
     scheme: ThresholdScheme,
     group: Group,
+    key_id: String,
     message_channel_sender: Option<tokio::sync::mpsc::Sender<NetMessage>>,
     status: String,
     finished: bool,
     result: Option<Result<Vec<u8>, ProtocolError>>,
+    blame: Vec<Misbehavior>,
 }
 
 impl fmt::Display for Instance {
@@ -34,16 +36,19 @@ impl Instance {
         id: String,
         scheme: ThresholdScheme,
         group: Group,
+        key_id: String,
         message_channel_sender: Option<tokio::sync::mpsc::Sender<NetMessage>>,
     ) -> Self {
         return Self {
             id,
             scheme,
             group,
+            key_id,
             message_channel_sender,
             status: String::from("created"),
             finished: false,
             result: Option::None,
+            blame: Vec::new(),
         };
     }
 
@@ -67,6 +72,14 @@ impl Instance {
         }
     }
 
+    pub fn add_blame(&mut self, misbehavior: &[Misbehavior]) {
+        self.blame.extend(misbehavior.iter().cloned());
+    }
+
+    pub fn get_blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
+
     pub fn get_scheme(&self) -> ThresholdScheme {
         self.scheme.clone()
     }
@@ -75,6 +88,10 @@ impl Instance {
         self.group.clone()
     }
 
+    pub fn get_key_id(&self) -> &str {
+        &self.key_id
+    }
+
     pub async fn send_message(&self, message: NetMessage) -> Result<(), SendError<NetMessage>> {
         if let Some(sender) = &self.message_channel_sender {
             return sender.send(message).await
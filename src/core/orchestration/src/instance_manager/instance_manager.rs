@@ -7,24 +7,33 @@ use log::{debug, error, info, warn};
 use mcore::hash256::HASH256;
 use reqwest::header::CACHE_CONTROL;
 use theta_events::event::Event;
-use theta_network::types::message::NetMessage;
-use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_network::types::message::{committee_topic, NetMessage};
+use theta_proto::scheme_types::{Group, KeyStatus, ThresholdOperation, ThresholdScheme};
 use theta_protocols::{
-    frost::protocol::FrostProtocol, interface::{ProtocolError, ThresholdRoundProtocol}, threshold_cipher::protocol::ThresholdCipherProtocol, threshold_coin::protocol::ThresholdCoinProtocol, threshold_signature::protocol::ThresholdSignatureProtocol
+    frost::{protocol::FrostProtocol, robust::{RobustFrostPrecomputation, RobustFrostProtocol}}, interface::{AuthenticatedMessage, Misbehavior, ProtocolError, ThresholdRoundProtocol}, threshold_cipher::protocol::ThresholdCipherProtocol, threshold_coin::protocol::ThresholdCoinProtocol, threshold_signature::protocol::ThresholdSignatureProtocol
     // threshold_coin::protocol::ThresholdCoinProtocol,
     // threshold_signature::protocol::ThresholdSignatureProtocol,
 };
 use theta_schemes::{
-    dl_schemes::signatures::frost::FrostOptions, interface::{Ciphertext, SchemeError}, keys::{key_store::KeyEntry, keys::PrivateKeyShare}
+    dl_schemes::signatures::frost::FrostOptions, interface::{Ciphertext, SchemeError}, keys::{key_store::KeyEntry, keys::PrivateKeyShare},
+    scheme_types_impl::SchemeDetails,
 };
 use tokio::sync::{oneshot, Notify};
 use tonic::{Code, Status};
 
 use crate::{
     instance_manager::instance::{self, Instance},
+    instance_manager::admission::{
+        requested_key_id, AdmissionController, AdmissionLimits, AdmissionMetrics, PendingRequest,
+        PendingResponder,
+    },
     instance_manager::protocol_executor::ThresholdProtocolExecutor,
+    instance_manager::reputation::{PeerReputation, ReputationTable},
+    instance_manager::share::{self, NodeShare},
     interface::ThresholdProtocol,
     key_manager::key_manager::KeyManagerCommand,
+    key_manager::nonce_pool::{FrostCommitmentBatch, FROST_POOL_INSTANCE_ID},
+    signing_policy::signing_policy::SigningPolicy,
 };
 /// Upper bound on the number of finished instances which to store.
 const DEFAULT_INSTANCE_CACHE_SIZE: usize = 10000;
@@ -36,8 +45,8 @@ const INSTANCE_CACHE_CLEANUP_SCAN_LENGTH: usize = 5000;
 /// It is configured with an upper bound on the number of finished instances it will store. If a
 /// new instance is added while at capacity, the oldest terminated instance is ejected.
 ///
-/// Due to only evicting stored instances, there is no actual upper bound on its size. There could
-/// well be an unbounded number of running instances.
+/// Due to only evicting stored instances, there is no actual upper bound on its size. The number of
+/// running instances is bounded by the AdmissionController of the InstanceManager instead.
 struct InstanceCache {
     instance_data: HashMap<String, Instance>,
     capacity: usize,
@@ -134,10 +143,24 @@ pub struct InstanceManager {
     instances: InstanceCache,
     backlog: HashMap<String, BacklogData>,
     backlog_interval: tokio::time::Interval,
+    nonce_pool_interval: tokio::time::Interval,
+    admission_interval: tokio::time::Interval,
     event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+    reputation: ReputationTable,
+    signing_policy: SigningPolicy,
+    admission: AdmissionController,
+    // Selects the FROST protocol: FrostOptions::Robust runs RobustFrostProtocol, any other option
+    // the plain FrostProtocol.
+    frost_options: FrostOptions,
+    // Numbers the admission slots of share requests, which have no instance id.
+    share_counter: u64,
+    // Tasks running the admitted instances and share computations, by admission slot id.
+    tasks: HashMap<String, tokio::task::JoinHandle<()>>,
 }
 
 const BACKLOG_CHECK_INTERVAL: u64 = 60;
+// How often admission slots are checked for instances that exceeded the instance timeout.
+const ADMISSION_CHECK_INTERVAL: u64 = 5;
 
 // BacklogData keeps all the messages that are destined for a specific instance,
 // plus a field checked, which is used to detect too old backlog data.
@@ -146,7 +169,7 @@ struct BacklogData {
     checked: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StartInstanceRequest {
     Decryption {
         ciphertext: Ciphertext,
@@ -168,19 +191,25 @@ pub enum StartInstanceRequest {
 
 // InstanceStatus describes the currenct state of a protocol instance.
 // The field result has meaning only when finished == true.
+// The field blame lists the parties that misbehaved during the instance so far.
+// The field key_id is the id of the key the instance runs with.
 #[derive(Debug, Clone)]
 pub struct InstanceStatus {
     pub scheme: ThresholdScheme,
     pub group: Group,
+    pub key_id: String,
     pub finished: bool,
     pub result: Option<Result<Vec<u8>, ProtocolError>>,
+    pub blame: Vec<Misbehavior>,
 }
 
 #[derive(Debug)]
 pub enum InstanceManagerCommand {
     CreateInstance {
         request: StartInstanceRequest,
-        responder: tokio::sync::oneshot::Sender<Result<String, SchemeError>>,
+        // Identity of the requesting client, if it authenticated itself. Used for per-client limits.
+        client_id: Option<String>,
+        responder: tokio::sync::oneshot::Sender<Result<String, ProtocolError>>,
     },
 
     GetInstanceStatus {
@@ -188,6 +217,20 @@ pub enum InstanceManagerCommand {
         responder: tokio::sync::oneshot::Sender<Option<InstanceStatus>>,
     },
 
+    // Returns the share of this node for a request of a non-interactive scheme, without starting
+    // an instance. The client verifies and combines the shares of the nodes itself.
+    CreateShare {
+        request: StartInstanceRequest,
+        // Identity of the requesting client, if it authenticated itself. Used for per-client limits.
+        client_id: Option<String>,
+        responder: tokio::sync::oneshot::Sender<Result<NodeShare, ProtocolError>>,
+    },
+
+    // Sent by the task computing a share once it is done, frees the admission slot of the share.
+    ShareFinished {
+        slot_id: String,
+    },
+
     StoreResult {
         instance_id: String,
         result: Result<Vec<u8>, ProtocolError>,
@@ -198,6 +241,19 @@ pub enum InstanceManagerCommand {
         status: String,
         error: Option<ProtocolError>,
     },
+
+    ReportMisbehavior {
+        instance_id: String,
+        misbehavior: Vec<Misbehavior>,
+    },
+
+    GetPeerReputation {
+        responder: tokio::sync::oneshot::Sender<Vec<PeerReputation>>,
+    },
+
+    GetAdmissionMetrics {
+        responder: tokio::sync::oneshot::Sender<AdmissionMetrics>,
+    },
 }
 
 impl InstanceManager {
@@ -208,6 +264,10 @@ impl InstanceManager {
         outgoing_p2p_sender: tokio::sync::mpsc::Sender<NetMessage>,
         incoming_p2p_receiver: tokio::sync::mpsc::Receiver<NetMessage>,
         event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+        signing_policy: SigningPolicy,
+        admission_limits: AdmissionLimits,
+        nonce_pool_check_interval: tokio::time::Duration,
+        frost_options: FrostOptions,
     ) -> Self {
         return Self {
             key_manager_command_sender,
@@ -220,7 +280,17 @@ impl InstanceManager {
             backlog_interval: tokio::time::interval(tokio::time::Duration::from_secs(
                 BACKLOG_CHECK_INTERVAL as u64,
             )),
+            nonce_pool_interval: tokio::time::interval(nonce_pool_check_interval),
+            admission_interval: tokio::time::interval(tokio::time::Duration::from_secs(
+                ADMISSION_CHECK_INTERVAL,
+            )),
             event_emitter_sender,
+            reputation: ReputationTable::new(),
+            signing_policy,
+            admission: AdmissionController::new(admission_limits),
+            frost_options,
+            share_counter: 0,
+            tasks: HashMap::new(),
         };
     }
 
@@ -237,16 +307,16 @@ impl InstanceManager {
                             match cmd {
                                 InstanceManagerCommand::CreateInstance{
                                         request,
+                                        client_id,
                                         responder
                                     } => {
-                                        let result = self.start(request).await;
-                                        if result.is_err() {
-                                            error!("Error starting instance: {:?}", result.unwrap_err());
+                                        // Requests for existing instances do not occupy a new slot.
+                                        let exists = self.instances.contains_key(&assign_instance_id(&request));
+                                        let key = self.admission_key(&request).await;
+                                        if exists || self.admission.can_admit(&client_id, &key) {
+                                            self.create_instance(request, client_id, key, responder).await;
                                         } else {
-                                            if let Err(e) = responder.send(Ok(result.unwrap())) {
-                                                error!("Error sending response to instance creation request: {:?}", e);
-                                                // return Err("Error sending response to instance creation request".to_string());
-                                            };
+                                            self.enqueue(PendingRequest::new(request, client_id, key, PendingResponder::Instance(responder)));
                                         }
                                     },
 
@@ -257,8 +327,10 @@ impl InstanceManager {
                                             Some(InstanceStatus {
                                                 scheme: instance.get_scheme().clone(),
                                                 group: instance.get_group().clone(),
+                                                key_id: instance.get_key_id().to_string(),
                                                 finished: instance.is_finished(),
-                                                result: instance.get_result().clone()
+                                                result: instance.get_result().clone(),
+                                                blame: instance.get_blame().clone(),
                                             })
                                         },
                                         None => {
@@ -269,6 +341,19 @@ impl InstanceManager {
                                     responder.send(result).expect("The receiver for responder in StateUpdateCommand::GetInstanceResult has been closed.");
                                 },
 
+                                InstanceManagerCommand::CreateShare { request, client_id, responder } => {
+                                    let key = self.admission_key(&request).await;
+                                    if self.admission.can_admit(&client_id, &key) {
+                                        self.create_share(request, client_id, key, responder).await;
+                                    } else {
+                                        self.enqueue(PendingRequest::new(request, client_id, key, PendingResponder::Share(responder)));
+                                    }
+                                },
+
+                                InstanceManagerCommand::ShareFinished { slot_id } => {
+                                    self.release_slot(&slot_id).await;
+                                },
+
                                 InstanceManagerCommand::StoreResult {instance_id, result } => {
                                     let instance = self.instances.get_mut(&instance_id);
 
@@ -279,6 +364,7 @@ impl InstanceManager {
                                         },
                                         None => error!("Error storing instance result for instance {}", instance_id)
                                     }
+                                    self.release_slot(&instance_id).await;
                                 },
 
                                 InstanceManagerCommand::UpdateInstanceStatus {instance_id, status, error } => {
@@ -292,6 +378,7 @@ impl InstanceManager {
                                             },
                                             None => error!("Error updating instance failed status for instance {}", instance_id)
                                         }
+                                        self.release_slot(&instance_id).await;
                                     } else {
                                         let instance = self.instances.get_mut(&instance_id);
                                         match instance {
@@ -299,6 +386,28 @@ impl InstanceManager {
                                             None => error!("Error updating instance status for instance {}", instance_id)
                                         }
                                     }
+                                },
+
+                                InstanceManagerCommand::ReportMisbehavior { instance_id, misbehavior } => {
+                                    for m in &misbehavior {
+                                        self.reputation.record(&instance_id, m);
+                                    }
+                                    match self.instances.get_mut(&instance_id) {
+                                        Some(_instance) => _instance.add_blame(&misbehavior),
+                                        None => error!("Error storing blame for instance {}", instance_id)
+                                    }
+                                },
+
+                                InstanceManagerCommand::GetPeerReputation { responder } => {
+                                    if responder.send(self.reputation.get_all()).is_err() {
+                                        error!("The receiver for responder in InstanceManagerCommand::GetPeerReputation has been closed.");
+                                    }
+                                },
+
+                                InstanceManagerCommand::GetAdmissionMetrics { responder } => {
+                                    if responder.send(self.admission.metrics()).is_err() {
+                                        error!("The receiver for responder in InstanceManagerCommand::GetAdmissionMetrics has been closed.");
+                                    }
                                 }
                             }
                         },
@@ -311,6 +420,10 @@ impl InstanceManager {
 
                 incoming_message = self.incoming_p2p_receiver.recv() => {
                     match incoming_message {
+                        // Commitments of precomputed FROST nonces are not bound to an instance.
+                        Some(net_message) if net_message.get_instace_id() == FROST_POOL_INSTANCE_ID => {
+                            self.store_frost_commitments(&net_message).await;
+                        },
                         Some(net_message) => {
                             let instance =  self.instances.get(&net_message.get_instace_id());
 
@@ -366,10 +479,199 @@ impl InstanceManager {
                     // also clean the cache every minute
                     // self.instances.attempt_eject();
                 }
+
+                // Refill the FROST nonce pools that run low and announce the new commitments. Only
+                // the robust protocol uses precomputed nonces.
+                _ = self.nonce_pool_interval.tick() => {
+                    if self.frost_options == FrostOptions::Robust {
+                        self.replenish_frost_pools().await;
+                    }
+                }
+
+                // Free the slots of instances and share computations that did not report back within
+                // the instance timeout, e.g., because their task panicked.
+                _ = self.admission_interval.tick() => {
+                    self.expire_slots().await;
+                }
+            }
+        }
+    }
+
+    // Starts an admitted request and answers the requester.
+    async fn create_instance(
+        &mut self,
+        request: StartInstanceRequest,
+        client_id: Option<String>,
+        request_key: String,
+        responder: tokio::sync::oneshot::Sender<Result<String, ProtocolError>>,
+    ) {
+        let is_new = !self.instances.contains_key(&assign_instance_id(&request));
+
+        let result = self.start(request).await;
+        match &result {
+            Ok(instance_id) => {
+                if is_new {
+                    self.admission.admit(instance_id, client_id, request_key);
+                }
+            }
+            Err(e) => error!("Error starting instance: {:?}", e),
+        }
+
+        if let Err(e) = responder.send(result) {
+            error!("Error sending response to instance creation request: {:?}", e);
+        };
+    }
+
+    // Queues a request that cannot be admitted yet, or rejects it if the queue is full.
+    fn enqueue(&mut self, pending: PendingRequest) {
+        match self.admission.enqueue(pending) {
+            Ok(queue_depth) => {
+                debug!("Request queued for admission, queue depth: {}", queue_depth);
+            }
+            Err(pending) => {
+                warn!("Admission queue full, rejecting request");
+                pending.responder.reject(ProtocolError::ResourceExhausted);
+            }
+        }
+    }
+
+    // Frees the admission slot of a terminated instance or a computed share and starts queued
+    // requests that fit now.
+    async fn release_slot(&mut self, slot_id: &str) {
+        self.tasks.remove(slot_id);
+        if !self.admission.release(slot_id) {
+            return;
+        }
+        self.admit_queued().await;
+    }
+
+    // Frees the slots held beyond the instance timeout. The task of an instance is aborted before
+    // its slot is freed, and the instance fails with a timeout. A share computation runs on a
+    // blocking thread and cannot be aborted, so its slot is only freed once the computation ended.
+    async fn expire_slots(&mut self) {
+        let mut freed = 0;
+        for slot_id in self.admission.expired(Instant::now()) {
+            let is_instance = self.instances.contains_key(&slot_id);
+            if let Some(task) = self.tasks.remove(&slot_id) {
+                if !is_instance && !task.is_finished() {
+                    self.tasks.insert(slot_id, task);
+                    continue;
+                }
+                task.abort();
+                let _ = task.await;
+            }
+
+            if let Some(instance) = self.instances.get_mut(&slot_id) {
+                if !instance.is_finished() {
+                    instance.set_result(Err(ProtocolError::Timeout));
+                    self.instances.inform_of_termination(slot_id.clone());
+                }
+            }
+            self.admission.release(&slot_id);
+            freed += 1;
+        }
+
+        if freed > 0 {
+            warn!("Freed {} admission slots held beyond the instance timeout", freed);
+            self.admit_queued().await;
+        }
+    }
+
+    // Starts the queued requests that fit within the limits.
+    async fn admit_queued(&mut self) {
+        while let Some(pending) = self.admission.pop_admissible() {
+            debug!(
+                "Admitting queued request after {}ms",
+                pending.waiting_time_ms()
+            );
+            match pending.responder {
+                PendingResponder::Instance(responder) => {
+                    self.create_instance(pending.request, pending.client_id, pending.key, responder)
+                        .await
+                }
+                PendingResponder::Share(responder) => {
+                    self.create_share(pending.request, pending.client_id, pending.key, responder)
+                        .await
+                }
             }
         }
     }
 
+    // Identifies the key a request uses for accounting per-key limits. Requests for the default
+    // key are accounted for under the id of that key, so they share the limit with requests that
+    // name the key explicitly.
+    pub(crate) async fn admission_key(&self, request: &StartInstanceRequest) -> String {
+        if let Some(key_id) = requested_key_id(request) {
+            return key_id;
+        }
+
+        let (scheme, group) = match request {
+            StartInstanceRequest::Signature { scheme, group, .. }
+            | StartInstanceRequest::Coin { scheme, group, .. } => (scheme, group),
+            StartInstanceRequest::Decryption { .. } => unreachable!("ciphertexts name their key"),
+        };
+        match self.get_key_by_scheme_and_group(scheme, group).await {
+            Ok(key) => key.id.clone(),
+            // Without a default key the request fails when it is started, so it never holds a slot.
+            Err(_) => format!("{}/{}", scheme.as_str_name(), group.as_str_name()),
+        }
+    }
+
+    // Looks up the key and checks the signing policy. The share is computed in a separate task, so
+    // expensive schemes (e.g., SH00) do not hold up other commands. The task holds an admission
+    // slot until the share is computed.
+    async fn create_share(
+        &mut self,
+        request: StartInstanceRequest,
+        client_id: Option<String>,
+        request_key: String,
+        responder: tokio::sync::oneshot::Sender<Result<NodeShare, ProtocolError>>,
+    ) {
+        let (scheme, group, key_id) = match &request {
+            StartInstanceRequest::Decryption { ciphertext } => (
+                ciphertext.get_scheme(),
+                ciphertext.get_group().clone(),
+                Some(ciphertext.get_key_id().to_string()),
+            ),
+            StartInstanceRequest::Signature { scheme, group, key_id, .. } => (*scheme, group.clone(), key_id.clone()),
+            StartInstanceRequest::Coin { scheme, group, key_id, .. } => (*scheme, group.clone(), key_id.clone()),
+        };
+
+        let key = match self.get_private_key(scheme, &group, key_id).await {
+            Ok(key) => key,
+            Err(e) => {
+                error!("Error creating share: {}", e.message());
+                let _ = responder.send(Err(ProtocolError::SchemeError(SchemeError::Aborted(e.message().to_string()))));
+                return;
+            }
+        };
+
+        if let StartInstanceRequest::Signature { message, label, .. } = &request {
+            let versions = self.key_versions(key.get_key_id()).await;
+            if let Err(reason) = self.signing_policy.evaluate_versions(&versions, message, label) {
+                let _ = responder.send(Err(ProtocolError::PolicyViolation(reason)));
+                return;
+            }
+        }
+
+        let slot_id = format!("share-{}", self.share_counter);
+        self.share_counter += 1;
+        self.admission.admit(&slot_id, client_id, request_key);
+
+        let sender = self.instance_command_sender.clone();
+        let id = slot_id.clone();
+        let task = tokio::task::spawn_blocking(move || {
+            let result = share::create_share(&request, &key);
+            if responder.send(result).is_err() {
+                error!("The receiver for responder in InstanceManagerCommand::CreateShare has been closed.");
+            }
+            if sender.blocking_send(InstanceManagerCommand::ShareFinished { slot_id: id }).is_err() {
+                error!("Could not release the admission slot of a share, channel closed");
+            }
+        });
+        self.tasks.insert(slot_id, task);
+    }
+
     pub async fn start<'a>(
         &mut self,
         instance_request: StartInstanceRequest,
@@ -396,11 +698,12 @@ impl InstanceManager {
                     if e.code() == Code::AlreadyExists {
                         return Ok(instance_id);
                     }
-                    error!("Key not found");
-                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(String::from("key not found"))));
+                    error!("Key not usable: {}", e.message());
+                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(e.message().to_string())));
                 }
 
                 let key = key.unwrap();
+                let outgoing = self.committee_sender(key.get_key_id());
 
                 let (sender, receiver) = tokio::sync::mpsc::channel::<NetMessage>(32);
 
@@ -408,6 +711,7 @@ impl InstanceManager {
                     instance_id.clone(),
                     ciphertext.get_scheme(),
                     ciphertext.get_group().clone(),
+                    key.get_key_id().to_string(),
                     Some(sender),
                 );
 
@@ -417,9 +721,10 @@ impl InstanceManager {
 
                 let executor = ThresholdProtocolExecutor::new(
                     receiver,
-                    self.outgoing_p2p_sender.clone(),
+                    outgoing,
                     instance_id.clone(),
                     self.event_emitter_sender.clone(),
+                    self.instance_command_sender.clone(),
                     prot,
                 );
 
@@ -427,19 +732,22 @@ impl InstanceManager {
 
                 let sender = self.instance_command_sender.clone();
                 let id = instance_id.clone();
+                let timeout = self.admission.instance_timeout();
 
                 // Start it in a new thread, so that the client does not block until the protocol is finished.
-                tokio::spawn(async move {
+                let task = tokio::spawn(async move {
                     let result = Self::execute_protocol(
                         executor,
                         id,
                         sender,
+                        timeout,
                     ).await;
                     if result.is_err() {
                         let error = result.unwrap_err();
                         error!("Error during protocol execution protocol: {:?}", error);  
                     }
                 });
+                self.tasks.insert(instance_id.clone(), task);
 
                 // Maybe here handle error situations
                 _ = self.forward_backlogged_messages(instance_id.clone());
@@ -467,48 +775,106 @@ impl InstanceManager {
                     if e.code() == Code::AlreadyExists {
                         return Ok(instance_id);
                     }
-                    error!("Key not found");
-                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(String::from("key not found"))));
+                    error!("Key not usable: {}", e.message());
+                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(e.message().to_string())));
                 }
 
                 let key = key.unwrap();
+                let outgoing = self.committee_sender(key.get_key_id());
+
+                // Every node checks the signing policy of the key before producing a signature share.
+                let versions = self.key_versions(key.get_key_id()).await;
+                if let Err(reason) = self.signing_policy.evaluate_versions(&versions, &message, &label) {
+                    return Err(ProtocolError::PolicyViolation(reason));
+                }
 
                 let (sender, receiver) = tokio::sync::mpsc::channel::<NetMessage>(32);
 
-                let instance = Instance::new(instance_id.clone(), scheme, group.clone(), Some(sender));
+                let instance = Instance::new(
+                    instance_id.clone(),
+                    scheme,
+                    group.clone(),
+                    key.get_key_id().to_string(),
+                    Some(sender),
+                );
 
                 match scheme {
-                    ThresholdScheme::Frost => {
+                    ThresholdScheme::Frost if self.frost_options != FrostOptions::Robust => {
                         let prot = FrostProtocol::new(
-                            key, 
-                            &message, 
+                            key,
+                            &message,
                             b"label",
-                            FrostOptions::NoPrecomputation,
-                            Option::None
+                            self.frost_options.clone(),
+                            Option::None,
                         );
                         let executor = ThresholdProtocolExecutor::new(
                             receiver,
-                            self.outgoing_p2p_sender.clone(),
+                            outgoing,
+                            instance_id.clone(),
+                            self.event_emitter_sender.clone(),
+                            self.instance_command_sender.clone(),
+                            prot,
+                        );
+                        self.instances.insert(instance_id.clone(), instance);
+
+                        let sender = self.instance_command_sender.clone();
+                        let id = instance_id.clone();
+                        let timeout = self.admission.instance_timeout();
+
+                        // Start it in a new thread, so that the client does not block until the protocol is finished.
+                        let task = tokio::spawn(async move {
+                            let result = Self::execute_protocol(
+                                executor,
+                                id,
+                                sender,
+                                timeout,
+                            ).await;
+                            if result.is_err() {
+                                error!("Error starting protocol: {:?}", result.unwrap_err());
+                            }
+                        });
+                        self.tasks.insert(instance_id.clone(), task);
+
+                        _ = self.forward_backlogged_messages(instance_id.clone());
+
+                        return Ok(instance_id.clone());
+                    },
+                    ThresholdScheme::Frost => {
+                        // The robust mode re-selects signers until a valid signature is produced, so
+                        // crashed or misbehaving signers cannot stall the instance. With precomputed
+                        // nonces from the pool, the first sessions start without a commitment round.
+                        let precomputation = self.take_frost_precomputation(key.get_key_id()).await;
+                        let prot = match precomputation {
+                            Some(precomputation) => RobustFrostProtocol::with_precomputation(key, &message, b"label", precomputation),
+                            None => RobustFrostProtocol::new(key, &message, b"label"),
+                        };
+                        let executor = ThresholdProtocolExecutor::new(
+                            receiver,
+                            outgoing,
                             instance_id.clone(),
                             self.event_emitter_sender.clone(),
+                            self.instance_command_sender.clone(),
                             prot,
                         );
                         self.instances.insert(instance_id.clone(), instance);
 
                         let sender = self.instance_command_sender.clone();
                         let id = instance_id.clone();
+                        let timeout = self.admission.instance_timeout();
 
                         // Start it in a new thread, so that the client does not block until the protocol is finished.
-                        tokio::spawn(async move {
+                        let task = tokio::spawn(async move {
                             let result = Self::execute_protocol(
                                 executor,
                                 id,
                                 sender,
+                                timeout,
                             ).await;
                             if result.is_err() {
                                 error!("Error starting protocol: {:?}", result.unwrap_err());
                             }
                         });
+                        self.tasks.insert(instance_id.clone(), task);
 
                         _ = self.forward_backlogged_messages(instance_id.clone());
                 
@@ -518,27 +884,31 @@ impl InstanceManager {
                         let prot = ThresholdSignatureProtocol::new(key,Some(&message),&label);
                         let executor = ThresholdProtocolExecutor::new(
                             receiver,
-                            self.outgoing_p2p_sender.clone(),
+                            outgoing,
                             instance_id.clone(),
                             self.event_emitter_sender.clone(),
+                            self.instance_command_sender.clone(),
                             prot,
                         );
                         self.instances.insert(instance_id.clone(), instance);
 
                         let sender = self.instance_command_sender.clone();
                         let id = instance_id.clone();
+                        let timeout = self.admission.instance_timeout();
 
                         // Start it in a new thread, so that the client does not block until the protocol is finished.
-                        tokio::spawn(async move {
+                        let task = tokio::spawn(async move {
                             let result = Self::execute_protocol(
                                 executor,
                                 id,
                                 sender,
+                                timeout,
                             ).await;
                             if result.is_err() {
                                 error!("Error starting protocol: {:?}", result.unwrap_err());
                             }
                         });
+                        self.tasks.insert(instance_id.clone(), task);
 
                         _ = self.forward_backlogged_messages(instance_id.clone());
         
@@ -562,15 +932,22 @@ impl InstanceManager {
                     if e.code() == Code::AlreadyExists {
                         return Ok(instance_id);
                     }
-                    error!("Key not found");
-                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(String::from("key not found"))));
+                    error!("Key not usable: {}", e.message());
+                    return Err(ProtocolError::SchemeError(SchemeError::Aborted(e.message().to_string())));
                 }
 
                 let key = key.unwrap();
+                let outgoing = self.committee_sender(key.get_key_id());
 
                 let (sender, receiver) = tokio::sync::mpsc::channel::<NetMessage>(32);
 
-                let instance = Instance::new(instance_id.clone(), scheme, group, Some(sender));
+                let instance = Instance::new(
+                    instance_id.clone(),
+                    scheme,
+                    group,
+                    key.get_key_id().to_string(),
+                    Some(sender),
+                );
 
                 // Create the new protocol instance
                 let prot = ThresholdCoinProtocol::new(
@@ -580,9 +957,10 @@ impl InstanceManager {
 
                 let executor = ThresholdProtocolExecutor::new(
                     receiver,
-                    self.outgoing_p2p_sender.clone(),
+                    outgoing,
                     instance_id.clone(),
                     self.event_emitter_sender.clone(),
+                    self.instance_command_sender.clone(),
                     prot,
                 );
 
@@ -590,18 +968,21 @@ impl InstanceManager {
 
                 let sender = self.instance_command_sender.clone();
                 let id = instance_id.clone();
+                let timeout = self.admission.instance_timeout();
 
                 // Start it in a new thread, so that the client does not block until the protocol is finished.
-                tokio::spawn(async move {
+                let task = tokio::spawn(async move {
                     let result = Self::execute_protocol(
                         executor,
                         id,
                         sender,
+                        timeout,
                     ).await;
                     if result.is_err() {
                         error!("Error starting protocol: {:?}", result.unwrap_err());
                     }
                 });
+                self.tasks.insert(instance_id.clone(), task);
 
                 _ = self.forward_backlogged_messages(instance_id.clone());
                 
@@ -615,9 +996,17 @@ impl InstanceManager {
         mut executor: (impl ThresholdProtocol + std::marker::Send + 'static),
         instance_id: String,
         sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
+        timeout: Option<tokio::time::Duration>,
     ) -> Result<(), ProtocolError> {
         
-            let result = executor.run().await;
+            // An instance that does not terminate within the timeout fails and frees its slot.
+            let result = match timeout {
+                Some(timeout) => match tokio::time::timeout(timeout, executor.run()).await {
+                    Ok(result) => result,
+                    Err(_) => Err(ProtocolError::Timeout),
+                },
+                None => executor.run().await,
+            };
             let instance_manager_sender = sender.clone();
 
             match result {
@@ -745,6 +1134,104 @@ impl InstanceManager {
         return Err(String::from("Got no response from key manager"));
     }
 
+    // Returns the ids of the key with the given id and of its previous versions, newest first.
+    async fn key_versions(&self, key_id: &str) -> Vec<String> {
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<Arc<KeyEntry>>>();
+        let cmd = KeyManagerCommand::ListKeys {
+            responder: response_sender,
+        };
+        let keys = match self.key_manager_command_sender.send(cmd).await {
+            Ok(_) => response_receiver.await.unwrap_or_default(),
+            Err(_) => Vec::new(),
+        };
+
+        let key = match keys.iter().find(|k| k.id == key_id) {
+            Some(key) => key,
+            None => return vec![key_id.to_string()],
+        };
+        let mut versions: Vec<&Arc<KeyEntry>> = keys
+            .iter()
+            .filter(|k| k.lineage() == key.lineage() && k.metadata.version <= key.metadata.version)
+            .collect();
+        versions.sort_by_key(|k| std::cmp::Reverse(k.metadata.version));
+        versions.iter().map(|k| k.id.clone()).collect()
+    }
+
+    async fn replenish_frost_pools(&self) {
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<FrostCommitmentBatch>>();
+        let cmd = KeyManagerCommand::ReplenishFrostPools {
+            responder: response_sender,
+        };
+        if self.key_manager_command_sender.send(cmd).await.is_err() {
+            error!("Could not reach the key manager to replenish the FROST nonce pools");
+            return;
+        }
+
+        let batches = match response_receiver.await {
+            Ok(batches) => batches,
+            Err(_) => return,
+        };
+        for batch in batches {
+            if let Err(e) = self.outgoing_p2p_sender.send(batch.to_net_message()).await {
+                error!("Error announcing FROST commitments: {}", e);
+            }
+        }
+    }
+
+    // Commitments from other nodes are only stored if they were sent and signed by their signer.
+    // They are checked by the protocol when used, a bad commitment makes a session fail over to
+    // fresh commitments, it cannot break signing.
+    async fn store_frost_commitments(&self, net_message: &NetMessage) {
+        // share ids are u16, a node with a larger id holds no share
+        let sender = match net_message.authenticated_sender().map(u16::try_from) {
+            Some(Ok(sender)) => sender,
+            Some(Err(_)) | None => {
+                warn!("Ignoring FROST commitments of an unknown sender");
+                return;
+            }
+        };
+        let batch = match FrostCommitmentBatch::from_net_message(net_message) {
+            Ok(batch) => batch,
+            Err(e) => {
+                warn!("{}", e);
+                return;
+            }
+        };
+
+        let cmd = KeyManagerCommand::StoreFrostCommitments { sender, batch };
+        if self.key_manager_command_sender.send(cmd).await.is_err() {
+            error!("Could not reach the key manager to store FROST commitments");
+        }
+    }
+
+    async fn take_frost_precomputation(&self, key_id: &str) -> Option<RobustFrostPrecomputation> {
+        let (response_sender, response_receiver) = oneshot::channel::<Option<RobustFrostPrecomputation>>();
+        let cmd = KeyManagerCommand::TakeFrostPrecomputation {
+            key_id: key_id.to_string(),
+            responder: response_sender,
+        };
+        self.key_manager_command_sender.send(cmd).await.ok()?;
+        response_receiver.await.ok().flatten()
+    }
+
+    // Returns the sender for the messages of an instance using the given key. The messages are
+    // tagged with the topic of the key's committee, so only its members receive them.
+    fn committee_sender(&self, key_id: &str) -> tokio::sync::mpsc::Sender<NetMessage> {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel::<NetMessage>(32);
+        let outgoing_p2p_sender = self.outgoing_p2p_sender.clone();
+        let topic = committee_topic(key_id);
+        // ends once the executor of the instance drops its sender
+        tokio::spawn(async move {
+            while let Some(mut message) = receiver.recv().await {
+                message.set_topic(topic.clone());
+                if outgoing_p2p_sender.send(message).await.is_err() {
+                    break;
+                }
+            }
+        });
+        sender
+    }
+
     async fn setup_instance<'a>(
         &self,
         scheme: ThresholdScheme,
@@ -763,6 +1250,22 @@ impl InstanceManager {
             ));
         }
 
+        let key = self.get_private_key(scheme, group, key_id).await?;
+        info!(
+            "Using key with id: {:?} for request {:?}",
+            key.get_key_id(), &instance_id
+        );
+
+        Ok(key)
+    }
+
+    // Returns the private key share with the given id, or the default key for scheme and group.
+    async fn get_private_key(
+        &self,
+        scheme: ThresholdScheme,
+        group: &Group,
+        key_id: Option<String>,
+    ) -> Result<Arc<PrivateKeyShare>, Status> {
         // Retrieve private key for this instance
         let key: Arc<KeyEntry>;
         if let Some(kid) = key_id {
@@ -780,10 +1283,18 @@ impl InstanceManager {
                 Err(err) => return Err(Status::new(Code::InvalidArgument, err)),
             };
         };
-        info!(
-            "Using key with id: {:?} for request {:?}",
-            key.id, &instance_id
-        );
+        // Old versions of rotated keys can still decrypt, but never sign or flip coins
+        let usable = match key.status() {
+            KeyStatus::Active => true,
+            KeyStatus::DecryptOnly => scheme.get_operation() == ThresholdOperation::Encryption,
+            KeyStatus::Retired => false,
+        };
+        if !usable {
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                format!("Key {} is {}", key.id, key.status().as_str_name()),
+            ));
+        }
 
         let key = key.sk.clone();
 
@@ -1,3 +1,10 @@
+pub mod admission;
 pub mod instance;
 pub mod instance_manager;
 pub mod protocol_executor;
+pub mod reputation;
+pub mod share;
+#[cfg(test)]
+mod tests;
+#[cfg(test)]
+mod admission_tests;
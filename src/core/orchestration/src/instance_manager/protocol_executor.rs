@@ -2,11 +2,14 @@ use std::fmt::Debug;
 
 use chrono::Utc;
 use theta_events::event::Event;
-use theta_protocols::interface::{ProtocolMessageWrapper, ThresholdRoundProtocol};
+use theta_protocols::interface::{
+    AuthenticatedMessage, Misbehavior, ProtocolMessageWrapper, ThresholdRoundProtocol,
+};
 use tokio::sync::mpsc::error::SendError;
 
+use crate::instance_manager::instance_manager::InstanceManagerCommand;
 use crate::interface::ThresholdProtocol;
-use log::{error, info};
+use log::{error, info, warn};
 use theta_protocols::interface::ProtocolError;
 use tonic::async_trait;
 
@@ -18,7 +21,9 @@ where
     chan_out: tokio::sync::mpsc::Sender<T>,
     instance_id: String,
     event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+    instance_command_sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
     protocol: P,
+    reported_blame: usize,
 }
 
 impl<P: ThresholdRoundProtocol<T>, T> ThresholdProtocolExecutor<P, T> {
@@ -27,6 +32,7 @@ impl<P: ThresholdRoundProtocol<T>, T> ThresholdProtocolExecutor<P, T> {
         chan_out: tokio::sync::mpsc::Sender<T>,
         instance_id: String,
         event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+        instance_command_sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
         protocol: P,
     ) -> Self {
         return Self {
@@ -34,15 +40,54 @@ impl<P: ThresholdRoundProtocol<T>, T> ThresholdProtocolExecutor<P, T> {
             chan_out,
             instance_id,
             event_emitter_sender,
+            instance_command_sender,
             protocol,
+            reported_blame: 0,
         };
     }
+
+    // Emits an event for every misbehavior the protocol detected since the last call
+    // and forwards the new entries to the instance manager. The new entries were caused by the
+    // message from `sender` that the protocol just processed.
+    async fn report_misbehavior(&mut self, sender: Option<u32>) {
+        let blame = self.protocol.blame();
+        if blame.len() <= self.reported_blame {
+            return;
+        }
+        let mut new_entries: Vec<Misbehavior> = blame[self.reported_blame..].to_vec();
+        self.reported_blame = blame.len();
+
+        for misbehavior in new_entries.iter_mut() {
+            misbehavior.sender = sender;
+            warn!(
+                "<{:?}>: Party with share id {} misbehaved (sent by {:?}): {:?}",
+                &self.instance_id, misbehavior.share_id, sender, misbehavior.kind
+            );
+            let event = Event::MisbehaviorDetected {
+                timestamp: Utc::now(),
+                instance_id: self.instance_id.clone(),
+                share_id: misbehavior.share_id,
+                kind: format!("{:?}", misbehavior.kind),
+            };
+            self.event_emitter_sender.send(event).await.unwrap();
+        }
+
+        let cmd = InstanceManagerCommand::ReportMisbehavior {
+            instance_id: self.instance_id.clone(),
+            misbehavior: new_entries,
+        };
+        if self.instance_command_sender.send(cmd).await.is_err() {
+            error!("<{:?}>: Could not report misbehavior, instance manager channel closed", &self.instance_id);
+        }
+    }
 }
 
 //TODO: Handle trowing the errors
 #[async_trait]
-impl<P: ThresholdRoundProtocol<T> + std::marker::Send, T: std::marker::Send + Debug>
-    ThresholdProtocol for ThresholdProtocolExecutor<P, T>
+impl<
+        P: ThresholdRoundProtocol<T> + std::marker::Send,
+        T: AuthenticatedMessage + std::marker::Send + Debug,
+    > ThresholdProtocol for ThresholdProtocolExecutor<P, T>
 {
     async fn run(&mut self) -> Result<Vec<u8>, ProtocolError> {
         info!(
@@ -85,9 +130,17 @@ impl<P: ThresholdRoundProtocol<T> + std::marker::Send, T: std::marker::Send + De
         loop {
             match self.chan_in.recv().await {
                 Some(net_message) => {
+                    let sender = net_message.authenticated_sender();
                     let protocol_message: <P as ThresholdRoundProtocol<T>>::ProtocolMessage =
-                        *ProtocolMessageWrapper::unwrap(net_message).unwrap(); // handle the error
+                        match ProtocolMessageWrapper::unwrap(net_message) {
+                            Ok(message) => *message,
+                            Err(e) => {
+                                warn!("Dropping malformed message for instance {}: {:?}", self.instance_id, e);
+                                continue;
+                            }
+                        };
                     let result = self.protocol.update(protocol_message.into());
+                    self.report_misbehavior(sender).await;
                     match result {
                         Ok(_) => {
                             if self.protocol.is_ready_to_finalize() {
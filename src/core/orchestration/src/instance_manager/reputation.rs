@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use theta_protocols::interface::{Misbehavior, MisbehaviorKind};
+
+/// PeerReputation counts the misbehavior attributed to a single peer over all protocol instances.
+/// Peers are identified by their node id, as authenticated by the network layer when the peer
+/// sent the offending share. The share id claimed in the share is not used, as any peer can send
+/// a share under the id of another.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PeerReputation {
+    pub peer_id: u32,
+    pub invalid_shares: u64,
+    pub duplicate_shares: u64,
+    pub equivocations: u64,
+    pub last_instance_id: Option<String>,
+}
+
+impl PeerReputation {
+    fn new(peer_id: u32) -> Self {
+        Self {
+            peer_id,
+            ..Default::default()
+        }
+    }
+
+    /// Total number of misbehaviors attributed to this peer.
+    pub fn total(&self) -> u64 {
+        self.invalid_shares + self.duplicate_shares + self.equivocations
+    }
+}
+
+/// ReputationTable keeps a per-peer counter of detected misbehavior. Peers which never misbehaved
+/// do not appear in the table, nor does misbehavior whose sender is unknown.
+pub struct ReputationTable {
+    peers: HashMap<u32, PeerReputation>,
+}
+
+impl ReputationTable {
+    pub fn new() -> Self {
+        Self {
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Records a misbehavior detected in the given instance against the peer that sent it.
+    pub fn record(&mut self, instance_id: &str, misbehavior: &Misbehavior) {
+        let sender = match misbehavior.sender {
+            Some(sender) => sender,
+            None => return,
+        };
+        let entry = self
+            .peers
+            .entry(sender)
+            .or_insert_with(|| PeerReputation::new(sender));
+
+        match misbehavior.kind {
+            MisbehaviorKind::InvalidShare => entry.invalid_shares += 1,
+            MisbehaviorKind::DuplicateShare => entry.duplicate_shares += 1,
+            MisbehaviorKind::CoordinatorEquivocation => entry.equivocations += 1,
+        }
+        entry.last_instance_id = Some(instance_id.to_string());
+    }
+
+    /// Returns the reputation of all peers that misbehaved at least once, ordered by peer id.
+    pub fn get_all(&self) -> Vec<PeerReputation> {
+        let mut peers: Vec<PeerReputation> = self.peers.values().cloned().collect();
+        peers.sort_by_key(|p| p.peer_id);
+        peers
+    }
+}
@@ -0,0 +1,73 @@
+use theta_proto::scheme_types::ThresholdScheme;
+use theta_protocols::interface::ProtocolError;
+use theta_schemes::interface::{
+    SchemeError, Serializable, ThresholdCipher, ThresholdCipherParams, ThresholdCoin,
+    ThresholdSignature, ThresholdSignatureParams,
+};
+use theta_schemes::keys::keys::PrivateKeyShare;
+use theta_schemes::rand::{RngAlgorithm, RNG};
+
+use super::instance_manager::StartInstanceRequest;
+
+// The share of this node for a request, returned to a client that combines the shares itself.
+#[derive(Debug, Clone)]
+pub struct NodeShare {
+    pub key_id: String,
+    pub share_id: u16,
+    // serialized DecryptionShare, SignatureShare or CoinShare
+    pub share: Vec<u8>,
+}
+
+// Schemes whose result is assembled from a single round of shares, so a client can collect the
+// shares from the nodes without any interaction between them.
+pub fn is_non_interactive(scheme: ThresholdScheme) -> bool {
+    matches!(
+        scheme,
+        ThresholdScheme::Sg02
+            | ThresholdScheme::Bz03
+            | ThresholdScheme::Bls04
+            | ThresholdScheme::Cks05
+            | ThresholdScheme::Sh00
+    )
+}
+
+// Computes the share of `key` for the request. Like the decryption protocol, no share is created
+// for an invalid ciphertext.
+pub fn create_share(
+    request: &StartInstanceRequest,
+    key: &PrivateKeyShare,
+) -> Result<NodeShare, ProtocolError> {
+    let scheme = key.get_scheme();
+    if !is_non_interactive(scheme) {
+        return Err(ProtocolError::SchemeError(SchemeError::Aborted(format!(
+            "{} requires interaction between the nodes, shares are only available for non-interactive schemes",
+            scheme.as_str_name()
+        ))));
+    }
+
+    let (share_id, share) = match request {
+        StartInstanceRequest::Decryption { ciphertext } => {
+            if !ThresholdCipher::verify_ciphertext(ciphertext, &key.get_public_key())? {
+                return Err(ProtocolError::InvalidCiphertext);
+            }
+            let mut params = ThresholdCipherParams::new();
+            let share = ThresholdCipher::partial_decrypt(ciphertext, key, &mut params)?;
+            (share.get_id(), share.to_bytes()?)
+        }
+        StartInstanceRequest::Signature { message, label, .. } => {
+            let mut params = ThresholdSignatureParams::new();
+            let share = ThresholdSignature::partial_sign(message, label, key, &mut params)?;
+            (share.get_id(), share.to_bytes()?)
+        }
+        StartInstanceRequest::Coin { name, .. } => {
+            let share = ThresholdCoin::create_share(name, key, &mut RNG::new(RngAlgorithm::OsRng))?;
+            (share.get_id(), share.to_bytes()?)
+        }
+    };
+
+    Ok(NodeShare {
+        key_id: key.get_key_id().to_string(),
+        share_id,
+        share,
+    })
+}
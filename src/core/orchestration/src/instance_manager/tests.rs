@@ -0,0 +1,181 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use theta_events::event::emitter::start_null_emitter;
+use theta_network::types::message::{Channel, NetMessage, NetMessageMetadata};
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_protocols::interface::{
+    AuthenticatedMessage, Misbehavior, MisbehaviorKind, ProtocolError,
+};
+use theta_schemes::{
+    dl_schemes::signatures::frost::FrostOptions,
+    keys::{key_generator::KeyGenerator, key_store::KeyStore, keys::PrivateKeyShare},
+    rand::{RngAlgorithm, RNG},
+};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+use crate::instance_manager::admission::AdmissionLimits;
+use crate::instance_manager::instance_manager::{
+    InstanceManager, InstanceManagerCommand, StartInstanceRequest,
+};
+use crate::instance_manager::reputation::ReputationTable;
+use crate::key_manager::key_manager::KeyManager;
+use crate::key_manager::nonce_pool::{FrostNoncePool, NoncePoolSettings};
+use crate::signing_policy::{rules::AllowedLabels, signing_policy::SigningPolicy};
+
+fn misbehavior(share_id: u16, kind: MisbehaviorKind, sender: Option<u32>) -> Misbehavior {
+    let mut misbehavior = Misbehavior::new(share_id, kind);
+    misbehavior.sender = sender;
+    misbehavior
+}
+
+#[test]
+fn test_reputation_counts_per_sender() {
+    let mut table = ReputationTable::new();
+    assert!(table.get_all().is_empty());
+
+    table.record("i1", &misbehavior(3, MisbehaviorKind::InvalidShare, Some(3)));
+    table.record("i2", &misbehavior(3, MisbehaviorKind::DuplicateShare, Some(3)));
+    table.record("i2", &misbehavior(2, MisbehaviorKind::InvalidShare, Some(2)));
+
+    let peers = table.get_all();
+    assert_eq!(peers.len(), 2);
+    assert_eq!(peers[0].peer_id, 2);
+    assert_eq!(peers[0].total(), 1);
+    assert_eq!(peers[1].peer_id, 3);
+    assert_eq!(peers[1].invalid_shares, 1);
+    assert_eq!(peers[1].duplicate_shares, 1);
+    assert_eq!(peers[1].last_instance_id.as_deref(), Some("i2"));
+}
+
+#[test]
+fn test_coordinator_equivocation_is_counted() {
+    let mut table = ReputationTable::new();
+    table.record("i1", &misbehavior(1, MisbehaviorKind::CoordinatorEquivocation, Some(1)));
+
+    let peers = table.get_all();
+    assert_eq!(peers[0].equivocations, 1);
+    assert_eq!(peers[0].duplicate_shares, 0);
+    assert_eq!(peers[0].total(), 1);
+}
+
+#[test]
+fn test_spoofed_share_id_is_blamed_on_sender() {
+    let mut table = ReputationTable::new();
+
+    // node 4 sends an invalid share under the share id of node 1
+    table.record("i1", &misbehavior(1, MisbehaviorKind::InvalidShare, Some(4)));
+
+    let peers = table.get_all();
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].peer_id, 4);
+    assert_eq!(peers[0].invalid_shares, 1);
+}
+
+#[test]
+fn test_unauthenticated_misbehavior_is_not_recorded() {
+    let mut table = ReputationTable::new();
+    table.record("i1", &misbehavior(1, MisbehaviorKind::InvalidShare, None));
+    assert!(table.get_all().is_empty());
+}
+
+#[test]
+fn test_authenticated_sender_of_message() {
+    let mut message = NetMessage::new(
+        String::from("i1"),
+        NetMessageMetadata::new(Channel::Gossip),
+        Vec::new(),
+    );
+    assert_eq!(message.authenticated_sender(), None);
+
+    message.set_sender(Some(5));
+    assert_eq!(message.authenticated_sender(), Some(5));
+}
+
+fn bls04_share() -> PrivateKeyShare {
+    KeyGenerator::generate_keys(
+        3,
+        5,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &ThresholdScheme::Bls04,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap()
+    .remove(0)
+}
+
+#[tokio::test]
+async fn test_signing_policy_applies_to_rotated_key() {
+    let mut keystore = KeyStore::new();
+    let first = keystore.insert_private_key(bls04_share()).unwrap();
+    let second = keystore.rotate_key(&first, bls04_share()).unwrap();
+
+    // the policy was written for the first version
+    let mut policy = SigningPolicy::new();
+    policy.add_rule(
+        &first,
+        Box::new(AllowedLabels::new(vec![b"release".to_vec()])),
+    );
+
+    let shutdown = Arc::new(Notify::new());
+    let keychain_path = std::env::temp_dir().join(format!(
+        "thetacrypt-rotated-policy-{}.json",
+        std::process::id()
+    ));
+    let (key_manager_sender, key_manager_receiver) = mpsc::channel(32);
+    let mut key_manager = KeyManager::from_keystore(
+        keystore,
+        keychain_path,
+        FrostNoncePool::new(NoncePoolSettings::default()),
+        key_manager_receiver,
+        None,
+    );
+    let shutdown_key_manager = shutdown.clone();
+    tokio::spawn(async move { key_manager.run(shutdown_key_manager).await });
+
+    let (emitter_sender, _) = start_null_emitter(shutdown.clone());
+    let (outgoing_sender, _outgoing) = mpsc::channel(32);
+    let (_incoming, incoming_receiver) = mpsc::channel(32);
+    let (commands, instance_manager_receiver) = mpsc::channel(32);
+    let mut instance_manager = InstanceManager::new(
+        key_manager_sender,
+        instance_manager_receiver,
+        commands.clone(),
+        outgoing_sender,
+        incoming_receiver,
+        emitter_sender,
+        policy,
+        AdmissionLimits::default(),
+        Duration::from_secs(10),
+        FrostOptions::Robust,
+    );
+    tokio::spawn(async move { instance_manager.run(shutdown).await });
+
+    let sign = |label: &str| {
+        let commands = commands.clone();
+        let request = StartInstanceRequest::Signature {
+            message: b"message".to_vec(),
+            label: label.as_bytes().to_vec(),
+            scheme: ThresholdScheme::Bls04,
+            group: Group::Bls12381,
+            key_id: Some(second.clone()),
+        };
+        async move {
+            let (responder, response) = oneshot::channel();
+            let create = InstanceManagerCommand::CreateInstance {
+                request,
+                client_id: None,
+                responder,
+            };
+            commands.send(create).await.unwrap();
+            response.await.unwrap()
+        }
+    };
+
+    assert!(matches!(
+        sign("other").await,
+        Err(ProtocolError::PolicyViolation(_))
+    ));
+    assert!(sign("release").await.is_ok());
+}
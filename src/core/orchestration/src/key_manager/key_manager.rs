@@ -1,21 +1,30 @@
-use std::{borrow::BorrowMut, collections::HashMap, path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc};
 
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use theta_proto::{
     scheme_types::PublicKeyEntry,
-    scheme_types::{Group, ThresholdScheme},
+    scheme_types::{Group, KeyStatus, ThresholdScheme},
 };
-use theta_protocols::frost::protocol::FrostPrecomputation;
+use theta_protocols::frost::robust::RobustFrostPrecomputation;
 use theta_schemes::{
-    dl_schemes::signatures::frost::PublicCommitment,
-    keys::key_store::{KeyEntry, KeyStore},
+    dl_schemes::signatures::frost::FrostPrivateKey,
+    keys::{
+        key_store::{KeyEntry, KeyMetadata, KeyStore},
+        keys::PrivateKeyShare,
+    },
 };
+use theta_network::{network_manager::network_manager::NetworkCommand, types::message::committee_topic};
 use tokio::sync::Notify;
 
+use super::nonce_pool::{FrostCommitmentBatch, FrostNoncePool, NoncePoolStatus};
+
 pub struct KeyManager {
     command_receiver: tokio::sync::mpsc::Receiver<KeyManagerCommand>,
     keystore: KeyStore,
-    frost_precomputes: HashMap<String, Vec<FrostPrecomputation>>,
+    keychain_path: PathBuf,
+    nonce_pool: FrostNoncePool,
+    // Used to subscribe to the committee topics of the keys in the keystore
+    network_command_sender: Option<tokio::sync::mpsc::Sender<NetworkCommand>>,
 }
 
 #[derive(Debug)]
@@ -35,21 +44,68 @@ pub enum KeyManagerCommand {
         group: Group,
         responder: tokio::sync::oneshot::Sender<Result<Arc<KeyEntry>, String>>,
     },
-    PopFrostPrecomputation {
-        key_id: String,
-        responder: tokio::sync::oneshot::Sender<Option<FrostPrecomputation>>,
+    // Refills the nonce pools of all FROST keys that run low and returns the commitments to announce
+    ReplenishFrostPools {
+        responder: tokio::sync::oneshot::Sender<Vec<FrostCommitmentBatch>>,
     },
-    PushFrostPrecomputations {
+    // Stores the commitments another node announced for us as coordinator
+    StoreFrostCommitments {
+        sender: u16,
+        batch: FrostCommitmentBatch,
+    },
+    // Takes precomputed nonces and commitments for a signing instance, if there are any
+    TakeFrostPrecomputation {
         key_id: String,
-        precomputations: Vec<FrostPrecomputation>,
+        responder: tokio::sync::oneshot::Sender<Option<RobustFrostPrecomputation>>,
+    },
+    // Returns the state of the nonce pool of every FROST key
+    GetFrostPoolStatus {
+        responder: tokio::sync::oneshot::Sender<Vec<NoncePoolStatus>>,
+    },
+    // Returns all keys (including whether a private key share is available), sorted by id
+    ListKeys {
+        responder: tokio::sync::oneshot::Sender<Vec<Arc<KeyEntry>>>,
+    },
+    // Adds a private key share with the given metadata to the keystore and returns its id. If a
+    // previous key is given, the share is added as its next version.
+    ImportKey {
+        key: PrivateKeyShare,
+        set_default: bool,
+        metadata: KeyMetadata,
+        previous: Option<String>,
+        responder: tokio::sync::oneshot::Sender<Result<String, String>>,
+    },
+    // Removes the key matching the id from the keystore
+    DeleteKey {
+        id: String,
+        responder: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    // Makes the key matching the id the default key for its scheme and group
+    SetDefaultKey {
+        id: String,
+        responder: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    // Replaces the alias, description and tags of the key matching the id
+    SetKeyMetadata {
+        id: String,
+        metadata: KeyMetadata,
+        responder: tokio::sync::oneshot::Sender<Result<(), String>>,
+    },
+    // Sets the status of the key version matching the id
+    SetKeyStatus {
+        id: String,
+        status: KeyStatus,
+        responder: tokio::sync::oneshot::Sender<Result<(), String>>,
     },
 }
 
 impl KeyManager {
     pub fn new(
         keychain_path: PathBuf,
+        nonce_pool: FrostNoncePool,
         command_receiver: tokio::sync::mpsc::Receiver<KeyManagerCommand>,
-    ) -> Self {
+        network_command_sender: Option<tokio::sync::mpsc::Sender<NetworkCommand>>,
+    ) -> Result<Self, String> {
         let mut keystore = KeyStore::new();
         if let Err(e) = keystore.load(&keychain_path) {
             error!(
@@ -59,16 +115,51 @@ impl KeyManager {
             );
         };
 
+        // Refuse to start with key shares that are corrupted or do not belong to their public key
+        let invalid: Vec<String> = keystore
+            .list_keys()
+            .iter()
+            .filter_map(|entry| {
+                let share = entry.sk.as_ref()?;
+                share.validate().err().map(|e| format!("{} ({})", entry.id, e))
+            })
+            .collect();
+        if !invalid.is_empty() {
+            for key in invalid.iter() {
+                error!("Invalid key share {}", key);
+            }
+            return Err(format!(
+                "Keystore '{}' contains invalid key shares: {}",
+                keychain_path.display(),
+                invalid.join(", ")
+            ));
+        }
+
         info!("Keychain loaded successfully");
 
+        Ok(Self::from_keystore(keystore, keychain_path, nonce_pool, command_receiver, network_command_sender))
+    }
+
+    // Creates a KeyManager for an already loaded keystore. Changes to the keystore are written
+    // to keychain_path.
+    pub fn from_keystore(
+        keystore: KeyStore,
+        keychain_path: PathBuf,
+        nonce_pool: FrostNoncePool,
+        command_receiver: tokio::sync::mpsc::Receiver<KeyManagerCommand>,
+        network_command_sender: Option<tokio::sync::mpsc::Sender<NetworkCommand>>,
+    ) -> Self {
         Self {
             command_receiver,
             keystore,
-            frost_precomputes: HashMap::new(),
+            keychain_path,
+            nonce_pool,
+            network_command_sender,
         }
     }
 
     pub async fn run(&mut self, shutdown_notify: Arc<Notify>) -> Result<(), String> {
+        self.announce_topics().await;
         loop {
             tokio::select! {
                 _ = shutdown_notify.notified() => {
@@ -85,18 +176,20 @@ impl KeyManager {
                                     let result = self.keystore.list_public_keys();
                                     responder.send(result).expect("The receiver for responder in KeyManagerCommand::GetInstanceResult has been closed.");
                                 },
-                                KeyManagerCommand::PopFrostPrecomputation {
-                                    key_id,
-                                    responder
-                                } => {
-                                    let result = self.pop_precomputation(&key_id);
-
-                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::PopFrostPrecomputation has been closed.");
-
-                                    info!("{} FROST precomputations left", self.num_precomputations());
+                                KeyManagerCommand::ReplenishFrostPools { responder } => {
+                                    let result = self.replenish_frost_pools();
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::ReplenishFrostPools has been closed.");
                                 },
-                                KeyManagerCommand::PushFrostPrecomputations { key_id, precomputations } => {
-                                    self.append_precomputations(&key_id, precomputations);
+                                KeyManagerCommand::StoreFrostCommitments { sender, batch } => {
+                                    self.store_frost_commitments(sender, batch);
+                                },
+                                KeyManagerCommand::TakeFrostPrecomputation { key_id, responder } => {
+                                    let result = self.take_frost_precomputation(&key_id);
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::TakeFrostPrecomputation has been closed.");
+                                },
+                                KeyManagerCommand::GetFrostPoolStatus { responder } => {
+                                    let result = self.frost_keys().iter().map(|(id, _)| self.nonce_pool.status(id)).collect();
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::GetFrostPoolStatus has been closed.");
                                 },
                                 KeyManagerCommand::GetKeyById {id, responder} => {
                                     info!("Searching for key with id {}", &id);
@@ -116,6 +209,39 @@ impl KeyManager {
                                     } else {
                                         responder.send(Err(result.unwrap_err())).expect("The receiver for responder in KeyManagerCommand::PopFrostPrecomputation has been closed.");
                                     }
+                                },
+                                KeyManagerCommand::ListKeys { responder } => {
+                                    let result = self.keystore.list_keys().into_iter().map(Arc::new).collect();
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::ListKeys has been closed.");
+                                },
+                                KeyManagerCommand::ImportKey { key, set_default, metadata, previous, responder } => {
+                                    let result = match previous {
+                                        Some(previous) => self.rotate_key(&previous, key, metadata),
+                                        None => self.import_key(key, set_default, metadata),
+                                    };
+                                    if result.is_ok() {
+                                        self.announce_topics().await;
+                                    }
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::ImportKey has been closed.");
+                                },
+                                KeyManagerCommand::DeleteKey { id, responder } => {
+                                    let result = self.delete_key(&id);
+                                    if result.is_ok() {
+                                        self.announce_topics().await;
+                                    }
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::DeleteKey has been closed.");
+                                },
+                                KeyManagerCommand::SetDefaultKey { id, responder } => {
+                                    let result = self.set_default_key(&id);
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::SetDefaultKey has been closed.");
+                                },
+                                KeyManagerCommand::SetKeyMetadata { id, metadata, responder } => {
+                                    let result = self.set_key_metadata(&id, metadata);
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::SetKeyMetadata has been closed.");
+                                },
+                                KeyManagerCommand::SetKeyStatus { id, status, responder } => {
+                                    let result = self.set_key_status(&id, status);
+                                    responder.send(result).expect("The receiver for responder in KeyManagerCommand::SetKeyStatus has been closed.");
                                 }
                             }
                         },
@@ -129,35 +255,221 @@ impl KeyManager {
         }
     }
 
-    pub fn num_precomputations(&self) -> usize {
-        return self.frost_precomputes.len();
+    pub fn import_key(
+        &mut self,
+        key: PrivateKeyShare,
+        set_default: bool,
+        metadata: KeyMetadata,
+    ) -> Result<String, String> {
+        key.validate().map_err(|e| e.to_string())?;
+        self.keystore
+            .check_metadata(key.get_key_id(), &metadata)
+            .map_err(|e| e.to_string())?;
+
+        let id = self
+            .keystore
+            .insert_private_key(key)
+            .map_err(|e| e.to_string())?;
+        if metadata != KeyMetadata::default() {
+            self.keystore
+                .set_metadata(&id, metadata)
+                .map_err(|e| e.to_string())?;
+        }
+
+        if set_default {
+            self.keystore
+                .set_default(&id)
+                .map_err(|e| e.to_string())?;
+        }
+
+        info!("Imported key {}", &id);
+        self.persist()?;
+        Ok(id)
     }
 
-    pub fn append_precomputations(
+    // Imports a key share as the next version of the key `previous`, see KeyStore::rotate_key.
+    // The new version takes over the alias, description and tags of the previous one. Fields set
+    // in `metadata` replace the ones taken over, the others are kept.
+    pub fn rotate_key(
         &mut self,
-        key_id: &String,
-        precomputations: Vec<FrostPrecomputation>,
-    ) {
-        let current = self.frost_precomputes.get(key_id);
-        if current.is_none() {
-            self.frost_precomputes
-                .insert(key_id.clone(), precomputations);
-            return;
+        previous: &str,
+        key: PrivateKeyShare,
+        metadata: KeyMetadata,
+    ) -> Result<String, String> {
+        key.validate().map_err(|e| e.to_string())?;
+        let replaced = self
+            .keystore
+            .get_versions(previous)
+            .map_err(|e| e.to_string())?
+            .pop()
+            .unwrap();
+        // the alias of the latest version moves to the new one, any other alias must be free
+        if metadata.alias.is_some() && metadata.alias != replaced.metadata.alias {
+            self.keystore
+                .check_metadata(key.get_key_id(), &metadata)
+                .map_err(|e| e.to_string())?;
+        }
+
+        let id = self
+            .keystore
+            .rotate_key(previous, key)
+            .map_err(|e| e.to_string())?;
+        let moved = self
+            .keystore
+            .get_key_by_id(&id)
+            .map_err(|e| e.to_string())?
+            .metadata;
+        let merged = KeyMetadata {
+            alias: metadata.alias.or(moved.alias.clone()),
+            description: metadata.description.or(moved.description.clone()),
+            tags: if metadata.tags.is_empty() {
+                moved.tags.clone()
+            } else {
+                metadata.tags
+            },
+            ..moved.clone()
+        };
+        if merged != moved {
+            self.keystore
+                .set_metadata(&id, merged)
+                .map_err(|e| e.to_string())?;
+        }
+
+        info!("Imported key {} as the next version of {}", &id, previous);
+        self.persist()?;
+        Ok(id)
+    }
+
+    pub fn delete_key(&mut self, id: &str) -> Result<(), String> {
+        let entry = self.keystore.remove_key(id).map_err(|e| e.to_string())?;
+        if let Err(e) = self.nonce_pool.remove_key(&entry.id) {
+            error!("Error removing the FROST nonce pool of key {}: {}", entry.id, e);
         }
 
-        self.frost_precomputes.insert(
-            key_id.clone(),
-            [precomputations, current.unwrap().clone()].concat(),
-        );
+        info!("Deleted key {}", entry.id);
+        self.persist()
+    }
+
+    pub fn set_default_key(&mut self, id: &str) -> Result<(), String> {
+        self.keystore.set_default(id).map_err(|e| e.to_string())?;
+
+        info!("Key {} is now the default for its scheme and group", id);
+        self.persist()
+    }
+
+    pub fn set_key_status(&mut self, id: &str, status: KeyStatus) -> Result<(), String> {
+        self.keystore
+            .set_status(id, status)
+            .map_err(|e| e.to_string())?;
+
+        info!("Key {} is now {}", id, status.as_str_name());
+        self.persist()
+    }
+
+    pub fn set_key_metadata(&mut self, id: &str, metadata: KeyMetadata) -> Result<(), String> {
+        self.keystore
+            .set_metadata(id, metadata)
+            .map_err(|e| e.to_string())?;
+
+        info!("Updated the metadata of key {}", id);
+        self.persist()
+    }
+
+    // Subscribes the network layer to the committee topics of all keys this node holds a share of.
+    async fn announce_topics(&self) {
+        let sender = match &self.network_command_sender {
+            Some(sender) => sender,
+            None => return,
+        };
+
+        let topics: Vec<String> = self
+            .keystore
+            .list_keys()
+            .into_iter()
+            .filter(|entry| entry.sk.is_some())
+            .map(|entry| committee_topic(&entry.id))
+            .collect();
+        debug!("Subscribing to {} committee topics", topics.len());
+        if sender.send(NetworkCommand::SetTopics { topics }).await.is_err() {
+            error!("Could not update the committee topics, the network command channel is closed");
+        }
     }
 
-    pub fn pop_precomputation(&mut self, key_id: &String) -> Option<FrostPrecomputation> {
-        let mut vec = self.frost_precomputes.get_mut(key_id);
-        if vec.is_none() {
+    // Writes the keystore back to the keychain file. The in-memory keystore stays updated even if
+    // writing fails, so the error tells the caller that the change is lost on restart.
+    fn persist(&self) -> Result<(), String> {
+        let path = match self.keychain_path.to_str() {
+            Some(path) => path,
+            None => return Err(String::from("Keychain path is not valid UTF-8")),
+        };
+
+        if let Err(e) = self.keystore.to_file(path) {
+            error!(
+                "Error writing keystore '{}': {}",
+                self.keychain_path.display(),
+                e.to_string()
+            );
+            return Err(format!(
+                "The change was applied but could not be persisted: {}",
+                e
+            ));
+        }
+
+        Ok(())
+    }
+
+    // Returns the id and private key share of every active FROST key.
+    fn frost_keys(&self) -> Vec<(String, FrostPrivateKey)> {
+        self.keystore
+            .list_keys()
+            .into_iter()
+            .filter(|entry| entry.status() == KeyStatus::Active)
+            .filter_map(|entry| match entry.sk {
+                Some(PrivateKeyShare::Frost(key)) => Some((entry.id, key)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn replenish_frost_pools(&mut self) -> Vec<FrostCommitmentBatch> {
+        let mut batches = Vec::new();
+        for (key_id, key) in self.frost_keys() {
+            match self.nonce_pool.replenish(&key_id, &key) {
+                Ok(Some(batch)) => batches.push(batch),
+                Ok(None) => {}
+                // nothing is announced for nonces that could not be persisted
+                Err(e) => error!("Error replenishing the FROST nonce pool of key {}: {}", key_id, e),
+            }
+        }
+        batches
+    }
+
+    pub fn store_frost_commitments(&mut self, sender: u16, batch: FrostCommitmentBatch) {
+        let key = self
+            .frost_keys()
+            .into_iter()
+            .find(|(id, _)| *id == batch.key_id);
+
+        match key {
+            Some((_, key)) => {
+                let stored = self.nonce_pool.store_commitments(&key, sender, batch);
+                debug!("Stored {} FROST commitments", stored);
+            }
+            None => warn!("Ignoring FROST commitments for unknown key {}", batch.key_id),
+        }
+    }
+
+    pub fn take_frost_precomputation(&mut self, key_id: &str) -> Option<RobustFrostPrecomputation> {
+        if !self.frost_keys().iter().any(|(id, _)| id == key_id) {
             return None;
         }
 
-        let mut vec = vec.unwrap();
-        vec.pop()
+        match self.nonce_pool.take(key_id) {
+            Ok(precomputation) => precomputation,
+            Err(e) => {
+                error!("Error taking from the FROST nonce pool of key {}: {}", key_id, e);
+                None
+            }
+        }
     }
 }
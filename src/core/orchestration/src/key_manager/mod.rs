@@ -1 +1,4 @@
 pub mod key_manager;
+pub mod nonce_pool;
+#[cfg(test)]
+mod tests;
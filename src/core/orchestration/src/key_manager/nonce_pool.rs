@@ -0,0 +1,399 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use theta_network::types::message::{committee_topic, Channel, NetMessage, NetMessageMetadata};
+use theta_protocols::frost::robust::{coordinators, RobustFrostPrecomputation};
+use theta_schemes::{
+    dl_schemes::signatures::frost::{
+        commit, sign_with_share, verify_with_share, FrostPrivateKey, FrostShareSignature, Nonce,
+        PublicCommitment,
+    },
+    interface::Serializable,
+    rand::{RngAlgorithm, RNG},
+};
+
+/// Instance id under which nodes exchange the commitments of their precomputed nonces.
+pub const FROST_POOL_INSTANCE_ID: &str = "frost-nonce-pool";
+
+/// Settings of the FROST nonce pool.
+#[derive(Debug, Clone)]
+pub struct NoncePoolSettings {
+    /// File the pool is persisted to. Without a file, the pool only lives in memory and is lost
+    /// (never reused) on restart.
+    pub path: Option<PathBuf>,
+    /// Number of nonces kept available per key and coordinator.
+    pub target_size: usize,
+    /// A key is refilled to `target_size` once fewer than `low_watermark` nonces are left for one
+    /// of its coordinators.
+    pub low_watermark: usize,
+}
+
+impl Default for NoncePoolSettings {
+    fn default() -> Self {
+        Self {
+            path: None,
+            target_size: 20,
+            low_watermark: 5,
+        }
+    }
+}
+
+/// Commitments of precomputed nonces, announced by a signer to all coordinators.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FrostCommitmentBatch {
+    pub key_id: String,
+    pub signer: u16,
+    /// Entries of the form (coordinator, sequence number, commitment).
+    pub commitments: Vec<(u16, u64, PublicCommitment)>,
+    /// Signature of the signer over the other fields, made with its key share.
+    pub signature: FrostShareSignature,
+}
+
+impl FrostCommitmentBatch {
+    /// Creates a batch of the commitments of `key`'s share and signs it with the share.
+    pub fn new(
+        key_id: &str,
+        key: &FrostPrivateKey,
+        commitments: Vec<(u16, u64, PublicCommitment)>,
+    ) -> Self {
+        let signer = key.get_share_id();
+        let message = FrostCommitmentBatch::signed_data(key_id, signer, &commitments);
+        let signature = sign_with_share(key, &message, &mut RNG::new(RngAlgorithm::OsRng));
+        Self {
+            key_id: key_id.to_string(),
+            signer,
+            commitments,
+            signature,
+        }
+    }
+
+    /// Checks that the batch was signed by the share of `signer` of `key`.
+    pub fn verify(&self, key: &FrostPrivateKey) -> bool {
+        let message =
+            FrostCommitmentBatch::signed_data(&self.key_id, self.signer, &self.commitments);
+        self.signature.get_id() == self.signer
+            && verify_with_share(&self.signature, key.get_public_key(), &message)
+    }
+
+    fn signed_data(
+        key_id: &str,
+        signer: u16,
+        commitments: &[(u16, u64, PublicCommitment)],
+    ) -> Vec<u8> {
+        serde_json::to_vec(&(key_id, signer, commitments))
+            .expect("Error serializing FrostCommitmentBatch")
+    }
+
+    pub fn to_net_message(&self) -> NetMessage {
+        let data = serde_json::to_vec(self).expect("Error serializing FrostCommitmentBatch");
+        let mut metadata = NetMessageMetadata::new(Channel::Gossip);
+        metadata.set_topic(committee_topic(&self.key_id));
+        NetMessage::new(FROST_POOL_INSTANCE_ID.to_string(), metadata, data)
+    }
+
+    pub fn from_net_message(message: &NetMessage) -> Result<Self, String> {
+        serde_json::from_slice(message.get_message_data())
+            .map_err(|e| format!("Invalid FROST commitment batch: {}", e))
+    }
+}
+
+/// Number of precomputed nonces and commitments available for a key.
+#[derive(Debug, Clone)]
+pub struct NoncePoolStatus {
+    pub key_id: String,
+    /// Own nonces left, per coordinator.
+    pub nonces: BTreeMap<u16, usize>,
+    /// Commitments of other signers available to this node as coordinator, per signer.
+    pub commitments: BTreeMap<u16, usize>,
+    pub target_size: usize,
+    pub low_watermark: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct StoredNonce {
+    seq: u64,
+    // hex encoding of the serialized nonce
+    nonce: String,
+    commitment: PublicCommitment,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct KeyPool {
+    next_seq: u64,
+    // own nonces per coordinator, in the order in which they are used
+    nonces: BTreeMap<u16, VecDeque<StoredNonce>>,
+    // commitments of other signers for this node as coordinator, per signer
+    commitments: BTreeMap<u16, VecDeque<(u64, PublicCommitment)>>,
+    // highest sequence number received from each signer
+    #[serde(default)]
+    received: BTreeMap<u16, u64>,
+    // whether the nonces loaded from disk have been announced since startup
+    #[serde(skip)]
+    announced: bool,
+}
+
+/// FrostNoncePool keeps precomputed FROST nonces of this node and the commitments other nodes
+/// precomputed for it as coordinator, so that signing instances can start right away.
+///
+/// Every instance takes one nonce per coordinator and one commitment per signer, whether it ends
+/// up using them or not, so that all nodes consume their pools in the same order. A nonce is
+/// removed from the pool file before it is handed to an instance, hence it is never used twice,
+/// not even across restarts.
+pub struct FrostNoncePool {
+    settings: NoncePoolSettings,
+    pools: HashMap<String, KeyPool>,
+}
+
+impl FrostNoncePool {
+    /// Creates an empty pool.
+    pub fn new(settings: NoncePoolSettings) -> Self {
+        Self {
+            settings,
+            pools: HashMap::new(),
+        }
+    }
+
+    /// Loads the pool from the file in `settings`, or creates an empty pool if the file does not exist.
+    pub fn load(settings: NoncePoolSettings) -> Result<Self, String> {
+        let mut pool = Self::new(settings);
+        let path = match &pool.settings.path {
+            Some(path) if path.exists() => path.clone(),
+            _ => return Ok(pool),
+        };
+
+        let data = fs::read_to_string(&path)
+            .map_err(|e| format!("Error reading FROST nonce pool '{}': {}", path.display(), e))?;
+        pool.pools = serde_json::from_str(&data)
+            .map_err(|e| format!("Error parsing FROST nonce pool '{}': {}", path.display(), e))?;
+
+        info!("Loaded FROST nonce pool for {} key(s)", pool.pools.len());
+        Ok(pool)
+    }
+
+    /// Writes the pool to its file. The file is replaced atomically and is only readable by its owner.
+    pub fn persist(&self) -> Result<(), String> {
+        let path = match &self.settings.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let data = serde_json::to_vec(&self.pools)
+            .map_err(|e| format!("Error serializing FROST nonce pool: {}", e))?;
+
+        let tmp_path = PathBuf::from(format!("{}.tmp", path.display()));
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(&tmp_path)
+            .map_err(|e| format!("Error writing FROST nonce pool: {}", e))?;
+        file.write_all(&data)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Error writing FROST nonce pool: {}", e))?;
+        fs::rename(&tmp_path, path).map_err(|e| format!("Error writing FROST nonce pool: {}", e))
+    }
+
+    /// Refills the nonces of `key` if one of its coordinators runs low. The new nonces are
+    /// persisted before their commitments are returned for broadcasting. Right after startup, the
+    /// batch also contains the commitments of all nonces loaded from disk, in case a peer missed them.
+    pub fn replenish(
+        &mut self,
+        key_id: &str,
+        key: &FrostPrivateKey,
+    ) -> Result<Option<FrostCommitmentBatch>, String> {
+        let target_size = self.settings.target_size;
+        let low_watermark = self.settings.low_watermark;
+        let pool = self.pools.entry(key_id.to_string()).or_default();
+
+        let mut commitments = Vec::new();
+        if !pool.announced {
+            for (coordinator, nonces) in pool.nonces.iter() {
+                for stored in nonces {
+                    commitments.push((*coordinator, stored.seq, stored.commitment.clone()));
+                }
+            }
+            pool.announced = true;
+        }
+
+        let coordinators = coordinators(key);
+        let runs_low = coordinators.iter().any(|c| {
+            pool.nonces.get(c).map_or(0, |nonces| nonces.len()) < low_watermark
+        });
+
+        let mut generated = 0;
+        if runs_low {
+            let mut rng = RNG::new(RngAlgorithm::OsRng);
+            for coordinator in coordinators {
+                let nonces = pool.nonces.entry(coordinator).or_default();
+                while nonces.len() < target_size {
+                    let (commitment, nonce) = commit(key, &mut rng);
+                    let nonce = nonce
+                        .to_bytes()
+                        .map_err(|e| format!("Error serializing nonce: {}", e))?;
+                    let seq = pool.next_seq;
+                    pool.next_seq += 1;
+
+                    commitments.push((coordinator, seq, commitment.clone()));
+                    nonces.push_back(StoredNonce {
+                        seq,
+                        nonce: hex::encode(nonce),
+                        commitment,
+                    });
+                    generated += 1;
+                }
+            }
+        }
+
+        if commitments.is_empty() {
+            return Ok(None);
+        }
+
+        if generated > 0 {
+            info!("Precomputed {} FROST nonces for key {}", generated, key_id);
+            self.persist()?;
+        }
+
+        Ok(Some(FrostCommitmentBatch::new(key_id, key, commitments)))
+    }
+
+    /// Stores the commitments of a batch that are addressed to this node as coordinator. `sender`
+    /// is the node that the batch was received from, as authenticated by the network. Returns the
+    /// number of stored commitments.
+    pub fn store_commitments(
+        &mut self,
+        key: &FrostPrivateKey,
+        sender: u16,
+        batch: FrostCommitmentBatch,
+    ) -> usize {
+        let own_id = key.get_share_id();
+        if batch.signer == own_id {
+            return 0;
+        }
+        if batch.signer != sender {
+            warn!(
+                "Ignoring FROST commitments of signer {} sent by node {}",
+                batch.signer, sender
+            );
+            return 0;
+        }
+        if !batch.verify(key) {
+            warn!(
+                "Ignoring FROST commitments of signer {} with an invalid signature",
+                batch.signer
+            );
+            return 0;
+        }
+
+        // a misbehaving peer must not be able to fill our memory
+        let capacity = 2 * self.settings.target_size;
+        // A signer numbers its nonces for all coordinators in one sequence, so between two of our
+        // commitments it cannot have precomputed more than this many nonces.
+        let max_gap = (capacity * key.get_public_key().get_n() as usize) as u64;
+        let pool = self.pools.entry(batch.key_id.clone()).or_default();
+        let stored = pool.commitments.entry(batch.signer).or_default();
+
+        let mut count = 0;
+        for (coordinator, seq, commitment) in batch.commitments {
+            if coordinator != own_id || commitment.get_id() != batch.signer {
+                continue;
+            }
+            if let Some(last) = pool.received.get(&batch.signer) {
+                // commitments are announced in order, older or repeated ones are ignored
+                if *last >= seq {
+                    continue;
+                }
+                // a jump would block all later commitments of the signer, it is not stored
+                if seq - *last > max_gap {
+                    warn!(
+                        "Ignoring FROST commitment {} of signer {}, the last one was {}",
+                        seq, batch.signer, last
+                    );
+                    break;
+                }
+            }
+            if stored.len() >= capacity {
+                warn!(
+                    "Dropping FROST commitments of signer {}: pool is full",
+                    batch.signer
+                );
+                break;
+            }
+            stored.push_back((seq, commitment));
+            pool.received.insert(batch.signer, seq);
+            count += 1;
+        }
+
+        count
+    }
+
+    /// Takes the precomputed material for one signing instance of a key, if there is any. The
+    /// taken nonces are removed from the pool file before they are returned.
+    pub fn take(&mut self, key_id: &str) -> Result<Option<RobustFrostPrecomputation>, String> {
+        let pool = match self.pools.get_mut(key_id) {
+            Some(pool) => pool,
+            None => return Ok(None),
+        };
+
+        let mut nonces = HashMap::new();
+        for (coordinator, stored) in pool.nonces.iter_mut() {
+            if let Some(stored) = stored.pop_front() {
+                let bytes = hex::decode(&stored.nonce)
+                    .map_err(|e| format!("Invalid nonce in pool: {}", e))?;
+                let nonce = Nonce::from_bytes(&bytes)
+                    .map_err(|e| format!("Invalid nonce in pool: {}", e))?;
+                nonces.insert(*coordinator, (nonce, stored.commitment));
+            }
+        }
+
+        let mut commitments = BTreeMap::new();
+        for (signer, stored) in pool.commitments.iter_mut() {
+            if let Some((_, commitment)) = stored.pop_front() {
+                commitments.insert(*signer, commitment);
+            }
+        }
+
+        if nonces.is_empty() && commitments.is_empty() {
+            return Ok(None);
+        }
+
+        // if this fails, the nonces are dropped instead of being used without a persisted record
+        self.persist()?;
+
+        Ok(Some(RobustFrostPrecomputation {
+            nonces,
+            commitments,
+        }))
+    }
+
+    /// Discards everything stored for a key.
+    pub fn remove_key(&mut self, key_id: &str) -> Result<(), String> {
+        if self.pools.remove(key_id).is_some() {
+            return self.persist();
+        }
+        Ok(())
+    }
+
+    pub fn status(&self, key_id: &str) -> NoncePoolStatus {
+        let pool = self.pools.get(key_id);
+        NoncePoolStatus {
+            key_id: key_id.to_string(),
+            nonces: pool.map_or(BTreeMap::new(), |p| {
+                p.nonces.iter().map(|(c, n)| (*c, n.len())).collect()
+            }),
+            commitments: pool.map_or(BTreeMap::new(), |p| {
+                p.commitments.iter().map(|(s, c)| (*s, c.len())).collect()
+            }),
+            target_size: self.settings.target_size,
+            low_watermark: self.settings.low_watermark,
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use std::path::PathBuf;
+
+use theta_schemes::{
+    dl_schemes::signatures::frost::FrostPrivateKey,
+    integers::sizedint::SizedBigInt,
+    interface::{Group, ThresholdScheme},
+    keys::{
+        key_generator::KeyGenerator,
+        key_store::{KeyMetadata, KeyStore},
+        keys::PrivateKeyShare,
+    },
+    rand::{RngAlgorithm, RNG},
+};
+
+use crate::key_manager::{
+    key_manager::KeyManager,
+    nonce_pool::{FrostCommitmentBatch, FrostNoncePool, NoncePoolSettings},
+};
+
+const KEY_ID: &str = "frost-key";
+
+fn frost_keys() -> Vec<FrostPrivateKey> {
+    KeyGenerator::generate_keys(
+        3,
+        5,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &ThresholdScheme::Frost,
+        &Group::Ed25519,
+        &Option::None,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|key| match key {
+        PrivateKeyShare::Frost(key) => key,
+        _ => panic!("expected a FROST key"),
+    })
+    .collect()
+}
+
+fn settings(path: Option<PathBuf>) -> NoncePoolSettings {
+    NoncePoolSettings {
+        path,
+        target_size: 4,
+        low_watermark: 2,
+    }
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("thetacrypt-{}-{}.json", name, std::process::id()))
+}
+
+#[test]
+fn test_replenish_only_when_low() {
+    let keys = frost_keys();
+    let mut pool = FrostNoncePool::new(settings(None));
+
+    let batch = pool.replenish(KEY_ID, &keys[0]).unwrap().unwrap();
+    // coordinators 1..=n-t+1, target_size nonces each
+    assert_eq!(batch.commitments.len(), 3 * 4);
+    assert!(pool.replenish(KEY_ID, &keys[0]).unwrap().is_none());
+
+    pool.take(KEY_ID).unwrap().unwrap();
+    pool.take(KEY_ID).unwrap().unwrap();
+    assert!(pool.replenish(KEY_ID, &keys[0]).unwrap().is_none());
+
+    pool.take(KEY_ID).unwrap().unwrap();
+    let batch = pool.replenish(KEY_ID, &keys[0]).unwrap().unwrap();
+    assert_eq!(batch.commitments.len(), 3 * 3);
+    assert!(pool.status(KEY_ID).nonces.values().all(|n| *n == 4));
+}
+
+#[test]
+fn test_taken_nonces_are_not_reloaded() {
+    let keys = frost_keys();
+    let path = temp_path("nonce-pool");
+
+    let mut pool = FrostNoncePool::load(settings(Some(path.clone()))).unwrap();
+    pool.replenish(KEY_ID, &keys[0]).unwrap();
+    let taken = pool.take(KEY_ID).unwrap().unwrap();
+
+    let mut reloaded = FrostNoncePool::load(settings(Some(path.clone()))).unwrap();
+    assert!(reloaded.status(KEY_ID).nonces.values().all(|n| *n == 3));
+
+    // after a restart, the remaining nonces are announced again
+    let batch = reloaded.replenish(KEY_ID, &keys[0]).unwrap().unwrap();
+    assert_eq!(batch.commitments.len(), 3 * 3);
+    for (coordinator, (_, commitment)) in taken.nonces.iter() {
+        assert!(!batch
+            .commitments
+            .iter()
+            .any(|(c, _, other)| c == coordinator && other == commitment));
+    }
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_store_commitments() {
+    let keys = frost_keys();
+    let mut signer_pool = FrostNoncePool::new(settings(None));
+    let mut coordinator_pool = FrostNoncePool::new(settings(None));
+
+    let batch = signer_pool.replenish(KEY_ID, &keys[3]).unwrap().unwrap();
+
+    // only the commitments for the coordinator itself are kept, and each of them only once
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, batch.clone()), 4);
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, batch.clone()), 0);
+    assert_eq!(coordinator_pool.status(KEY_ID).commitments.get(&4), Some(&4));
+
+    // a batch claiming to come from another signer is ignored
+    let mut spoofed = batch.clone();
+    spoofed.signer = 5;
+    assert_eq!(coordinator_pool.store_commitments(&keys[1], 5, spoofed), 0);
+
+    let precomputation = coordinator_pool.take(KEY_ID).unwrap().unwrap();
+    assert!(precomputation.nonces.is_empty());
+    assert_eq!(precomputation.commitments.len(), 1);
+    assert_eq!(precomputation.commitments[&4].get_id(), 4);
+}
+
+#[test]
+fn test_commitments_are_bound_to_their_sender() {
+    let keys = frost_keys();
+    let mut signer_pool = FrostNoncePool::new(settings(None));
+    let mut coordinator_pool = FrostNoncePool::new(settings(None));
+
+    let batch = signer_pool.replenish(KEY_ID, &keys[3]).unwrap().unwrap();
+
+    // relayed by another node
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 5, batch.clone()), 0);
+
+    // changed after it was signed
+    let mut modified = batch.clone();
+    modified.commitments.truncate(1);
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, modified), 0);
+
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, batch), 4);
+}
+
+#[test]
+fn test_sequence_jumps_are_rejected() {
+    let keys = frost_keys();
+    let mut signer_pool = FrostNoncePool::new(settings(None));
+    let mut coordinator_pool = FrostNoncePool::new(settings(None));
+
+    let batch = signer_pool.replenish(KEY_ID, &keys[3]).unwrap().unwrap();
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, batch.clone()), 4);
+
+    // a signed commitment with the highest sequence number would block all later ones
+    let (coordinator, _, commitment) = batch
+        .commitments
+        .iter()
+        .find(|(c, _, _)| *c == 1)
+        .unwrap()
+        .clone();
+    let jump =
+        FrostCommitmentBatch::new(KEY_ID, &keys[3], vec![(coordinator, u64::MAX, commitment)]);
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, jump), 0);
+
+    // the signer's next commitments are still accepted
+    signer_pool.take(KEY_ID).unwrap().unwrap();
+    signer_pool.take(KEY_ID).unwrap().unwrap();
+    signer_pool.take(KEY_ID).unwrap().unwrap();
+    let batch = signer_pool.replenish(KEY_ID, &keys[3]).unwrap().unwrap();
+    assert_eq!(coordinator_pool.store_commitments(&keys[0], 4, batch), 3);
+}
+
+#[test]
+fn test_invalid_key_shares_are_rejected_on_load() {
+    let keys = frost_keys();
+    let path = temp_path("invalid-keystore");
+
+    // the share does not match the verification key of party 1
+    let mut keystore = KeyStore::new();
+    let x = SizedBigInt::new_int(&Group::Ed25519, 42);
+    let invalid = FrostPrivateKey::new(1, &x, keys[0].get_public_key());
+    keystore.insert_private_key(PrivateKeyShare::Frost(invalid)).unwrap();
+    keystore.to_file(path.to_str().unwrap()).unwrap();
+
+    let (_, receiver) = tokio::sync::mpsc::channel(1);
+    let pool = FrostNoncePool::new(settings(None));
+    let result = KeyManager::new(path.clone(), pool, receiver, None);
+    assert!(result.is_err());
+
+    let mut keystore = KeyStore::new();
+    keystore.insert_private_key(PrivateKeyShare::Frost(keys[0].clone())).unwrap();
+    keystore.to_file(path.to_str().unwrap()).unwrap();
+
+    let (_, receiver) = tokio::sync::mpsc::channel(1);
+    let pool = FrostNoncePool::new(settings(None));
+    let result = KeyManager::new(path.clone(), pool, receiver, None);
+    assert!(result.is_ok());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_rotation_keeps_alias_and_merges_metadata() {
+    let path = temp_path("rotation-keystore");
+    let mut keystore = KeyStore::new();
+    let first = keystore
+        .insert_private_key(PrivateKeyShare::Frost(frost_keys()[0].clone()))
+        .unwrap();
+    let metadata = KeyMetadata {
+        alias: Some(String::from("signer")),
+        description: Some(String::from("release signing")),
+        tags: vec![String::from("prod")],
+        ..Default::default()
+    };
+    keystore.set_metadata(&first, metadata).unwrap();
+
+    let (_, receiver) = tokio::sync::mpsc::channel(1);
+    let pool = FrostNoncePool::new(settings(None));
+    let mut key_manager = KeyManager::from_keystore(keystore, path.clone(), pool, receiver, None);
+
+    // only the tags are given, the alias and description move to the new version
+    let tags = KeyMetadata {
+        tags: vec![String::from("rotated")],
+        ..Default::default()
+    };
+    let second = key_manager
+        .rotate_key(
+            "signer",
+            PrivateKeyShare::Frost(frost_keys()[0].clone()),
+            tags,
+        )
+        .unwrap();
+
+    let keystore = KeyStore::from_file(&path).unwrap();
+    let rotated = keystore.get_key_by_id("signer").unwrap();
+    assert_eq!(rotated.id, second);
+    assert_eq!(
+        rotated.metadata.description.as_deref(),
+        Some("release signing")
+    );
+    assert_eq!(rotated.metadata.tags, vec![String::from("rotated")]);
+    assert_eq!(keystore.get_key_by_id(&first).unwrap().metadata.alias, None);
+
+    // without metadata, everything moves to the next version
+    let third = key_manager
+        .rotate_key(
+            &second,
+            PrivateKeyShare::Frost(frost_keys()[0].clone()),
+            KeyMetadata::default(),
+        )
+        .unwrap();
+
+    let keystore = KeyStore::from_file(&path).unwrap();
+    let rotated = keystore.get_key_by_id("signer").unwrap();
+    assert_eq!(rotated.id, third);
+    assert_eq!(rotated.metadata.tags, vec![String::from("rotated")]);
+
+    std::fs::remove_file(&path).unwrap();
+}
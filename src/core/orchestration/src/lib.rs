@@ -1,3 +1,6 @@
 pub mod instance_manager;
 pub mod key_manager;
 pub mod interface;
+pub mod signing_policy;
+#[cfg(any(test, feature = "test-harness"))]
+pub mod test_harness;
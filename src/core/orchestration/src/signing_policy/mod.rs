@@ -0,0 +1,4 @@
+pub mod rules;
+pub mod signing_policy;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,211 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// A SigningRule is evaluated by a node before it produces a signature share for a message.
+///
+/// `check` must not change the state of the rule. `record` is called once all rules of a key
+/// accepted a request, so that stateful rules (e.g., rate limits) only account for requests
+/// which actually get signed.
+pub trait SigningRule: Send {
+    fn check(&self, message: &[u8], label: &[u8]) -> Result<(), String>;
+
+    fn record(&mut self) {}
+}
+
+/// Serializable description of the built-in rules, as used in the signing policy file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleConfig {
+    /// Messages must not be longer than `max_bytes`.
+    MaxMessageLength { max_bytes: usize },
+    /// The label of the request must be one of `labels`.
+    AllowedLabels { labels: Vec<String> },
+    /// Messages must be JSON objects containing all `required_fields`.
+    Json { required_fields: Vec<String> },
+    /// The numeric JSON field `field` (dot separated path) must exist and be at most `max`.
+    JsonNumberLimit { field: String, max: f64 },
+    /// At most `max_requests` messages are signed within `period_secs` seconds.
+    RateLimit { max_requests: usize, period_secs: u64 },
+}
+
+impl RuleConfig {
+    pub fn build(&self) -> Box<dyn SigningRule> {
+        match self {
+            RuleConfig::MaxMessageLength { max_bytes } => Box::new(MaxMessageLength::new(*max_bytes)),
+            RuleConfig::AllowedLabels { labels } => Box::new(AllowedLabels::new(
+                labels.iter().map(|l| l.as_bytes().to_vec()).collect(),
+            )),
+            RuleConfig::Json { required_fields } => Box::new(JsonFormat::new(required_fields.clone())),
+            RuleConfig::JsonNumberLimit { field, max } => {
+                Box::new(JsonNumberLimit::new(field, *max))
+            }
+            RuleConfig::RateLimit {
+                max_requests,
+                period_secs,
+            } => Box::new(RateLimit::new(
+                *max_requests,
+                Duration::from_secs(*period_secs),
+            )),
+        }
+    }
+}
+
+pub struct MaxMessageLength {
+    max_bytes: usize,
+}
+
+impl MaxMessageLength {
+    pub fn new(max_bytes: usize) -> Self {
+        Self { max_bytes }
+    }
+}
+
+impl SigningRule for MaxMessageLength {
+    fn check(&self, message: &[u8], _label: &[u8]) -> Result<(), String> {
+        if message.len() > self.max_bytes {
+            return Err(format!(
+                "message has {} bytes, at most {} are allowed",
+                message.len(),
+                self.max_bytes
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct AllowedLabels {
+    labels: Vec<Vec<u8>>,
+}
+
+impl AllowedLabels {
+    pub fn new(labels: Vec<Vec<u8>>) -> Self {
+        Self { labels }
+    }
+}
+
+impl SigningRule for AllowedLabels {
+    fn check(&self, _message: &[u8], label: &[u8]) -> Result<(), String> {
+        if !self.labels.iter().any(|l| l == label) {
+            return Err(format!(
+                "label '{}' is not allowed",
+                String::from_utf8_lossy(label)
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub struct JsonFormat {
+    required_fields: Vec<String>,
+}
+
+impl JsonFormat {
+    pub fn new(required_fields: Vec<String>) -> Self {
+        Self { required_fields }
+    }
+}
+
+impl SigningRule for JsonFormat {
+    fn check(&self, message: &[u8], _label: &[u8]) -> Result<(), String> {
+        let value = parse_json_object(message)?;
+        for field in &self.required_fields {
+            if lookup(&value, field).is_none() {
+                return Err(format!("message is missing the field '{}'", field));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub struct JsonNumberLimit {
+    field: String,
+    max: f64,
+}
+
+impl JsonNumberLimit {
+    pub fn new(field: &str, max: f64) -> Self {
+        Self {
+            field: field.to_string(),
+            max,
+        }
+    }
+}
+
+impl SigningRule for JsonNumberLimit {
+    fn check(&self, message: &[u8], _label: &[u8]) -> Result<(), String> {
+        let value = parse_json_object(message)?;
+        let number = match lookup(&value, &self.field).and_then(|v| v.as_f64()) {
+            Some(number) => number,
+            None => return Err(format!("message has no numeric field '{}'", self.field)),
+        };
+        if number > self.max {
+            return Err(format!(
+                "field '{}' is {}, at most {} is allowed",
+                self.field, number, self.max
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Sliding window rate limit.
+pub struct RateLimit {
+    max_requests: usize,
+    period: Duration,
+    accepted: VecDeque<Instant>,
+}
+
+impl RateLimit {
+    pub fn new(max_requests: usize, period: Duration) -> Self {
+        Self {
+            max_requests,
+            period,
+            accepted: VecDeque::new(),
+        }
+    }
+
+    fn requests_in_window(&self, now: Instant) -> usize {
+        self.accepted
+            .iter()
+            .filter(|t| now.duration_since(**t) < self.period)
+            .count()
+    }
+}
+
+impl SigningRule for RateLimit {
+    fn check(&self, _message: &[u8], _label: &[u8]) -> Result<(), String> {
+        if self.requests_in_window(Instant::now()) >= self.max_requests {
+            return Err(format!(
+                "rate limit of {} signatures per {}s exceeded",
+                self.max_requests,
+                self.period.as_secs()
+            ));
+        }
+        Ok(())
+    }
+
+    fn record(&mut self) {
+        let now = Instant::now();
+        while let Some(t) = self.accepted.front() {
+            if now.duration_since(*t) < self.period {
+                break;
+            }
+            self.accepted.pop_front();
+        }
+        self.accepted.push_back(now);
+    }
+}
+
+fn parse_json_object(message: &[u8]) -> Result<serde_json::Value, String> {
+    match serde_json::from_slice::<serde_json::Value>(message) {
+        Ok(value) if value.is_object() => Ok(value),
+        Ok(_) => Err("message is not a JSON object".to_string()),
+        Err(e) => Err(format!("message is not valid JSON: {}", e)),
+    }
+}
+
+fn lookup<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    path.split('.').try_fold(value, |v, key| v.get(key))
+}
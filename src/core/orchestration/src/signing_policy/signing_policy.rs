@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use super::rules::{RuleConfig, SigningRule};
+
+/// Serializable form of a signing policy, as stored in the signing policy file.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SigningPolicyConfig {
+    /// Rules per key id. All rules of a key must accept a request.
+    #[serde(default)]
+    pub keys: HashMap<String, Vec<RuleConfig>>,
+    /// If true, keys without an entry in `keys` must not sign anything.
+    #[serde(default)]
+    pub deny_unlisted: bool,
+}
+
+/// SigningPolicy holds the validation rules of every signing key. It is evaluated by each node
+/// before it creates a signature instance, so a node never produces a signature share for a
+/// message that violates the policy of the key, regardless of who requested the signature.
+pub struct SigningPolicy {
+    rules: HashMap<String, Vec<Box<dyn SigningRule>>>,
+    deny_unlisted: bool,
+}
+
+impl SigningPolicy {
+    /// Creates an empty policy, which allows every key to sign every message.
+    pub fn new() -> Self {
+        Self {
+            rules: HashMap::new(),
+            deny_unlisted: false,
+        }
+    }
+
+    pub fn from_config(config: &SigningPolicyConfig) -> Self {
+        let mut policy = Self::new();
+        policy.deny_unlisted = config.deny_unlisted;
+        for (key_id, rules) in &config.keys {
+            for rule in rules {
+                policy.add_rule(key_id, rule.build());
+            }
+        }
+        policy
+    }
+
+    /// Read a signing policy from a JSON encoding on disk.
+    pub fn from_file(file: &PathBuf) -> Result<SigningPolicy, String> {
+        let data = match fs::read_to_string(file) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Error reading signing policy file: {}", e)),
+        };
+
+        match SigningPolicy::from_json(&data) {
+            Ok(policy) => Ok(policy),
+            Err(e) => Err(format!("Error parsing signing policy file: {}", e)),
+        }
+    }
+
+    /// Build a signing policy based on a JSON serialization.
+    pub fn from_json(data: &str) -> Result<SigningPolicy, String> {
+        let config: SigningPolicyConfig = match serde_json::from_str(data) {
+            Ok(config) => config,
+            Err(e) => return Err(format!("Invalid JSON: {}", e)),
+        };
+
+        Ok(SigningPolicy::from_config(&config))
+    }
+
+    /// Adds a rule for the given key. This is the extension point for custom validation logic.
+    pub fn add_rule(&mut self, key_id: &str, rule: Box<dyn SigningRule>) {
+        self.rules
+            .entry(key_id.to_string())
+            .or_insert_with(Vec::new)
+            .push(rule);
+    }
+
+    pub fn set_deny_unlisted(&mut self, deny_unlisted: bool) {
+        self.deny_unlisted = deny_unlisted;
+    }
+
+    /// Evaluates all rules of `key_id` for the given request. If all rules accept it, stateful
+    /// rules account for the request and Ok is returned. Otherwise, the first violation is returned.
+    pub fn evaluate(&mut self, key_id: &str, message: &[u8], label: &[u8]) -> Result<(), String> {
+        self.evaluate_versions(&[key_id.to_string()], message, label)
+    }
+
+    /// Like `evaluate`, for a key with several versions. `versions` are the ids of the key and of
+    /// its previous versions, newest first. The rules of the newest version that has rules apply,
+    /// so the rules of a key keep applying after it was rotated.
+    pub fn evaluate_versions(
+        &mut self,
+        versions: &[String],
+        message: &[u8],
+        label: &[u8],
+    ) -> Result<(), String> {
+        let key_id = versions.first().map(String::as_str).unwrap_or_default();
+        let listed = versions.iter().find(|id| self.rules.contains_key(*id));
+        let rules = match listed.and_then(|id| self.rules.get_mut(id)) {
+            Some(rules) => rules,
+            None => {
+                if self.deny_unlisted {
+                    return Err(format!("no signing policy defined for key {}", key_id));
+                }
+                return Ok(());
+            }
+        };
+
+        for rule in rules.iter() {
+            if let Err(e) = rule.check(message, label) {
+                warn!("Signing request for key {} violates policy: {}", key_id, e);
+                return Err(e);
+            }
+        }
+
+        for rule in rules.iter_mut() {
+            rule.record();
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::signing_policy::{
+    rules::{AllowedLabels, SigningRule},
+    signing_policy::SigningPolicy,
+};
+
+const POLICY: &str = r#"{
+    "keys": {
+        "key1": [
+            { "type": "json", "required_fields": ["to", "amount"] },
+            { "type": "json_number_limit", "field": "amount", "max": 100 },
+            { "type": "allowed_labels", "labels": ["transfer"] },
+            { "type": "rate_limit", "max_requests": 2, "period_secs": 3600 }
+        ]
+    }
+}"#;
+
+#[test]
+fn test_json_rules() {
+    let mut policy = SigningPolicy::from_json(POLICY).unwrap();
+
+    assert!(policy
+        .evaluate("key1", br#"{"to": "bob", "amount": 50}"#, b"transfer")
+        .is_ok());
+    assert!(policy
+        .evaluate("key1", br#"{"to": "bob", "amount": 500}"#, b"transfer")
+        .is_err());
+    assert!(policy
+        .evaluate("key1", br#"{"amount": 50}"#, b"transfer")
+        .is_err());
+    assert!(policy.evaluate("key1", b"not json", b"transfer").is_err());
+    assert!(policy
+        .evaluate("key1", br#"{"to": "bob", "amount": 50}"#, b"other")
+        .is_err());
+}
+
+#[test]
+fn test_rate_limit_counts_only_accepted_requests() {
+    let mut policy = SigningPolicy::from_json(POLICY).unwrap();
+    let msg = br#"{"to": "bob", "amount": 1}"#;
+
+    // rejected requests do not consume the rate limit
+    assert!(policy.evaluate("key1", msg, b"other").is_err());
+    assert!(policy.evaluate("key1", msg, b"transfer").is_ok());
+    assert!(policy.evaluate("key1", msg, b"transfer").is_ok());
+    assert!(policy.evaluate("key1", msg, b"transfer").is_err());
+}
+
+#[test]
+fn test_unlisted_keys() {
+    let mut policy = SigningPolicy::from_json(POLICY).unwrap();
+    assert!(policy.evaluate("key2", b"anything", b"").is_ok());
+
+    policy.set_deny_unlisted(true);
+    assert!(policy.evaluate("key2", b"anything", b"").is_err());
+}
+
+#[test]
+fn test_rules_of_previous_versions() {
+    let mut policy = SigningPolicy::from_json(POLICY).unwrap();
+    let msg = br#"{"to": "bob", "amount": 1}"#;
+    let versions = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<String>>();
+
+    // the rules of key1, including its rate limit, apply to its next version
+    assert!(policy
+        .evaluate_versions(&versions(&["key3", "key1"]), msg, b"other")
+        .is_err());
+    assert!(policy.evaluate("key1", msg, b"transfer").is_ok());
+    assert!(policy
+        .evaluate_versions(&versions(&["key3", "key1"]), msg, b"transfer")
+        .is_ok());
+    assert!(policy
+        .evaluate_versions(&versions(&["key3", "key1"]), msg, b"transfer")
+        .is_err());
+
+    // the newest version with rules takes precedence
+    policy.add_rule(
+        "key3",
+        Box::new(AllowedLabels::new(vec![b"other".to_vec()])),
+    );
+    assert!(policy
+        .evaluate_versions(&versions(&["key3", "key1"]), msg, b"other")
+        .is_ok());
+
+    policy.set_deny_unlisted(true);
+    assert!(policy
+        .evaluate_versions(&versions(&["key4", "key2"]), msg, b"transfer")
+        .is_err());
+}
+
+struct RejectAll;
+
+impl SigningRule for RejectAll {
+    fn check(&self, _message: &[u8], _label: &[u8]) -> Result<(), String> {
+        Err("rejected".to_string())
+    }
+}
+
+#[test]
+fn test_custom_rule() {
+    let mut policy = SigningPolicy::new();
+    policy.add_rule("key1", Box::new(AllowedLabels::new(vec![b"a".to_vec()])));
+    assert!(policy.evaluate("key1", b"msg", b"a").is_ok());
+
+    policy.add_rule("key1", Box::new(RejectAll));
+    assert!(policy.evaluate("key1", b"msg", b"a").is_err());
+}
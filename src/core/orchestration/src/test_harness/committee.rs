@@ -0,0 +1,298 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use theta_events::event::emitter::start_null_emitter;
+use theta_network::network_manager::network_manager::{NetworkCommand, NetworkManager};
+use theta_network::simulation::simulated_network::{SimulatedNetwork, SimulationConfig};
+use theta_network::types::config::{NetworkConfig, NetworkPeer};
+use theta_network::types::message::NetMessage;
+use theta_network::types::peer_table::PeerStatus;
+use theta_protocols::interface::ProtocolError;
+use theta_schemes::dl_schemes::signatures::frost::FrostOptions;
+use theta_schemes::keys::{key_store::KeyStore, keys::PrivateKeyShare};
+use tokio::sync::{mpsc, oneshot, Notify};
+
+use crate::instance_manager::admission::AdmissionLimits;
+use crate::instance_manager::instance_manager::{
+    InstanceManager, InstanceManagerCommand, InstanceStatus, StartInstanceRequest,
+};
+use crate::instance_manager::share::NodeShare;
+use crate::key_manager::key_manager::{KeyManager, KeyManagerCommand};
+use crate::key_manager::nonce_pool::{FrostNoncePool, NoncePoolSettings, NoncePoolStatus};
+use crate::signing_policy::signing_policy::SigningPolicy;
+
+static COMMITTEE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+const NONCE_POOL_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A node of a `SimulatedCommittee`, running its own NetworkManager, KeyManager and
+/// InstanceManager.
+pub struct SimulatedNode {
+    pub id: u32,
+    pub instance_manager: mpsc::Sender<InstanceManagerCommand>,
+    pub key_manager: mpsc::Sender<KeyManagerCommand>,
+    pub network: mpsc::Sender<NetworkCommand>,
+    shutdown: Arc<Notify>,
+}
+
+/// SimulatedCommittee runs the full stack of n nodes in the current tokio runtime, connected
+/// through a `SimulatedNetwork`. Node ids start at 1 and match the share ids of the keys.
+pub struct SimulatedCommittee {
+    pub network: SimulatedNetwork,
+    nodes: Vec<SimulatedNode>,
+}
+
+impl SimulatedCommittee {
+    /// Starts one node per keystore. The keystore at position i belongs to the node with id i + 1.
+    pub fn start(keystores: Vec<KeyStore>, config: SimulationConfig) -> Self {
+        let network = SimulatedNetwork::new(config);
+        let committee_id = COMMITTEE_COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        let nodes = keystores
+            .into_iter()
+            .enumerate()
+            .map(|(i, keystore)| {
+                let id = (i + 1) as u32;
+                let keychain_path = std::env::temp_dir().join(format!(
+                    "thetacrypt-sim-{}-{}-node{}.json",
+                    std::process::id(),
+                    committee_id,
+                    id
+                ));
+                start_node(id, keystore, keychain_path, &network)
+            })
+            .collect();
+
+        Self { network, nodes }
+    }
+
+    pub fn nodes(&self) -> &Vec<SimulatedNode> {
+        &self.nodes
+    }
+
+    pub fn node(&self, id: u32) -> &SimulatedNode {
+        &self.nodes[(id - 1) as usize]
+    }
+
+    /// Simulates a crash of node `id`: it is cut off the network and its components shut down.
+    pub fn crash(&self, id: u32) {
+        self.network.disconnect(id);
+        self.node(id).shutdown.notify_waiters();
+    }
+
+    /// Submits `request` to node `id`, like a client would do over RPC.
+    pub async fn request(
+        &self,
+        id: u32,
+        request: StartInstanceRequest,
+    ) -> Result<String, ProtocolError> {
+        let (responder, response) = oneshot::channel();
+        self.node(id)
+            .instance_manager
+            .send(InstanceManagerCommand::CreateInstance {
+                request,
+                client_id: None,
+                responder,
+            })
+            .await
+            .map_err(|_| ProtocolError::InternalError)?;
+        response.await.map_err(|_| ProtocolError::InternalError)?
+    }
+
+    /// Submits `request` to each of the given nodes and returns the responses in the same order.
+    pub async fn request_on(
+        &self,
+        ids: &[u32],
+        request: &StartInstanceRequest,
+    ) -> Vec<Result<String, ProtocolError>> {
+        let mut results = Vec::new();
+        for id in ids {
+            results.push(self.request(*id, request.clone()).await);
+        }
+        results
+    }
+
+    /// Asks node `id` for its share of `request`, like a client combining the shares itself.
+    pub async fn share(
+        &self,
+        id: u32,
+        request: StartInstanceRequest,
+    ) -> Result<NodeShare, ProtocolError> {
+        let (responder, response) = oneshot::channel();
+        self.node(id)
+            .instance_manager
+            .send(InstanceManagerCommand::CreateShare {
+                request,
+                client_id: None,
+                responder,
+            })
+            .await
+            .map_err(|_| ProtocolError::InternalError)?;
+        response.await.map_err(|_| ProtocolError::InternalError)?
+    }
+
+    pub async fn status(&self, id: u32, instance_id: &str) -> Option<InstanceStatus> {
+        let (responder, response) = oneshot::channel();
+        self.node(id)
+            .instance_manager
+            .send(InstanceManagerCommand::GetInstanceStatus {
+                instance_id: instance_id.to_string(),
+                responder,
+            })
+            .await
+            .ok()?;
+        response.await.ok()?
+    }
+
+    /// Returns the health of the other nodes as seen by node `id`.
+    pub async fn peer_status(&self, id: u32) -> Vec<PeerStatus> {
+        let (responder, response) = oneshot::channel();
+        if self
+            .node(id)
+            .network
+            .send(NetworkCommand::GetPeerStatus { responder })
+            .await
+            .is_err()
+        {
+            return Vec::new();
+        }
+        response.await.unwrap_or_default()
+    }
+
+    pub async fn frost_pool_status(&self, id: u32) -> Vec<NoncePoolStatus> {
+        let (responder, response) = oneshot::channel();
+        if self
+            .node(id)
+            .key_manager
+            .send(KeyManagerCommand::GetFrostPoolStatus { responder })
+            .await
+            .is_err()
+        {
+            return Vec::new();
+        }
+        response.await.unwrap_or_default()
+    }
+
+    /// Waits until the instance terminated at node `id` and returns its result, or None if it
+    /// did not terminate within `timeout`.
+    pub async fn wait_for_result(
+        &self,
+        id: u32,
+        instance_id: &str,
+        timeout: Duration,
+    ) -> Option<Result<Vec<u8>, ProtocolError>> {
+        let wait = async {
+            loop {
+                if let Some(status) = self.status(id, instance_id).await {
+                    if status.finished {
+                        if let Some(result) = status.result {
+                            return result;
+                        }
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.ok()
+    }
+
+    pub fn shutdown(&self) {
+        for node in &self.nodes {
+            node.shutdown.notify_waiters();
+        }
+    }
+}
+
+/// Returns one keystore per node, holding the share of that node (the share at position i belongs
+/// to node i + 1). Shares of further keys can be added with `add_shares`.
+pub fn keystores_from_shares(shares: Vec<PrivateKeyShare>) -> Vec<KeyStore> {
+    let mut keystores: Vec<KeyStore> = shares.iter().map(|_| KeyStore::new()).collect();
+    add_shares(&mut keystores, shares);
+    keystores
+}
+
+pub fn add_shares(keystores: &mut [KeyStore], shares: Vec<PrivateKeyShare>) {
+    for (keystore, share) in keystores.iter_mut().zip(shares) {
+        keystore
+            .insert_private_key(share)
+            .expect("Could not add key share to keystore");
+    }
+}
+
+fn start_node(
+    id: u32,
+    keystore: KeyStore,
+    keychain_path: PathBuf,
+    network: &SimulatedNetwork,
+) -> SimulatedNode {
+    let shutdown = Arc::new(Notify::new());
+
+    let (net_to_prot_sender, net_to_prot_receiver) = mpsc::channel::<NetMessage>(32);
+    let (prot_to_net_sender, prot_to_net_receiver) = mpsc::channel::<NetMessage>(32);
+
+    let network_config = NetworkConfig {
+        local_peer: NetworkPeer {
+            id,
+            ip: String::from("127.0.0.1"),
+            port: 0,
+            identity: None,
+        },
+        peers: None,
+        proxy: None,
+        base_listen_address: String::from("127.0.0.1"),
+        identity_file: None,
+    };
+    let (network_sender, network_receiver) = mpsc::channel::<NetworkCommand>(32);
+    let mut network_manager = NetworkManager::new(
+        prot_to_net_receiver,
+        net_to_prot_sender,
+        network_config,
+        id,
+        Box::new(network.gossip_endpoint(id)),
+        None,
+        Some(network_receiver),
+    );
+    let shutdown_network = shutdown.clone();
+    tokio::spawn(async move { network_manager.run(shutdown_network).await });
+
+    let (key_manager_sender, key_manager_receiver) = mpsc::channel::<KeyManagerCommand>(32);
+    let nonce_pool = FrostNoncePool::new(NoncePoolSettings::default());
+    let mut key_manager = KeyManager::from_keystore(
+        keystore,
+        keychain_path,
+        nonce_pool,
+        key_manager_receiver,
+        Some(network_sender.clone()),
+    );
+    let shutdown_key_manager = shutdown.clone();
+    tokio::spawn(async move { key_manager.run(shutdown_key_manager).await });
+
+    let (emitter_sender, _) = start_null_emitter(shutdown.clone());
+
+    let (instance_manager_sender, instance_manager_receiver) =
+        mpsc::channel::<InstanceManagerCommand>(32);
+    let mut instance_manager = InstanceManager::new(
+        key_manager_sender.clone(),
+        instance_manager_receiver,
+        instance_manager_sender.clone(),
+        prot_to_net_sender,
+        net_to_prot_receiver,
+        emitter_sender,
+        SigningPolicy::new(),
+        AdmissionLimits::default(),
+        NONCE_POOL_CHECK_INTERVAL,
+        FrostOptions::Robust,
+    );
+    let shutdown_instance_manager = shutdown.clone();
+    tokio::spawn(async move { instance_manager.run(shutdown_instance_manager).await });
+
+    SimulatedNode {
+        id,
+        instance_manager: instance_manager_sender,
+        key_manager: key_manager_sender,
+        network: network_sender,
+        shutdown,
+    }
+}
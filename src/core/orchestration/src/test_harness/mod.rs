@@ -0,0 +1,3 @@
+pub mod committee;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use theta_network::simulation::simulated_network::SimulationConfig;
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_schemes::{
+    interface::{
+        DecryptionShare, Serializable, Signature, SignatureShare, ThresholdCipher,
+        ThresholdCipherParams, ThresholdSignature,
+    },
+    integers::bigint::BigInt,
+    keys::{
+        key_generator::{KeyGenerator, KeyParams},
+        keys::PrivateKeyShare,
+    },
+    rand::{RngAlgorithm, RNG},
+};
+
+use crate::instance_manager::instance_manager::StartInstanceRequest;
+
+use super::committee::{add_shares, keystores_from_shares, SimulatedCommittee};
+
+const N: usize = 5;
+const K: usize = 3;
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+fn generate_shares(scheme: ThresholdScheme, group: Group) -> Vec<PrivateKeyShare> {
+    let params = match group {
+        Group::Rsa512 | Group::Rsa1024 | Group::Rsa2048 | Group::Rsa4096 => {
+            let mut params = KeyParams::new();
+            params.set_e(&BigInt::new_int(13));
+            Some(params)
+        }
+        _ => None,
+    };
+
+    KeyGenerator::generate_keys(
+        K,
+        N,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &group,
+        &params,
+    )
+    .unwrap()
+}
+
+fn config(seed: u64) -> SimulationConfig {
+    SimulationConfig {
+        seed,
+        min_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(50),
+        drop_probability: 0.0,
+    }
+}
+
+fn all_nodes() -> Vec<u32> {
+    (1..=N as u32).collect()
+}
+
+// Decrypts a ciphertext of `scheme` on all nodes of a committee without faults.
+async fn assert_decryption(scheme: ThresholdScheme, seed: u64) {
+    let shares = generate_shares(scheme, Group::Bls12381);
+    let public_key = shares[0].get_public_key();
+    let committee = SimulatedCommittee::start(keystores_from_shares(shares), config(seed));
+
+    let plaintext = b"simulated plaintext".to_vec();
+    let mut params = ThresholdCipherParams::new();
+    let ciphertext = ThresholdCipher::encrypt(&plaintext, b"label", &public_key, &mut params).unwrap();
+    let request = StartInstanceRequest::Decryption { ciphertext };
+
+    let instance_ids = committee.request_on(&all_nodes(), &request).await;
+    let instance_id = instance_ids[0].clone().unwrap();
+
+    for id in all_nodes() {
+        let result = committee.wait_for_result(id, &instance_id, TIMEOUT).await;
+        assert_eq!(result.unwrap().unwrap(), plaintext);
+    }
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_decryption() {
+    assert_decryption(ThresholdScheme::Sg02, 1).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_bz03_decryption() {
+    assert_decryption(ThresholdScheme::Bz03, 8).await;
+}
+
+// Signs a message with `scheme` on a committee in which n - k nodes crashed, i.e., as many as
+// may crash.
+async fn assert_signature_with_crashed_nodes(scheme: ThresholdScheme, group: Group, seed: u64) {
+    let shares = generate_shares(scheme, group);
+    let public_key = shares[0].get_public_key();
+    let committee = SimulatedCommittee::start(keystores_from_shares(shares), config(seed));
+
+    committee.crash(4);
+    committee.crash(5);
+
+    let message = b"simulated message".to_vec();
+    let request = StartInstanceRequest::Signature {
+        message: message.clone(),
+        label: b"label".to_vec(),
+        scheme,
+        group,
+        key_id: None,
+    };
+
+    let instance_ids = committee.request_on(&[1, 2, 3], &request).await;
+    let instance_id = instance_ids[0].clone().unwrap();
+
+    for id in [1, 2, 3] {
+        let result = committee.wait_for_result(id, &instance_id, TIMEOUT).await;
+        let signature = Signature::from_bytes(&result.unwrap().unwrap()).unwrap();
+        assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+    }
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_signature_with_crashed_nodes() {
+    assert_signature_with_crashed_nodes(ThresholdScheme::Bls04, Group::Bls12381, 2).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_sh00_signature_with_crashed_nodes() {
+    assert_signature_with_crashed_nodes(ThresholdScheme::Sh00, Group::Rsa512, 9).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_peer_status_reports_crashed_nodes() {
+    let committee = SimulatedCommittee::start(keystores_from_shares(generate_shares(ThresholdScheme::Sg02, Group::Bls12381)), config(5));
+
+    committee.crash(4);
+
+    let status = committee.peer_status(1).await;
+    let connected: Vec<(u32, bool)> = status.iter().map(|s| (s.id, s.connected)).collect();
+    assert_eq!(connected, vec![(2, true), (3, true), (4, false), (5, true)]);
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_coin_under_partition() {
+    let mut keystores = keystores_from_shares(generate_shares(ThresholdScheme::Cks05, Group::Bls12381));
+    add_shares(&mut keystores, generate_shares(ThresholdScheme::Sg02, Group::Bls12381));
+    let committee = SimulatedCommittee::start(keystores, config(3));
+
+    // only the group of k nodes can make progress
+    committee.network.partition(&[vec![1, 2], vec![3, 4, 5]]);
+
+    let request = StartInstanceRequest::Coin {
+        name: b"coin".to_vec(),
+        scheme: ThresholdScheme::Cks05,
+        group: Group::Bls12381,
+        key_id: None,
+    };
+
+    let instance_ids = committee.request_on(&all_nodes(), &request).await;
+    let instance_id = instance_ids[0].clone().unwrap();
+
+    let mut coins = Vec::new();
+    for id in [3, 4, 5] {
+        let result = committee.wait_for_result(id, &instance_id, TIMEOUT).await;
+        coins.push(result.unwrap().unwrap());
+    }
+    assert!(coins.iter().all(|c| *c == coins[0]));
+
+    for id in [1, 2] {
+        let result = committee
+            .wait_for_result(id, &instance_id, Duration::from_secs(5))
+            .await;
+        assert!(result.is_none());
+    }
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_client_side_decryption_without_network() {
+    let shares = generate_shares(ThresholdScheme::Sg02, Group::Bls12381);
+    let public_key = shares[0].get_public_key();
+    let committee = SimulatedCommittee::start(keystores_from_shares(shares), config(6));
+
+    // the shares are collected by the client, the nodes never talk to each other
+    for id in all_nodes() {
+        committee.network.disconnect(id);
+    }
+
+    let plaintext = b"combined by the client".to_vec();
+    let mut params = ThresholdCipherParams::new();
+    let ciphertext = ThresholdCipher::encrypt(&plaintext, b"label", &public_key, &mut params).unwrap();
+    let request = StartInstanceRequest::Decryption { ciphertext: ciphertext.clone() };
+
+    let mut decryption_shares = Vec::new();
+    for id in [1, 3, 5] {
+        let share = committee.share(id, request.clone()).await.unwrap();
+        assert_eq!(share.share_id, id as u16);
+        let share = DecryptionShare::from_bytes(&share.share).unwrap();
+        assert!(ThresholdCipher::verify_share(&share, &ciphertext, &public_key).unwrap());
+        decryption_shares.push(share);
+    }
+
+    assert_eq!(ThresholdCipher::assemble(&decryption_shares, &ciphertext).unwrap(), plaintext);
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_client_side_signature() {
+    let shares = generate_shares(ThresholdScheme::Bls04, Group::Bls12381);
+    let public_key = shares[0].get_public_key();
+    let committee = SimulatedCommittee::start(keystores_from_shares(shares), config(7));
+
+    let message = b"signed by the client".to_vec();
+    let request = StartInstanceRequest::Signature {
+        message: message.clone(),
+        label: b"label".to_vec(),
+        scheme: ThresholdScheme::Bls04,
+        group: Group::Bls12381,
+        key_id: None,
+    };
+
+    let mut signature_shares = Vec::new();
+    for id in [2, 3, 4] {
+        let share = committee.share(id, request.clone()).await.unwrap();
+        let share = SignatureShare::from_bytes(&share.share).unwrap();
+        assert!(ThresholdSignature::verify_share(&share, &message, &public_key).unwrap());
+        signature_shares.push(share);
+    }
+
+    let signature = ThresholdSignature::assemble(&signature_shares, &message, &public_key).unwrap();
+    assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+
+    committee.shutdown();
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_frost_signature_with_nonce_pool() {
+    let shares = generate_shares(ThresholdScheme::Frost, Group::Ed25519);
+    let public_key = shares[0].get_public_key();
+    let committee = SimulatedCommittee::start(keystores_from_shares(shares), config(4));
+
+    // give the nodes time to fill their pools and exchange the commitments
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    let status = committee.frost_pool_status(1).await;
+    assert_eq!(status.len(), 1);
+    assert!(status[0].nonces.values().all(|n| *n == status[0].target_size));
+    assert_eq!(status[0].commitments.len(), N - 1);
+
+    let message = b"simulated message".to_vec();
+    let request = StartInstanceRequest::Signature {
+        message: message.clone(),
+        label: b"label".to_vec(),
+        scheme: ThresholdScheme::Frost,
+        group: Group::Ed25519,
+        key_id: None,
+    };
+
+    let instance_ids = committee.request_on(&all_nodes(), &request).await;
+    let instance_id = instance_ids[0].clone().unwrap();
+
+    for id in all_nodes() {
+        let result = committee.wait_for_result(id, &instance_id, TIMEOUT).await;
+        let signature = Signature::from_bytes(&result.unwrap().unwrap()).unwrap();
+        assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+    }
+
+    // every instance consumes one nonce per coordinator
+    let status = committee.frost_pool_status(1).await;
+    assert!(status[0].nonces.values().all(|n| *n == status[0].target_size - 1));
+
+    committee.shutdown();
+}
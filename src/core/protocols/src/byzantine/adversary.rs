@@ -0,0 +1,291 @@
+use std::collections::VecDeque;
+
+use log::debug;
+use theta_network::types::message::NetMessage;
+
+use crate::interface::{Misbehavior, ProtocolError, ProtocolMessageWrapper, ThresholdRoundProtocol};
+
+// Instance id used for all messages exchanged by the nodes of a test committee.
+pub const INSTANCE_ID: &str = "byzantine-test-instance";
+
+// A message addressed to a single node of the committee.
+pub type Envelope = (u16, NetMessage);
+
+// Behavior of a faulty node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Behavior {
+    // Never sends anything.
+    Silent,
+    // Sends shares computed with a key share that does not belong to the committee.
+    InvalidShare,
+    // Sends shares under the share id of an honest node.
+    WrongShareId,
+    // Sends its correct messages to one half of the committee and invalid ones to the other half.
+    Equivocate,
+    // Sends correct shares that belong to a different instance, i.e., computed on another input.
+    Replay,
+}
+
+// ProtocolRunner drives a single protocol instance the same way the ThresholdProtocolExecutor
+// does: a first round on start, then update on every message, followed by finalize or the next
+// round. An error aborts the instance.
+pub struct ProtocolRunner<P: ThresholdRoundProtocol<NetMessage>> {
+    protocol: P,
+    result: Option<Result<Vec<u8>, ProtocolError>>,
+}
+
+impl<P: ThresholdRoundProtocol<NetMessage>> ProtocolRunner<P> {
+    pub fn new(protocol: P) -> Self {
+        Self {
+            protocol,
+            result: None,
+        }
+    }
+
+    pub fn start(&mut self) -> Option<NetMessage> {
+        let round = self.protocol.do_round();
+        self.handle_round(round)
+    }
+
+    pub fn receive(&mut self, message: NetMessage) -> Option<NetMessage> {
+        if self.result.is_some() {
+            return None;
+        }
+
+        let message = match <P::ProtocolMessage as ProtocolMessageWrapper<NetMessage>>::unwrap(message) {
+            Ok(message) => *message,
+            Err(e) => {
+                debug!("Dropping message that could not be unwrapped: {:?}", e);
+                return None;
+            }
+        };
+
+        if let Err(e) = self.protocol.update(message) {
+            self.result = Some(Err(e));
+            return None;
+        }
+
+        if self.protocol.is_ready_to_finalize() {
+            self.result = Some(self.protocol.finalize());
+            return None;
+        }
+
+        if self.protocol.is_ready_for_next_round() {
+            let round = self.protocol.do_round();
+            return self.handle_round(round);
+        }
+
+        None
+    }
+
+    pub fn result(&self) -> Option<&Result<Vec<u8>, ProtocolError>> {
+        self.result.as_ref()
+    }
+
+    pub fn blame(&self) -> Vec<Misbehavior> {
+        self.protocol.blame().clone()
+    }
+
+    fn handle_round(
+        &mut self,
+        round: Result<P::ProtocolMessage, ProtocolError>,
+    ) -> Option<NetMessage> {
+        match round {
+            Ok(message) if message.is_default() => None,
+            Ok(message) => Some(
+                message
+                    .wrap(&INSTANCE_ID.to_string())
+                    .expect("protocol message could not be wrapped"),
+            ),
+            Err(e) => {
+                self.result = Some(Err(e));
+                None
+            }
+        }
+    }
+}
+
+// A member of a test committee.
+pub trait TestNode {
+    fn id(&self) -> u16;
+    // Starts the protocol and returns the messages to send.
+    fn start(&mut self, committee: &[u16]) -> Vec<Envelope>;
+    // Processes a message and returns the messages to send in response.
+    fn receive(&mut self, message: NetMessage, committee: &[u16]) -> Vec<Envelope>;
+    fn is_faulty(&self) -> bool;
+    fn result(&self) -> Option<&Result<Vec<u8>, ProtocolError>>;
+    fn blame(&self) -> Vec<Misbehavior>;
+}
+
+fn broadcast(message: Option<NetMessage>, committee: &[u16]) -> Vec<Envelope> {
+    match message {
+        Some(message) => committee.iter().map(|id| (*id, message.clone())).collect(),
+        None => Vec::new(),
+    }
+}
+
+pub struct HonestNode<P: ThresholdRoundProtocol<NetMessage>> {
+    id: u16,
+    runner: ProtocolRunner<P>,
+}
+
+impl<P: ThresholdRoundProtocol<NetMessage>> HonestNode<P> {
+    pub fn new(id: u16, protocol: P) -> Self {
+        Self {
+            id,
+            runner: ProtocolRunner::new(protocol),
+        }
+    }
+}
+
+impl<P: ThresholdRoundProtocol<NetMessage>> TestNode for HonestNode<P> {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn start(&mut self, committee: &[u16]) -> Vec<Envelope> {
+        broadcast(self.runner.start(), committee)
+    }
+
+    fn receive(&mut self, message: NetMessage, committee: &[u16]) -> Vec<Envelope> {
+        broadcast(self.runner.receive(message), committee)
+    }
+
+    fn is_faulty(&self) -> bool {
+        false
+    }
+
+    fn result(&self) -> Option<&Result<Vec<u8>, ProtocolError>> {
+        self.runner.result()
+    }
+
+    fn blame(&self) -> Vec<Misbehavior> {
+        self.runner.blame()
+    }
+}
+
+// ByzantineNode wraps a correct protocol instance and, depending on the behavior, a forged
+// instance that runs on a wrong key or a wrong input. Both instances see every incoming message,
+// so the forged instance stays in the same round as the correct one. The behavior decides which
+// of the two outputs is sent to whom.
+pub struct ByzantineNode<P: ThresholdRoundProtocol<NetMessage>> {
+    id: u16,
+    behavior: Behavior,
+    honest: ProtocolRunner<P>,
+    forged: Option<ProtocolRunner<P>>,
+}
+
+impl<P: ThresholdRoundProtocol<NetMessage>> ByzantineNode<P> {
+    // `forged` is the instance producing the faulty messages. It is ignored by Silent nodes and
+    // required by all other behaviors.
+    pub fn new(id: u16, behavior: Behavior, honest: P, forged: Option<P>) -> Self {
+        assert!(
+            behavior == Behavior::Silent || forged.is_some(),
+            "behavior {:?} requires a forged protocol instance",
+            behavior
+        );
+        Self {
+            id,
+            behavior,
+            honest: ProtocolRunner::new(honest),
+            forged: forged.map(ProtocolRunner::new),
+        }
+    }
+
+    fn dispatch(
+        &self,
+        honest: Option<NetMessage>,
+        forged: Option<NetMessage>,
+        committee: &[u16],
+    ) -> Vec<Envelope> {
+        match self.behavior {
+            Behavior::Silent => Vec::new(),
+            Behavior::InvalidShare | Behavior::WrongShareId | Behavior::Replay => {
+                broadcast(forged, committee)
+            }
+            Behavior::Equivocate => {
+                let (first, second) = committee.split_at(committee.len() / 2);
+                let mut envelopes = broadcast(honest, first);
+                envelopes.extend(broadcast(forged, second));
+                envelopes
+            }
+        }
+    }
+}
+
+impl<P: ThresholdRoundProtocol<NetMessage>> TestNode for ByzantineNode<P> {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn start(&mut self, committee: &[u16]) -> Vec<Envelope> {
+        let honest = self.honest.start();
+        let forged = self.forged.as_mut().and_then(|f| f.start());
+        self.dispatch(honest, forged, committee)
+    }
+
+    fn receive(&mut self, message: NetMessage, committee: &[u16]) -> Vec<Envelope> {
+        let forged = self
+            .forged
+            .as_mut()
+            .and_then(|f| f.receive(message.clone()));
+        let honest = self.honest.receive(message);
+        self.dispatch(honest, forged, committee)
+    }
+
+    fn is_faulty(&self) -> bool {
+        true
+    }
+
+    fn result(&self) -> Option<&Result<Vec<u8>, ProtocolError>> {
+        self.honest.result()
+    }
+
+    fn blame(&self) -> Vec<Misbehavior> {
+        self.honest.blame()
+    }
+}
+
+// Sets the sender of the envelopes of node `sender`, as the network layer of the recipient does
+// for every message it receives.
+fn stamp_sender(sender: u16, envelopes: Vec<Envelope>) -> Vec<Envelope> {
+    envelopes
+        .into_iter()
+        .map(|(recipient, mut message)| {
+            message.set_sender(Some(sender as u32));
+            (recipient, message)
+        })
+        .collect()
+}
+
+// Runs a committee until no message is in flight. Messages are delivered in FIFO order, every
+// node also receives its own messages (as with the network layer), and the messages of faulty
+// nodes are sent first, so they reach the honest nodes before any correct share. Returns the
+// number of delivered messages.
+pub fn run_committee(nodes: &mut Vec<Box<dyn TestNode>>, max_messages: usize) -> usize {
+    nodes.sort_by_key(|node| (!node.is_faulty(), node.id()));
+    let committee: Vec<u16> = nodes.iter().map(|node| node.id()).collect();
+
+    let mut queue: VecDeque<Envelope> = VecDeque::new();
+    for node in nodes.iter_mut() {
+        queue.extend(stamp_sender(node.id(), node.start(&committee)));
+    }
+
+    let mut delivered = 0;
+    while let Some((recipient, message)) = queue.pop_front() {
+        delivered += 1;
+        assert!(
+            delivered <= max_messages,
+            "committee did not quiesce after {} messages",
+            max_messages
+        );
+
+        let node = nodes
+            .iter_mut()
+            .find(|node| node.id() == recipient)
+            .expect("message addressed to unknown node");
+        queue.extend(stamp_sender(node.id(), node.receive(message, &committee)));
+    }
+
+    delivered
+}
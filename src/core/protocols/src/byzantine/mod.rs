@@ -0,0 +1,3 @@
+pub mod adversary;
+#[cfg(test)]
+mod tests;
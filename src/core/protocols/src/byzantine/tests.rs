@@ -0,0 +1,661 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use theta_network::types::message::NetMessage;
+use theta_schemes::{
+    dl_schemes::signatures::frost,
+    integers::bigint::BigInt,
+    interface::{
+        Group, Serializable, Signature, ThresholdCipher, ThresholdCipherParams, ThresholdCoin,
+        ThresholdScheme, ThresholdSignature,
+    },
+    keys::{
+        key_generator::{KeyGenerator, KeyParams},
+        keys::PrivateKeyShare,
+    },
+    rand::{RngAlgorithm, RNG},
+};
+
+use crate::{
+    byzantine::adversary::{
+        run_committee, Behavior, ByzantineNode, Envelope, HonestNode, TestNode, INSTANCE_ID,
+    },
+    frost::{
+        message_types::{FrostData, FrostMessage, RobustFrostData},
+        robust::{coordinators, RobustFrostPrecomputation, RobustFrostProtocol},
+    },
+    interface::{
+        Misbehavior, MisbehaviorKind, ProtocolError, ProtocolMessageWrapper, ThresholdRoundProtocol,
+    },
+    threshold_cipher::protocol::ThresholdCipherProtocol,
+    threshold_coin::protocol::ThresholdCoinProtocol,
+    threshold_signature::protocol::ThresholdSignatureProtocol,
+};
+
+const N: usize = 7;
+const K: usize = 3;
+// Share ids of the faulty nodes. With K = 3 shares needed, the committee tolerates t = K - 1 = 2
+// faulty nodes.
+const FAULTY: [u16; 2] = [2, 5];
+// Honest node whose share id gets used by nodes with Behavior::WrongShareId.
+const VICTIM: u16 = 1;
+const MAX_MESSAGES: usize = 10_000;
+
+const BEHAVIORS: [Behavior; 5] = [
+    Behavior::Silent,
+    Behavior::InvalidShare,
+    Behavior::WrongShareId,
+    Behavior::Equivocate,
+    Behavior::Replay,
+];
+
+// Key and input a protocol instance gets created with.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Setup {
+    // The committee key and the input of the instance under test.
+    Correct,
+    // A key share that does not belong to the committee.
+    BogusKey,
+    // The committee key, but the input of another instance.
+    OtherInput,
+}
+
+fn generate_keys(scheme: ThresholdScheme, group: Group) -> Vec<PrivateKeyShare> {
+    let params = match group {
+        Group::Rsa512 | Group::Rsa1024 | Group::Rsa2048 | Group::Rsa4096 => {
+            let mut params = KeyParams::new();
+            params.set_e(&BigInt::new_int(13));
+            Some(params)
+        }
+        _ => None,
+    };
+
+    KeyGenerator::generate_keys(
+        K,
+        N,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &group,
+        &params,
+    )
+    .unwrap()
+}
+
+// Builds a committee with one node per key share of `keys`, in which the nodes in `faulty` follow
+// `behavior`. `bogus` are shares of an unrelated key with the same parameters.
+fn build_committee<P, F>(
+    keys: &[PrivateKeyShare],
+    bogus: &[PrivateKeyShare],
+    faulty: &[u16],
+    behavior: Behavior,
+    make: &F,
+) -> Vec<Box<dyn TestNode>>
+where
+    P: ThresholdRoundProtocol<NetMessage> + 'static,
+    F: Fn(Arc<PrivateKeyShare>, Setup) -> P,
+{
+    keys.iter()
+        .map(|key| {
+            let id = key.get_share_id();
+            let honest = make(Arc::new(key.clone()), Setup::Correct);
+            if !faulty.contains(&id) {
+                return Box::new(HonestNode::new(id, honest)) as Box<dyn TestNode>;
+            }
+
+            let bogus_share = |share_id: u16| Arc::new(bogus[(share_id - 1) as usize].clone());
+            let forged = match behavior {
+                Behavior::Silent => None,
+                Behavior::InvalidShare | Behavior::Equivocate => {
+                    Some(make(bogus_share(id), Setup::BogusKey))
+                }
+                Behavior::WrongShareId => Some(make(bogus_share(VICTIM), Setup::BogusKey)),
+                Behavior::Replay => Some(make(Arc::new(key.clone()), Setup::OtherInput)),
+            };
+            Box::new(ByzantineNode::new(id, behavior, honest, forged)) as Box<dyn TestNode>
+        })
+        .collect()
+}
+
+// Runs the committee for every behavior and checks that all honest nodes terminate with a result
+// accepted by `check`.
+fn assert_honest_nodes_terminate<P, F, C>(
+    keys: &[PrivateKeyShare],
+    bogus: &[PrivateKeyShare],
+    make: F,
+    check: C,
+) where
+    P: ThresholdRoundProtocol<NetMessage> + 'static,
+    F: Fn(Arc<PrivateKeyShare>, Setup) -> P,
+    C: Fn(&[u8]),
+{
+    for behavior in BEHAVIORS {
+        let mut nodes = build_committee(keys, bogus, &FAULTY, behavior, &make);
+        run_committee(&mut nodes, MAX_MESSAGES);
+
+        for node in nodes.iter().filter(|node| !node.is_faulty()) {
+            match node.result() {
+                Some(Ok(result)) => check(result),
+                other => panic!(
+                    "honest node {} did not terminate with faulty behavior {:?}: {:?}",
+                    node.id(),
+                    behavior,
+                    other
+                ),
+            }
+        }
+    }
+}
+
+fn cipher_protocol_factory(
+    keys: &[PrivateKeyShare],
+    bogus: &[PrivateKeyShare],
+    plaintext: &[u8],
+) -> impl Fn(Arc<PrivateKeyShare>, Setup) -> ThresholdCipherProtocol {
+    let encrypt = |key: &PrivateKeyShare, msg: &[u8]| {
+        ThresholdCipher::encrypt(
+            msg,
+            b"label",
+            &key.get_public_key(),
+            &mut ThresholdCipherParams::new(),
+        )
+        .unwrap()
+    };
+    let ciphertext = encrypt(&keys[0], plaintext);
+    let bogus_ciphertext = encrypt(&bogus[0], plaintext);
+    let other_ciphertext = encrypt(&keys[0], b"another message");
+
+    move |key, setup| {
+        let ciphertext = match setup {
+            Setup::Correct => ciphertext.clone(),
+            Setup::BogusKey => bogus_ciphertext.clone(),
+            Setup::OtherInput => other_ciphertext.clone(),
+        };
+        ThresholdCipherProtocol::new(key, ciphertext, INSTANCE_ID.to_string())
+    }
+}
+
+fn test_cipher(scheme: ThresholdScheme, group: Group) {
+    let keys = generate_keys(scheme, group);
+    let bogus = generate_keys(scheme, group);
+    let plaintext = b"This is a test message!";
+
+    assert_honest_nodes_terminate(
+        &keys,
+        &bogus,
+        cipher_protocol_factory(&keys, &bogus, plaintext),
+        |result| assert_eq!(result, plaintext),
+    );
+}
+
+fn test_signature(scheme: ThresholdScheme, group: Group) {
+    let keys = generate_keys(scheme, group);
+    let bogus = generate_keys(scheme, group);
+    let public_key = keys[0].get_public_key();
+    let message = b"This is a test message!".to_vec();
+    let other_message = b"another message".to_vec();
+    let label = b"label".to_vec();
+
+    assert_honest_nodes_terminate(
+        &keys,
+        &bogus,
+        |key, setup| {
+            let message = match setup {
+                Setup::OtherInput => &other_message,
+                _ => &message,
+            };
+            ThresholdSignatureProtocol::new(key, Some(message), &label)
+        },
+        |result| {
+            let signature = Signature::from_bytes(&result.to_vec()).unwrap();
+            assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+        },
+    );
+}
+
+#[test]
+fn test_sg02_with_faulty_nodes() {
+    test_cipher(ThresholdScheme::Sg02, Group::Bls12381);
+}
+
+#[test]
+fn test_bz03_with_faulty_nodes() {
+    test_cipher(ThresholdScheme::Bz03, Group::Bls12381);
+}
+
+#[test]
+fn test_bls04_with_faulty_nodes() {
+    test_signature(ThresholdScheme::Bls04, Group::Bls12381);
+}
+
+#[test]
+fn test_sh00_with_faulty_nodes() {
+    test_signature(ThresholdScheme::Sh00, Group::Rsa512);
+}
+
+#[test]
+fn test_cks05_with_faulty_nodes() {
+    let keys = generate_keys(ThresholdScheme::Cks05, Group::Bls12381);
+    let bogus = generate_keys(ThresholdScheme::Cks05, Group::Bls12381);
+    let name = b"coin".to_vec();
+    let other_name = b"another coin".to_vec();
+
+    // The coin is unique, so every honest node must output the value assembled from any K
+    // correct shares.
+    let mut rng = RNG::new(RngAlgorithm::OsRng);
+    let shares = keys[K..]
+        .iter()
+        .map(|key| ThresholdCoin::create_share(&name, key, &mut rng).unwrap())
+        .collect();
+    let expected = ThresholdCoin::assemble(&shares).unwrap();
+
+    assert_honest_nodes_terminate(
+        &keys,
+        &bogus,
+        |key, setup| {
+            let name = match setup {
+                Setup::OtherInput => &other_name,
+                _ => &name,
+            };
+            ThresholdCoinProtocol::new(key, name)
+        },
+        |result| assert_eq!(result, &[expected]),
+    );
+}
+
+fn frost_protocol_factory<'a>(
+    message: &'a Vec<u8>,
+    other_message: &'a Vec<u8>,
+) -> impl Fn(Arc<PrivateKeyShare>, Setup) -> RobustFrostProtocol + 'a {
+    move |key, setup| {
+        let message = match setup {
+            Setup::OtherInput => other_message,
+            _ => message,
+        };
+        RobustFrostProtocol::new(key, message, b"label")
+    }
+}
+
+#[test]
+fn test_frost_with_faulty_nodes() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let bogus = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let public_key = keys[0].get_public_key();
+    let message = b"This is a test message!".to_vec();
+    let other_message = b"another message".to_vec();
+
+    assert_honest_nodes_terminate(
+        &keys,
+        &bogus,
+        frost_protocol_factory(&message, &other_message),
+        |result| {
+            let signature = Signature::from_bytes(&result.to_vec()).unwrap();
+            assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+        },
+    );
+}
+
+#[test]
+fn test_frost_with_n_minus_t_crashed_nodes() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let public_key = keys[0].get_public_key();
+    let message = b"This is a test message!".to_vec();
+    let other_message = b"another message".to_vec();
+    let make = frost_protocol_factory(&message, &other_message);
+
+    // Only K nodes are alive, and the crashed nodes include the coordinators with the lowest ids.
+    let crashed: Vec<u16> = (1..=(N - K) as u16).collect();
+    let mut nodes = build_committee(&keys, &keys, &crashed, Behavior::Silent, &make);
+    run_committee(&mut nodes, MAX_MESSAGES);
+
+    for node in nodes.iter().filter(|node| !node.is_faulty()) {
+        match node.result() {
+            Some(Ok(result)) => {
+                let signature = Signature::from_bytes(&result.to_vec()).unwrap();
+                assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+            }
+            other => panic!("node {} did not terminate: {:?}", node.id(), other),
+        }
+    }
+}
+
+#[test]
+fn test_frost_excludes_signers_with_invalid_shares() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let bogus = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let message = b"This is a test message!".to_vec();
+    let other_message = b"another message".to_vec();
+    let make = frost_protocol_factory(&message, &other_message);
+
+    // Faulty nodes are ready first, so the first session of every coordinator includes them.
+    let mut nodes = build_committee(&keys, &bogus, &FAULTY, Behavior::InvalidShare, &make);
+    run_committee(&mut nodes, MAX_MESSAGES);
+
+    for node in nodes.iter().filter(|node| !node.is_faulty()) {
+        assert!(matches!(node.result(), Some(Ok(_))));
+        let blame = node.blame();
+        for id in FAULTY {
+            assert!(
+                blame
+                    .iter()
+                    .any(|m| m.share_id == id && m.kind == MisbehaviorKind::InvalidShare),
+                "node {} did not blame node {}",
+                node.id(),
+                id
+            );
+        }
+    }
+}
+
+fn frost_key(key: &PrivateKeyShare) -> &frost::FrostPrivateKey {
+    match key {
+        PrivateKeyShare::Frost(key) => key,
+        _ => panic!("expected a FROST key"),
+    }
+}
+
+// A message of `sender` with a single item of the robust protocol.
+fn robust_message(sender: u16, data: RobustFrostData) -> FrostMessage {
+    FrostMessage {
+        id: sender,
+        data: FrostData::Robust(vec![data]),
+        sender: Some(sender as u32),
+    }
+}
+
+#[test]
+fn test_frost_early_shares_are_capped() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let message = b"This is a test message!".to_vec();
+    let mut node = RobustFrostProtocol::new(Arc::new(keys[0].clone()), &message, b"label");
+    let coordinators = node.get_coordinators();
+
+    // shares of node 4 for sessions that are never announced
+    let signer = frost_key(&keys[3]);
+    let share = |session: u32, coordinator: u16| {
+        let (commitment, nonce) = frost::commit(signer, &mut RNG::new(RngAlgorithm::OsRng));
+        let (share, _) =
+            frost::partial_sign(&nonce, &mut [commitment], &message, signer, 4).unwrap();
+        robust_message(
+            4,
+            RobustFrostData::Share {
+                coordinator,
+                session,
+                share,
+            },
+        )
+    };
+
+    // one share per session is kept, none for parties that are not coordinators
+    node.update(share(0, 1)).unwrap();
+    node.update(share(0, 1)).unwrap();
+    assert!(!coordinators.contains(&(N as u16)));
+    node.update(share(0, N as u16)).unwrap();
+    assert_eq!(node.early_share_count(4), 1);
+
+    for session in 1..100 {
+        node.update(share(session, 2)).unwrap();
+    }
+    assert_eq!(node.early_share_count(4), 2 * coordinators.len());
+}
+
+#[test]
+fn test_frost_coordinator_equivocation_is_blamed() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let message = b"This is a test message!".to_vec();
+    let mut node = RobustFrostProtocol::new(Arc::new(keys[6].clone()), &message, b"label");
+
+    let session = |signers: &[usize]| {
+        let commitments = signers
+            .iter()
+            .map(|i| frost::commit(frost_key(&keys[*i]), &mut RNG::new(RngAlgorithm::OsRng)).0)
+            .collect();
+        RobustFrostData::Session {
+            coordinator: 1,
+            session: 0,
+            commitments,
+        }
+    };
+
+    node.update(robust_message(1, session(&[0, 1, 2]))).unwrap();
+    node.update(robust_message(1, session(&[0, 3, 4]))).unwrap();
+
+    let blame = node.blame();
+    assert_eq!(blame.len(), 1);
+    assert_eq!(blame[0].share_id, 1);
+    assert_eq!(blame[0].kind, MisbehaviorKind::CoordinatorEquivocation);
+}
+
+// A node that follows the protocol, but first announces a session in the name of another node.
+struct Impersonator {
+    node: Box<dyn TestNode>,
+    forged: NetMessage,
+}
+
+impl TestNode for Impersonator {
+    fn id(&self) -> u16 {
+        self.node.id()
+    }
+
+    fn start(&mut self, committee: &[u16]) -> Vec<Envelope> {
+        let mut envelopes: Vec<Envelope> = committee
+            .iter()
+            .map(|id| (*id, self.forged.clone()))
+            .collect();
+        envelopes.extend(self.node.start(committee));
+        envelopes
+    }
+
+    fn receive(&mut self, message: NetMessage, committee: &[u16]) -> Vec<Envelope> {
+        self.node.receive(message, committee)
+    }
+
+    fn is_faulty(&self) -> bool {
+        true
+    }
+
+    fn result(&self) -> Option<&Result<Vec<u8>, ProtocolError>> {
+        self.node.result()
+    }
+
+    fn blame(&self) -> Vec<Misbehavior> {
+        self.node.blame()
+    }
+}
+
+#[test]
+fn test_frost_impersonated_coordinator_is_ignored() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let public_key = keys[0].get_public_key();
+    let message = b"This is a test message!".to_vec();
+    let make = frost_protocol_factory(&message, &message);
+
+    // Node 5 announces the first session of coordinator 1 before coordinator 1 does. Were the
+    // announcement accepted, the real one would look like an equivocation of coordinator 1.
+    let commitments = keys[..K]
+        .iter()
+        .map(|key| frost::commit(frost_key(key), &mut RNG::new(RngAlgorithm::OsRng)).0)
+        .collect();
+    let forged = robust_message(
+        1,
+        RobustFrostData::Session {
+            coordinator: 1,
+            session: 0,
+            commitments,
+        },
+    )
+    .wrap(&INSTANCE_ID.to_string())
+    .unwrap();
+
+    let mut nodes = build_committee(&keys, &keys, &[], Behavior::Silent, &make);
+    let index = nodes.iter().position(|node| node.id() == 5).unwrap();
+    let node = nodes.remove(index);
+    nodes.push(Box::new(Impersonator { node, forged }));
+    run_committee(&mut nodes, MAX_MESSAGES);
+
+    for node in nodes.iter().filter(|node| !node.is_faulty()) {
+        match node.result() {
+            Some(Ok(result)) => {
+                let signature = Signature::from_bytes(&result.to_vec()).unwrap();
+                assert!(ThresholdSignature::verify(&signature, &public_key, &message).unwrap());
+            }
+            other => panic!("node {} did not terminate: {:?}", node.id(), other),
+        }
+        assert!(
+            node.blame().is_empty(),
+            "node {} blamed {:?}",
+            node.id(),
+            node.blame()
+        );
+    }
+}
+
+// Precomputes one nonce per coordinator for every node, the way the nonce pools of the nodes do.
+// Returns the nonces of every node and the commitments of every coordinator.
+fn precompute_frost(
+    keys: &[PrivateKeyShare],
+) -> (
+    HashMap<u16, HashMap<u16, (frost::Nonce, frost::PublicCommitment)>>,
+    HashMap<u16, BTreeMap<u16, frost::PublicCommitment>>,
+) {
+    let mut nonces = HashMap::new();
+    let mut commitments: HashMap<u16, BTreeMap<u16, frost::PublicCommitment>> = HashMap::new();
+    for key in keys {
+        let key = match key {
+            PrivateKeyShare::Frost(key) => key,
+            _ => panic!("expected a FROST key"),
+        };
+        let signer = key.get_share_id();
+        let mut own = HashMap::new();
+        for coordinator in coordinators(key) {
+            let (commitment, nonce) = frost::commit(key, &mut RNG::new(RngAlgorithm::OsRng));
+            if coordinator != signer {
+                commitments
+                    .entry(coordinator)
+                    .or_default()
+                    .insert(signer, commitment.clone());
+            }
+            own.insert(coordinator, (nonce, commitment));
+        }
+        nonces.insert(signer, own);
+    }
+    (nonces, commitments)
+}
+
+// Runs an honest committee in which every node starts from the given precomputation and returns
+// the number of delivered messages.
+fn run_precomputed_frost(
+    keys: &[PrivateKeyShare],
+    message: &Vec<u8>,
+    nonces: HashMap<u16, HashMap<u16, (frost::Nonce, frost::PublicCommitment)>>,
+    commitments: HashMap<u16, BTreeMap<u16, frost::PublicCommitment>>,
+) -> usize {
+    let public_key = keys[0].get_public_key();
+    let nonces = RefCell::new(nonces);
+    let commitments = RefCell::new(commitments);
+    let make = |key: Arc<PrivateKeyShare>, _setup: Setup| {
+        let id = key.get_share_id();
+        let precomputation = RobustFrostPrecomputation {
+            nonces: nonces.borrow_mut().remove(&id).unwrap_or_default(),
+            commitments: commitments.borrow_mut().remove(&id).unwrap_or_default(),
+        };
+        RobustFrostProtocol::with_precomputation(key, message, b"label", precomputation)
+    };
+
+    let mut nodes = build_committee(keys, keys, &[], Behavior::Silent, &make);
+    let delivered = run_committee(&mut nodes, MAX_MESSAGES);
+
+    for node in nodes.iter() {
+        match node.result() {
+            Some(Ok(result)) => {
+                let signature = Signature::from_bytes(&result.to_vec()).unwrap();
+                assert!(ThresholdSignature::verify(&signature, &public_key, message).unwrap());
+            }
+            other => panic!("node {} did not terminate: {:?}", node.id(), other),
+        }
+    }
+    delivered
+}
+
+#[test]
+fn test_frost_with_precomputation_needs_fewer_messages() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let message = b"This is a test message!".to_vec();
+    let make = frost_protocol_factory(&message, &message);
+
+    let mut nodes = build_committee(&keys, &keys, &[], Behavior::Silent, &make);
+    let without = run_committee(&mut nodes, MAX_MESSAGES);
+
+    let (nonces, commitments) = precompute_frost(&keys);
+    let with = run_precomputed_frost(&keys, &message, nonces, commitments);
+
+    assert!(
+        with < without,
+        "precomputation did not save messages: {} vs {}",
+        with,
+        without
+    );
+}
+
+#[test]
+fn test_frost_with_mismatching_precomputation() {
+    let keys = generate_keys(ThresholdScheme::Frost, Group::Ed25519);
+    let message = b"This is a test message!".to_vec();
+
+    // The coordinators hold commitments that belong to other nonces than the ones of the signers,
+    // as happens when the pools of the nodes are consumed in a different order.
+    let (nonces, _) = precompute_frost(&keys);
+    let (_, commitments) = precompute_frost(&keys);
+    run_precomputed_frost(&keys, &message, nonces, commitments);
+}
+
+#[test]
+fn test_faulty_nodes_are_blamed() {
+    let keys = generate_keys(ThresholdScheme::Sg02, Group::Bls12381);
+    let bogus = generate_keys(ThresholdScheme::Sg02, Group::Bls12381);
+    let make = cipher_protocol_factory(&keys, &bogus, b"This is a test message!");
+
+    for behavior in [Behavior::InvalidShare, Behavior::Replay] {
+        let mut nodes = build_committee(&keys, &bogus, &FAULTY, behavior, &make);
+        run_committee(&mut nodes, MAX_MESSAGES);
+
+        // Faulty nodes send first, so every honest node verifies their shares before finishing.
+        for node in nodes.iter().filter(|node| !node.is_faulty()) {
+            let blame = node.blame();
+            for id in FAULTY {
+                assert!(
+                    blame
+                        .iter()
+                        .any(|m| m.share_id == id && m.kind == MisbehaviorKind::InvalidShare),
+                    "node {} did not blame node {} for {:?}",
+                    node.id(),
+                    id,
+                    behavior
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_spoofed_share_id_does_not_block_victim() {
+    let keys = generate_keys(ThresholdScheme::Sg02, Group::Bls12381);
+    let bogus = generate_keys(ThresholdScheme::Sg02, Group::Bls12381);
+    let make = cipher_protocol_factory(&keys, &bogus, b"This is a test message!");
+
+    let mut nodes = build_committee(&keys, &bogus, &FAULTY, Behavior::WrongShareId, &make);
+    run_committee(&mut nodes, MAX_MESSAGES);
+
+    // The invalid shares sent under the victim's id are blamed on the victim's id, but its
+    // genuine share is still accepted and it is never blamed for a duplicate. The reputation of
+    // the nodes is not affected, it is kept per authenticated sender (see ReputationTable).
+    for node in nodes.iter().filter(|node| !node.is_faulty()) {
+        assert!(matches!(node.result(), Some(Ok(_))));
+        assert!(!node
+            .blame()
+            .iter()
+            .any(|m| m.share_id == VICTIM && m.kind == MisbehaviorKind::DuplicateShare));
+    }
+}
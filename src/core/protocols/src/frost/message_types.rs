@@ -14,6 +14,10 @@ use super::protocol::FrostPrecomputation;
 pub struct FrostMessage {
     pub(crate) id: u16,
     pub(crate) data: FrostData,
+    // Node id of the sender as authenticated by the network layer, set when the message is
+    // unwrapped. None if the network did not authenticate the sender.
+    #[serde(skip)]
+    pub(crate) sender: Option<u32>,
 }
 
 #[repr(C)]
@@ -22,9 +26,33 @@ pub enum FrostData {
     Commitment(PublicCommitment),
     Share(FrostSignatureShare),
     Precomputation(Vec<PublicCommitment>),
+    // Messages of the robust mode (see RobustFrostProtocol), bundled so that a single round can
+    // answer several signing sessions at once.
+    Robust(Vec<RobustFrostData>),
     Default,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub enum RobustFrostData {
+    // Fresh commitment of the sender, to be used in the next session of `coordinator`.
+    Commitment {
+        coordinator: u16,
+        commitment: PublicCommitment,
+    },
+    // Signing session started by `coordinator` with the commitments of the selected signers.
+    Session {
+        coordinator: u16,
+        session: u32,
+        commitments: Vec<PublicCommitment>,
+    },
+    // Signature share of the sender for a session of `coordinator`.
+    Share {
+        coordinator: u16,
+        session: u32,
+        share: FrostSignatureShare,
+    },
+}
+
 impl Default for FrostData {
     fn default() -> Self {
         FrostData::Default
@@ -37,13 +65,11 @@ impl Default for FrostData {
 impl ProtocolMessageWrapper<NetMessage> for FrostMessage {
     fn unwrap(wrapped: NetMessage) -> Result<Box<FrostMessage>, ProtocolError> {
         let bytes = wrapped.get_message_data().to_owned();
-        let result = serde_json::from_str::<FrostMessage>(
-            &String::from_utf8(bytes).expect("Error serializing the JSON"),
-        );
+        let result = serde_json::from_slice::<FrostMessage>(&bytes);
         match result {
             Ok(message) => {
                 let mut msg = message.clone();
-                // msg.id = wrapped.get_metadata().get_sender().clone(); //TODO: to implement the logic in the network 
+                msg.sender = wrapped.get_metadata().get_sender();
                 return Ok(Box::new(msg));
             }
             Err(_) => {
@@ -1,3 +1,4 @@
 pub mod message_types;
 pub mod protocol;
+pub mod robust;
 pub mod tests;
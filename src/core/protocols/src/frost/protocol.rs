@@ -12,7 +12,9 @@ use theta_schemes::{
     }, groups::group::GroupElement, interface::{DlShare, SchemeError, Serializable, Signature}, keys::keys::PrivateKeyShare, rand::{RngAlgorithm, RNG}
 };
 
-use crate::interface::{ProtocolError, ThresholdRoundProtocol};
+use crate::interface::{
+    report_misbehavior, Misbehavior, MisbehaviorKind, ProtocolError, ThresholdRoundProtocol,
+};
 
 use super::message_types::{FrostData, FrostMessage};
 
@@ -39,6 +41,7 @@ pub struct FrostProtocol {
     finished: bool,
     options: FrostOptions,
     signer_group: SignerGroup,
+    blame: Vec<Misbehavior>,
 }
 
 impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
@@ -73,14 +76,19 @@ impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
                             &self.msg,
                             &mut commitment_list,
                         );
-                        if result.is_err() {
-                            error!("invalid share with id {}: error: {:?}", &message.id, result.err());
+                        // verify_share returns Ok(false) for a share that does not verify
+                        if !matches!(result, Ok(true)) {
+                            error!("invalid share with id {}: result: {:?}", &message.id, result);
+                            report_misbehavior(&mut self.blame, message.id, MisbehaviorKind::InvalidShare);
                             return Err(ProtocolError::InvalidShare);
                         } 
     
                         self.shares.insert(message.id, share.clone());
                         self.not_verified_shares.remove(&message.id);
                     }
+                } else if self.commitment_list.get(&message.id).map_or(false, |c| *c != result) {
+                    // a second, different commitment from the same signer
+                    report_misbehavior(&mut self.blame, message.id, MisbehaviorKind::DuplicateShare);
                 }
 
                 Ok(())
@@ -106,12 +114,16 @@ impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
                         &self.msg,
                         &mut commitment_list,
                     );
-                    if result.is_err() {
-                        error!("invalid share with id {}: error: {:?}", &message.id, result.err());
+                    if !matches!(result, Ok(true)) {
+                        error!("invalid share with id {}: result: {:?}", &message.id, result);
+                        report_misbehavior(&mut self.blame, message.id, MisbehaviorKind::InvalidShare);
                         return Err(ProtocolError::InvalidShare);
                     } 
 
                     self.shares.insert(message.id, share.clone());
+                } else if self.shares.get(&message.id).map_or(false, |s| *s != share) {
+                    // a second, different share from the same signer
+                    report_misbehavior(&mut self.blame, message.id, MisbehaviorKind::DuplicateShare);
                 }
 
                 Ok(())
@@ -228,6 +240,7 @@ impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
             message = FrostMessage {
                 id: self.key.get_share_id(),
                 data,
+                sender: None,
             };
             return Ok(message);
         } else if self.round == 1 {
@@ -267,6 +280,7 @@ impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
             let message = FrostMessage {
                 id: self.key.get_share_id(),
                 data: data,
+                sender: None,
             };
 
             return Ok(message);
@@ -313,6 +327,10 @@ impl ThresholdRoundProtocol<NetMessage> for FrostProtocol {
             }
         }
     }
+
+    fn blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
 }
 
 impl FrostProtocol {
@@ -328,6 +346,9 @@ impl FrostProtocol {
         } else {
             panic!("");
         };
+        if options == FrostOptions::Robust {
+            panic!("The robust mode is implemented by RobustFrostProtocol");
+        }
         if precomputation.is_none() {
             return Self {
                 round: 0,
@@ -345,6 +366,7 @@ impl FrostProtocol {
                 finished: false,
                 options,
                 signer_group: SignerGroup::new(key.get_threshold(), msg),
+                blame: Vec::new(),
             };
         }
         let precomputation = precomputation.unwrap();
@@ -364,6 +386,7 @@ impl FrostProtocol {
             finished: false,
             options,
             signer_group: SignerGroup::new(key.get_threshold(), msg),
+            blame: Vec::new(),
         }
     }
 
@@ -0,0 +1,577 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+use theta_network::types::message::NetMessage;
+use theta_schemes::{
+    dl_schemes::signatures::frost::{
+        self, FrostPrivateKey, FrostSignatureShare, Nonce, PublicCommitment,
+    },
+    interface::{DlShare, Serializable, Signature},
+    keys::keys::PrivateKeyShare,
+    rand::{RngAlgorithm, RNG},
+};
+
+use crate::interface::{
+    report_misbehavior, Misbehavior, MisbehaviorKind, ProtocolError, ThresholdRoundProtocol,
+};
+
+use super::message_types::{FrostData, FrostMessage, RobustFrostData};
+
+// Number of shares per coordinator kept of a sender for sessions that were not announced yet. An
+// honest signer only signs sessions it has seen, so it is rarely more than one session ahead of
+// another party per coordinator; the limit leaves room for reordering by the network.
+const EARLY_SHARES_PER_COORDINATOR: usize = 2;
+
+// A signing session: the commitments of the t signers selected by a coordinator and the valid
+// signature shares received so far.
+struct Session {
+    commitments: Vec<PublicCommitment>,
+    shares: HashMap<u16, FrostSignatureShare>,
+}
+
+impl Session {
+    fn contains(&self, id: u16) -> bool {
+        self.commitments.iter().any(|c| c.get_id() == id)
+    }
+
+    fn is_complete(&self) -> bool {
+        self.shares.len() == self.commitments.len()
+    }
+}
+
+// Material precomputed before an instance starts, taken from the nonce pool of a node. With it,
+// a coordinator can start a session right away and signing needs a single round in the common case.
+#[derive(Debug)]
+pub struct RobustFrostPrecomputation {
+    // Our nonce and its commitment for the first session of each coordinator.
+    pub nonces: HashMap<u16, (Nonce, PublicCommitment)>,
+    // Commitments the other signers precomputed for us as coordinator, by signer id.
+    pub commitments: BTreeMap<u16, PublicCommitment>,
+}
+
+// Returns the ids of the parties acting as coordinators for the given key: 1..=n-t+1, so that at
+// least one of them is honest whenever at least t parties are.
+pub fn coordinators(key: &FrostPrivateKey) -> Vec<u16> {
+    let n = key.get_public_key().get_n();
+    let t = key.get_threshold();
+    (1..=n - t + 1).collect()
+}
+
+// State of a node in its role as coordinator.
+#[derive(Default)]
+struct Coordinator {
+    // Signers with a fresh commitment that are not part of an open session, ordered by id.
+    ready: BTreeMap<u16, PublicCommitment>,
+    // Signers selected for a session that did not deliver their share yet.
+    busy: HashSet<u16>,
+    // Signers that sent an invalid share. They are never selected again.
+    excluded: HashSet<u16>,
+    // Commitments taken from the nonce pool that the signer did not confirm yet.
+    precomputed: HashMap<u16, PublicCommitment>,
+    next_session: u32,
+}
+
+// RobustFrostProtocol is a FROST signing protocol that stays live under up to n - t crashed or
+// malicious parties, following the approach of ROAST (Ruffing et al., CCS 2022).
+//
+// The parties 1..=n-t+1 act as coordinators, so at least one of them is honest. Every party keeps
+// a separate nonce for each coordinator and announces the matching commitment. As soon as t
+// parties are ready, a coordinator starts a session with their commitments (round two of FROST).
+// A signer answers with its share and a fresh commitment, which makes it ready again once the
+// share is verified. Signers that send an invalid share are blamed and excluded, and signers that
+// never answer simply stay unavailable, so a coordinator keeps starting new sessions with the
+// remaining parties until one of them completes.
+//
+// All messages are broadcast, hence every party verifies the shares of every session and
+// terminates with the first session for which all shares are valid. A nonce is discarded as soon
+// as it has been used, so it never signs in two sessions.
+pub struct RobustFrostProtocol {
+    key: FrostPrivateKey,
+    msg: Vec<u8>,
+    label: Vec<u8>,
+    started: bool,
+    coordinators: Vec<u16>,
+    // Nonce and commitment to use in the next session of each coordinator.
+    nonces: HashMap<u16, (Nonce, PublicCommitment)>,
+    coordinator: Option<Coordinator>,
+    sessions: HashMap<(u16, u32), Session>,
+    // Shares that arrived before the session they belong to.
+    early_shares: HashMap<(u16, u32), Vec<(u16, FrostSignatureShare)>>,
+    outbox: Vec<RobustFrostData>,
+    completed: Option<(u16, u32)>,
+    finished: bool,
+    blame: Vec<Misbehavior>,
+}
+
+impl ThresholdRoundProtocol<NetMessage> for RobustFrostProtocol {
+    type ProtocolMessage = FrostMessage;
+
+    // Sends all pending messages as one bundle. The first call announces the commitments for
+    // every coordinator, using precomputed nonces where available. Pending messages are applied
+    // locally before sending, so the protocol does not depend on receiving its own messages from
+    // the network.
+    fn do_round(&mut self) -> Result<FrostMessage, ProtocolError> {
+        if !self.started {
+            self.started = true;
+            for coordinator in self.coordinators.clone() {
+                let commitment = match self.nonces.get(&coordinator) {
+                    Some((_, commitment)) => commitment.clone(),
+                    None => self.refresh_nonce(coordinator),
+                };
+                self.outbox.push(RobustFrostData::Commitment {
+                    coordinator,
+                    commitment,
+                });
+            }
+        }
+
+        let own_id = self.key.get_share_id();
+        let mut bundle = Vec::new();
+        while !self.outbox.is_empty() {
+            let pending: Vec<RobustFrostData> = self.outbox.drain(..).collect();
+            for data in pending.iter() {
+                self.handle(own_id, data.clone());
+            }
+            bundle.extend(pending);
+        }
+
+        if bundle.is_empty() {
+            return Ok(FrostMessage::default());
+        }
+
+        Ok(FrostMessage {
+            id: own_id,
+            data: FrostData::Robust(bundle),
+            sender: None,
+        })
+    }
+
+    fn is_ready_for_next_round(&self) -> bool {
+        !self.started || !self.outbox.is_empty()
+    }
+
+    fn is_ready_to_finalize(&self) -> bool {
+        self.completed.is_some() && !self.finished
+    }
+
+    fn finalize(&mut self) -> Result<Vec<u8>, ProtocolError> {
+        let id = match self.completed {
+            Some(id) => id,
+            None => return Err(ProtocolError::NotFinished),
+        };
+        let session = &self.sessions[&id];
+
+        let mut commitments = session.commitments.clone();
+        let group_commitment = frost::get_group_commitment(&mut commitments, &self.msg, &self.key)?;
+        let shares: Vec<FrostSignatureShare> = session.shares.values().cloned().collect();
+        let signature = frost::assemble(&group_commitment, &self.key, &shares);
+
+        if !frost::verify(&signature, self.key.get_public_key(), &self.msg) {
+            return Err(ProtocolError::InternalError);
+        }
+
+        info!(
+            "Assembled FROST signature in session {} of coordinator {}",
+            id.1, id.0
+        );
+        self.finished = true;
+        Ok(Signature::Frost(signature).to_bytes()?)
+    }
+
+    fn update(&mut self, message: FrostMessage) -> Result<(), ProtocolError> {
+        if self.finished {
+            return Ok(());
+        }
+
+        // The items of a bundle carry no signature, so a party could announce sessions or send
+        // shares in the name of another one. Only bundles of the authenticated sender are handled.
+        if message.sender != Some(message.id as u32) {
+            warn!(
+                "Ignoring FROST message claiming sender {} from {:?}",
+                message.id, message.sender
+            );
+            return Ok(());
+        }
+
+        match message.data {
+            FrostData::Robust(bundle) => {
+                for data in bundle {
+                    self.handle(message.id, data);
+                }
+            }
+            _ => {
+                // Messages of the non-robust protocol are not an error worth aborting for.
+                warn!("Ignoring non-robust FROST message from {}", message.id);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
+}
+
+impl RobustFrostProtocol {
+    pub fn new(key: Arc<PrivateKeyShare>, msg: &[u8], label: &[u8]) -> Self {
+        let key = match key.as_ref() {
+            PrivateKeyShare::Frost(key) => key.clone(),
+            _ => panic!("RobustFrostProtocol requires a FROST key"),
+        };
+
+        let coordinators = coordinators(&key);
+        let coordinator = if coordinators.contains(&key.get_share_id()) {
+            Some(Coordinator::default())
+        } else {
+            None
+        };
+
+        Self {
+            key,
+            msg: msg.to_vec(),
+            label: label.to_vec(),
+            started: false,
+            coordinators,
+            nonces: HashMap::new(),
+            coordinator,
+            sessions: HashMap::new(),
+            early_shares: HashMap::new(),
+            outbox: Vec::new(),
+            completed: None,
+            finished: false,
+            blame: Vec::new(),
+        }
+    }
+
+    // Creates an instance that uses precomputed nonces and commitments. Nonces for coordinators
+    // that are not covered by the precomputation are generated when the instance starts.
+    pub fn with_precomputation(
+        key: Arc<PrivateKeyShare>,
+        msg: &[u8],
+        label: &[u8],
+        precomputation: RobustFrostPrecomputation,
+    ) -> Self {
+        let mut protocol = Self::new(key, msg, label);
+
+        for (coordinator, nonce) in precomputation.nonces {
+            if protocol.coordinators.contains(&coordinator) {
+                protocol.nonces.insert(coordinator, nonce);
+            }
+        }
+
+        if let Some(state) = protocol.coordinator.as_mut() {
+            for (signer, commitment) in precomputation.commitments {
+                if commitment.get_id() == signer {
+                    state.ready.insert(signer, commitment.clone());
+                    state.precomputed.insert(signer, commitment);
+                }
+            }
+        }
+
+        protocol
+    }
+
+    pub fn get_label(&self) -> Vec<u8> {
+        self.label.clone()
+    }
+
+    pub fn get_coordinators(&self) -> Vec<u16> {
+        self.coordinators.clone()
+    }
+
+    // Number of shares of `sender` kept for sessions that were not announced yet.
+    #[cfg(test)]
+    pub(crate) fn early_share_count(&self, sender: u16) -> usize {
+        self.early_shares
+            .values()
+            .flatten()
+            .filter(|(id, _)| *id == sender)
+            .count()
+    }
+
+    // Replaces the nonce for `coordinator` with a fresh one and returns its commitment.
+    fn refresh_nonce(&mut self, coordinator: u16) -> PublicCommitment {
+        let (commitment, nonce) = frost::commit(&self.key, &mut RNG::new(RngAlgorithm::OsRng));
+        self.nonces
+            .insert(coordinator, (nonce, commitment.clone()));
+        commitment
+    }
+
+    fn handle(&mut self, sender: u16, data: RobustFrostData) {
+        match data {
+            RobustFrostData::Commitment {
+                coordinator,
+                commitment,
+            } => self.handle_commitment(sender, coordinator, commitment),
+            RobustFrostData::Session {
+                coordinator,
+                session,
+                commitments,
+            } => self.handle_session(sender, coordinator, session, commitments),
+            RobustFrostData::Share {
+                coordinator,
+                session,
+                share,
+            } => self.handle_share(sender, coordinator, session, share),
+        }
+    }
+
+    fn handle_commitment(&mut self, sender: u16, coordinator: u16, commitment: PublicCommitment) {
+        if coordinator != self.key.get_share_id() || commitment.get_id() != sender {
+            return;
+        }
+
+        let state = match self.coordinator.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+        if state.excluded.contains(&sender) {
+            return;
+        }
+
+        let precomputed = state.precomputed.remove(&sender);
+        if state.busy.contains(&sender) {
+            // If the pools of the signer and ours were consumed in a different order, the signer
+            // never signs in a session with the precomputed commitment. Its own announcement then
+            // differs from that commitment and the signer can be selected again.
+            match precomputed {
+                Some(precomputed) if precomputed != commitment => {
+                    state.busy.remove(&sender);
+                }
+                _ => return,
+            }
+        }
+        state.ready.insert(sender, commitment);
+
+        self.start_sessions();
+    }
+
+    // Starts a new session with the t ready signers of lowest id, as long as there are enough.
+    fn start_sessions(&mut self) {
+        if self.completed.is_some() {
+            return;
+        }
+
+        let t = self.key.get_threshold() as usize;
+        let coordinator = self.key.get_share_id();
+        let state = match self.coordinator.as_mut() {
+            Some(state) => state,
+            None => return,
+        };
+
+        while state.ready.len() >= t {
+            let signers: Vec<u16> = state.ready.keys().take(t).cloned().collect();
+            let commitments: Vec<PublicCommitment> = signers
+                .iter()
+                .map(|id| state.ready.remove(id).unwrap())
+                .collect();
+            state.busy.extend(signers.iter().cloned());
+
+            let session = state.next_session;
+            state.next_session += 1;
+            info!(
+                "Coordinator {} starts session {} with signers {:?}",
+                coordinator, session, signers
+            );
+
+            self.outbox.push(RobustFrostData::Session {
+                coordinator,
+                session,
+                commitments,
+            });
+        }
+    }
+
+    fn handle_session(
+        &mut self,
+        sender: u16,
+        coordinator: u16,
+        session: u32,
+        commitments: Vec<PublicCommitment>,
+    ) {
+        if sender != coordinator || !self.coordinators.contains(&coordinator) {
+            warn!("Ignoring session announced by {} for coordinator {}", sender, coordinator);
+            return;
+        }
+
+        let signers: HashSet<u16> = commitments.iter().map(|c| c.get_id()).collect();
+        if commitments.len() != self.key.get_threshold() as usize || signers.len() != commitments.len() {
+            warn!(
+                "Ignoring malformed session {} of coordinator {}",
+                session, coordinator
+            );
+            return;
+        }
+
+        if let Some(existing) = self.sessions.get(&(coordinator, session)) {
+            if existing.commitments != commitments {
+                // the coordinator announced two different sessions under the same id
+                report_misbehavior(
+                    &mut self.blame,
+                    coordinator,
+                    MisbehaviorKind::CoordinatorEquivocation,
+                );
+            }
+            return;
+        }
+
+        self.sessions.insert(
+            (coordinator, session),
+            Session {
+                commitments: commitments.clone(),
+                shares: HashMap::new(),
+            },
+        );
+
+        let own_id = self.key.get_share_id();
+        if signers.contains(&own_id) {
+            self.sign(coordinator, session, commitments);
+        }
+
+        if let Some(early) = self.early_shares.remove(&(coordinator, session)) {
+            for (sender, share) in early {
+                self.handle_share(sender, coordinator, session, share);
+            }
+        }
+    }
+
+    // Signs in a session, provided that it uses our current commitment for the coordinator.
+    fn sign(&mut self, coordinator: u16, session: u32, mut commitments: Vec<PublicCommitment>) {
+        if self.completed.is_some() {
+            return;
+        }
+
+        let own_id = self.key.get_share_id();
+        let uses_current = match self.nonces.get(&coordinator) {
+            Some((_, commitment)) => commitments.contains(commitment),
+            None => false,
+        };
+        if !uses_current {
+            debug!(
+                "Session {} of coordinator {} does not use our current commitment",
+                session, coordinator
+            );
+            return;
+        }
+
+        // the nonce is consumed no matter whether signing succeeds
+        let (nonce, _) = self.nonces.remove(&coordinator).unwrap();
+        match frost::partial_sign(&nonce, &mut commitments, &self.msg, &self.key, own_id) {
+            Ok((share, _)) => {
+                let commitment = self.refresh_nonce(coordinator);
+                self.outbox.push(RobustFrostData::Share {
+                    coordinator,
+                    session,
+                    share,
+                });
+                self.outbox.push(RobustFrostData::Commitment {
+                    coordinator,
+                    commitment,
+                });
+            }
+            Err(e) => {
+                warn!(
+                    "Could not sign in session {} of coordinator {}: {:?}",
+                    session, coordinator, e
+                );
+            }
+        }
+    }
+
+    // Keeps a share whose session was not announced yet. A sender gets at most one buffered share
+    // per session and EARLY_SHARES_PER_COORDINATOR per coordinator on average, so a malicious
+    // party cannot grow the buffer with shares for sessions that are never announced.
+    fn buffer_early_share(
+        &mut self,
+        sender: u16,
+        coordinator: u16,
+        session: u32,
+        share: FrostSignatureShare,
+    ) {
+        if !self.coordinators.contains(&coordinator) {
+            return;
+        }
+
+        let buffered_by_sender = self
+            .early_shares
+            .values()
+            .flatten()
+            .filter(|(id, _)| *id == sender)
+            .count();
+        if buffered_by_sender >= EARLY_SHARES_PER_COORDINATOR * self.coordinators.len() {
+            warn!("Dropping early share of {}, too many shares buffered", sender);
+            return;
+        }
+
+        let early = self
+            .early_shares
+            .entry((coordinator, session))
+            .or_insert_with(Vec::new);
+        if !early.iter().any(|(id, _)| *id == sender) {
+            early.push((sender, share));
+        }
+    }
+
+    fn handle_share(
+        &mut self,
+        sender: u16,
+        coordinator: u16,
+        session: u32,
+        share: FrostSignatureShare,
+    ) {
+        if share.get_id() != sender {
+            warn!("Ignoring share with id {} sent by {}", share.get_id(), sender);
+            return;
+        }
+
+        let state = match self.sessions.get_mut(&(coordinator, session)) {
+            Some(state) => state,
+            None => {
+                self.buffer_early_share(sender, coordinator, session, share);
+                return;
+            }
+        };
+
+        if !state.contains(sender) {
+            return;
+        }
+
+        if let Some(existing) = state.shares.get(&sender) {
+            if *existing != share {
+                report_misbehavior(&mut self.blame, sender, MisbehaviorKind::DuplicateShare);
+            }
+            return;
+        }
+
+        let mut commitments = state.commitments.clone();
+        let valid = matches!(
+            frost::verify_share(&share, self.key.get_public_key(), &self.msg, &mut commitments),
+            Ok(true)
+        );
+
+        if valid {
+            state.shares.insert(sender, share);
+            if state.is_complete() && self.completed.is_none() {
+                self.completed = Some((coordinator, session));
+            }
+        } else {
+            warn!(
+                "Invalid share from {} in session {} of coordinator {}",
+                sender, session, coordinator
+            );
+            report_misbehavior(&mut self.blame, sender, MisbehaviorKind::InvalidShare);
+        }
+
+        if coordinator == self.key.get_share_id() {
+            if let Some(state) = self.coordinator.as_mut() {
+                state.busy.remove(&sender);
+                if !valid {
+                    state.excluded.insert(sender);
+                    state.ready.remove(&sender);
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 
 use serde::{Deserialize, Serialize};
 use theta_network::types::message::NetMessage;
@@ -16,7 +16,78 @@ pub enum ProtocolError {
     NotFinished,
     InvalidRound,
     InvalidShare,
+    PolicyViolation(String),
+    ResourceExhausted,
+    Timeout,
 }
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::SchemeError(e) => write!(f, "Scheme error: {}", e),
+            ProtocolError::PolicyViolation(reason) => write!(f, "Policy violation: {}", reason),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// MisbehaviorKind describes why a party got blamed during a protocol execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MisbehaviorKind {
+    /// The party sent a share that did not pass verification.
+    InvalidShare,
+    /// The party sent a second, different share for the same share id.
+    DuplicateShare,
+    /// The party, acting as coordinator, announced two different signing sessions under the same
+    /// id.
+    CoordinatorEquivocation,
+}
+
+/// Misbehavior records a single misbehaving party, identified by its share id. The share id is
+/// taken from the offending share and can be claimed by anybody, so `sender` additionally records
+/// the node that sent the share, as authenticated by the network layer. Protocols leave it empty,
+/// the executor fills it in from the message that carried the share.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Misbehavior {
+    pub share_id: u16,
+    pub kind: MisbehaviorKind,
+    pub sender: Option<u32>,
+}
+
+impl Misbehavior {
+    pub fn new(share_id: u16, kind: MisbehaviorKind) -> Self {
+        Self {
+            share_id,
+            kind,
+            sender: None,
+        }
+    }
+}
+
+/// Messages which know the node that sent them.
+pub trait AuthenticatedMessage {
+    /// The id of the node that sent the message, or None if the network layer could not
+    /// authenticate the sender.
+    fn authenticated_sender(&self) -> Option<u32>;
+}
+
+impl AuthenticatedMessage for NetMessage {
+    fn authenticated_sender(&self) -> Option<u32> {
+        self.get_metadata().get_sender()
+    }
+}
+
+/// Adds an entry to a blame list, unless the same share id has already been blamed for the same kind
+/// of misbehavior. Returns true if the entry was added.
+pub fn report_misbehavior(blame: &mut Vec<Misbehavior>, share_id: u16, kind: MisbehaviorKind) -> bool {
+    let misbehavior = Misbehavior::new(share_id, kind);
+    if blame.contains(&misbehavior) {
+        return false;
+    }
+    blame.push(misbehavior);
+    true
+}
+
 impl From<SchemeError> for ProtocolError {
     fn from(tc_error: SchemeError) -> Self {
         ProtocolError::SchemeError(tc_error)
@@ -49,4 +120,7 @@ pub trait ThresholdRoundProtocol<T> {
     fn is_ready_to_finalize(&self) -> bool;
     fn finalize(&mut self) -> Result<Vec<u8>, ProtocolError>;
     fn update(&mut self, message: Self::ProtocolMessage) -> Result<(), ProtocolError>;
+    /// Returns the parties that misbehaved so far, in the order in which the misbehavior was
+    /// detected.
+    fn blame(&self) -> &Vec<Misbehavior>;
 }
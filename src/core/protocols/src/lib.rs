@@ -1,3 +1,5 @@
+#[cfg(test)]
+mod byzantine;
 pub mod frost;
 pub mod interface;
 pub mod threshold_cipher;
@@ -56,9 +56,7 @@ impl Default for DecryptionMessage {
 impl ProtocolMessageWrapper<NetMessage> for DecryptionMessage {
     fn unwrap(wrapped: NetMessage) -> Result<Box<DecryptionMessage>, ProtocolError> {
         let bytes = wrapped.get_message_data().to_owned();
-        let result = serde_json::from_str::<DecryptionMessage>(
-            &String::from_utf8(bytes).expect("Error serializing the JSON"),
-        );
+        let result = serde_json::from_slice::<DecryptionMessage>(&bytes);
         match result {
             Ok(message) => return Ok(Box::new(message)),
             Err(_) => {
@@ -8,7 +8,9 @@ use theta_schemes::interface::{
 };
 use theta_schemes::keys::keys::PrivateKeyShare;
 
-use crate::interface::{ProtocolError, ThresholdRoundProtocol};
+use crate::interface::{
+    report_misbehavior, Misbehavior, MisbehaviorKind, ProtocolError, ThresholdRoundProtocol,
+};
 use crate::threshold_cipher::message_types::{DecryptionMessage, DecryptionShareMessage};
 
 
@@ -20,6 +22,7 @@ pub struct ThresholdCipherProtocol {
     decrypted: bool,
     decrypted_plaintext: Vec<u8>,
     received_share_ids: HashSet<u16>,
+    blame: Vec<Misbehavior>,
 }
 
 //ROSE: see this function can be NOT async
@@ -70,17 +73,20 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCipherProtocol {
 
                 //check duplicates
                 if self.received_share_ids.contains(&share.get_id()) {
+                    // Receiving the very same share again (e.g., our own share looped back by the
+                    // network layer) is harmless, only a conflicting share counts as misbehavior.
+                    if self.valid_shares.contains(&share) {
+                        return Ok(());
+                    }
                     warn!(
                         "<{:?}>: Found share {:?} to be DUPLICATE. Share will be ignored.",
                         &self.instance_id,
                         share.get_id()
                     );
+                    report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::DuplicateShare);
                     return Ok(());
                 }
 
-                //update the state
-                self.received_share_ids.insert(share.get_id());
-
                 let verification_result = ThresholdCipher::verify_share(
                     &share,
                     &self.ciphertext,
@@ -90,15 +96,20 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCipherProtocol {
                     Ok(is_valid) => {
                         if !is_valid {
                             warn!("<{:?}>: Received INVALID share with share_id: {:?}. Share will be ingored.", &self.instance_id, share.get_id());
+                            report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
                             return Ok(());
                         }
                     }
                     Err(err) => {
                         warn!("<{:?}>: Encountered error when validating share with id {:?}. Error:{:?}. Share will be ingored.", &self.instance_id, err, share.get_id());
+                        report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
                         return Ok(());
                     }
                 }
 
+                // Only a verified share occupies its id, so an invalid share sent under the id of
+                // another party cannot block that party's genuine share.
+                self.received_share_ids.insert(share.get_id());
                 self.valid_shares.push(share.clone());
 
                 debug!(
@@ -142,6 +153,10 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCipherProtocol {
 
         Ok(DecryptionMessage::ShareMessage(message))
     }
+
+    fn blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
 }
 
 impl ThresholdCipherProtocol {
@@ -158,6 +173,7 @@ impl ThresholdCipherProtocol {
             decrypted: false,
             decrypted_plaintext: Vec::new(),
             received_share_ids: HashSet::new(),
+            blame: Vec::new(),
         }
     }
 
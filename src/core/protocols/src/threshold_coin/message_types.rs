@@ -21,7 +21,7 @@ impl Default for CoinMessage {
 impl ProtocolMessageWrapper<NetMessage> for CoinMessage {
     fn unwrap(wrapped: NetMessage) -> Result<Box<Self>, crate::interface::ProtocolError> {
         let bytes = wrapped.get_message_data().to_owned();
-        let result = serde_json::from_str::<CoinMessage>(&String::from_utf8(bytes).expect("Error serializing the JSON"));
+        let result = serde_json::from_slice::<CoinMessage>(&bytes);
         match result {
             Ok(message) => {
                 return Ok(Box::new(message))
@@ -10,7 +10,9 @@ use theta_schemes::keys::keys::PrivateKeyShare;
 use theta_schemes::rand::RNG;
 use tonic::async_trait;
 
-use crate::interface::{ProtocolError, ThresholdRoundProtocol};
+use crate::interface::{
+    report_misbehavior, Misbehavior, MisbehaviorKind, ProtocolError, ThresholdRoundProtocol,
+};
 
 use super::message_types::CoinMessage;
 
@@ -21,6 +23,7 @@ pub struct ThresholdCoinProtocol {
     finished: bool,
     coin: Option<u8>,
     received_share_ids: HashSet<u16>,
+    blame: Vec<Misbehavior>,
 }
 
 impl ThresholdRoundProtocol<NetMessage> for ThresholdCoinProtocol {
@@ -75,14 +78,17 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCoinProtocol {
                 }
         
                 if self.received_share_ids.contains(&share.get_id()) {
+                    // the same share delivered twice is not a misbehavior
+                    if self.valid_shares.contains(&share) {
+                        return Ok(());
+                    }
+                    report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::DuplicateShare);
                     warn!(
                         "Found share with id {:?} to be DUPLICATE. Share will be ignored.",
                         share.get_id()
                     );
                     return Ok(());
                 }
-                self.received_share_ids.insert(share.get_id());
-        
                 let verification_result =
                     ThresholdCoin::verify_share(&share, &self.name, &self.private_key.get_public_key());
                 match verification_result {
@@ -92,15 +98,20 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCoinProtocol {
                                 "Received INVALID share with id {:?}. Share will be ingored.",
                                 share.get_id()
                             );
+                            report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
                             return Ok(()); 
                         }
                     }
                     Err(err) => {
                         warn!("Encountered error when validating share with id {:?}. Error:{:?}. Share will be ingored.", err, share.get_id());
+                        report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
                         return Ok(());
                     }
                 }
         
+                // Only a verified share occupies its id, so an invalid share sent under the id of
+                // another party cannot block that party's genuine share.
+                self.received_share_ids.insert(share.get_id());
                 self.valid_shares.push(share);
 
                 debug!(
@@ -117,6 +128,10 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdCoinProtocol {
         }
         
     }
+
+    fn blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
 }
 
 impl ThresholdCoinProtocol {
@@ -131,6 +146,7 @@ impl ThresholdCoinProtocol {
             finished: false,
             coin: Option::None,
             received_share_ids: HashSet::new(),
+            blame: Vec::new(),
         }
     }
 }
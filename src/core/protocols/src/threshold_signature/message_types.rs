@@ -20,7 +20,7 @@ impl Default for SignatureMessage {
 impl ProtocolMessageWrapper<NetMessage> for SignatureMessage{
     fn unwrap(wrapped: NetMessage) -> Result<Box<Self>, crate::interface::ProtocolError> {
         let bytes = wrapped.get_message_data().to_owned();
-        let result = serde_json::from_str::<SignatureMessage>(&String::from_utf8(bytes).expect("Error serializing the JSON"));
+        let result = serde_json::from_slice::<SignatureMessage>(&bytes);
         match result {
             Ok(message) => {
                 return Ok(Box::new(message))
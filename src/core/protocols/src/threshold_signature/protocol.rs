@@ -13,7 +13,9 @@ use theta_schemes::keys::keys::PrivateKeyShare;
 use theta_schemes::scheme_types_impl::SchemeDetails;
 use tonic::async_trait;
 
-use crate::interface::{ProtocolError, ThresholdRoundProtocol};
+use crate::interface::{
+    report_misbehavior, Misbehavior, MisbehaviorKind, ProtocolError, ThresholdRoundProtocol,
+};
 
 use super::message_types::SignatureMessage;
 
@@ -25,6 +27,7 @@ pub struct ThresholdSignatureProtocol {
     finished: bool,
     signature: Option<Signature>,
     received_share_ids: HashSet<u16>,
+    blame: Vec<Misbehavior>,
     // options: Option<ThresholdSignatureOptions>, //TODO: to review and remove
 }
 
@@ -80,13 +83,17 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdSignatureProtocol {
                 }
         
                 if self.received_share_ids.contains(&share.get_id()) {
+                    // the same share delivered twice is not a misbehavior
+                    if self.valid_shares.contains(&share) {
+                        return Ok(());
+                    }
+                    report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::DuplicateShare);
                     warn!(
                         "Found share to be DUPLICATE with id {:?}. Share will be ignored.",
                         share.get_id()
                     );
                     return Ok(());
                 }
-                self.received_share_ids.insert(share.get_id());
 
                 let verification_result = ThresholdSignature::verify_share(
                     &share,
@@ -97,21 +104,30 @@ impl ThresholdRoundProtocol<NetMessage> for ThresholdSignatureProtocol {
                     Ok(is_valid) => {
                         if !is_valid {
                             warn!("Received INVALID share with share_id: {:?}. Share will be ingored.", share.get_id());
-                            return Ok(()); //TODO: maybe to hadle better, count how many we esclude
+                            report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
+                            return Ok(());
                         }
                     }
                     Err(err) => {
                         warn!("Encountered error when validating share with share_id: {:?}. Error:{:?}. Share will be ingored.",err, share.get_id());
+                        report_misbehavior(&mut self.blame, share.get_id(), MisbehaviorKind::InvalidShare);
                         return Ok(());
                     }
                 }
         
+                // Only a verified share occupies its id, so an invalid share sent under the id of
+                // another party cannot block that party's genuine share.
+                self.received_share_ids.insert(share.get_id());
                 self.valid_shares.push(share);
                 return Ok(());
             },
             _ => todo!() //default, unreachable
         }
     }
+
+    fn blame(&self) -> &Vec<Misbehavior> {
+        &self.blame
+    }
 }
 
 
@@ -302,6 +318,7 @@ impl<'a> ThresholdSignatureProtocol {
             finished: false,
             signature: Option::None,
             received_share_ids: HashSet::new(),
+            blame: Vec::new(),
         }
     }
     
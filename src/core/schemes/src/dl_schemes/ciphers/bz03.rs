@@ -18,7 +18,7 @@ use crate::groups::group::GroupElement;
 use crate::groups::group::GroupOperations;
 use crate::integers::sizedint::SizedBigInt;
 use crate::interface::{DlShare, SchemeError, Serializable, ThresholdCipherParams};
-use crate::keys::keys::calc_key_id;
+use crate::keys::keys::{calc_key_id, matches_verification_key};
 use crate::scheme_types_impl::GroupDetails;
 use theta_proto::scheme_types::{Group, ThresholdScheme};
 
@@ -166,6 +166,11 @@ impl Bz03PrivateKey {
         &self.pubkey
     }
 
+    // Checks that the share matches its verification key, i.e. g^xi = h_i.
+    pub fn is_consistent(&self) -> bool {
+        matches_verification_key(self.get_group(), self.id, &self.xi, &self.pubkey.verification_key)
+    }
+
     pub fn get_order(&self) -> SizedBigInt {
         self.get_group().get_order()
     }
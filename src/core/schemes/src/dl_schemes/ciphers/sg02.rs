@@ -1,6 +1,6 @@
 use crate::groups::group::GroupOperations;
 use crate::interface::Serializable;
-use crate::keys::keys::calc_key_id;
+use crate::keys::keys::{calc_key_id, matches_verification_key};
 use crate::{
     interface::{SchemeError, ThresholdScheme},
     scheme_types_impl::GroupDetails,
@@ -193,6 +193,11 @@ impl Sg02PrivateKey {
     pub fn get_public_key(&self) -> &Sg02PublicKey {
         &self.pubkey
     }
+
+    // Checks that the share matches its verification key, i.e. g^xi = h_i.
+    pub fn is_consistent(&self) -> bool {
+        matches_verification_key(self.get_group(), self.id, &self.xi, &self.pubkey.verification_key)
+    }
 }
 
 impl Serializable for Sg02PrivateKey {
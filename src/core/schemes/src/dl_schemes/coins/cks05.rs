@@ -15,7 +15,7 @@ use theta_derive::DlShare;
 
 use crate::integers::sizedint::SizedBigInt;
 use crate::interface::{DlShare, SchemeError, Serializable};
-use crate::keys::keys::calc_key_id;
+use crate::keys::keys::{calc_key_id, matches_verification_key};
 use crate::scheme_types_impl::GroupDetails;
 use crate::{dl_schemes::common::interpolate, groups::group::GroupElement, rand::RNG};
 use theta_proto::scheme_types::{Group, ThresholdScheme};
@@ -187,6 +187,11 @@ impl Cks05PrivateKey {
     pub fn get_public_key(&self) -> &Cks05PublicKey {
         &self.pubkey
     }
+
+    // Checks that the share matches its verification key, i.e. g^xi = h_i.
+    pub fn is_consistent(&self) -> bool {
+        matches_verification_key(self.get_group(), self.id, &self.xi, &self.pubkey.verification_key)
+    }
 }
 
 impl Serializable for Cks05PrivateKey {
@@ -10,7 +10,7 @@ use theta_derive::DlShare;
 
 use crate::groups::group::GroupOperations;
 use crate::integers::sizedint::SizedBigInt;
-use crate::keys::keys::calc_key_id;
+use crate::keys::keys::{calc_key_id, matches_verification_key};
 use crate::{
     dl_schemes::common::interpolate,
     groups::group::GroupElement,
@@ -188,6 +188,11 @@ impl Bls04PrivateKey {
     pub fn get_public_key(&self) -> Bls04PublicKey {
         self.pubkey.clone()
     }
+
+    // Checks that the share matches its verification key, i.e. g^xi = h_i.
+    pub fn is_consistent(&self) -> bool {
+        matches_verification_key(self.get_group(), self.id, &self.xi, &self.pubkey.verification_key)
+    }
 }
 
 impl Serializable for Bls04PrivateKey {
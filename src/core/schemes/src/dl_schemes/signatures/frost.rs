@@ -14,7 +14,7 @@ use crate::{
     groups::group::GroupElement,
     integers::{bigint::BigInt, sizedint::SizedBigInt},
     interface::{DlShare, SchemeError, Serializable},
-    keys::keys::{calc_key_id, PublicKey},
+    keys::keys::{calc_key_id, matches_verification_key, PublicKey},
     rand::{RngAlgorithm, RNG},
     scheme_types_impl::GroupDetails,
 };
@@ -23,6 +23,8 @@ use mcore::hash512::HASH512;
 use theta_proto::scheme_types::{Group, ThresholdScheme};
 
 const NUM_PRECOMPUTATIONS: usize = 10;
+// Separates the challenges of share signatures from the FROST challenges, which also use h2.
+const SHARE_SIGNATURE_DOMAIN: &[u8] = b"theta/frost/commitment-batch";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct FrostPublicKey {
@@ -170,6 +172,11 @@ impl FrostPrivateKey {
         &self.pubkey
     }
 
+    // Checks that the share matches its verification key, i.e. g^xi = h_i.
+    pub fn is_consistent(&self) -> bool {
+        matches_verification_key(self.get_group(), self.id, &self.x, &self.pubkey.h)
+    }
+
     pub fn get_key_id(&self) -> &str {
         self.pubkey.get_key_id()
     }
@@ -358,6 +365,95 @@ impl Serializable for PublicCommitment {
     }
 }
 
+/// Schnorr signature of a single party, made with its key share and verified with its
+/// verification key. Authenticates the messages of a party outside of a signing session, such as
+/// the announcements of precomputed commitments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrostShareSignature {
+    id: u16,
+    r: GroupElement,
+    z: SizedBigInt,
+}
+
+impl FrostShareSignature {
+    pub fn get_id(&self) -> u16 {
+        self.id
+    }
+}
+
+impl Serialize for FrostShareSignature {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let bytes = self.to_bytes().unwrap();
+
+        let mut seq = serializer.serialize_seq(Some(bytes.len()))?;
+        for element in bytes {
+            seq.serialize_element(&element)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for FrostShareSignature {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = deserializer.deserialize_byte_buf(ByteBufVisitor)?;
+        FrostShareSignature::from_bytes(&value)
+            .map_err(|e| serde::de::Error::custom(format!("{}", e.to_string())))
+    }
+}
+
+impl Serializable for FrostShareSignature {
+    fn to_bytes(&self) -> Result<Vec<u8>, SchemeError> {
+        let result = asn1::write(|w| {
+            w.write_element(&asn1::SequenceWriter::new(&|w| {
+                w.write_element(&(self.id as u64))?;
+                w.write_element(&(self.r.get_group().clone() as i32))?;
+                w.write_element(&self.r.to_bytes().as_slice())?;
+                w.write_element(&self.z.to_bytes().as_slice())?;
+                Ok(())
+            }))
+        });
+
+        if result.is_err() {
+            return Err(SchemeError::SerializationFailed);
+        }
+
+        Ok(result.unwrap())
+    }
+
+    fn from_bytes(bytes: &Vec<u8>) -> Result<Self, SchemeError> {
+        let result: asn1::ParseResult<_> = asn1::parse(bytes, |d| {
+            return d.read_element::<asn1::Sequence>()?.parse(|d| {
+                let id = d.read_element::<u64>()? as u16;
+                let g = Group::from_i32(d.read_element::<i32>()?);
+                if g.is_none() {
+                    return Err(ParseError::new(asn1::ParseErrorKind::EncodedDefault));
+                }
+                let group = g.unwrap();
+
+                let bytes = d.read_element::<&[u8]>()?;
+                let r = GroupElement::from_bytes(bytes, &group, Option::None);
+                let bytes = d.read_element::<&[u8]>()?;
+                let z = SizedBigInt::from_bytes(&group, bytes);
+
+                return Ok(Self { id, r, z });
+            });
+        });
+
+        if result.is_err() {
+            error!("{}", result.err().unwrap().to_string());
+            return Err(SchemeError::DeserializationFailed);
+        }
+
+        Ok(result.unwrap())
+    }
+}
+
 #[derive(PartialEq, Clone, Debug)]
 pub struct Nonce {
     pub(crate) hiding_nonce: SizedBigInt,
@@ -596,6 +692,7 @@ pub enum FrostOptions {
     PrecomputeOnly, // precompute commitments (execute round 1 NUM_PRECOMPUTATIONS times) and exit protocol
     Precomputation, // precompute commitments and use first commitment to generate signature in second round
     NoPrecomputation, // do not precompute commitments (might still use previously generated precomputations)
+    Robust, // re-select signers until a signature is produced, implemented by RobustFrostProtocol
 }
 
 pub struct FrostState {}
@@ -684,6 +781,46 @@ pub fn commit(key: &FrostPrivateKey, rng: &mut RNG) -> (PublicCommitment, Nonce)
     (comm, nonce)
 }
 
+/// Signs `message` with the key share of `key` alone. The signature is verified with
+/// `verify_with_share` against the verification key of the share.
+pub fn sign_with_share(key: &FrostPrivateKey, message: &[u8], rng: &mut RNG) -> FrostShareSignature {
+    let group = key.get_group();
+    let order = group.get_order();
+    let verification_key = key.get_public_key().get_verification_key(key.get_share_id());
+
+    let k = SizedBigInt::new_rand(group, &order, rng);
+    let r = GroupElement::new_pow_big(group, &k);
+    let challenge = compute_share_challenge(&r, verification_key, message, group);
+    let z = k.add(&challenge.mul_mod(&key.x, &order)).rmod(&order);
+
+    FrostShareSignature {
+        id: key.get_share_id(),
+        r,
+        z,
+    }
+}
+
+/// Verifies a signature made with `sign_with_share` by the party `signature.get_id()` of `pubkey`.
+pub fn verify_with_share(
+    signature: &FrostShareSignature,
+    pubkey: &FrostPublicKey,
+    message: &[u8],
+) -> bool {
+    if signature.id == 0 || signature.id > pubkey.get_n() {
+        return false;
+    }
+    let group = pubkey.get_group();
+    if signature.r.get_group() != group || signature.z.get_group() != group {
+        return false;
+    }
+
+    let verification_key = pubkey.get_verification_key(signature.id);
+    let challenge = compute_share_challenge(&signature.r, verification_key, message, group);
+    let l = GroupElement::new_pow_big(group, &signature.z);
+    let r = signature.r.mul(&verification_key.pow(&challenge));
+    l.eq(&r)
+}
+
 pub fn assemble(
     group_commitment: &GroupElement,
     key: &FrostPrivateKey,
@@ -942,6 +1079,22 @@ fn compute_challenge(
     h2(&challenge_input, &pk.get_group())
 }
 
+fn compute_share_challenge(
+    r: &GroupElement,
+    verification_key: &GroupElement,
+    msg: &[u8],
+    group: &Group,
+) -> SizedBigInt {
+    let challenge_input = [
+        SHARE_SIGNATURE_DOMAIN.to_vec(),
+        r.to_bytes(),
+        verification_key.to_bytes(),
+        msg.to_vec(),
+    ]
+    .concat();
+    h2(&challenge_input, group)
+}
+
 fn encode_group_commitment_list(commitment_list: &[PublicCommitment]) -> Vec<u8> {
     let mut encoded = Vec::new();
     for i in 0..commitment_list.len() {
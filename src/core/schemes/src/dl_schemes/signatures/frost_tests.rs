@@ -13,7 +13,10 @@ use crate::{
 use hex::FromHex;
 use theta_proto::scheme_types::Group;
 
-use super::frost::{FrostPrivateKey, FrostPublicKey, PublicCommitment};
+use super::frost::{
+    sign_with_share, verify_with_share, FrostPrivateKey, FrostPublicKey, FrostShareSignature,
+    PublicCommitment,
+};
 
 /* ToDo: make test vector work
 
@@ -151,3 +154,40 @@ fn test_vector() {
         assert!(e3.equals(&r.get_share()));
     }*/
 }*/
+
+fn frost_keys() -> Vec<FrostPrivateKey> {
+    KeyGenerator::generate_keys(
+        3,
+        5,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &ThresholdScheme::Frost,
+        &Group::Ed25519,
+        &Option::None,
+    )
+    .unwrap()
+    .into_iter()
+    .map(|key| match key {
+        PrivateKeyShare::Frost(key) => key,
+        _ => panic!("expected a FROST key"),
+    })
+    .collect()
+}
+
+#[test]
+fn test_share_signature() {
+    let keys = frost_keys();
+    let pubkey = keys[0].get_public_key();
+    let mut rng = RNG::new(RngAlgorithm::OsRng);
+
+    let signature = sign_with_share(&keys[1], b"message", &mut rng);
+    assert_eq!(signature.get_id(), 2);
+    assert!(verify_with_share(&signature, pubkey, b"message"));
+    assert!(!verify_with_share(&signature, pubkey, b"other message"));
+
+    let decoded = FrostShareSignature::from_bytes(&signature.to_bytes().unwrap()).unwrap();
+    assert_eq!(decoded, signature);
+
+    // the signature does not verify under the key shares of another key
+    let other_keys = frost_keys();
+    assert!(!verify_with_share(&signature, other_keys[0].get_public_key(), b"message"));
+}
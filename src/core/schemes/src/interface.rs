@@ -1175,6 +1175,7 @@ pub enum SchemeError {
     KeyNotFound,
     MacFailure,
     NoMoreCommitments,
+    InvalidKey(String),
 }
 
 impl Error for SchemeError {}
@@ -1212,6 +1213,7 @@ impl Display for SchemeError {
             Self::MacFailure => write!(f, "MAC Failure"),
             Self::KeyNotFound => write!(f, "Key not found"),
             Self::NoMoreCommitments => write!(f, "No more commitments available"),
+            Self::InvalidKey(s) => write!(f, "Invalid key: {}", s),
         }
     }
 }
@@ -0,0 +1,416 @@
+use std::fs;
+use std::path::PathBuf;
+
+use base64::{engine::general_purpose, Engine as _};
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use ed25519_dalek::{
+    Keypair, PublicKey as VerifyingKey, SecretKey, Signature as ManifestSignature, Signer,
+    Verifier,
+};
+use mcore::hash256::HASH256;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey as BundlePublicKey, StaticSecret};
+
+use super::key_store::KeyStore;
+use super::keys::{PrivateKeyShare, PublicKey};
+
+/// A public key of a key ceremony, as listed in the manifest.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ManifestEntry {
+    pub key_id: String,
+    pub scheme: String,
+    pub group: String,
+    pub threshold: u16,
+    pub n: u16,
+    /// The encoded public key, including the verification keys of all shares.
+    pub public_key: String,
+}
+
+/// The public keys generated in a key ceremony. Nodes check their shares against it before they
+/// go live, clients can import the public keys from it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyManifest {
+    pub nodes: u16,
+    pub keys: Vec<ManifestEntry>,
+}
+
+impl KeyManifest {
+    pub fn new(nodes: u16, public_keys: &[PublicKey]) -> Result<Self, String> {
+        let mut keys = Vec::new();
+        for key in public_keys {
+            keys.push(ManifestEntry {
+                key_id: key.get_key_id().to_string(),
+                scheme: key.get_scheme().as_str_name().to_string(),
+                group: key.get_group().as_str_name().to_string(),
+                threshold: key.get_threshold(),
+                n: key.get_n(),
+                public_key: key.pem().map_err(|e| e.to_string())?,
+            });
+        }
+        Ok(Self { nodes, keys })
+    }
+
+    /// SHA-256 of the serialized manifest. Bundles refer to the manifest by its digest.
+    pub fn digest(&self) -> Vec<u8> {
+        let mut hash = HASH256::new();
+        hash.process_array(&self.canonical_bytes());
+        hash.hash().to_vec()
+    }
+
+    pub fn get_public_key(&self, key_id: &str) -> Result<PublicKey, String> {
+        let entry = self
+            .keys
+            .iter()
+            .find(|e| e.key_id == key_id)
+            .ok_or_else(|| format!("Key {} is not listed in the manifest", key_id))?;
+        let key = PublicKey::from_pem(&entry.public_key)
+            .map_err(|e| format!("Invalid public key {} in the manifest: {}", key_id, e))?;
+        if key.get_key_id() != entry.key_id {
+            return Err(format!("Public key {} does not match its id", key_id));
+        }
+        Ok(key)
+    }
+
+    pub fn public_keys(&self) -> Result<Vec<PublicKey>, String> {
+        self.keys
+            .iter()
+            .map(|e| self.get_public_key(&e.key_id))
+            .collect()
+    }
+
+    pub fn sign(self, signing_key: &SigningKey) -> SignedManifest {
+        let signature = signing_key.sign(&self.canonical_bytes());
+        SignedManifest {
+            manifest: self,
+            signer: encode(signing_key.verifying_key().as_bytes()),
+            signature: encode(&signature.to_bytes()),
+        }
+    }
+
+    fn canonical_bytes(&self) -> Vec<u8> {
+        serde_json::to_vec(self).expect("Serializing the manifest failed")
+    }
+}
+
+/// A manifest signed by the operator of the ceremony.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SignedManifest {
+    pub manifest: KeyManifest,
+    /// The ed25519 key that signed the manifest.
+    pub signer: String,
+    pub signature: String,
+}
+
+impl SignedManifest {
+    pub fn from_file(filename: &PathBuf) -> Result<Self, String> {
+        let content = fs::read_to_string(filename)
+            .map_err(|e| format!("Error reading {}: {}", filename.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid manifest {}: {}", filename.display(), e))
+    }
+
+    pub fn to_file(&self, filename: &str) -> std::io::Result<()> {
+        fs::write(filename, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Checks the signature and returns the manifest. If `trusted_signer` is given, the manifest
+    /// must have been signed with that key. Otherwise only the integrity of the manifest is checked,
+    /// and the caller must compare `signer` out of band.
+    pub fn verify(&self, trusted_signer: Option<&VerifyingKey>) -> Result<&KeyManifest, String> {
+        let signer = decode_verifying_key(&self.signer)?;
+        if let Some(trusted) = trusted_signer {
+            if trusted != &signer {
+                return Err(format!(
+                    "The manifest was signed by {}, not by {}",
+                    self.signer,
+                    encode(trusted.as_bytes())
+                ));
+            }
+        }
+
+        let signature = ManifestSignature::try_from(&decode(&self.signature)?[..])
+            .map_err(|_| String::from("Invalid manifest signature"))?;
+        signer
+            .verify(&self.manifest.canonical_bytes(), &signature)
+            .map_err(|_| String::from("The manifest signature is invalid"))?;
+
+        Ok(&self.manifest)
+    }
+}
+
+/// Encryption of a bundle to the key of its node.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BundleEncryption {
+    /// The X25519 key of the node.
+    pub recipient: String,
+    /// The ephemeral X25519 key of the sender.
+    pub ephemeral: String,
+}
+
+/// The keystore of one node, as produced by a key ceremony. The keystore is optionally encrypted
+/// to an X25519 key of the node, and bound to the node id and the manifest.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NodeBundle {
+    pub node_id: u16,
+    /// Digest of the manifest of the ceremony.
+    pub manifest: String,
+    pub encryption: Option<BundleEncryption>,
+    pub payload: String,
+}
+
+impl NodeBundle {
+    pub fn seal(
+        node_id: u16,
+        keystore: &KeyStore,
+        manifest: &KeyManifest,
+        recipient: Option<&BundlePublicKey>,
+    ) -> Result<Self, String> {
+        let digest = manifest.digest();
+        let keystore = keystore.to_json().into_bytes();
+
+        let (encryption, payload) = match recipient {
+            Some(recipient) => {
+                // a fresh key for every bundle
+                let ephemeral = StaticSecret::from(random_bytes());
+                let ephemeral_public = BundlePublicKey::from(&ephemeral);
+                let shared = ephemeral.diffie_hellman(recipient);
+                let cipher = bundle_cipher(shared.as_bytes(), &ephemeral_public, recipient);
+                let aad = bundle_aad(node_id, &digest);
+                let payload = cipher
+                    .encrypt(
+                        bundle_nonce(),
+                        Payload {
+                            msg: &keystore,
+                            aad: &aad,
+                        },
+                    )
+                    .map_err(|_| String::from("Encrypting the bundle failed"))?;
+                let encryption = BundleEncryption {
+                    recipient: encode(recipient.as_bytes()),
+                    ephemeral: encode(ephemeral_public.as_bytes()),
+                };
+                (Some(encryption), payload)
+            }
+            None => (None, keystore),
+        };
+
+        Ok(Self {
+            node_id,
+            manifest: encode(&digest),
+            encryption,
+            payload: encode(&payload),
+        })
+    }
+
+    /// Returns the keystore of the bundle. `secret` is the X25519 key of the node, it is needed if
+    /// the bundle is encrypted.
+    pub fn open(&self, manifest: &KeyManifest, secret: Option<&StaticSecret>) -> Result<KeyStore, String> {
+        let digest = manifest.digest();
+        if decode(&self.manifest)? != digest {
+            return Err(String::from("The bundle belongs to a different manifest"));
+        }
+
+        let payload = decode(&self.payload)?;
+        let keystore = match &self.encryption {
+            Some(encryption) => {
+                let secret = secret.ok_or_else(|| {
+                    String::from("The bundle is encrypted, the key of the node is required")
+                })?;
+                let recipient = BundlePublicKey::from(secret);
+                if encode(recipient.as_bytes()) != encryption.recipient {
+                    return Err(String::from("The bundle is encrypted to a different key"));
+                }
+                let ephemeral = decode_bundle_key(&encryption.ephemeral)?;
+                let shared = secret.diffie_hellman(&ephemeral);
+                let cipher = bundle_cipher(shared.as_bytes(), &ephemeral, &recipient);
+                let aad = bundle_aad(self.node_id, &digest);
+                cipher
+                    .decrypt(
+                        bundle_nonce(),
+                        Payload {
+                            msg: &payload,
+                            aad: &aad,
+                        },
+                    )
+                    .map_err(|_| String::from("Decrypting the bundle failed"))?
+            }
+            None => payload,
+        };
+
+        let keystore = String::from_utf8(keystore).map_err(|_| String::from("Invalid keystore"))?;
+        KeyStore::from_str(keystore).map_err(|e| format!("Invalid keystore: {}", e))
+    }
+
+    pub fn from_file(filename: &PathBuf) -> Result<Self, String> {
+        let content = fs::read_to_string(filename)
+            .map_err(|e| format!("Error reading {}: {}", filename.display(), e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid bundle {}: {}", filename.display(), e))
+    }
+
+    pub fn to_file(&self, filename: &str) -> std::io::Result<()> {
+        fs::write(filename, serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// The result of checking one key of a keystore against a manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyCheck {
+    pub key_id: String,
+    pub error: Option<String>,
+}
+
+/// Checks that `keystore` holds a share of every key of the manifest, that the public keys match
+/// the manifest, and that every share is consistent with its verification key. If `node_id` is
+/// given, every share must have that id, otherwise all shares must have the same id.
+pub fn verify_keystore(
+    keystore: &KeyStore,
+    manifest: &KeyManifest,
+    node_id: Option<u16>,
+) -> Vec<KeyCheck> {
+    let mut checks = Vec::new();
+    let mut expected_id = node_id;
+
+    for entry in manifest.keys.iter() {
+        let result = keystore
+            .get_key_by_id(&entry.key_id)
+            .map_err(|e| e.to_string())
+            .and_then(|key| {
+                key.sk.ok_or_else(|| String::from("The keystore holds no share of this key"))
+            })
+            .and_then(|share| {
+                let id = share.get_share_id();
+                match expected_id {
+                    Some(expected) if expected != id => Err(format!(
+                        "Share {} instead of share {}",
+                        id, expected
+                    )),
+                    _ => {
+                        expected_id = Some(id);
+                        verify_share(&share, manifest)
+                    }
+                }
+            });
+
+        checks.push(KeyCheck {
+            key_id: entry.key_id.clone(),
+            error: result.err(),
+        });
+    }
+
+    checks
+}
+
+/// Checks a single share against the public key of the manifest, and against the verification
+/// key of the share (see `PrivateKeyShare::validate`).
+pub fn verify_share(share: &PrivateKeyShare, manifest: &KeyManifest) -> Result<(), String> {
+    let public_key = manifest.get_public_key(share.get_key_id())?;
+    if share.get_public_key() != public_key {
+        return Err(String::from("The public key of the share differs from the manifest"));
+    }
+    share.validate().map_err(|e| e.to_string())
+}
+
+/// The ed25519 key of the operator of a ceremony, used to sign the manifest.
+pub struct SigningKey(Keypair);
+
+impl SigningKey {
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, String> {
+        let secret =
+            SecretKey::from_bytes(bytes).map_err(|_| String::from("Invalid ed25519 secret key"))?;
+        let public = VerifyingKey::from(&secret);
+        Ok(Self(Keypair { secret, public }))
+    }
+
+    /// The secret key, as stored in the key file.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        self.0.secret.to_bytes()
+    }
+
+    pub fn verifying_key(&self) -> VerifyingKey {
+        self.0.public
+    }
+
+    fn sign(&self, message: &[u8]) -> ManifestSignature {
+        self.0.sign(message)
+    }
+}
+
+pub fn generate_signing_key() -> SigningKey {
+    SigningKey::from_bytes(&random_bytes()).expect("Every 32-byte string is an ed25519 secret key")
+}
+
+/// Generates an X25519 key pair a node uses to receive its bundle.
+pub fn generate_bundle_key() -> (StaticSecret, BundlePublicKey) {
+    let secret = StaticSecret::from(random_bytes());
+    let public = BundlePublicKey::from(&secret);
+    (secret, public)
+}
+
+pub fn encode_key(key: &[u8; 32]) -> String {
+    encode(key)
+}
+
+pub fn decode_signing_key(encoded: &str) -> Result<SigningKey, String> {
+    SigningKey::from_bytes(&decode_key(encoded)?)
+}
+
+pub fn decode_verifying_key(encoded: &str) -> Result<VerifyingKey, String> {
+    VerifyingKey::from_bytes(&decode_key(encoded)?)
+        .map_err(|_| String::from("Invalid ed25519 public key"))
+}
+
+pub fn decode_bundle_secret(encoded: &str) -> Result<StaticSecret, String> {
+    Ok(StaticSecret::from(decode_key(encoded)?))
+}
+
+pub fn decode_bundle_key(encoded: &str) -> Result<BundlePublicKey, String> {
+    Ok(BundlePublicKey::from(decode_key(encoded)?))
+}
+
+fn random_bytes() -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn decode_key(encoded: &str) -> Result<[u8; 32], String> {
+    decode(encoded.trim())?
+        .try_into()
+        .map_err(|_| String::from("A key must be 32 bytes long"))
+}
+
+// The key of a bundle is only used once, so the nonce can be constant.
+fn bundle_cipher(
+    shared: &[u8; 32],
+    ephemeral: &BundlePublicKey,
+    recipient: &BundlePublicKey,
+) -> ChaCha20Poly1305 {
+    let mut hash = HASH256::new();
+    hash.process_array(shared);
+    hash.process_array(ephemeral.as_bytes());
+    hash.process_array(recipient.as_bytes());
+    ChaCha20Poly1305::new(Key::from_slice(&hash.hash()))
+}
+
+fn bundle_nonce() -> &'static Nonce {
+    Nonce::from_slice(&[0u8; 12])
+}
+
+fn bundle_aad(node_id: u16, manifest_digest: &[u8]) -> Vec<u8> {
+    [&node_id.to_be_bytes()[..], manifest_digest].concat()
+}
+
+fn encode(bytes: &[u8]) -> String {
+    general_purpose::URL_SAFE.encode(bytes)
+}
+
+fn decode(encoded: &str) -> Result<Vec<u8>, String> {
+    general_purpose::URL_SAFE
+        .decode(encoded)
+        .map_err(|_| String::from("Invalid base64 encoding"))
+}
@@ -0,0 +1,115 @@
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+
+use crate::rand::{RngAlgorithm, RNG};
+
+use super::{
+    ceremony::{
+        generate_bundle_key, generate_signing_key, verify_keystore, KeyManifest, NodeBundle,
+    },
+    key_generator::KeyGenerator,
+    key_store::KeyStore,
+    keys::PrivateKeyShare,
+};
+
+// Generates a key set per scheme and returns the shares of every node.
+fn ceremony(schemes: &[ThresholdScheme]) -> (KeyManifest, Vec<Vec<PrivateKeyShare>>) {
+    let mut public_keys = Vec::new();
+    let mut nodes: Vec<Vec<PrivateKeyShare>> = vec![Vec::new(); 4];
+    for scheme in schemes {
+        let keys = KeyGenerator::generate_keys(
+            2,
+            4,
+            &mut RNG::new(RngAlgorithm::OsRng),
+            scheme,
+            &Group::Bls12381,
+            &Option::None,
+        )
+        .unwrap();
+        public_keys.push(keys[0].get_public_key());
+        for (i, key) in keys.into_iter().enumerate() {
+            nodes[i].push(key);
+        }
+    }
+    (KeyManifest::new(4, &public_keys).unwrap(), nodes)
+}
+
+fn keystore(shares: &[PrivateKeyShare]) -> KeyStore {
+    let mut keystore = KeyStore::new();
+    for share in shares {
+        keystore.insert_private_key(share.clone()).unwrap();
+    }
+    keystore
+}
+
+#[test]
+fn test_manifest_signature() {
+    let (manifest, _) = ceremony(&[ThresholdScheme::Sg02]);
+    let signing_key = generate_signing_key();
+    let signed = manifest.sign(&signing_key);
+
+    assert!(signed.verify(Some(&signing_key.verifying_key())).is_ok());
+    assert!(signed.verify(None).is_ok());
+
+    let other = generate_signing_key();
+    assert!(signed.verify(Some(&other.verifying_key())).is_err());
+
+    let mut tampered = signed.clone();
+    tampered.manifest.nodes = 5;
+    assert!(tampered.verify(None).is_err());
+}
+
+#[test]
+fn test_sealed_bundle() {
+    let (manifest, nodes) = ceremony(&[ThresholdScheme::Bls04]);
+    let keystore = keystore(&nodes[0]);
+    let (secret, public) = generate_bundle_key();
+
+    let bundle = NodeBundle::seal(1, &keystore, &manifest, Some(&public)).unwrap();
+    assert!(bundle.open(&manifest, None).is_err());
+    let (other_secret, _) = generate_bundle_key();
+    assert!(bundle.open(&manifest, Some(&other_secret)).is_err());
+    assert_eq!(bundle.open(&manifest, Some(&secret)).unwrap(), keystore);
+
+    // the node id is authenticated
+    let mut moved = bundle.clone();
+    moved.node_id = 2;
+    assert!(moved.open(&manifest, Some(&secret)).is_err());
+
+    let plain = NodeBundle::seal(1, &keystore, &manifest, None).unwrap();
+    assert_eq!(plain.open(&manifest, None).unwrap(), keystore);
+
+    let (other_manifest, _) = ceremony(&[ThresholdScheme::Bls04]);
+    assert!(plain.open(&other_manifest, None).is_err());
+}
+
+#[test]
+fn test_verify_keystore() {
+    let schemes = [
+        ThresholdScheme::Sg02,
+        ThresholdScheme::Bz03,
+        ThresholdScheme::Bls04,
+        ThresholdScheme::Cks05,
+    ];
+    let (manifest, nodes) = ceremony(&schemes);
+
+    let checks = verify_keystore(&keystore(&nodes[1]), &manifest, Some(2));
+    assert_eq!(checks.len(), schemes.len());
+    assert!(checks.iter().all(|c| c.error.is_none()));
+
+    // the shares belong to node 2
+    let checks = verify_keystore(&keystore(&nodes[1]), &manifest, Some(1));
+    assert!(checks.iter().all(|c| c.error.is_some()));
+
+    // shares of different nodes
+    let mixed = vec![nodes[0][0].clone(), nodes[1][1].clone(), nodes[0][2].clone(), nodes[0][3].clone()];
+    let checks = verify_keystore(&keystore(&mixed), &manifest, None);
+    assert_eq!(checks.iter().filter(|c| c.error.is_some()).count(), 1);
+
+    // a share missing, and a share of a key that is not in the manifest
+    let (_, foreign) = ceremony(&[ThresholdScheme::Sg02]);
+    let mut incomplete = nodes[0][1..].to_vec();
+    incomplete.push(foreign[0][0].clone());
+    let checks = verify_keystore(&keystore(&incomplete), &manifest, None);
+    assert!(checks[0].error.is_some());
+    assert!(checks[1..].iter().all(|c| c.error.is_none()));
+}
@@ -3,10 +3,12 @@ use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use theta_proto::scheme_types::PublicKeyEntry;
-use theta_proto::scheme_types::{Group, ThresholdOperation, ThresholdScheme};
+use theta_proto::scheme_types::{Group, KeyStatus, ThresholdOperation, ThresholdScheme};
 
 use crate::interface::Serializable;
 use crate::scheme_types_impl::SchemeDetails;
@@ -14,15 +16,96 @@ use crate::scheme_types_impl::SchemeDetails;
 use super::keys::{key2id, PrivateKeyShare, PublicKey};
 use super::KeyStoreError;
 
+/// Longest alias accepted for a key.
+pub const MAX_ALIAS_LENGTH: usize = 64;
+
+// Information about a key that is not part of the key itself. It is local to a keystore, so
+// different nodes may use different aliases for the same key.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct KeyMetadata {
+    // Human-friendly name that can be used wherever a key id is expected, unique per keystore
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Unix time in seconds at which the key was added to the keystore, unknown for keys from
+    // keystores written before it was recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    // Version of the key within its lineage, a rotation adds the next version
+    #[serde(default = "first_version", skip_serializing_if = "is_first_version")]
+    pub version: u32,
+    // Id of the first version of the key, not set for first versions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lineage: Option<String>,
+    #[serde(default, skip_serializing_if = "is_active")]
+    pub status: KeyStatus,
+}
+
+impl Default for KeyMetadata {
+    fn default() -> Self {
+        KeyMetadata {
+            alias: None,
+            description: None,
+            tags: Vec::new(),
+            created_at: None,
+            version: first_version(),
+            lineage: None,
+            status: KeyStatus::Active,
+        }
+    }
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+fn is_first_version(version: &u32) -> bool {
+    *version == 1
+}
+
+fn is_active(status: &KeyStatus) -> bool {
+    *status == KeyStatus::Active
+}
+
+impl KeyMetadata {
+    fn created_now() -> Self {
+        KeyMetadata {
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs()),
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 pub struct KeyEntry {
     pub id: String,
     pub(crate) is_default: bool,
     pub sk: Option<PrivateKeyShare>,
     pub pk: PublicKey,
+    #[serde(default)]
+    pub metadata: KeyMetadata,
 }
 
 impl KeyEntry {
+    pub fn is_default(&self) -> bool {
+        self.is_default
+    }
+
+    // Id of the first version of the key, shared by all its versions
+    pub fn lineage(&self) -> &str {
+        self.metadata.lineage.as_deref().unwrap_or(&self.id)
+    }
+
+    pub fn status(&self) -> KeyStatus {
+        self.metadata.status
+    }
+
     pub fn to_string(&self) -> String {
         let mut postfix = String::from("");
         if self.sk.is_some() {
@@ -33,10 +116,21 @@ impl KeyEntry {
         if self.is_default {
             default_string.push_str("\t(default)");
         }
+        if self.metadata.version > 1 {
+            default_string.push_str(&format!("\tv{}", self.metadata.version));
+        }
+        if self.metadata.status != KeyStatus::Active {
+            default_string.push_str(&format!("\t{}", self.metadata.status.as_str_name()));
+        }
+
+        let mut id = self.id.clone();
+        if let Some(alias) = &self.metadata.alias {
+            id.push_str(&format!(" ({})", alias));
+        }
 
         format!(
             "{} [{}/{}] {} {}",
-            &self.id,
+            id,
             self.pk.get_scheme().as_str_name(),
             self.pk.get_group().as_str_name(),
             postfix,
@@ -59,14 +153,20 @@ struct SerializedKeyEntry {
     pub scheme: String,
     pub operation: String,
     pub key: String,
+    // missing in keystores written before defaults were stored, the defaults are derived then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_default: Option<bool>,
+    #[serde(flatten)]
+    pub metadata: KeyMetadata,
 }
 
 impl From<Vec<SerializedKeyEntry>> for KeyStore {
     fn from(value: Vec<SerializedKeyEntry>) -> Self {
         let mut kc = Self::new();
+        let mut stored_defaults = Vec::new();
 
         for entry in value {
-            match entry.key_type.as_str() {
+            let id = match entry.key_type.as_str() {
                 "secret" => {
                     let key = PrivateKeyShare::from_pem(&entry.key);
                     if key.is_err() {
@@ -80,7 +180,9 @@ impl From<Vec<SerializedKeyEntry>> for KeyStore {
                     let id = kc.insert_private_key(key.clone());
                     if id.is_err() {
                         error!("Error inserting private key: {}", id.unwrap_err());
+                        continue;
                     }
+                    id.unwrap()
                 }
                 _ => {
                     let key = PublicKey::from_pem(&entry.key);
@@ -95,13 +197,29 @@ impl From<Vec<SerializedKeyEntry>> for KeyStore {
 
                     if id.is_err() {
                         error!("Error inserting public key: {}", id.unwrap_err());
+                        continue;
                     }
 
                     /*  let id = id.unwrap();
                     if id != key.unwrap().get_key_id() {
                         error!("Key id changed: {} - {}", key.unwrap().get_key_id(), &id);
                     }*/
+                    id.unwrap()
                 }
+            };
+
+            if let Err(e) = kc.replace_metadata(&id, entry.metadata) {
+                error!("Error loading the metadata of key {}: {}", id, e);
+            }
+            if let Some(is_default) = entry.is_default {
+                stored_defaults.push((id, is_default));
+            }
+        }
+
+        // The stored flags are applied last, as inserting a key may change the default
+        for (id, is_default) in stored_defaults {
+            if let Some(entry) = kc.key_entries.get_mut(&id) {
+                entry.is_default = is_default;
             }
         }
 
@@ -138,11 +256,26 @@ impl KeyStore {
     }
 
     pub fn to_file(&self, filename: &str) -> std::io::Result<()> {
+        let serialized = self.to_json();
+
+        // Write to a temporary file first and move it into place afterwards, so that a crash
+        // while writing never leaves a truncated keystore behind.
+        let tmp_filename = format!("{}.tmp", filename);
+        let mut file = fs::File::create(&tmp_filename)?;
+        file.write_all(serialized.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_filename, filename)?;
+
+        Ok(())
+    }
+
+    // Serializes the keystore in the format of the keystore files.
+    pub fn to_json(&self) -> String {
         let mut keys = Vec::new();
 
-        for (id, key) in &self.key_entries {
+        for key in self.list_keys().iter() {
             keys.push(SerializedKeyEntry {
-                id: id.clone(),
+                id: key.id.clone(),
                 key_type: match key.sk.is_some() {
                     true => String::from("secret"),
                     false => String::from("public"),
@@ -154,26 +287,38 @@ impl KeyStore {
                     true => key.sk.as_ref().unwrap().pem().unwrap(),
                     false => key.pk.pem().unwrap(),
                 },
+                is_default: Some(key.is_default),
+                metadata: key.metadata.clone(),
             });
         }
 
-        let serialized = serde_json::to_string(&keys).unwrap();
-        fs::write(filename, serialized)?;
-
-        Ok(())
+        serde_json::to_string(&keys).unwrap()
     }
 
+    // Imports the public keys a node announced. Keys that are already in the keystore take over
+    // the version and status of the announcement, so that rotations are picked up.
     pub fn import_public_keys(&mut self, public_keys: &[PublicKeyEntry]) -> Result<(), String> {
         for entry in public_keys {
             let key = PublicKey::from_bytes(&entry.key);
             if key.is_ok() {
-                let id = self.insert_public_key(key.unwrap());
-                if let Err(e) = id {
-                    println!("Error: {}", e.to_string());
-                    continue;
+                let id = match self.insert_public_key(key.unwrap()) {
+                    Ok(id) => id,
+                    Err(KeyStoreError::DuplicateEntry(id)) => id,
+                    Err(e) => {
+                        println!("Error: {}", e.to_string());
+                        continue;
+                    }
+                };
+
+                if entry.version > 0 {
+                    let key_entry = self.key_entries.get_mut(&id).unwrap();
+                    key_entry.metadata.version = entry.version;
+                    key_entry.metadata.lineage = entry.lineage.clone();
                 }
+                let status = KeyStatus::from_i32(entry.status).unwrap_or_default();
+                self.set_status(&id, status).map_err(|e| e.to_string())?;
 
-                debug!("Imported public key {}", id.unwrap());
+                debug!("Imported public key {}", id);
             } else {
                 return Err(format!("Error: {}", key.unwrap_err().to_string()));
             }
@@ -218,8 +363,8 @@ impl KeyStore {
 
     // Inserts a key to the key_chain and returns the unique id of the key
     // The function, and eventually the KeyStore, gets ownership of the key.
-    // A key is_default_for_scheme_and_group if it is the first key created for its scheme and group.
-    // A key is_default_for_operation if it is the first key created for its operation.
+    // A key is the default for its scheme and group if there is no default key for them yet.
+    // If the public key was already stored, the entry keeps its default flag and metadata.
     pub fn insert_private_key(&mut self, key: PrivateKeyShare) -> Result<String, KeyStoreError> {
         let key_id = key2id(&key.get_public_key());
 
@@ -236,26 +381,13 @@ impl KeyStore {
             return Err(KeyStoreError::DuplicateEntry(key_id.clone()));
         }
 
-        let operation = key.get_scheme().get_operation();
-        let matching_keys: Vec<(&String, &mut KeyEntry)> = self
-            .key_entries
-            .iter_mut()
-            .filter(|e| {
-                e.1.sk.is_some()
-                    && e.1.sk.as_ref().unwrap().get_scheme().get_operation() == operation
-            })
-            .collect();
-
-        let mut is_default = true;
-        for _k in matching_keys {
-            _k.1.is_default = false;
-            is_default = false;
-        }
-
-        let entry = self.key_entries.get(&key_id);
-        if entry.is_some() {
-            self.key_entries.remove_entry(&key_id);
-        }
+        let (is_default, metadata) = match self.key_entries.remove(&key_id) {
+            Some(entry) => (entry.is_default, entry.metadata),
+            None => (
+                !self.has_default(key.get_scheme(), key.get_group()),
+                KeyMetadata::created_now(),
+            ),
+        };
 
         self.key_entries.insert(
             key_id.clone(),
@@ -264,6 +396,7 @@ impl KeyStore {
                 is_default,
                 pk: key.get_public_key(),
                 sk: Some(key),
+                metadata,
             },
         );
 
@@ -282,11 +415,7 @@ impl KeyStore {
             return Err(KeyStoreError::DuplicateEntry(key_id.clone()));
         }
 
-        let operation = key.get_scheme().get_operation();
-        let is_default = !self
-            .key_entries
-            .iter()
-            .any(|e| e.1.pk.get_scheme().get_operation() == operation);
+        let is_default = !self.has_default(key.get_scheme(), key.get_group());
 
         self.key_entries.insert(
             key_id.clone(),
@@ -295,25 +424,249 @@ impl KeyStore {
                 is_default,
                 sk: None,
                 pk: key,
+                metadata: KeyMetadata::created_now(),
             },
         );
         Ok(key_id)
     }
 
-    // Return the matching key with the given key_id, or an error if no key with key_id exists.
+    // Removes the key with the given id or alias and returns it. If the removed key was the
+    // default key for its scheme and group, another key of the same scheme and group becomes the
+    // default.
+    pub fn remove_key(&mut self, id: &str) -> Result<KeyEntry, KeyStoreError> {
+        let id = self.resolve_id(id)?;
+        let entry = match self.key_entries.remove(&id) {
+            Some(entry) => entry,
+            None => return Err(KeyStoreError::IdNotFound(id)),
+        };
+
+        if entry.is_default {
+            self.elect_default(entry.pk.get_scheme(), entry.pk.get_group());
+        }
+
+        Ok(entry)
+    }
+
+    // Makes the key with the given id or alias the default key for its scheme and group. All
+    // other keys of the same scheme and group lose their default flag. Only active keys can be
+    // the default.
+    pub fn set_default(&mut self, id: &str) -> Result<(), KeyStoreError> {
+        let id = self.resolve_id(id)?;
+        let (scheme, group) = match self.key_entries.get(&id) {
+            Some(entry) if entry.status() != KeyStatus::Active => {
+                return Err(KeyStoreError::NotActive(id))
+            }
+            Some(entry) => (entry.pk.get_scheme(), *entry.pk.get_group()),
+            None => return Err(KeyStoreError::IdNotFound(id)),
+        };
+
+        for entry in self.key_entries.values_mut() {
+            if entry.pk.get_scheme() == scheme && group.eq(entry.pk.get_group()) {
+                entry.is_default = entry.id == id;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Return all keys, sorted by id.
+    pub fn list_keys(&self) -> Vec<KeyEntry> {
+        let mut keys: Vec<KeyEntry> = self.key_entries.values().cloned().collect();
+        keys.sort_by(|a, b| a.id.cmp(&b.id));
+        keys
+    }
+
+    // Return the matching key with the given key_id or alias, or an error if no such key exists.
     pub fn get_key_by_id(&self, id: &str) -> Result<KeyEntry, KeyStoreError> {
-        if self.key_entries.contains_key(id) == false {
-            error!("No entry for id {}", &id);
-            return Err(KeyStoreError::IdNotFound(String::from(id)));
+        let id = match self.resolve_id(id) {
+            Ok(id) => id,
+            Err(e) => {
+                error!("No entry for id {}", &id);
+                return Err(e);
+            }
+        };
+
+        return Ok(self.key_entries.get(&id).unwrap().clone());
+    }
+
+    // Returns the id of the key with the given id or alias. Aliases cannot collide with key ids,
+    // as they never contain the padding of the base64 encoded ids.
+    pub fn resolve_id(&self, id: &str) -> Result<String, KeyStoreError> {
+        if self.key_entries.contains_key(id) {
+            return Ok(String::from(id));
+        }
+
+        self.key_entries
+            .values()
+            .find(|e| e.metadata.alias.as_deref() == Some(id))
+            .map(|e| e.id.clone())
+            .ok_or_else(|| KeyStoreError::IdNotFound(String::from(id)))
+    }
+
+    // Replaces the alias, description and tags of the key with the given id or alias. The
+    // creation time, version and status are kept.
+    pub fn set_metadata(&mut self, id: &str, metadata: KeyMetadata) -> Result<(), KeyStoreError> {
+        let id = self.resolve_id(id)?;
+        let current = self.key_entries[&id].metadata.clone();
+        self.replace_metadata(
+            &id,
+            KeyMetadata {
+                alias: metadata.alias,
+                description: metadata.description,
+                tags: metadata.tags,
+                ..current
+            },
+        )
+    }
+
+    // Sets the status of the key with the given id or alias. If the key was the default for its
+    // scheme and group and is not active anymore, another active key becomes the default.
+    pub fn set_status(&mut self, id: &str, status: KeyStatus) -> Result<(), KeyStoreError> {
+        let id = self.resolve_id(id)?;
+        let entry = self.key_entries.get_mut(&id).unwrap();
+        entry.metadata.status = status;
+        if status != KeyStatus::Active {
+            entry.is_default = false;
         }
 
-        return Ok(self.key_entries.get(id).unwrap().clone());
+        let (scheme, group) = (entry.pk.get_scheme(), *entry.pk.get_group());
+        self.elect_default(scheme, &group);
+        Ok(())
+    }
+
+    // Returns all versions of the key with the given id or alias, oldest first.
+    pub fn get_versions(&self, id: &str) -> Result<Vec<KeyEntry>, KeyStoreError> {
+        let entry = self.get_key_by_id(id)?;
+        let mut versions: Vec<KeyEntry> = self
+            .key_entries
+            .values()
+            .filter(|e| e.lineage() == entry.lineage())
+            .cloned()
+            .collect();
+        versions.sort_by_key(|e| e.metadata.version);
+        Ok(versions)
     }
 
-    // First filter all keys and keep those that match the given scheme and group.
+    // Returns the newest active version of the key with the given id or alias. New data is
+    // encrypted under this version, older versions may only be used to decrypt.
+    pub fn get_active_version(&self, id: &str) -> Result<KeyEntry, KeyStoreError> {
+        self.get_versions(id)?
+            .into_iter()
+            .rev()
+            .find(|e| e.status() == KeyStatus::Active)
+            .ok_or_else(|| KeyStoreError::NoActiveVersion(String::from(id)))
+    }
+
+    // Adds `key` as the next version of the key with the given id or alias (any version of it) and
+    // returns its id.
+    // The new version takes over the alias, description, tags and default flag. The previous
+    // version becomes decrypt-only for encryption keys and retired otherwise.
+    pub fn rotate_key(
+        &mut self,
+        previous: &str,
+        key: PrivateKeyShare,
+    ) -> Result<String, KeyStoreError> {
+        // a rotation always continues from the latest version
+        let previous = self.get_versions(previous)?.pop().unwrap();
+        if previous.pk.get_scheme() != key.get_scheme()
+            || !previous.pk.get_group().eq(key.get_group())
+        {
+            return Err(KeyStoreError::VersionMismatch(previous.id));
+        }
+        let version = previous.metadata.version + 1;
+
+        let id = self.insert_private_key(key)?;
+        let entry = self.key_entries.get_mut(&id).unwrap();
+        entry.metadata = KeyMetadata {
+            alias: None,
+            description: previous.metadata.description.clone(),
+            tags: previous.metadata.tags.clone(),
+            created_at: entry.metadata.created_at,
+            version,
+            lineage: Some(previous.lineage().to_string()),
+            status: KeyStatus::Active,
+        };
+
+        // the alias moves to the new version
+        if let Some(alias) = &previous.metadata.alias {
+            self.key_entries.get_mut(&previous.id).unwrap().metadata.alias = None;
+            self.key_entries.get_mut(&id).unwrap().metadata.alias = Some(alias.clone());
+        }
+        if previous.is_default {
+            self.set_default(&id)?;
+        }
+
+        let status = match previous.pk.get_operation() {
+            ThresholdOperation::Encryption => KeyStatus::DecryptOnly,
+            _ => KeyStatus::Retired,
+        };
+        self.set_status(&previous.id, status)?;
+        Ok(id)
+    }
+
+    // Checks that the metadata can be assigned to the key with the given id, i.e., that its alias
+    // is valid and not used by another key. The key does not have to be in the keystore yet.
+    pub fn check_metadata(&self, id: &str, metadata: &KeyMetadata) -> Result<(), KeyStoreError> {
+        if let Some(alias) = &metadata.alias {
+            check_alias(alias)?;
+            if self
+                .key_entries
+                .values()
+                .any(|e| e.id != id && e.metadata.alias.as_ref() == Some(alias))
+            {
+                return Err(KeyStoreError::DuplicateAlias(alias.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn replace_metadata(&mut self, id: &str, metadata: KeyMetadata) -> Result<(), KeyStoreError> {
+        self.check_metadata(id, &metadata)?;
+
+        match self.key_entries.get_mut(id) {
+            Some(entry) => {
+                entry.metadata = metadata;
+                Ok(())
+            }
+            None => Err(KeyStoreError::IdNotFound(String::from(id))),
+        }
+    }
+
+    fn has_default(&self, scheme: ThresholdScheme, group: &Group) -> bool {
+        self.key_entries
+            .values()
+            .any(|e| e.is_default && e.pk.get_scheme() == scheme && group.eq(e.pk.get_group()))
+    }
+
+    // Makes the active key of the scheme and group with the smallest id the default, unless one
+    // of them already is.
+    fn elect_default(&mut self, scheme: ThresholdScheme, group: &Group) {
+        if self.has_default(scheme, group) {
+            return;
+        }
+
+        let replacement = self
+            .key_entries
+            .values()
+            .filter(|e| {
+                e.status() == KeyStatus::Active
+                    && e.pk.get_scheme() == scheme
+                    && group.eq(e.pk.get_group())
+            })
+            .map(|e| e.id.clone())
+            .min();
+
+        if let Some(id) = replacement {
+            self.key_entries.get_mut(&id).unwrap().is_default = true;
+        }
+    }
+
+    // First filter all active keys and keep those that match the given scheme and group.
     // If there is no matching key, return an error.
     // If there is only one, return it.
     // Otherwise, return the 'default' key among the matching ones (there should be only one).
+    // If there is none or more than one, the error lists the matching keys sorted by id.
     pub fn get_key_by_scheme_and_group(
         &self,
         scheme: ThresholdScheme,
@@ -322,10 +675,14 @@ impl KeyStore {
         let matching_key_entries: Vec<(&String, &KeyEntry)> = self
             .key_entries
             .iter()
-            .filter(|&entry| entry.1.pk.get_scheme() == scheme && group.eq(entry.1.pk.get_group()))
+            .filter(|&entry| {
+                entry.1.status() == KeyStatus::Active
+                    && entry.1.pk.get_scheme() == scheme
+                    && group.eq(entry.1.pk.get_group())
+            })
             .collect();
         return match matching_key_entries.len() {
-            0 => Err(String::from("No key matches the given scheme and group.")),
+            0 => Err(String::from("No active key matches the given scheme and group.")),
             1 => Ok(matching_key_entries[0].1.clone()),
             _ => {
                 let default_key_entries: Vec<(&String, &KeyEntry)> = matching_key_entries
@@ -333,15 +690,35 @@ impl KeyStore {
                     .filter(|&entry| entry.1.is_default)
                     .map(|e| *e)
                     .collect();
+                let mut candidates: Vec<String> = matching_key_entries
+                    .iter()
+                    .map(|e| match &e.1.metadata.alias {
+                        Some(alias) => format!("{} ({})", e.1.id, alias),
+                        None => e.1.id.clone(),
+                    })
+                    .collect();
+                candidates.sort();
                 match default_key_entries.len() {
                     0 => {
                         error!("One key should always be specified as default.");
-                        Err(String::from("Could not find a default key for this scheme. Please specify a key id."))
+                        Err(format!(
+                            "{} keys match scheme {} and group {} and none of them is the default. Please specify one of: {}",
+                            candidates.len(),
+                            scheme.as_str_name(),
+                            group.as_str_name(),
+                            candidates.join(", ")
+                        ))
                     }
                     1 => Ok(default_key_entries[0].1.clone()),
                     _ => {
                         error!("No more than one key should always be specified as default.");
-                        Err(String::from("Could not select a default key for this scheme. Please specify a key id."))
+                        Err(format!(
+                            "{} keys match scheme {} and group {} and more than one of them is the default. Please specify one of: {}",
+                            candidates.len(),
+                            scheme.as_str_name(),
+                            group.as_str_name(),
+                            candidates.join(", ")
+                        ))
                     }
                 }
             }
@@ -381,9 +758,25 @@ impl KeyStore {
                 scheme: entry.1.pk.get_scheme().into(),
                 group: (*entry.1.pk.get_group()).into(),
                 key: entry.1.pk.to_bytes().unwrap(),
+                version: entry.1.metadata.version,
+                lineage: entry.1.metadata.lineage.clone(),
+                status: entry.1.status().into(),
             })
             .for_each(|i| keys.push(Arc::new(i)));
 
         keys
     }
 }
+
+// Aliases are 1 to MAX_ALIAS_LENGTH letters, digits, '.', '_' or '-'.
+fn check_alias(alias: &str) -> Result<(), KeyStoreError> {
+    let valid = !alias.is_empty()
+        && alias.len() <= MAX_ALIAS_LENGTH
+        && alias
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-');
+    match valid {
+        true => Ok(()),
+        false => Err(KeyStoreError::InvalidAlias(String::from(alias))),
+    }
+}
@@ -2,16 +2,18 @@ use std::{collections::HashMap, fs::remove_file, path::PathBuf};
 
 use base64::{engine::general_purpose, Engine};
 use mcore::hash256::HASH256;
-use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_proto::scheme_types::{Group, KeyStatus, ThresholdScheme};
 
 use crate::{
-    interface::{Serializable, ThresholdCipherParams},
+    dl_schemes::coins::cks05::Cks05PrivateKey,
+    integers::sizedint::SizedBigInt,
+    interface::{SchemeError, Serializable, ThresholdCipherParams},
     rand::{RngAlgorithm, RNG},
 };
 
 use super::{
     key_generator::KeyGenerator,
-    key_store::KeyStore,
+    key_store::{KeyMetadata, KeyStore},
     keys::{calc_key_id, key2id, PrivateKeyShare},
 };
 
@@ -132,3 +134,271 @@ fn fill_key_chain() -> (KeyStore, HashMap<String, PrivateKeyShare>) {
     }
     (key_chain, keys)
 }
+
+#[test]
+fn test_set_default_key() {
+    let (mut key_chain, keys) = fill_key_chain();
+    let mut ids: Vec<&String> = keys.keys().collect();
+    ids.sort();
+
+    for id in &ids {
+        key_chain.set_default(id).expect("KeyStore::set_default returned Err");
+        let default_key = key_chain
+            .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+            .expect("There should be a default key");
+        assert_eq!(&default_key.id, *id);
+    }
+
+    assert!(key_chain.set_default("unknown").is_err());
+}
+
+#[test]
+fn test_remove_default_key() {
+    let (mut key_chain, keys) = fill_key_chain();
+    let mut ids: Vec<&String> = keys.keys().collect();
+    ids.sort();
+
+    key_chain.set_default(ids[0]).unwrap();
+    let removed = key_chain.remove_key(ids[0]).expect("KeyStore::remove_key returned Err");
+    assert_eq!(&removed.id, ids[0]);
+    assert!(key_chain.get_key_by_id(ids[0]).is_err());
+    assert!(key_chain.remove_key(ids[0]).is_err());
+
+    // the remaining key takes over as default
+    let default_key = key_chain
+        .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+        .unwrap();
+    assert_eq!(&default_key.id, ids[1]);
+    assert!(default_key.is_default());
+    assert_eq!(key_chain.list_keys().len(), 1);
+}
+
+#[test]
+fn test_keychain_overwrite_file() {
+    let (mut key_chain, keys) = fill_key_chain();
+    let filename = "test_keychain_overwrite.txt";
+
+    key_chain.to_file(filename).unwrap();
+    let id = keys.keys().next().unwrap();
+    key_chain.remove_key(id).unwrap();
+    key_chain.to_file(filename).unwrap();
+
+    let key_chain_unser = KeyStore::from_file(&PathBuf::from(filename));
+    let tmp_exists = PathBuf::from(format!("{}.tmp", filename)).exists();
+    let _ = remove_file(filename);
+
+    assert!(!tmp_exists);
+    let ids: Vec<String> = key_chain.list_keys().into_iter().map(|k| k.id).collect();
+    let ids_unser: Vec<String> = key_chain_unser
+        .unwrap()
+        .list_keys()
+        .into_iter()
+        .map(|k| k.id)
+        .collect();
+    assert_eq!(ids, ids_unser);
+}
+
+#[test]
+fn test_validate_key_shares() {
+    let subjects = [
+        (ThresholdScheme::Sg02, Group::Bls12381),
+        (ThresholdScheme::Bz03, Group::Bls12381),
+        (ThresholdScheme::Bls04, Group::Bls12381),
+        (ThresholdScheme::Cks05, Group::Bls12381),
+        (ThresholdScheme::Frost, Group::Ed25519),
+        (ThresholdScheme::Sh00, Group::Rsa512),
+    ];
+
+    for (scheme, group) in subjects.iter() {
+        let keys = KeyGenerator::generate_keys(
+            2,
+            3,
+            &mut RNG::new(RngAlgorithm::OsRng),
+            scheme,
+            group,
+            &Option::None,
+        )
+        .unwrap();
+        for key in keys.iter() {
+            assert!(key.validate().is_ok(), "{:?} share is invalid", scheme);
+        }
+    }
+}
+
+#[test]
+fn test_validate_detects_mismatched_shares() {
+    let mut rng = RNG::new(RngAlgorithm::OsRng);
+    let keys = KeyGenerator::generate_keys(
+        2,
+        3,
+        &mut rng,
+        &ThresholdScheme::Cks05,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap();
+    let key = match &keys[0] {
+        PrivateKeyShare::Cks05(key) => key,
+        _ => panic!("expected a CKS05 key"),
+    };
+
+    // a secret share that does not belong to the public key
+    let secret = SizedBigInt::new_rand(&Group::Bls12381, &key.get_order(), &mut rng);
+    let mismatched = Cks05PrivateKey::new(1, &secret, key.get_public_key());
+    let mismatched = PrivateKeyShare::Cks05(mismatched);
+    assert!(matches!(mismatched.validate(), Err(SchemeError::InvalidKey(_))));
+
+    let out_of_range = Cks05PrivateKey::new(4, &secret, key.get_public_key());
+    let out_of_range = PrivateKeyShare::Cks05(out_of_range);
+    assert!(matches!(out_of_range.validate(), Err(SchemeError::InvalidKey(_))));
+}
+
+fn generate_key(scheme: ThresholdScheme) -> PrivateKeyShare {
+    KeyGenerator::generate_keys(
+        2,
+        3,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &Group::Bls12381,
+        &None,
+    )
+    .unwrap()
+    .remove(0)
+}
+
+#[test]
+fn test_key_aliases() {
+    let (mut key_chain, keys) = fill_key_chain();
+    let mut ids: Vec<&String> = keys.keys().collect();
+    ids.sort();
+
+    let metadata = KeyMetadata {
+        alias: Some(String::from("payments")),
+        description: Some(String::from("signs payment batches")),
+        tags: vec![String::from("prod")],
+        ..Default::default()
+    };
+    key_chain.set_metadata(ids[0], metadata.clone()).unwrap();
+
+    let entry = key_chain.get_key_by_id("payments").unwrap();
+    assert_eq!(&entry.id, ids[0]);
+    assert_eq!(entry.metadata.alias, metadata.alias);
+    assert_eq!(entry.metadata.tags, metadata.tags);
+    // the creation time is kept
+    assert!(entry.metadata.created_at.is_some());
+
+    // aliases are unique and restricted to a small set of characters
+    assert!(key_chain.set_metadata(ids[1], metadata.clone()).is_err());
+    let invalid = KeyMetadata {
+        alias: Some(String::from("not an alias")),
+        ..Default::default()
+    };
+    assert!(key_chain.set_metadata(ids[1], invalid).is_err());
+
+    // the metadata is stored in the keystore file
+    let key_chain_unser = KeyStore::from_str(key_chain.to_json()).unwrap();
+    assert_eq!(key_chain, key_chain_unser);
+
+    key_chain.set_default("payments").unwrap();
+    let removed = key_chain.remove_key("payments").unwrap();
+    assert_eq!(&removed.id, ids[0]);
+    assert!(key_chain.get_key_by_id("payments").is_err());
+}
+
+#[test]
+fn test_default_per_scheme_and_group() {
+    let (mut key_chain, _) = fill_key_chain();
+
+    // the first key of a scheme and group stays the default when more keys are added
+    let default_id = key_chain
+        .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+        .unwrap()
+        .id;
+    key_chain.insert_private_key(generate_key(ThresholdScheme::Sg02)).unwrap();
+    let bz03_id = key_chain.insert_private_key(generate_key(ThresholdScheme::Bz03)).unwrap();
+    let default_key = key_chain
+        .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+        .unwrap();
+    assert_eq!(default_key.id, default_id);
+    assert!(key_chain.get_key_by_id(&bz03_id).unwrap().is_default());
+
+    // the defaults are stored in the keystore file
+    let key_chain_unser = KeyStore::from_str(key_chain.to_json()).unwrap();
+    assert_eq!(key_chain, key_chain_unser);
+
+    // without a default, the error lists the candidates in a stable order
+    let (key_chain, keys) = fill_key_chain();
+    let json = key_chain.to_json().replace("\"is_default\":true", "\"is_default\":false");
+    let key_chain = KeyStore::from_str(json).unwrap();
+    let first = key_chain.get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381);
+    let second = key_chain.get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381);
+    let error = first.unwrap_err();
+    assert!(keys.keys().all(|id| error.contains(id.as_str())));
+    assert_eq!(error, second.unwrap_err());
+}
+
+#[test]
+fn test_key_rotation() {
+    let mut key_chain = KeyStore::new();
+    let first = key_chain.insert_private_key(generate_key(ThresholdScheme::Sg02)).unwrap();
+    let metadata = KeyMetadata {
+        alias: Some(String::from("backups")),
+        ..Default::default()
+    };
+    key_chain.set_metadata(&first, metadata).unwrap();
+
+    let second = key_chain
+        .rotate_key("backups", generate_key(ThresholdScheme::Sg02))
+        .unwrap();
+    let third = key_chain
+        .rotate_key(&first, generate_key(ThresholdScheme::Sg02))
+        .unwrap();
+
+    let versions: Vec<(String, u32, KeyStatus)> = key_chain
+        .get_versions(&second)
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.id, e.metadata.version, e.status()))
+        .collect();
+    assert_eq!(
+        versions,
+        vec![
+            (first.clone(), 1, KeyStatus::DecryptOnly),
+            (second.clone(), 2, KeyStatus::DecryptOnly),
+            (third.clone(), 3, KeyStatus::Active),
+        ]
+    );
+
+    // the alias and the default move to the newest version
+    assert_eq!(key_chain.get_key_by_id("backups").unwrap().id, third);
+    assert_eq!(key_chain.get_active_version(&first).unwrap().id, third);
+    let default_key = key_chain
+        .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+        .unwrap();
+    assert_eq!(default_key.id, third);
+    assert!(key_chain.set_default(&first).is_err());
+
+    // versions of a key share its scheme and group
+    assert!(key_chain.rotate_key(&third, generate_key(ThresholdScheme::Bz03)).is_err());
+
+    let key_chain_unser = KeyStore::from_str(key_chain.to_json()).unwrap();
+    assert_eq!(key_chain, key_chain_unser);
+
+    key_chain.set_status(&third, KeyStatus::Retired).unwrap();
+    assert!(key_chain.get_active_version(&first).is_err());
+    assert!(key_chain
+        .get_key_by_scheme_and_group(ThresholdScheme::Sg02, Group::Bls12381)
+        .is_err());
+}
+
+#[test]
+fn test_rotated_signature_keys_are_retired() {
+    let mut key_chain = KeyStore::new();
+    let first = key_chain.insert_private_key(generate_key(ThresholdScheme::Bls04)).unwrap();
+    let second = key_chain
+        .rotate_key(&first, generate_key(ThresholdScheme::Bls04))
+        .unwrap();
+
+    assert_eq!(key_chain.get_key_by_id(&first).unwrap().status(), KeyStatus::Retired);
+    assert_eq!(key_chain.get_key_by_id(&second).unwrap().lineage(), first);
+}
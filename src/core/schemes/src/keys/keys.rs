@@ -18,6 +18,8 @@ use crate::dl_schemes::signatures::bls04::Bls04PrivateKey;
 use crate::dl_schemes::signatures::bls04::Bls04PublicKey;
 use crate::dl_schemes::signatures::frost::FrostPrivateKey;
 use crate::dl_schemes::signatures::frost::FrostPublicKey;
+use crate::groups::group::{GroupElement, GroupOperations};
+use crate::integers::sizedint::SizedBigInt;
 use crate::interface::SchemeError;
 use crate::interface::Serializable;
 use crate::scheme_types_impl::SchemeDetails;
@@ -121,6 +123,54 @@ impl PrivateKeyShare {
         }
     }
 
+    /// Checks that the share is consistent with its public key: the key id matches the public key,
+    /// the share id and threshold are within the number of parties, and the secret share matches
+    /// the verification key of the share (`g^x_i = h_i` for the DL schemes, `v^s_i = v_i` for SH00).
+    pub fn validate(&self) -> Result<(), SchemeError> {
+        let public_key = self.get_public_key();
+        if key2id(&public_key) != self.get_key_id() {
+            return Err(SchemeError::InvalidKey(format!(
+                "key id {} does not match the public key",
+                self.get_key_id()
+            )));
+        }
+
+        let n = public_key.get_n();
+        let threshold = self.get_threshold();
+        if threshold == 0 || threshold > n {
+            return Err(SchemeError::InvalidKey(format!(
+                "threshold {} with {} parties",
+                threshold, n
+            )));
+        }
+
+        let share_id = self.get_share_id();
+        if share_id == 0 || share_id > n {
+            return Err(SchemeError::InvalidKey(format!(
+                "share id {} with {} parties",
+                share_id, n
+            )));
+        }
+
+        let consistent = match self {
+            PrivateKeyShare::Sg02(key) => key.is_consistent(),
+            PrivateKeyShare::Bz03(key) => key.is_consistent(),
+            PrivateKeyShare::Bls04(key) => key.is_consistent(),
+            PrivateKeyShare::Cks05(key) => key.is_consistent(),
+            PrivateKeyShare::Sh00(key) => key.is_consistent(),
+            PrivateKeyShare::Frost(key) => key.is_consistent(),
+        };
+        if !consistent {
+            return Err(SchemeError::InvalidKey(format!(
+                "share {} of key {} does not match its verification key",
+                share_id,
+                self.get_key_id()
+            )));
+        }
+
+        Ok(())
+    }
+
     pub fn pem(&self) -> Result<String, SchemeError> {
         let r = self.to_bytes();
         if let Ok(bytes) = r {
@@ -744,6 +794,23 @@ pub fn calc_key_id(bytes: &[u8]) -> String {
     general_purpose::URL_SAFE.encode(hash.hash())
 }
 
+/// Checks that the secret `xi` of share `id` matches its entry in the verification key of a
+/// discrete logarithm scheme, i.e. g^xi = h_i.
+pub(crate) fn matches_verification_key(
+    group: &Group,
+    id: u16,
+    xi: &SizedBigInt,
+    verification_key: &[GroupElement],
+) -> bool {
+    if id == 0 {
+        return false;
+    }
+    match verification_key.get((id - 1) as usize) {
+        Some(hi) => GroupElement::new_pow_big(group, xi) == *hi,
+        None => false,
+    }
+}
+
 pub fn key2id(key: &PublicKey) -> String {
     let bytes = key.to_bytes().unwrap();
     let inner_bytes: Result<Vec<u8>, ParseError> = asn1::parse(&bytes, |d| {
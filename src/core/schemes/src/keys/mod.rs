@@ -1,5 +1,8 @@
 use std::fmt::{self, Debug, Display};
 
+pub mod ceremony;
+#[cfg(test)]
+mod ceremony_tests;
 pub mod key_generator;
 pub mod key_store;
 #[cfg(test)]
@@ -25,8 +28,13 @@ macro_rules! unwrap_enum_vec {
 
 pub enum KeyStoreError {
     DuplicateEntry(String),
+    DuplicateAlias(String),
     IdMismatch,
     IdNotFound(String),
+    InvalidAlias(String),
+    NoActiveVersion(String),
+    NotActive(String),
+    VersionMismatch(String),
 }
 
 impl Display for KeyStoreError {
@@ -37,6 +45,22 @@ impl Display for KeyStoreError {
                 write!(f, "Could not find a key with the given key_id '{}'", id)
             }
             KeyStoreError::IdMismatch => write!(f, "Key id does not match key"),
+            Self::DuplicateAlias(alias) => {
+                write!(f, "Alias '{}' is already used by another key", alias)
+            }
+            Self::InvalidAlias(alias) => write!(
+                f,
+                "Invalid alias '{}', aliases consist of up to {} letters, digits, '.', '_' and '-'",
+                alias,
+                key_store::MAX_ALIAS_LENGTH
+            ),
+            Self::NoActiveVersion(id) => write!(f, "Key '{}' has no active version", id),
+            Self::NotActive(id) => write!(f, "Key '{}' is not active", id),
+            Self::VersionMismatch(id) => write!(
+                f,
+                "A new version of key '{}' must use the same scheme and group",
+                id
+            ),
         }
     }
 }
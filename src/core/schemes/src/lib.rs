@@ -6,5 +6,9 @@ pub mod keys;
 pub mod rand;
 pub mod rsa_schemes;
 pub mod scheme_types_impl;
+pub mod stream;
 pub mod util;
 const DEBUG: bool = true;
+
+#[cfg(test)]
+mod stream_tests;
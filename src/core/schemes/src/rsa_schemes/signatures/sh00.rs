@@ -195,6 +195,18 @@ impl Sh00PrivateKey {
         &self.pubkey
     }
 
+    // Checks that the share matches its verification key, i.e. v^si = vi mod N.
+    pub fn is_consistent(&self) -> bool {
+        if self.id == 0 {
+            return false;
+        }
+        let verification_key = &self.pubkey.verification_key;
+        match verification_key.vi.get((self.id - 1) as usize) {
+            Some(vi) => verification_key.v.pow_mod(&self.si, &self.pubkey.N).equals(vi),
+            None => false,
+        }
+    }
+
     pub fn get_share_id(&self) -> u16 {
         self.id
     }
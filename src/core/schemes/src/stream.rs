@@ -0,0 +1,262 @@
+use std::io::{ErrorKind, Read, Write};
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use log::error;
+use theta_proto::scheme_types::ThresholdScheme;
+
+use crate::{
+    dl_schemes::common::gen_symm_key,
+    interface::{Ciphertext, SchemeError, Serializable, ThresholdCipher, ThresholdCipherParams},
+    keys::keys::PublicKey,
+};
+
+/// Identifies the streaming format and its version.
+pub const STREAM_MAGIC: &[u8; 8] = b"THSTRM01";
+/// Size of the plaintext segments written by `encrypt_stream`.
+pub const DEFAULT_SEGMENT_SIZE: usize = 64 * 1024;
+/// Largest segment size accepted when reading a header.
+pub const MAX_SEGMENT_SIZE: usize = 16 * 1024 * 1024;
+
+const NONCE_PREFIX_SIZE: usize = 7;
+const TAG_SIZE: usize = 16;
+const DATA_KEY_SIZE: usize = 32;
+// the threshold ciphertext only contains the data key and a few group elements
+const MAX_CIPHERTEXT_SIZE: usize = 64 * 1024;
+
+/// Header of an encrypted stream. It holds the data key, encrypted under a threshold public key,
+/// and the parameters needed to decrypt the segments that follow it.
+///
+/// Layout: magic (8 bytes), segment size (u32), nonce prefix (7 bytes), length of the threshold
+/// ciphertext (u32), threshold ciphertext. Integers are big endian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamHeader {
+    ciphertext: Ciphertext,
+    segment_size: usize,
+    nonce_prefix: [u8; NONCE_PREFIX_SIZE],
+}
+
+impl StreamHeader {
+    /// The threshold ciphertext of the data key. The committee decrypts it to release the data key.
+    pub fn get_ciphertext(&self) -> &Ciphertext {
+        &self.ciphertext
+    }
+
+    pub fn get_segment_size(&self) -> usize {
+        self.segment_size
+    }
+
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), SchemeError> {
+        let ciphertext = self.ciphertext.to_bytes()?;
+        writer.write_all(STREAM_MAGIC).map_err(io_error)?;
+        writer
+            .write_all(&(self.segment_size as u32).to_be_bytes())
+            .map_err(io_error)?;
+        writer.write_all(&self.nonce_prefix).map_err(io_error)?;
+        writer
+            .write_all(&(ciphertext.len() as u32).to_be_bytes())
+            .map_err(io_error)?;
+        writer.write_all(&ciphertext).map_err(io_error)
+    }
+
+    /// Reads the header from the start of an encrypted stream. The segments can then be read from
+    /// the same reader with `decrypt_stream`.
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, SchemeError> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic).map_err(io_error)?;
+        if &magic != STREAM_MAGIC {
+            return Err(SchemeError::DeserializationFailed);
+        }
+
+        let segment_size = read_u32(reader)? as usize;
+        if segment_size == 0 || segment_size > MAX_SEGMENT_SIZE {
+            return Err(SchemeError::InvalidParams(Some(format!(
+                "Invalid segment size {}",
+                segment_size
+            ))));
+        }
+
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_SIZE];
+        reader.read_exact(&mut nonce_prefix).map_err(io_error)?;
+
+        let length = read_u32(reader)? as usize;
+        if length > MAX_CIPHERTEXT_SIZE {
+            return Err(SchemeError::DeserializationFailed);
+        }
+        let mut ciphertext = vec![0u8; length];
+        reader.read_exact(&mut ciphertext).map_err(io_error)?;
+
+        Ok(Self {
+            ciphertext: Ciphertext::from_bytes(&ciphertext)?,
+            segment_size,
+            nonce_prefix,
+        })
+    }
+}
+
+/// Encrypts everything `reader` returns into `writer`, without holding more than two segments in
+/// memory. A fresh data key is encrypted under `pk` (SG02 or BZ03) with `label` and written to the
+/// header, the data is split into segments that are each encrypted with ChaCha20-Poly1305 under
+/// the data key. The nonce of a segment contains its position and whether it is the last one, so
+/// reordered, dropped or truncated segments are detected (STREAM construction).
+///
+/// Returns the number of plaintext bytes.
+pub fn encrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    label: &[u8],
+    pk: &PublicKey,
+    params: &mut ThresholdCipherParams,
+) -> Result<u64, SchemeError> {
+    encrypt_segments(reader, writer, label, pk, params, DEFAULT_SEGMENT_SIZE)
+}
+
+pub(crate) fn encrypt_segments<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    label: &[u8],
+    pk: &PublicKey,
+    params: &mut ThresholdCipherParams,
+    segment_size: usize,
+) -> Result<u64, SchemeError> {
+    match pk.get_scheme() {
+        ThresholdScheme::Sg02 | ThresholdScheme::Bz03 => {}
+        _ => return Err(SchemeError::WrongScheme),
+    }
+
+    let data_key = gen_symm_key(&mut params.rng);
+    let mut nonce_prefix = [0u8; NONCE_PREFIX_SIZE];
+    nonce_prefix.copy_from_slice(&gen_symm_key(&mut params.rng)[..NONCE_PREFIX_SIZE]);
+
+    let header = StreamHeader {
+        ciphertext: ThresholdCipher::encrypt(&data_key, label, pk, params)?,
+        segment_size,
+        nonce_prefix,
+    };
+    header.write_to(writer)?;
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&data_key));
+    let mut current = vec![0u8; segment_size];
+    let mut length = read_full(reader, &mut current)?;
+    let mut next = vec![0u8; segment_size];
+    let mut counter: u32 = 0;
+    let mut total: u64 = 0;
+
+    loop {
+        // a segment is the last one if no data follows it
+        let next_length = if length == segment_size {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_length == 0;
+
+        let nonce = segment_nonce(&nonce_prefix, counter, last);
+        let sealed = cipher
+            .encrypt(Nonce::from_slice(&nonce), &current[..length])
+            .map_err(|_| SchemeError::Aborted(String::from("Failed to encrypt segment")))?;
+        writer.write_all(&sealed).map_err(io_error)?;
+        total += length as u64;
+
+        if last {
+            break;
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        length = next_length;
+        counter = counter
+            .checked_add(1)
+            .ok_or_else(|| SchemeError::Aborted(String::from("Stream too long")))?;
+    }
+
+    writer.flush().map_err(io_error)?;
+    Ok(total)
+}
+
+/// Decrypts the segments following `header` in `reader` into `writer`, using the data key the
+/// committee released for `header.get_ciphertext()`. Fails with `SchemeError::MacFailure` if a
+/// segment was modified, reordered or dropped, or if the stream was truncated. Data written to
+/// `writer` before such a failure must be discarded.
+///
+/// Returns the number of plaintext bytes.
+pub fn decrypt_stream<R: Read, W: Write>(
+    header: &StreamHeader,
+    reader: &mut R,
+    writer: &mut W,
+    data_key: &[u8],
+) -> Result<u64, SchemeError> {
+    if data_key.len() != DATA_KEY_SIZE {
+        return Err(SchemeError::WrongKeyProvided);
+    }
+
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(data_key));
+    let sealed_size = header.segment_size + TAG_SIZE;
+    let mut current = vec![0u8; sealed_size];
+    let mut length = read_full(reader, &mut current)?;
+    let mut next = vec![0u8; sealed_size];
+    let mut counter: u32 = 0;
+    let mut total: u64 = 0;
+
+    loop {
+        let next_length = if length == sealed_size {
+            read_full(reader, &mut next)?
+        } else {
+            0
+        };
+        let last = next_length == 0;
+
+        let nonce = segment_nonce(&header.nonce_prefix, counter, last);
+        let segment = cipher
+            .decrypt(Nonce::from_slice(&nonce), &current[..length])
+            .map_err(|_| SchemeError::MacFailure)?;
+        writer.write_all(&segment).map_err(io_error)?;
+        total += segment.len() as u64;
+
+        if last {
+            break;
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        length = next_length;
+        counter = counter.checked_add(1).ok_or(SchemeError::MacFailure)?;
+    }
+
+    writer.flush().map_err(io_error)?;
+    Ok(total)
+}
+
+// nonce = prefix (7 bytes) || segment counter (u32, big endian) || last segment flag
+fn segment_nonce(prefix: &[u8; NONCE_PREFIX_SIZE], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    nonce[..NONCE_PREFIX_SIZE].copy_from_slice(prefix);
+    nonce[NONCE_PREFIX_SIZE..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    nonce
+}
+
+// Fills `buf` unless the reader ends before, returns the number of bytes read.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, SchemeError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(io_error(e)),
+        }
+    }
+    Ok(filled)
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, SchemeError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes).map_err(io_error)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn io_error(e: std::io::Error) -> SchemeError {
+    error!("Stream I/O error: {}", e);
+    SchemeError::IOError
+}
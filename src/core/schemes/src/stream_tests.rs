@@ -0,0 +1,117 @@
+use std::io::Cursor;
+
+use theta_proto::scheme_types::Group;
+
+use crate::{
+    interface::{SchemeError, ThresholdCipher, ThresholdCipherParams, ThresholdScheme},
+    keys::{key_generator::KeyGenerator, keys::PrivateKeyShare},
+    rand::{RngAlgorithm, RNG},
+    stream::{decrypt_stream, encrypt_segments, StreamHeader},
+};
+
+const SEGMENT_SIZE: usize = 16;
+
+fn generate_keys(scheme: ThresholdScheme) -> Vec<PrivateKeyShare> {
+    KeyGenerator::generate_keys(
+        3,
+        5,
+        &mut RNG::new(RngAlgorithm::OsRng),
+        &scheme,
+        &Group::Bls12381,
+        &Option::None,
+    )
+    .unwrap()
+}
+
+fn encrypt(msg: &[u8], keys: &[PrivateKeyShare]) -> Vec<u8> {
+    let mut params = ThresholdCipherParams::new();
+    let mut encrypted = Vec::new();
+    let length = encrypt_segments(
+        &mut Cursor::new(msg),
+        &mut encrypted,
+        b"backup",
+        &keys[0].get_public_key(),
+        &mut params,
+        SEGMENT_SIZE,
+    )
+    .unwrap();
+    assert_eq!(length, msg.len() as u64);
+    encrypted
+}
+
+// Releases the data key like the committee would, from the shares of the first three parties.
+fn decrypt(encrypted: &[u8], keys: &[PrivateKeyShare]) -> Result<Vec<u8>, SchemeError> {
+    let mut reader = Cursor::new(encrypted);
+    let header = StreamHeader::read_from(&mut reader)?;
+    assert_eq!(header.get_segment_size(), SEGMENT_SIZE);
+
+    let mut params = ThresholdCipherParams::new();
+    let shares: Vec<_> = keys[..3]
+        .iter()
+        .map(|key| ThresholdCipher::partial_decrypt(header.get_ciphertext(), key, &mut params).unwrap())
+        .collect();
+    let data_key = ThresholdCipher::assemble(&shares, header.get_ciphertext()).unwrap();
+
+    let mut decrypted = Vec::new();
+    decrypt_stream(&header, &mut reader, &mut decrypted, &data_key)?;
+    Ok(decrypted)
+}
+
+#[test]
+fn test_stream_roundtrip() {
+    for scheme in [ThresholdScheme::Sg02, ThresholdScheme::Bz03] {
+        let keys = generate_keys(scheme);
+        // empty, shorter than a segment, exactly two segments, and a partial last segment
+        for length in [0, 5, 2 * SEGMENT_SIZE, 3 * SEGMENT_SIZE + 7] {
+            let msg: Vec<u8> = (0..length).map(|i| i as u8).collect();
+            let encrypted = encrypt(&msg, &keys);
+            assert_eq!(decrypt(&encrypted, &keys).unwrap(), msg);
+        }
+    }
+}
+
+#[test]
+fn test_truncated_stream_is_rejected() {
+    let keys = generate_keys(ThresholdScheme::Sg02);
+    let msg = vec![7u8; 3 * SEGMENT_SIZE];
+    let encrypted = encrypt(&msg, &keys);
+
+    // drop the last segment, the remaining ones are intact
+    let sealed_size = SEGMENT_SIZE + 16;
+    let truncated = &encrypted[..encrypted.len() - sealed_size];
+    assert!(matches!(decrypt(truncated, &keys), Err(SchemeError::MacFailure)));
+}
+
+#[test]
+fn test_modified_or_reordered_segments_are_rejected() {
+    let keys = generate_keys(ThresholdScheme::Sg02);
+    let msg: Vec<u8> = (0..3 * SEGMENT_SIZE).map(|i| i as u8).collect();
+    let encrypted = encrypt(&msg, &keys);
+    let sealed_size = SEGMENT_SIZE + 16;
+    let body = encrypted.len() - 3 * sealed_size;
+
+    let mut modified = encrypted.clone();
+    modified[body + 1] ^= 1;
+    assert!(matches!(decrypt(&modified, &keys), Err(SchemeError::MacFailure)));
+
+    let mut reordered = encrypted[..body].to_vec();
+    reordered.extend_from_slice(&encrypted[body + sealed_size..body + 2 * sealed_size]);
+    reordered.extend_from_slice(&encrypted[body..body + sealed_size]);
+    reordered.extend_from_slice(&encrypted[body + 2 * sealed_size..]);
+    assert!(matches!(decrypt(&reordered, &keys), Err(SchemeError::MacFailure)));
+}
+
+#[test]
+fn test_header_serialization() {
+    let keys = generate_keys(ThresholdScheme::Bz03);
+    let encrypted = encrypt(b"message", &keys);
+
+    let header = StreamHeader::read_from(&mut Cursor::new(&encrypted)).unwrap();
+    let mut encoded = Vec::new();
+    header.write_to(&mut encoded).unwrap();
+    assert_eq!(encoded, encrypted[..encoded.len()]);
+
+    let mut invalid = encrypted.clone();
+    invalid[0] = b'X';
+    assert!(StreamHeader::read_from(&mut Cursor::new(&invalid)).is_err());
+}
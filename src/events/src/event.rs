@@ -28,6 +28,14 @@ pub enum Event {
         error_message: String,
     },
 
+    // Emitted when a party (identified by its share id) sent an invalid or conflicting share.
+    MisbehaviorDetected {
+        timestamp: DateTime<Utc>,
+        instance_id: String,
+        share_id: u16,
+        kind: String,
+    },
+
     // // Emitted when the server received a signing request.
     // ReceivedSigningRequest {
     //     timestamp: DateTime<Utc>,
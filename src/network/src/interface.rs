@@ -1,12 +1,30 @@
 //T wil be NetMessage
 use tonic::async_trait;
 
+use crate::types::config::NetworkConfig;
+use crate::types::peer_table::PeerStatus;
+
 #[async_trait]
 pub trait Gossip: Send {
     type T;
     fn broadcast(&mut self, message: Self::T) -> Result<(), String>;
+    // Returns the next message. Its sender is set to the node that authenticated itself as the
+    // author of the message, or to 0 if the author is unknown.
     async fn deliver(&mut self) -> Option<Self::T>;
     async fn init(&mut self) -> Result<(), String>;
+    // Health of the configured peers. Implementations without a notion of peers report none.
+    fn peer_status(&self) -> Vec<PeerStatus> {
+        Vec::new()
+    }
+    // Restricts delivery to messages without a topic and messages of the given committee topics.
+    // Implementations that cannot filter by topic deliver every message.
+    fn set_topics(&mut self, _topics: Vec<String>) -> Result<(), String> {
+        Ok(())
+    }
+    // Replaces the peers (and proxy) the implementation communicates with, without a restart.
+    fn update_config(&mut self, _config: NetworkConfig) -> Result<(), String> {
+        Err("The network does not support updating its peers at runtime".to_string())
+    }
 }
 
 #[async_trait]
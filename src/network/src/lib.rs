@@ -3,6 +3,8 @@ pub mod types;
 pub mod proxy;
 pub mod interface;
 pub mod network_manager;
+pub mod simulation;
+pub mod tob;
 
 pub mod lib {
     use std::any::type_name;
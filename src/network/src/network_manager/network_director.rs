@@ -1,4 +1,6 @@
-use crate::{p2p::p2p_component::P2PComponent, proxy::proxyp2p::P2PProxy, types::config::NetworkConfig};
+use std::time::Duration;
+
+use crate::{p2p::p2p_component::P2PComponent, proxy::proxyp2p::P2PProxy, tob::cometbft::CometBftTOB, types::config::NetworkConfig};
 
 use super::network_manager_builder::NetworkManagerBuilder;
 
@@ -6,14 +8,16 @@ use super::network_manager_builder::NetworkManagerBuilder;
 pub struct NetworkDirector;
 
 impl NetworkDirector{
-    pub fn construct_standalone_network(builder: &mut NetworkManagerBuilder, config: NetworkConfig, my_id: u32){
+    // Fails if the identity of a peer in the config is invalid
+    pub fn construct_standalone_network(builder: &mut NetworkManagerBuilder, config: NetworkConfig, my_id: u32) -> Result<(), String>{
         // Instanciathe the p2p compponent implementation
         let p2p_component = P2PComponent::new(
             config.clone(),
             my_id,
-        );
+        )?;
 
         builder.set_gossip_channel(Box::new(p2p_component));
+        Ok(())
     }
 
     pub fn construct_proxy_network(builder: &mut NetworkManagerBuilder, config: NetworkConfig, my_id: u32){
@@ -27,6 +31,13 @@ impl NetworkDirector{
 
         builder.set_gossip_channel(Box::new(p2p_proxy));
     }
+
+    // Orders the messages of the TOB channel through a CometBFT node, next to a gossip network built above
+    pub fn add_cometbft_tob(builder: &mut NetworkManagerBuilder, rpc_url: String, poll_interval: Duration){
+        let cometbft_tob = CometBftTOB::connect(rpc_url, poll_interval);
+
+        builder.set_tob_channel(Box::new(cometbft_tob));
+    }
 }
 // pub fn construct_blockchain_based_network<T, G: Gossip, P: TOB<T>>(builder: &NetworkManagerBuilder<T, G, P>){
 
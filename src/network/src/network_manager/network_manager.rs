@@ -1,10 +1,33 @@
+use std::net::Ipv4Addr;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use log::{error, info, warn};
-use tokio::sync::{mpsc::{Receiver, Sender}, Notify};
+use tokio::sync::{mpsc::{Receiver, Sender}, oneshot, Notify};
 
-use crate::{interface::{Gossip, TOB}, types::config::NetworkConfig};
+use crate::{interface::{Gossip, TOB}, types::config::{NetworkConfig, NetworkPeer, NetworkProxy}};
 use crate::types::message::{NetMessage, Channel};
+use crate::types::peer_table::PeerStatus;
+
+// Requests to the network layer from other components of the node.
+#[derive(Debug)]
+pub enum NetworkCommand {
+    // Returns the health of the configured peers
+    GetPeerStatus {
+        responder: oneshot::Sender<Vec<PeerStatus>>,
+    },
+    // Replaces the committee topics this node subscribes to
+    SetTopics {
+        topics: Vec<String>,
+    },
+    // Replaces the configured peers, and the proxy if one is given. The local peer must be part
+    // of the peers with its current address.
+    UpdatePeers {
+        peers: Vec<NetworkPeer>,
+        proxy: Option<NetworkProxy>,
+        responder: oneshot::Sender<Result<(), String>>,
+    },
+}
 
 // T is the generic for the message
 // G is the generic for the Gossip module
@@ -16,6 +39,7 @@ pub struct NetworkManager{
     my_id: u32,
     gossip_channel: Box<dyn Gossip<T= NetMessage>>,
     tob_channel: Option<Box<dyn TOB<T= NetMessage>>>,
+    command_receiver: Option<Receiver<NetworkCommand>>,
 }
 
 
@@ -26,7 +50,8 @@ impl NetworkManager{
         config: NetworkConfig,
         my_id: u32,
         gossip_channel: Box<dyn Gossip<T= NetMessage>>,
-        tob_channel: Option<Box<dyn TOB<T= NetMessage>>>
+        tob_channel: Option<Box<dyn TOB<T= NetMessage>>>,
+        command_receiver: Option<Receiver<NetworkCommand>>,
     ) -> Self{
             return NetworkManager{
                 outgoing_msg_receiver: outgoing_msg_receiver,
@@ -34,7 +59,8 @@ impl NetworkManager{
                 config: config,
                 my_id: my_id,
                 gossip_channel: gossip_channel,
-                tob_channel: None,
+                tob_channel: tob_channel,
+                command_receiver,
             };
     }
     
@@ -49,7 +75,6 @@ impl NetworkManager{
             return Err(error);
         }
 
-        let tob_ref = self.tob_channel.as_ref();
         loop{
             tokio::select! {
                 protocol_msg = self.outgoing_msg_receiver.recv() => { //if the channel closes, then the recv() returns None and the branch is ignored
@@ -63,14 +88,31 @@ impl NetworkManager{
                                 Channel::PointToPoint{receiver_id} => info!("Point to Point channel"), //here handle authentication   
                             };
                             info!("Received message from protocol layer");
-                            let _ = self.gossip_channel.broadcast(net_message.clone());
-                            info!("... sending to the network");
+                            // Messages on the TOB channel are ordered by the TOB implementation if there is one,
+                            // otherwise they are handed to the gossip implementation (e.g., a proxy that orders them).
+                            match self.tob_channel.as_mut() {
+                                Some(tob) if *net_message.get_metadata().get_channel() == Channel::TOB => {
+                                    tob.broadcast(net_message.clone());
+                                    info!("... submitting to total order broadcast");
+                                },
+                                _ => {
+                                    let _ = self.gossip_channel.broadcast(net_message.clone());
+                                    info!("... sending to the network");
+                                },
+                            }
 
                             // The next line implements the logic to give back to the protocol a message produced locally 
                             // so that a self-message appears in teh received ones. 
                             // It is up to the implementers of a certain protocol the decision of handling 
                             // a locally produced message already in the protocol to optimize in terms of transmission
                             // latency and verification time. 
+                            // Messages on the TOB channel are not given back right away, the ordering layer
+                            // delivers them to the sender as well, in the same order as to everybody else.
+                            if *net_message.get_metadata().get_channel() == Channel::TOB {
+                                continue;
+                            }
+                            let mut net_message = net_message;
+                            net_message.set_sender(Some(self.my_id));
                             let result = self.incoming_msg_sender.send(net_message).await;
                             match result {
                                 Ok(_) => info!("... forwarding my message back to the protocol"),
@@ -91,7 +133,7 @@ impl NetworkManager{
                         let channel = net_message.get_metadata().get_channel();
                         match channel {
                             Channel::Gossip => {info!("Gossip channel")},
-                            Channel::TOB => todo!(),
+                            Channel::TOB => {info!("TOB channel")},
                             Channel::PointToPoint{receiver_id} => {
                                 //check the receiver id and encrypt accordingly before broadcasting on gossip
                                 if receiver_id.contains(&self.my_id) {
@@ -110,18 +152,98 @@ impl NetworkManager{
                         return Err("The gossip channel has closed".to_string());
                     }
                 },
-                    // Some(tob_msg) = tob_ref.unwrap().deliver(), if !tob_ref.is_none() => {//after the comma we have a precondition to enable the branch
-                    //     todo!()
-                    // },
+                tob_msg = recv_tob(&self.tob_channel) => {
+                    info!("Received message from total order broadcast");
+                    // The ordering layer does not authenticate the node that submitted a message.
+                    let mut tob_msg = tob_msg;
+                    tob_msg.set_sender(None);
+                    let _ = self.incoming_msg_sender.send(tob_msg).await;
+                    info!("... forwarding to the protocol");
+                },
+                command = recv_command(&mut self.command_receiver) => {
+                    match command {
+                        Some(NetworkCommand::GetPeerStatus { responder }) => {
+                            if responder.send(self.gossip_channel.peer_status()).is_err() {
+                                error!("The receiver for responder in NetworkCommand::GetPeerStatus has been closed.");
+                            }
+                        },
+                        Some(NetworkCommand::SetTopics { topics }) => {
+                            if let Err(e) = self.gossip_channel.set_topics(topics) {
+                                error!("Error updating the subscribed topics: {}", e);
+                            }
+                        },
+                        Some(NetworkCommand::UpdatePeers { peers, proxy, responder }) => {
+                            let result = self.update_peers(peers, proxy);
+                            if let Err(e) = &result {
+                                warn!("Rejected peer update: {}", e);
+                            }
+                            if responder.send(result).is_err() {
+                                error!("The receiver for responder in NetworkCommand::UpdatePeers has been closed.");
+                            }
+                        },
+                        None => {
+                            warn!("The network command channel has been closed");
+                            self.command_receiver = None;
+                        }
+                    }
+                },
                 _ = shutdown_notify.notified() => {
                     info!("Shutting down the network layer");
                     return Ok(());
                 }
-                
-                // tob_message = self.tob_channel.as_ref().unwrap().deliver() => { //TODO: dangerous unwarp(), to handle
-                //     todo!()
-                // }
             }
         }
     }
-}
\ No newline at end of file
+
+    fn update_peers(&mut self, peers: Vec<NetworkPeer>, proxy: Option<NetworkProxy>) -> Result<(), String> {
+        let local = &self.config.local_peer;
+        if !peers.iter().any(|peer| peer.id == local.id && peer.ip == local.ip && peer.port == local.port) {
+            return Err(format!(
+                "The peers must contain this node (id {}) at {}:{}, changing its address requires a restart",
+                self.config.local_peer.id, self.config.local_peer.ip, self.config.local_peer.port
+            ));
+        }
+
+        for (i, peer) in peers.iter().enumerate() {
+            if Ipv4Addr::from_str(&peer.ip).is_err() {
+                return Err(format!("Invalid IPv4 address for peer {}: {}", peer.id, peer.ip));
+            }
+            peer.get_identity()?;
+            if peers[..i].iter().any(|other| other.id == peer.id) {
+                return Err(format!("Peer {} is configured more than once", peer.id));
+            }
+            if peers[..i].iter().any(|other| other.ip == peer.ip && other.port == peer.port) {
+                return Err(format!("Peers share the address {}:{}", peer.ip, peer.port));
+            }
+        }
+
+        let config = NetworkConfig {
+            local_peer: self.config.local_peer.clone(),
+            peers: Some(peers),
+            proxy: proxy.or_else(|| self.config.proxy.clone()),
+            base_listen_address: self.config.base_listen_address.clone(),
+            identity_file: self.config.identity_file.clone(),
+        };
+        self.gossip_channel.update_config(config.clone())?;
+
+        info!("Updated the network configuration to {} peers", config.peers.as_ref().map_or(0, |p| p.len()));
+        self.config = config;
+        Ok(())
+    }
+}
+
+// Waits for the next command, or forever if the network manager takes no commands.
+async fn recv_command(receiver: &mut Option<Receiver<NetworkCommand>>) -> Option<NetworkCommand> {
+    match receiver {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+// Waits for the next ordered message, or forever if the network manager has no TOB channel.
+async fn recv_tob(tob_channel: &Option<Box<dyn TOB<T= NetMessage>>>) -> NetMessage {
+    match tob_channel {
+        Some(tob) => tob.deliver().await,
+        None => std::future::pending().await,
+    }
+}
@@ -1,7 +1,7 @@
 use tokio::sync::mpsc::{Receiver, Sender};
 use crate::{interface::{Gossip, TOB}, types::{config::NetworkConfig, message::NetMessage}};
 
-use super::network_manager::NetworkManager;
+use super::network_manager::{NetworkCommand, NetworkManager};
 
 #[derive(Default)]
 pub struct NetworkManagerBuilder{
@@ -11,6 +11,7 @@ pub struct NetworkManagerBuilder{
     my_id: u32,
     gossip_channel: Option<Box<dyn Gossip<T= NetMessage>>>,
     tob_channel: Option<Box<dyn TOB<T= NetMessage>>>,
+    command_receiver: Option<Receiver<NetworkCommand>>,
 }
 
 impl NetworkManagerBuilder{
@@ -39,6 +40,10 @@ impl NetworkManagerBuilder{
         self.tob_channel = Some(tob_channel)
     }
 
+    pub fn set_command_receiver(&mut self, command_receiver: Receiver<NetworkCommand>){
+        self.command_receiver = Some(command_receiver)
+    }
+
     pub fn build(self) -> NetworkManager{
         return NetworkManager::new(
             self.outgoing_msg_receiver.expect("Set Receiver for NetworkManager"),
@@ -46,7 +51,8 @@ impl NetworkManagerBuilder{
             self.config.expect("Set config for NetworkManager"),
             self.my_id,
             self.gossip_channel.expect("Set gossip channel for NetworkManager"),
-            self.tob_channel
+            self.tob_channel,
+            self.command_receiver,
         )
     }
 
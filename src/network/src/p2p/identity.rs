@@ -0,0 +1,42 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use libp2p::{
+    identity::{ed25519, Keypair},
+    PeerId,
+};
+
+/// Creates a new ed25519 identity key for a node and writes its secret key to `path`, base64
+/// encoded. The file is created with permissions 0600. Returns the peer id of the new identity,
+/// which the other nodes list as the `identity` of this peer.
+pub fn generate_identity_file(path: &Path) -> Result<PeerId, String> {
+    let keypair = ed25519::Keypair::generate();
+    let encoded = base64::encode(keypair.secret().as_ref());
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(encoded.as_bytes()))
+        .map_err(|e| format!("Error writing identity file {}: {}", path.display(), e))?;
+
+    Ok(PeerId::from(Keypair::Ed25519(keypair).public()))
+}
+
+/// Reads the identity key of a node from a file written by `generate_identity_file`.
+pub fn load_identity_file(path: &Path) -> Result<Keypair, String> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| format!("Error reading identity file {}: {}", path.display(), e))?;
+    let mut bytes = base64::decode(data.trim())
+        .map_err(|e| format!("Invalid identity file {}: {}", path.display(), e))?;
+    let secret = ed25519::SecretKey::from_bytes(&mut bytes)
+        .map_err(|e| format!("Invalid identity file {}: {}", path.display(), e))?;
+
+    Ok(Keypair::Ed25519(secret.into()))
+}
@@ -0,0 +1,20 @@
+use std::{env, fs};
+
+use libp2p::PeerId;
+
+use super::identity::{generate_identity_file, load_identity_file};
+
+#[test]
+fn test_identity_file_roundtrip() {
+    let path = env::temp_dir().join(format!("theta_identity_{}.key", std::process::id()));
+    let peer_id = generate_identity_file(&path).unwrap();
+    let keypair = load_identity_file(&path).unwrap();
+    assert_eq!(PeerId::from(keypair.public()), peer_id);
+
+    // a new identity is generated every time
+    assert_ne!(generate_identity_file(&path).unwrap(), peer_id);
+
+    fs::write(&path, "not an identity").unwrap();
+    assert!(load_identity_file(&path).is_err());
+    fs::remove_file(&path).unwrap();
+}
@@ -1,2 +1,5 @@
+pub mod identity;
+#[cfg(test)]
+mod identity_tests;
 pub mod p2p_component;
-pub mod p2p_component_tests;
\ No newline at end of file
+pub mod p2p_component_tests;
@@ -1,5 +1,5 @@
-
-use std::time::Duration;
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
 
 use tokio::time;
 
@@ -7,24 +7,64 @@ use futures::StreamExt;
 use libp2p::{
     gossipsub::{Gossipsub, GossipsubEvent, IdentTopic as GossibsubTopic},
     identity,
+    ping::{Ping, PingEvent, PingSuccess},
     swarm::SwarmEvent,
-    PeerId, Swarm,
+    Multiaddr, NetworkBehaviour, PeerId, Swarm,
 };
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 
-use crate::{interface::Gossip, types::config::NetworkConfig};
+use crate::{interface::Gossip, types::config::{NetworkConfig, NetworkPeer}};
+use super::identity::load_identity_file;
 use crate::types::message::*;
+use crate::types::peer_table::{PeerStatus, PeerTable, INITIAL_REDIAL_BACKOFF, MAX_REDIAL_BACKOFF};
 
 
 //T wil be NetMessage
 use tonic::async_trait;
 
+// Interval in which disconnected peers are checked for a redial.
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+// Gossipsub for the messages, ping to keep connections alive and to measure the latency to peers.
+#[derive(NetworkBehaviour)]
+#[behaviour(out_event = "P2PEvent", event_process = false)]
+pub struct P2PBehaviour {
+    pub gossipsub: Gossipsub,
+    pub ping: Ping,
+}
+
+pub enum P2PEvent {
+    Gossipsub(GossipsubEvent),
+    Ping(PingEvent),
+}
+
+impl From<GossipsubEvent> for P2PEvent {
+    fn from(event: GossipsubEvent) -> Self {
+        P2PEvent::Gossipsub(event)
+    }
+}
+
+impl From<PingEvent> for P2PEvent {
+    fn from(event: PingEvent) -> Self {
+        P2PEvent::Ping(event)
+    }
+}
 
 //TODO: remove the pub and add a constructor
 pub struct P2PComponent {
     config: NetworkConfig,
-    swarm: Option<Swarm<Gossipsub>>,
+    swarm: Option<Swarm<P2PBehaviour>>,
+    // Topic of messages for all nodes, and the committee topics this node subscribed to.
     topic: GossibsubTopic,
+    committee_topics: BTreeSet<String>,
+    peer_table: PeerTable,
+    // Configured peers by dial address, and by libp2p id once a connection to them was established.
+    dial_addresses: HashMap<Multiaddr, u32>,
+    peer_ids: HashMap<PeerId, u32>,
+    // Identities of the peers that have one configured. If any, connections of other nodes are
+    // closed.
+    trusted_peers: HashMap<PeerId, u32>,
+    health_check: Option<time::Interval>,
 }
 
 #[async_trait]
@@ -38,8 +78,11 @@ impl Gossip for P2PComponent
     async fn init(&mut self) -> Result<(), String> { 
         // Create a Gossipsub topic
 
-       // Create a random Keypair and PeerId (hash of the public key)
-       let id_keys = identity::Keypair::generate_ed25519();
+       // Load the identity of this node, or create a random Keypair and PeerId (hash of the public key)
+       let id_keys = match &self.config.identity_file {
+           Some(path) => load_identity_file(path)?,
+           None => identity::Keypair::generate_ed25519(),
+       };
        let local_peer_id = PeerId::from(id_keys.public());
        // println!(">> NET: Local peer id: {:?}", local_peer_id);
 
@@ -51,7 +94,13 @@ impl Gossip for P2PComponent
        let transport = utils::create_tcp_transport(noise_keys);
 
        // Create a Swarm to manage peers and events.
-       let swarm = utils::create_gossipsub_swarm(&self.topic, id_keys.clone(), transport, local_peer_id);
+       let mut swarm = utils::create_gossipsub_swarm(&self.topic, id_keys.clone(), transport, local_peer_id);
+       for topic in self.committee_topics.iter() {
+           swarm.behaviour_mut()
+               .gossipsub
+               .subscribe(&GossibsubTopic::new(topic.clone()))
+               .map_err(|e| format!("Failed to subscribe to {}: {:?}", topic, e))?;
+       }
        self.swarm = Some(swarm);
 
        // load listener address from config file
@@ -76,9 +125,15 @@ impl Gossip for P2PComponent
     fn broadcast(&mut self, net_message: Self::T) -> Result<(), String> {
         
         debug!("NET: Sending a message");
+        // Messages of a committee are only published to the nodes subscribed to its topic.
+        let topic = match net_message.get_metadata().get_topic() {
+            Some(topic) => GossibsubTopic::new(topic.clone()),
+            None => self.topic.clone(),
+        };
         if let Some(swarm) = self.swarm.as_mut(){
             let _ = swarm.behaviour_mut()
-                        .publish(self.topic.clone(), net_message)
+                        .gossipsub
+                        .publish(topic, net_message)
                         .map_err(|e| {
                             error!("NET: Failed to publish message: {:?}", e);
                             return ((),"failed to publish message".to_string())
@@ -92,41 +147,122 @@ impl Gossip for P2PComponent
 
     async fn deliver(&mut self) -> Option<Self::T> {
         // put here the code that handles the swarm and the other cases should go in a different functions that checks the network of peers
-        
-           loop {
-                // TODO: add a timeout to avoid waiting forever? 
-                // do we need to handle the case of a timeout?
-                // polls swarm events
-                let event = self.swarm.as_mut().unwrap().select_next_some().await;
-                match event {
-                    // Handles (incoming) Gossipsub-Message
-                    SwarmEvent::Behaviour(GossipsubEvent::Message {message, ..}) => {
-                        debug!("NET: Received a message");
-                        let message: NetMessage = message.data.into();
+        loop {
+            let swarm = self.swarm.as_mut().unwrap();
+            let health_check = self
+                .health_check
+                .get_or_insert_with(|| time::interval(HEALTH_CHECK_INTERVAL));
+            tokio::select! {
+                event = swarm.select_next_some() => {
+                    if let Some(message) = self.handle_swarm_event(event) {
                         return Some(message);
                     }
-                    // SwarmEvent::NewListenAddr { address, .. } => {
-                    //     debug!("NET: Listening on {:?}", address);
-                    //     return None
-                    // }
-                    // SwarmEvent::Dialing(peer_id) => {
-                    //     debug!("NET: Attempting to dial peer {peer_id}");
-                    //     return None
-                    // }
-                    // SwarmEvent::ConnectionEstablished { peer_id, endpoint, num_established: _, concurrent_dial_errors: _} => {
-                    //     debug!("NET: Successfully established connection to peer {peer_id} on {}", endpoint.get_remote_address());
-                    //     return None
-                    // },
-                    // SwarmEvent::ConnectionClosed { peer_id, endpoint, num_established: _, cause } => {
-                    //     // TODO: handle the case of reconnection ?
-                    //     debug!("NET: Closed connection to peer {peer_id} on {} due to {:?}", endpoint.get_remote_address(), cause);
-                    //     return None
-                    // }
-                    _ => {}
                 }
+                _ = health_check.tick() => {
+                    self.redial_peers();
+                }
+            }
+        }
+    }
+
+    fn peer_status(&self) -> Vec<PeerStatus> {
+        self.peer_table.status()
+    }
+
+    fn set_topics(&mut self, topics: Vec<String>) -> Result<(), String> {
+        let topics: BTreeSet<String> = topics.into_iter().collect();
+        if let Some(swarm) = self.swarm.as_mut() {
+            let gossipsub = &mut swarm.behaviour_mut().gossipsub;
+            for topic in self.committee_topics.difference(&topics) {
+                info!("NET: Unsubscribing from {}", topic);
+                gossipsub
+                    .unsubscribe(&GossibsubTopic::new(topic.clone()))
+                    .map_err(|e| format!("Failed to unsubscribe from {}: {:?}", topic, e))?;
+            }
+            for topic in topics.difference(&self.committee_topics) {
+                info!("NET: Subscribing to {}", topic);
+                gossipsub
+                    .subscribe(&GossibsubTopic::new(topic.clone()))
+                    .map_err(|e| format!("Failed to subscribe to {}: {:?}", topic, e))?;
+            }
+        }
+        self.committee_topics = topics;
+        Ok(())
+    }
+
+    fn update_config(&mut self, config: NetworkConfig) -> Result<(), String> {
+        let local_id = self.config.local_peer.id;
+        let peers: Vec<NetworkPeer> = config
+            .peers
+            .clone()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|peer| peer.id != local_id)
+            .collect();
+        let dial_addresses: HashMap<Multiaddr, u32> = peers
+            .iter()
+            .map(|peer| (utils::get_dial_addr(peer), peer.id))
+            .collect();
+        let trusted_peers = utils::get_trusted_peers(&peers)?;
+
+        // Peers that were removed or moved to another address are disconnected.
+        let stale: Vec<(Multiaddr, u32)> = self
+            .dial_addresses
+            .iter()
+            .filter(|(dial_addr, id)| dial_addresses.get(*dial_addr) != Some(*id))
+            .map(|(dial_addr, id)| (dial_addr.clone(), *id))
+            .collect();
+        for (dial_addr, id) in stale {
+            info!("NET: Removing peer {} on {}", id, dial_addr);
+            self.dial_addresses.remove(&dial_addr);
+            self.peer_table.remove_peer(id);
+
+            let peer_ids: Vec<PeerId> = self
+                .peer_ids
+                .iter()
+                .filter(|(_, peer)| **peer == id)
+                .map(|(peer_id, _)| *peer_id)
+                .collect();
+            for peer_id in peer_ids {
+                self.peer_ids.remove(&peer_id);
+                if let Some(swarm) = self.swarm.as_mut() {
+                    let _ = swarm.disconnect_peer_id(peer_id);
+                }
+            }
+        }
+
+        // Connections of nodes whose identity is no longer trusted are closed.
+        if !trusted_peers.is_empty() {
+            let untrusted: Vec<PeerId> = self
+                .peer_ids
+                .keys()
+                .filter(|peer_id| trusted_peers.get(*peer_id) != self.peer_ids.get(*peer_id))
+                .cloned()
+                .collect();
+            for peer_id in untrusted {
+                info!("NET: Disconnecting {} whose identity is no longer trusted", peer_id);
+                self.peer_ids.remove(&peer_id);
+                if let Some(swarm) = self.swarm.as_mut() {
+                    let _ = swarm.disconnect_peer_id(peer_id);
+                }
+            }
+        }
+        self.trusted_peers = trusted_peers;
+
+        // New peers are dialed on the next health check.
+        let now = Instant::now();
+        for peer in peers.iter() {
+            let dial_addr = utils::get_dial_addr(peer);
+            if !self.dial_addresses.contains_key(&dial_addr) {
+                info!("NET: Adding peer {} on {}", peer.id, dial_addr);
+                self.dial_addresses.insert(dial_addr, peer.id);
+                self.peer_table.add_peer(peer, now);
+            }
+        }
+
+        self.config = config;
+        Ok(())
     }
-  
-}
 }
 
 impl P2PComponent {
@@ -138,12 +274,12 @@ impl P2PComponent {
             // Periodically list all our known peers.
             _tick = list_peers_timer.tick() => {
                 debug!("NET: My currently known peers: ");
-                for (peer, _) in swarm.behaviour().all_peers() {
+                for (peer, _) in swarm.behaviour().gossipsub.all_peers() {
                     debug!("- {}", peer);
                 }
 
                 debug!("NET: My currently connected mesh peers: ");
-                for peer in swarm.behaviour().all_mesh_peers() {
+                for peer in swarm.behaviour().gossipsub.all_mesh_peers() {
                     debug!("- {}", peer);
                 }
             }    
@@ -152,16 +288,133 @@ impl P2PComponent {
     }
 
     // TODO: apply get swarm
-    pub fn get_swarm(&mut self) -> Result<&mut Swarm<Gossipsub>, String> {
+    pub fn get_swarm(&mut self) -> Result<&mut Swarm<P2PBehaviour>, String> {
         self.swarm.as_mut().ok_or_else(|| "Swarm not initialized".to_string())
     }
 
-    pub fn new(config: NetworkConfig, id: u32) -> Self{
+    pub fn new(config: NetworkConfig, id: u32) -> Result<Self, String>{
         let topic: GossibsubTopic = GossibsubTopic::new("gossipsub broadcast");
-        return P2PComponent{
+        let peers = config.peers.clone().unwrap_or_default();
+        let peer_table = PeerTable::new(&peers, id, INITIAL_REDIAL_BACKOFF, MAX_REDIAL_BACKOFF);
+        let dial_addresses = peers
+            .iter()
+            .filter(|peer| peer_table.contains(peer.id))
+            .map(|peer| (utils::get_dial_addr(peer), peer.id))
+            .collect();
+        let trusted_peers = utils::get_trusted_peers(&peers)?;
+
+        return Ok(P2PComponent{
             config: config,
             swarm: None,
             topic: topic,
+            committee_topics: BTreeSet::new(),
+            peer_table,
+            dial_addresses,
+            peer_ids: HashMap::new(),
+            trusted_peers,
+            health_check: None,
+        })
+    }
+
+    // Updates the peer table from connection and ping events, and returns the message carried by
+    // a gossipsub event.
+    fn handle_swarm_event<E: std::fmt::Debug>(
+        &mut self,
+        event: SwarmEvent<P2PEvent, E>,
+    ) -> Option<NetMessage> {
+        match event {
+            // Handles (incoming) Gossipsub-Message
+            SwarmEvent::Behaviour(P2PEvent::Gossipsub(GossipsubEvent::Message { propagation_source, message, .. })) => {
+                debug!("NET: Received a message");
+                if let Some(id) = self.peer_ids.get(&propagation_source) {
+                    self.peer_table.on_seen(*id);
+                }
+                match NetMessage::try_from(message.data) {
+                    Ok(mut net_message) => {
+                        // Messages are signed by their author, so the source is authenticated.
+                        let sender = message
+                            .source
+                            .as_ref()
+                            .and_then(|source| self.node_id(source));
+                        net_message.set_sender(sender);
+                        return Some(net_message);
+                    }
+                    Err(e) => {
+                        warn!("NET: Dropping malformed message from {:?}: {}", message.source, e);
+                    }
+                }
+            }
+            SwarmEvent::Behaviour(P2PEvent::Ping(PingEvent { peer, result: Ok(PingSuccess::Ping { rtt }) })) => {
+                if let Some(id) = self.peer_ids.get(&peer) {
+                    self.peer_table.on_latency(*id, rtt);
+                }
+            }
+            SwarmEvent::ConnectionEstablished { peer_id, endpoint, .. } => {
+                debug!("NET: Successfully established connection to peer {peer_id} on {}", endpoint.get_remote_address());
+                // Peers are recognized by their identity if configured, or else by the address we
+                // dialed them on.
+                if !self.trusted_peers.is_empty() {
+                    match self.trusted_peers.get(&peer_id) {
+                        Some(id) => {
+                            self.peer_ids.insert(peer_id, *id);
+                        }
+                        None => {
+                            warn!("NET: Closing connection of unknown node {peer_id} on {}", endpoint.get_remote_address());
+                            if let Some(swarm) = self.swarm.as_mut() {
+                                let _ = swarm.disconnect_peer_id(peer_id);
+                            }
+                            return None;
+                        }
+                    }
+                } else if let Some(id) = self.dial_addresses.get(endpoint.get_remote_address()) {
+                    self.peer_ids.insert(peer_id, *id);
+                }
+                if let Some(id) = self.peer_ids.get(&peer_id) {
+                    info!("NET: Connected to peer {}", id);
+                    self.peer_table.on_connected(*id);
+                }
+            }
+            SwarmEvent::ConnectionClosed { peer_id, endpoint, num_established, cause } => {
+                debug!("NET: Closed connection to peer {peer_id} on {} due to {:?}", endpoint.get_remote_address(), cause);
+                if num_established == 0 {
+                    if let Some(id) = self.peer_ids.get(&peer_id) {
+                        warn!("NET: Lost connection to peer {}", id);
+                        self.peer_table.on_disconnected(*id, Instant::now());
+                    }
+                }
+            }
+            SwarmEvent::OutgoingConnectionError { peer_id, error } => {
+                debug!("NET: Dialing {:?} failed: {:?}", peer_id, error);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    // Returns the id of the configured node with the given libp2p id, if it is known.
+    fn node_id(&self, peer_id: &PeerId) -> Option<u32> {
+        self.trusted_peers
+            .get(peer_id)
+            .or_else(|| self.peer_ids.get(peer_id))
+            .copied()
+    }
+
+    // Dials the disconnected peers whose backoff expired.
+    fn redial_peers(&mut self) {
+        let due = self.peer_table.due_for_redial(Instant::now());
+        if due.is_empty() {
+            return;
+        }
+
+        let swarm = self.swarm.as_mut().unwrap();
+        for (dial_addr, id) in self.dial_addresses.iter() {
+            if !due.contains(id) {
+                continue;
+            }
+            info!("NET: Redialing peer {} on {}", id, dial_addr);
+            if let Err(e) = swarm.dial(dial_addr.clone()) {
+                debug!("NET: Dial {:?} failed: {:?}", dial_addr, e);
+            }
         }
     }
 
@@ -172,10 +425,7 @@ impl P2PComponent {
     async fn dial_local_net(&mut self) -> Result<(), String> {
         // Start by dialing all peers other than ourselves, letting the underlying network layer know
         // of their existenec, allowing it to connect if required.
-        let peers =  self.config.peers.as_ref().unwrap();
-        for peer in peers {
-
-            let dial_addr = utils::get_dial_addr(peer);
+        for dial_addr in self.dial_addresses.keys() {
             // Note that the dial() method will *not* erorr if connection fails - e.g. dialing a
             // not-yet-reachable peer will work just fine. I assume it will rather fail if the thing we
             // pass is not actually dial-able - e.g. an invalid IP, or a peer ID of a peer we do not
@@ -191,23 +441,24 @@ impl P2PComponent {
 
         debug!("NET: Waiting for connection to first peer");
         // Now we wait until we've successfully connected to at least one peer.
-        let swarm = self.swarm.as_mut().unwrap();
-
         // Wait for the first connection to be established or a timeout to occur.
         let timeout = tokio::time::timeout(Duration::from_secs(30), async {
             loop {
-                match swarm.select_next_some().await {
-                    SwarmEvent::ConnectionEstablished { endpoint, .. } => {
-                        info!(
-                            "NET: Successfully connected to first peer on: {:?}",
-                            endpoint.get_remote_address()
-                        );
-                        return;
-                    },
-                    _ => {}
+                let event = self.swarm.as_mut().unwrap().select_next_some().await;
+                if let SwarmEvent::ConnectionEstablished { endpoint, .. } = &event {
+                    info!(
+                        "NET: Successfully connected to first peer on: {:?}",
+                        endpoint.get_remote_address()
+                    );
+                    self.handle_swarm_event(event);
+                    return;
                 }
+                self.handle_swarm_event(event);
             }
         }).await;
+
+        // Peers that did not connect at startup are dialed again with backoff.
+        self.peer_table.schedule_unconnected(Instant::now());
         match timeout {
             Ok(_) => {
                 info!("NET: Ready for client requests...");
@@ -229,19 +480,20 @@ impl P2PComponent {
 
 mod utils {
     use std::{
-        collections::hash_map::DefaultHasher, 
+        collections::{hash_map::DefaultHasher, HashMap}, 
         hash::{Hash, Hasher}, 
         time::Duration
     };
 
     use libp2p::{
         core::{muxing::StreamMuxerBox, transport::Boxed, upgrade}, 
-        gossipsub::{self, Gossipsub, GossipsubMessage, IdentTopic as GossibsubTopic, MessageAuthenticity, MessageId, ValidationMode}, 
+        gossipsub::{self, GossipsubMessage, IdentTopic as GossibsubTopic, MessageAuthenticity, MessageId, ValidationMode}, 
         identity::Keypair, 
         mplex, 
         multiaddr::Protocol, 
         noise::{self, AuthenticKeypair, X25519Spec}, 
         swarm::SwarmBuilder, 
+        ping::{Ping, PingConfig},
         tcp::TokioTcpConfig, 
         Multiaddr, 
         PeerId, 
@@ -251,6 +503,8 @@ mod utils {
 
     use crate::types::config::NetworkPeer;
 
+    use super::P2PBehaviour;
+
     // Create a keypair for authenticated encryption of the transport.
     pub fn create_noise_keys(keypair: &Keypair) -> AuthenticKeypair<X25519Spec> {
         noise::Keypair::<noise::X25519Spec>::new()
@@ -277,7 +531,7 @@ mod utils {
             id_keys: Keypair,
             transport: Boxed<(PeerId, StreamMuxerBox)>,
             local_peer_id: PeerId,
-        ) -> Swarm<Gossipsub> {
+        ) -> Swarm<P2PBehaviour> {
             // To content-address message, we can take the hash of message and use it as an ID.
             let message_id_fn = |message: &GossipsubMessage| {
             let mut s = DefaultHasher::new();
@@ -303,8 +557,12 @@ mod utils {
             // subscribes to our topic
             gossipsub.subscribe(&topic).unwrap();
 
+            // pings keep idle connections open, so a closed connection means the peer is gone
+            let ping = Ping::new(PingConfig::new().with_keep_alive(true));
+            let behaviour = P2PBehaviour { gossipsub, ping };
+
             // build the swarm
-            SwarmBuilder::new(transport, gossipsub, local_peer_id)
+            SwarmBuilder::new(transport, behaviour, local_peer_id)
             // We want the connection backgro&mut und tasks to be spawned onto the tokio runtime.
             .executor(Box::new(|fut| {
                 tokio::spawn(fut);
@@ -312,6 +570,17 @@ mod utils {
             .build()
         }
 
+        // Maps the configured identities of the peers to their ids.
+        pub fn get_trusted_peers(peers: &[NetworkPeer]) -> Result<HashMap<PeerId, u32>, String> {
+            let mut trusted_peers = HashMap::new();
+            for peer in peers {
+                if let Some(identity) = peer.get_identity()? {
+                    trusted_peers.insert(identity, peer.id);
+                }
+            }
+            Ok(trusted_peers)
+        }
+
         pub fn get_dial_addr(peer: &NetworkPeer) -> Multiaddr {
             let ip_version = "/ip4/";
         
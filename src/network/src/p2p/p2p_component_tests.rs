@@ -12,21 +12,25 @@ mod tests{
             id: 1,
             ip: localhost.to_string(),
             port: 8181,
+            identity: None,
         };
         let peer2 = NetworkPeer{
             id: 2,
             ip: localhost.to_string(),
             port: 8182,
+            identity: None,
         };
         let peer3 = NetworkPeer{
             id: 3,
             ip: localhost.to_string(),
             port: 8183,
+            identity: None,
         };
         let peer4 = NetworkPeer{
             id: 4,
             ip: localhost.to_string(),
             port: 8184,
+            identity: None,
         };
 
         //Create network configuration
@@ -35,31 +39,35 @@ mod tests{
             peers: Some(vec![peer1.clone(),peer2.clone(), peer3.clone(), peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config2 = NetworkConfig{
             local_peer: peer2.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(), peer3.clone(), peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config3 = NetworkConfig{
             local_peer: peer3.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(), peer3.clone(), peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config4 = NetworkConfig{
             local_peer: peer4.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(), peer3.clone(), peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
 
         //Initialize peer components
-        let mut p2p_component1 = P2PComponent::new(network_config1.clone(), peer1.clone().id);
-        let mut p2p_component2 = P2PComponent::new(network_config2.clone(), peer2.clone().id);
-        let mut p2p_component3 = P2PComponent::new(network_config3.clone(), peer3.clone().id);
-        let mut p2p_component4 = P2PComponent::new(network_config4.clone(), peer4.clone().id);
+        let mut p2p_component1 = P2PComponent::new(network_config1.clone(), peer1.clone().id).unwrap();
+        let mut p2p_component2 = P2PComponent::new(network_config2.clone(), peer2.clone().id).unwrap();
+        let mut p2p_component3 = P2PComponent::new(network_config3.clone(), peer3.clone().id).unwrap();
+        let mut p2p_component4 = P2PComponent::new(network_config4.clone(), peer4.clone().id).unwrap();
 
 
         //define the async function to initialize the peers
@@ -89,21 +97,25 @@ mod tests{
             id: 1,
             ip: localhost.to_string(),
             port: 8081,
+            identity: None,
         };
         let peer2 = NetworkPeer{
             id: 2,
             ip: localhost.to_string(),
             port: 8082,
+            identity: None,
         };
         let peer3 = NetworkPeer{
             id: 3,
             ip: localhost.to_string(),
             port: 8083,
+            identity: None,
         };
         let peer4 = NetworkPeer{
             id: 4,
             ip: localhost.to_string(),
             port: 8084,
+            identity: None,
         };
 
         //Create network configuration
@@ -112,31 +124,35 @@ mod tests{
             peers: Some(vec![peer1.clone(),peer2.clone(),peer3.clone(),peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config2 = NetworkConfig{
             local_peer: peer2.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(),peer3.clone(),peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config3 = NetworkConfig{
             local_peer: peer3.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(),peer3.clone(),peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
         let network_config4 = NetworkConfig{
             local_peer: peer4.clone(),
             peers: Some(vec![peer1.clone(),peer2.clone(),peer3.clone(),peer4.clone()]),
             proxy: None,
             base_listen_address: listening_addr.to_string(),
+            identity_file: None,
         };
 
         //Initialize peer components
-        let mut p2p_component1 = P2PComponent::new(network_config1.clone(), peer1.clone().id);
-        let mut p2p_component2 = P2PComponent::new(network_config2.clone(), peer2.clone().id);
-        let mut p2p_component3 = P2PComponent::new(network_config3.clone(), peer3.clone().id);
-        let mut p2p_component4 = P2PComponent::new(network_config4.clone(), peer4.clone().id);
+        let mut p2p_component1 = P2PComponent::new(network_config1.clone(), peer1.clone().id).unwrap();
+        let mut p2p_component2 = P2PComponent::new(network_config2.clone(), peer2.clone().id).unwrap();
+        let mut p2p_component3 = P2PComponent::new(network_config3.clone(), peer3.clone().id).unwrap();
+        let mut p2p_component4 = P2PComponent::new(network_config4.clone(), peer4.clone().id).unwrap();
 
 
         //define the async function to initialize the peers
@@ -1,9 +1,13 @@
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use log::{info, error, debug};
+use log::{info, error, debug, warn};
 use theta_proto::proxy_api::proxy_api_server::{ProxyApi, ProxyApiServer};
-use theta_proto::proxy_api::{AtomicBroadcastRequest, AtomicBroadcastResponse, ForwardShareResponse};
+use theta_proto::proxy_api::{
+    AtomicBroadcastRequest, AtomicBroadcastResponse, Block, DeliverBlockRequest,
+    DeliverBlockResponse, ForwardShareResponse, GetBlocksRequest, GetBlocksResponse,
+};
 // Tokio
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
@@ -17,7 +21,7 @@ use tonic::transport::Server;
 use crate::interface::{Gossip, TOB};
 use crate::types::config::NetworkConfig;
 // Thetacrypt
-use crate::types::message::NetMessage;
+use crate::types::message::{Channel, NetMessage};
 
 use serde::{Deserialize, Serialize};
 
@@ -27,7 +31,9 @@ pub struct P2PProxy {
     pub config: NetworkConfig,
     pub id: u32,
     sender: Sender<Vec<u8>>,
-    receiver: Receiver<Vec<u8>>
+    receiver: Receiver<Vec<u8>>,
+    // Address of the proxy, shared with the service that fetches missing blocks from it
+    proxy_address: Arc<Mutex<String>>,
 }
 
 #[async_trait]
@@ -45,7 +51,9 @@ impl Gossip for P2PProxy {
 
         info!("[P2PProxyServer]: Request handler is starting. Listening for RPC on address: {address}");
         let service = P2PProxyService{
-            sender: self.sender.clone()
+            sender: self.sender.clone(),
+            proxy_address: self.proxy_address.clone(),
+            next_height: Arc::new(tokio::sync::Mutex::new(None)),
         };
         tokio::spawn(async move {
             info!("[P2PProxyServer] Server is starting");
@@ -63,29 +71,26 @@ impl Gossip for P2PProxy {
     fn broadcast(&mut self, message: NetMessage) -> Result<(), std::string::String> {
         info!("Receiving message from outgoing_channel");
         //here goes the target_platform ip
-
-        let proxy_node = self.config.proxy.as_ref().unwrap().clone();
-        let port: u16 = proxy_node.port;
-        let ip = proxy_node.ip;
-
-        let address: String = format!("http://{}:{}",ip, port)
-        .parse()
-        .expect(&format!(
-            ">> Fatal error: Could not format address for ip:{}, and port {}.",
-            ip,
-            port
-        ));
+        let address = self.proxy_address.lock().unwrap().clone();
 
         info!("Connecting to remote address: {}", address);
         tokio::spawn(async move {
             match ProxyApiClient::connect(address).await {
                 Ok(mut client) => {
                     println!("Id of the msg {}", message.get_instace_id().clone());
-                    let request = ForwardShareRequest {
-                        data: Vec::from(message),
-                    };
-
-                    tokio::spawn(async move { client.forward_share(request).await });
+                    // Messages for the TOB channel are ordered by the ledger and come back in a block
+                    if *message.get_metadata().get_channel() == Channel::TOB {
+                        let request = AtomicBroadcastRequest {
+                            id: message.get_instace_id().clone(),
+                            data: Vec::from(message),
+                        };
+                        tokio::spawn(async move { client.atomic_broadcast(request).await });
+                    } else {
+                        let request = ForwardShareRequest {
+                            data: Vec::from(message),
+                        };
+                        tokio::spawn(async move { client.forward_share(request).await });
+                    }
                     Ok(())
                 },
                 Err(e) => {
@@ -97,12 +102,32 @@ impl Gossip for P2PProxy {
         Ok(())
     }
 
+    fn update_config(&mut self, config: NetworkConfig) -> Result<(), String> {
+        if config.proxy.is_none() {
+            return Err("A proxy network requires a proxy node".to_string());
+        }
+        // messages are sent to the proxy of the current config, so the change applies right away
+        *self.proxy_address.lock().unwrap() = proxy_address(&config);
+        self.config = config;
+        Ok(())
+    }
+
     async fn deliver(&mut self) -> Option<NetMessage> {
-        tokio::select! {
-            Some(message) = self.receiver.recv() => {
-                let msg = NetMessage::from(message);
-                info!("Deliver message to the protocol layer");
-                return Some(msg)
+        loop {
+            tokio::select! {
+                Some(message) = self.receiver.recv() => {
+                    match NetMessage::try_from(message) {
+                        Ok(mut msg) => {
+                            // the proxy does not authenticate the nodes that submitted a message
+                            msg.set_sender(None);
+                            info!("Deliver message to the protocol layer");
+                            return Some(msg)
+                        }
+                        Err(e) => {
+                            warn!("Dropping malformed message from proxy: {}", e);
+                        }
+                    }
+                }
             }
         }
     }
@@ -110,7 +135,45 @@ impl Gossip for P2PProxy {
 
 
 struct P2PProxyService{
-    sender: Sender<Vec<u8>>
+    sender: Sender<Vec<u8>>,
+    proxy_address: Arc<Mutex<String>>,
+    // Height of the next block to deliver, unknown until the first block arrives
+    next_height: Arc<tokio::sync::Mutex<Option<u64>>>,
+}
+
+impl P2PProxyService {
+    // Hands the entries of a block to the higher layer, in the order decided by the ledger.
+    async fn deliver_entries(&self, block: Block) {
+        for entry in block.entries {
+            let _ = self.sender.send(entry.data).await;
+        }
+    }
+
+    // Fetches the blocks in [from_height, to_height) from the proxy.
+    async fn fetch_blocks(&self, from_height: u64, to_height: u64) -> Result<Vec<Block>, Status> {
+        let address = self.proxy_address.lock().unwrap().clone();
+        let mut client = ProxyApiClient::connect(address)
+            .await
+            .map_err(|e| Status::unavailable(format!("Could not connect to the proxy: {}", e)))?;
+        let response = client
+            .get_blocks(GetBlocksRequest {
+                from_height,
+                limit: (to_height - from_height) as u32,
+            })
+            .await?;
+
+        let blocks = response.into_inner().blocks;
+        let complete = blocks.len() as u64 == to_height - from_height
+            && blocks.iter().zip(from_height..).all(|(block, height)| block.height == height);
+        if !complete {
+            return Err(Status::unavailable(format!(
+                "The proxy did not return the blocks {} to {}",
+                from_height,
+                to_height - 1
+            )));
+        }
+        Ok(blocks)
+    }
 }
 
 #[tonic::async_trait]
@@ -138,16 +201,63 @@ impl ProxyApi for P2PProxyService {
         Ok(Response::new(AtomicBroadcastResponse {}))
     }
 
+    async fn deliver_block(
+        &self,
+        request: Request<DeliverBlockRequest>,
+    ) -> Result<Response<DeliverBlockResponse>, Status> {
+        let block = request
+            .into_inner()
+            .block
+            .ok_or_else(|| Status::invalid_argument("Missing block"))?;
+
+        // held until the block is delivered, so blocks are handed over in order
+        let mut next_height = self.next_height.lock().await;
+        let expected = next_height.unwrap_or(block.height);
+
+        if block.height < expected {
+            debug!("Ignoring block {}, it was already delivered", block.height);
+            return Ok(Response::new(DeliverBlockResponse {}));
+        }
+
+        if block.height > expected {
+            // a block got lost, e.g., while this node was unreachable
+            info!("Fetching the missing blocks {} to {} from the proxy", expected, block.height - 1);
+            for missing in self.fetch_blocks(expected, block.height).await? {
+                self.deliver_entries(missing).await;
+            }
+        }
+
+        info!("Delivering block {} to the protocol layer", block.height);
+        *next_height = Some(block.height + 1);
+        self.deliver_entries(block).await;
+
+        Ok(Response::new(DeliverBlockResponse {}))
+    }
+
+    async fn get_blocks(
+        &self,
+        _request: Request<GetBlocksRequest>,
+    ) -> Result<Response<GetBlocksResponse>, Status> {
+        Err(Status::unimplemented("Thetacrypt nodes do not keep a ledger"))
+    }
+
 }
 
 impl P2PProxy {
 
     pub fn new(config: NetworkConfig, id: u32) -> Self {
         let (sender, receiver) = tokio::sync::mpsc::channel::<Vec<u8>>(32);
-        return P2PProxy { config: config, id: id , sender: sender, receiver: receiver}
+        let proxy_address = Arc::new(Mutex::new(proxy_address(&config)));
+        return P2PProxy { config: config, id: id , sender: sender, receiver: receiver, proxy_address}
     }
 }
 
+// Returns the RPC address of the proxy in the config.
+fn proxy_address(config: &NetworkConfig) -> String {
+    let proxy_node = config.proxy.as_ref().expect("A proxy network requires a proxy node");
+    format!("http://{}:{}", proxy_node.ip, proxy_node.port)
+}
+
 //TODO: finish the implementation deciding if it is the blockchain part that should push when something is decided (maybe here consider the finality)
 pub struct ProxyTOBStub {
     pub config: NetworkConfig,
@@ -0,0 +1,3 @@
+pub mod simulated_network;
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,413 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, trace};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tonic::async_trait;
+
+use crate::interface::{Gossip, TOB};
+use crate::types::message::NetMessage;
+use crate::types::peer_table::PeerStatus;
+
+/// Parameters of a simulated network. All random decisions (delays, drops) are drawn from an RNG
+/// seeded with `seed`, and delivery times are based on the tokio clock, so a scenario executed on
+/// a current-thread runtime with paused time is reproducible.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub seed: u64,
+    /// Every message is delayed by a random duration between `min_delay` and `max_delay`.
+    /// Differing delays reorder messages.
+    pub min_delay: Duration,
+    pub max_delay: Duration,
+    /// Probability with which a gossip message to a single receiver is lost.
+    pub drop_probability: f64,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            min_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            drop_probability: 0.0,
+        }
+    }
+}
+
+/// Counters of the messages handled by a simulated network.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationStats {
+    pub sent: u64,
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+/// SimulatedNetwork connects nodes running in the same process through channels. It provides
+/// `Gossip` and `TOB` endpoints for every node and lets tests control delays, message loss,
+/// partitions and disconnected nodes.
+///
+/// Gossip messages are not delivered back to their sender (like with gossipsub); the
+/// `NetworkManager` takes care of that. The TOB channel delivers every message to every node,
+/// including the sender, in the same order. It models an ordering service that is fault tolerant
+/// by itself, so it is not affected by message loss or partitions.
+///
+/// A SimulatedNetwork must be created from within a tokio runtime, as it spawns the task that
+/// delivers scheduled messages.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    state: Arc<Mutex<SimulationState>>,
+    wakeup: Arc<Notify>,
+}
+
+struct SimulationState {
+    config: SimulationConfig,
+    rng: StdRng,
+    gossip_inboxes: BTreeMap<u32, UnboundedSender<NetMessage>>,
+    tob_inboxes: BTreeMap<u32, UnboundedSender<NetMessage>>,
+    // Nodes can only talk to nodes with the same partition index. Nodes without an entry share
+    // the default partition.
+    partitions: HashMap<u32, usize>,
+    disconnected: BTreeSet<u32>,
+    // Committee topics per node. Nodes without an entry receive the messages of every topic.
+    subscriptions: HashMap<u32, BTreeSet<String>>,
+    // Time of the last scheduled TOB delivery per node, used to keep the total order.
+    tob_last_delivery: HashMap<u32, Instant>,
+    queue: BinaryHeap<Reverse<ScheduledMessage>>,
+    next_seq: u64,
+    stats: SimulationStats,
+}
+
+struct ScheduledMessage {
+    deliver_at: Instant,
+    seq: u64,
+    receiver: u32,
+    tob: bool,
+    message: NetMessage,
+}
+
+impl PartialEq for ScheduledMessage {
+    fn eq(&self, other: &Self) -> bool {
+        self.deliver_at == other.deliver_at && self.seq == other.seq
+    }
+}
+
+impl Eq for ScheduledMessage {}
+
+impl PartialOrd for ScheduledMessage {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ScheduledMessage {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.deliver_at, self.seq).cmp(&(other.deliver_at, other.seq))
+    }
+}
+
+impl SimulationState {
+    fn connected(&self, a: u32, b: u32) -> bool {
+        if self.disconnected.contains(&a) || self.disconnected.contains(&b) {
+            return false;
+        }
+        self.partitions.get(&a) == self.partitions.get(&b)
+    }
+
+    fn sample_delay(&mut self) -> Duration {
+        let min = self.config.min_delay.as_micros() as u64;
+        let max = self.config.max_delay.as_micros() as u64;
+        if max <= min {
+            return Duration::from_micros(min);
+        }
+        Duration::from_micros(self.rng.gen_range(min..=max))
+    }
+
+    fn schedule(&mut self, deliver_at: Instant, receiver: u32, tob: bool, message: NetMessage) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.queue.push(Reverse(ScheduledMessage {
+            deliver_at,
+            seq,
+            receiver,
+            tob,
+            message,
+        }));
+    }
+}
+
+impl SimulatedNetwork {
+    pub fn new(config: SimulationConfig) -> Self {
+        let network = Self {
+            state: Arc::new(Mutex::new(SimulationState {
+                rng: StdRng::seed_from_u64(config.seed),
+                config,
+                gossip_inboxes: BTreeMap::new(),
+                tob_inboxes: BTreeMap::new(),
+                partitions: HashMap::new(),
+                disconnected: BTreeSet::new(),
+                subscriptions: HashMap::new(),
+                tob_last_delivery: HashMap::new(),
+                queue: BinaryHeap::new(),
+                next_seq: 0,
+                stats: SimulationStats::default(),
+            })),
+            wakeup: Arc::new(Notify::new()),
+        };
+
+        let scheduler = network.clone();
+        tokio::spawn(async move { scheduler.run_scheduler().await });
+
+        network
+    }
+
+    /// Creates the gossip endpoint of node `id`. Creating a second endpoint for the same node
+    /// replaces the first one.
+    pub fn gossip_endpoint(&self, id: u32) -> SimulatedGossip {
+        let (sender, inbox) = unbounded_channel();
+        self.state.lock().unwrap().gossip_inboxes.insert(id, sender);
+        SimulatedGossip {
+            id,
+            network: self.clone(),
+            inbox,
+        }
+    }
+
+    /// Creates the TOB endpoint of node `id`. Creating a second endpoint for the same node
+    /// replaces the first one.
+    pub fn tob_endpoint(&self, id: u32) -> SimulatedTOB {
+        let (sender, inbox) = unbounded_channel();
+        self.state.lock().unwrap().tob_inboxes.insert(id, sender);
+        SimulatedTOB {
+            id,
+            network: self.clone(),
+            inbox: tokio::sync::Mutex::new(inbox),
+        }
+    }
+
+    /// Splits the network into the given groups of nodes. Nodes not listed in any group form one
+    /// additional group. Messages between groups are lost.
+    pub fn partition(&self, groups: &[Vec<u32>]) {
+        let mut state = self.state.lock().unwrap();
+        state.partitions.clear();
+        for (i, group) in groups.iter().enumerate() {
+            for id in group {
+                state.partitions.insert(*id, i + 1);
+            }
+        }
+        debug!("SIM: Partitioned network into {:?}", groups);
+    }
+
+    /// Removes all partitions. Messages lost in the meantime are not delivered.
+    pub fn heal(&self) {
+        self.state.lock().unwrap().partitions.clear();
+        debug!("SIM: Healed network partitions");
+    }
+
+    /// Cuts node `id` off the gossip network, e.g., to simulate a crash.
+    pub fn disconnect(&self, id: u32) {
+        self.state.lock().unwrap().disconnected.insert(id);
+    }
+
+    pub fn reconnect(&self, id: u32) {
+        self.state.lock().unwrap().disconnected.remove(&id);
+    }
+
+    /// Sets the committee topics node `id` receives messages of, in addition to messages without
+    /// a topic.
+    pub fn subscribe(&self, id: u32, topics: Vec<String>) {
+        self.state
+            .lock()
+            .unwrap()
+            .subscriptions
+            .insert(id, topics.into_iter().collect());
+    }
+
+    pub fn set_drop_probability(&self, drop_probability: f64) {
+        self.state.lock().unwrap().config.drop_probability = drop_probability;
+    }
+
+    pub fn set_delay(&self, min_delay: Duration, max_delay: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.config.min_delay = min_delay;
+        state.config.max_delay = max_delay;
+    }
+
+    pub fn stats(&self) -> SimulationStats {
+        self.state.lock().unwrap().stats.clone()
+    }
+
+    /// Returns the status of the other nodes as seen by node `id`. A node counts as connected if
+    /// messages between the two are delivered.
+    pub fn peer_status(&self, id: u32) -> Vec<PeerStatus> {
+        let state = self.state.lock().unwrap();
+        state
+            .gossip_inboxes
+            .keys()
+            .filter(|other| **other != id)
+            .map(|other| PeerStatus {
+                id: *other,
+                address: format!("simulated-{}", other),
+                connected: state.connected(id, *other),
+                last_seen: None,
+                latency: None,
+                redial_attempts: 0,
+            })
+            .collect()
+    }
+
+    fn gossip(&self, sender: u32, mut message: NetMessage) {
+        // Like the signatures of gossipsub, the simulation authenticates the sender.
+        message.set_sender(Some(sender));
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let topic = message.get_metadata().get_topic();
+        let receivers: Vec<u32> = state
+            .gossip_inboxes
+            .keys()
+            .copied()
+            .filter(|id| *id != sender)
+            .filter(|id| match (topic, state.subscriptions.get(id)) {
+                (Some(topic), Some(topics)) => topics.contains(topic),
+                _ => true,
+            })
+            .collect();
+
+        for receiver in receivers {
+            state.stats.sent += 1;
+            let drop_probability = state.config.drop_probability;
+            if !state.connected(sender, receiver) || state.rng.gen_bool(drop_probability) {
+                trace!("SIM: Dropping message from {} to {}", sender, receiver);
+                state.stats.dropped += 1;
+                continue;
+            }
+
+            let delay = state.sample_delay();
+            state.schedule(now + delay, receiver, false, message.clone());
+        }
+        drop(state);
+
+        self.wakeup.notify_one();
+    }
+
+    fn total_order_broadcast(&self, message: NetMessage) {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let receivers: Vec<u32> = state.tob_inboxes.keys().copied().collect();
+
+        for receiver in receivers {
+            state.stats.sent += 1;
+            let delay = state.sample_delay();
+            let deliver_at = match state.tob_last_delivery.get(&receiver) {
+                Some(last) if *last > now + delay => *last,
+                _ => now + delay,
+            };
+            state.tob_last_delivery.insert(receiver, deliver_at);
+            state.schedule(deliver_at, receiver, true, message.clone());
+        }
+        drop(state);
+
+        self.wakeup.notify_one();
+    }
+
+    async fn run_scheduler(&self) {
+        loop {
+            let next_delivery = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                while state
+                    .queue
+                    .peek()
+                    .map_or(false, |Reverse(m)| m.deliver_at <= now)
+                {
+                    let Reverse(scheduled) = state.queue.pop().unwrap();
+                    let inbox = match scheduled.tob {
+                        true => state.tob_inboxes.get(&scheduled.receiver),
+                        false => state.gossip_inboxes.get(&scheduled.receiver),
+                    };
+                    let delivered = inbox.map_or(false, |i| i.send(scheduled.message).is_ok());
+                    if delivered {
+                        state.stats.delivered += 1;
+                    } else {
+                        state.stats.dropped += 1;
+                    }
+                }
+                state.queue.peek().map(|Reverse(m)| m.deliver_at)
+            };
+
+            match next_delivery {
+                Some(deliver_at) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep_until(deliver_at) => {},
+                        _ = self.wakeup.notified() => {},
+                    }
+                }
+                None => self.wakeup.notified().await,
+            }
+        }
+    }
+}
+
+/// Gossip endpoint of a node in a `SimulatedNetwork`.
+pub struct SimulatedGossip {
+    id: u32,
+    network: SimulatedNetwork,
+    inbox: UnboundedReceiver<NetMessage>,
+}
+
+#[async_trait]
+impl Gossip for SimulatedGossip {
+    type T = NetMessage;
+
+    fn broadcast(&mut self, message: NetMessage) -> Result<(), String> {
+        self.network.gossip(self.id, message);
+        Ok(())
+    }
+
+    async fn deliver(&mut self) -> Option<NetMessage> {
+        self.inbox.recv().await
+    }
+
+    async fn init(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn peer_status(&self) -> Vec<PeerStatus> {
+        self.network.peer_status(self.id)
+    }
+
+    fn set_topics(&mut self, topics: Vec<String>) -> Result<(), String> {
+        self.network.subscribe(self.id, topics);
+        Ok(())
+    }
+}
+
+/// TOB endpoint of a node in a `SimulatedNetwork`.
+pub struct SimulatedTOB {
+    id: u32,
+    network: SimulatedNetwork,
+    inbox: tokio::sync::Mutex<UnboundedReceiver<NetMessage>>,
+}
+
+#[async_trait]
+impl TOB for SimulatedTOB {
+    type T = NetMessage;
+
+    fn broadcast(&mut self, message: NetMessage) {
+        trace!("SIM: Node {} submits a message to TOB", self.id);
+        self.network.total_order_broadcast(message);
+    }
+
+    async fn deliver(&self) -> NetMessage {
+        self.inbox
+            .lock()
+            .await
+            .recv()
+            .await
+            .expect("The simulated network keeps the sender of every endpoint")
+    }
+}
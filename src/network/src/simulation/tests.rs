@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use crate::interface::{Gossip, TOB};
+use crate::types::message::{committee_topic, Channel, NetMessage, NetMessageMetadata};
+
+use super::simulated_network::{SimulatedGossip, SimulatedNetwork, SimulationConfig};
+
+fn message(instance_id: &str) -> NetMessage {
+    NetMessage::new(
+        String::from(instance_id),
+        NetMessageMetadata::new(Channel::Gossip),
+        instance_id.as_bytes().to_vec(),
+    )
+}
+
+// The message as delivered by the network, which records the sending node.
+fn sent_by(sender: u32, instance_id: &str) -> NetMessage {
+    let mut message = message(instance_id);
+    message.set_sender(Some(sender));
+    message
+}
+
+async fn try_deliver(endpoint: &mut SimulatedGossip) -> Option<NetMessage> {
+    tokio::time::timeout(Duration::from_secs(1), endpoint.deliver())
+        .await
+        .ok()
+        .flatten()
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_gossip_reaches_all_other_nodes() {
+    let network = SimulatedNetwork::new(SimulationConfig::default());
+    let mut endpoints: Vec<SimulatedGossip> = (1..=3).map(|i| network.gossip_endpoint(i)).collect();
+
+    endpoints[0].broadcast(message("m1")).unwrap();
+
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(sent_by(1, "m1")));
+    assert_eq!(try_deliver(&mut endpoints[2]).await, Some(sent_by(1, "m1")));
+    // gossip does not deliver a message back to its sender
+    assert_eq!(try_deliver(&mut endpoints[0]).await, None);
+    assert_eq!(network.stats().delivered, 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_partition_and_heal() {
+    let network = SimulatedNetwork::new(SimulationConfig::default());
+    let mut endpoints: Vec<SimulatedGossip> = (1..=3).map(|i| network.gossip_endpoint(i)).collect();
+
+    network.partition(&[vec![1, 2]]);
+    endpoints[0].broadcast(message("m1")).unwrap();
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(sent_by(1, "m1")));
+    assert_eq!(try_deliver(&mut endpoints[2]).await, None);
+
+    network.heal();
+    endpoints[0].broadcast(message("m2")).unwrap();
+    assert_eq!(try_deliver(&mut endpoints[2]).await, Some(sent_by(1, "m2")));
+
+    network.disconnect(3);
+    endpoints[0].broadcast(message("m3")).unwrap();
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(sent_by(1, "m2")));
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(sent_by(1, "m3")));
+    assert_eq!(try_deliver(&mut endpoints[2]).await, None);
+}
+
+async fn run_lossy_scenario(seed: u64) -> (Vec<Vec<String>>, u64) {
+    let network = SimulatedNetwork::new(SimulationConfig {
+        seed,
+        min_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(50),
+        drop_probability: 0.3,
+    });
+    let mut endpoints: Vec<SimulatedGossip> = (1..=4).map(|i| network.gossip_endpoint(i)).collect();
+
+    for round in 0..5 {
+        for (i, endpoint) in endpoints.iter_mut().enumerate() {
+            endpoint
+                .broadcast(message(&format!("{}-{}", i, round)))
+                .unwrap();
+        }
+    }
+
+    let mut received = Vec::new();
+    for endpoint in endpoints.iter_mut() {
+        let mut ids = Vec::new();
+        while let Some(m) = try_deliver(endpoint).await {
+            ids.push(m.get_instace_id().clone());
+        }
+        received.push(ids);
+    }
+
+    (received, network.stats().dropped)
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_same_seed_same_schedule() {
+    let (received1, dropped1) = run_lossy_scenario(7).await;
+    let (received2, dropped2) = run_lossy_scenario(7).await;
+
+    assert!(dropped1 > 0);
+    assert_eq!(dropped1, dropped2);
+    assert_eq!(received1, received2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_tob_delivers_same_order_everywhere() {
+    let network = SimulatedNetwork::new(SimulationConfig {
+        seed: 3,
+        min_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(100),
+        drop_probability: 0.5,
+    });
+    let mut endpoints: Vec<_> = (1..=3).map(|i| network.tob_endpoint(i)).collect();
+
+    for round in 0..4 {
+        for (i, endpoint) in endpoints.iter_mut().enumerate() {
+            endpoint.broadcast(message(&format!("{}-{}", i, round)));
+        }
+    }
+
+    let mut orders = Vec::new();
+    for endpoint in endpoints.iter() {
+        let mut order = Vec::new();
+        for _ in 0..12 {
+            order.push(endpoint.deliver().await.get_instace_id().clone());
+        }
+        orders.push(order);
+    }
+
+    assert_eq!(orders[0], orders[1]);
+    assert_eq!(orders[1], orders[2]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_peer_status() {
+    let network = SimulatedNetwork::new(SimulationConfig::default());
+    let endpoints: Vec<SimulatedGossip> = (1..=3).map(|i| network.gossip_endpoint(i)).collect();
+
+    network.disconnect(3);
+
+    let status = endpoints[0].peer_status();
+    let connected: Vec<(u32, bool)> = status.iter().map(|s| (s.id, s.connected)).collect();
+    assert_eq!(connected, vec![(2, true), (3, false)]);
+    assert!(endpoints[2].peer_status().iter().all(|s| !s.connected));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_committee_topics() {
+    let network = SimulatedNetwork::new(SimulationConfig::default());
+    let mut endpoints: Vec<SimulatedGossip> = (1..=3).map(|i| network.gossip_endpoint(i)).collect();
+
+    endpoints[0].set_topics(vec![committee_topic("a")]).unwrap();
+    endpoints[1].set_topics(vec![committee_topic("a")]).unwrap();
+    endpoints[2].set_topics(vec![committee_topic("b")]).unwrap();
+
+    let mut for_a = message("m1");
+    for_a.set_topic(committee_topic("a"));
+    endpoints[0].broadcast(for_a.clone()).unwrap();
+    for_a.set_sender(Some(1));
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(for_a));
+    assert_eq!(try_deliver(&mut endpoints[2]).await, None);
+
+    // messages without a topic reach every node
+    endpoints[0].broadcast(message("m2")).unwrap();
+    assert_eq!(try_deliver(&mut endpoints[1]).await, Some(sent_by(1, "m2")));
+    assert_eq!(try_deliver(&mut endpoints[2]).await, Some(sent_by(1, "m2")));
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{error, info, trace, warn};
+use serde_json::{json, Value};
+use tonic::async_trait;
+
+use crate::interface::TOB;
+use crate::types::message::NetMessage;
+
+/// Default interval in which `CometBftTOB` checks for new blocks.
+pub const DEFAULT_POLL_INTERVAL_MS: u64 = 500;
+
+/// The calls `CometBftTOB` makes to a CometBFT (Tendermint) node. Transactions are opaque bytes,
+/// ordering them is left to the consensus of the chain.
+#[async_trait]
+pub trait CometRpc: Send + Sync {
+    /// Submits a transaction to the mempool of the node.
+    async fn broadcast_tx(&self, tx: Vec<u8>) -> Result<(), String>;
+    /// Height of the latest committed block.
+    async fn latest_height(&self) -> Result<u64, String>;
+    /// Transactions of the committed block at `height`, in block order.
+    async fn block_txs(&self, height: u64) -> Result<Vec<Vec<u8>>, String>;
+}
+
+/// `CometRpc` over the JSON-RPC endpoint of a CometBFT node (usually port 26657).
+pub struct HttpCometRpc {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl HttpCometRpc {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self
+            .client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(|e| format!("Error calling {} on {}: {}", method, self.url, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Invalid response to {} from {}: {}", method, self.url, e))?;
+
+        if let Some(e) = response.get("error") {
+            return Err(format!("{} failed on {}: {}", method, self.url, e));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| format!("Response to {} from {} has no result", method, self.url))
+    }
+}
+
+#[async_trait]
+impl CometRpc for HttpCometRpc {
+    async fn broadcast_tx(&self, tx: Vec<u8>) -> Result<(), String> {
+        let result = self
+            .call("broadcast_tx_sync", json!({ "tx": base64::encode(tx) }))
+            .await?;
+        // a non-zero code means CheckTx rejected the transaction
+        match result.get("code").and_then(Value::as_u64) {
+            Some(0) | None => Ok(()),
+            Some(code) => Err(format!(
+                "The transaction was rejected with code {}: {}",
+                code,
+                result.get("log").and_then(Value::as_str).unwrap_or_default()
+            )),
+        }
+    }
+
+    async fn latest_height(&self) -> Result<u64, String> {
+        let result = self.call("status", json!({})).await?;
+        // heights are encoded as strings
+        result
+            .pointer("/sync_info/latest_block_height")
+            .and_then(Value::as_str)
+            .and_then(|height| height.parse::<u64>().ok())
+            .ok_or_else(|| format!("Status from {} has no valid latest block height", self.url))
+    }
+
+    async fn block_txs(&self, height: u64) -> Result<Vec<Vec<u8>>, String> {
+        let result = self
+            .call("block", json!({ "height": height.to_string() }))
+            .await?;
+        let txs = match result.pointer("/block/data/txs") {
+            Some(Value::Array(txs)) => txs,
+            // empty blocks have no (or a null) list of transactions
+            Some(Value::Null) | None => return Ok(Vec::new()),
+            Some(_) => return Err(format!("Block {} from {} has an invalid list of transactions", height, self.url)),
+        };
+        txs.iter()
+            .map(|tx| {
+                tx.as_str()
+                    .and_then(|tx| base64::decode(tx).ok())
+                    .ok_or_else(|| format!("Block {} from {} has an invalid transaction", height, self.url))
+            })
+            .collect()
+    }
+}
+
+// Blocks not yet delivered to the network manager.
+struct DeliveryState {
+    // None until the first poll, delivery starts with the block after the latest one at that time
+    next_height: Option<u64>,
+    queue: VecDeque<NetMessage>,
+}
+
+/// `TOB` channel backed by a CometBFT chain. Messages are broadcast as transactions to the mempool
+/// and delivered in the order in which they appear in the committed blocks, so every node of the
+/// committee (connected to a node of the same chain) delivers the same sequence, including its own
+/// messages. Transactions that are not thetacrypt messages are skipped, the chain may be shared
+/// with an application.
+pub struct CometBftTOB<C: CometRpc> {
+    rpc: Arc<C>,
+    poll_interval: Duration,
+    state: tokio::sync::Mutex<DeliveryState>,
+}
+
+impl CometBftTOB<HttpCometRpc> {
+    /// Connects to the JSON-RPC endpoint at `url`, e.g. `http://127.0.0.1:26657`.
+    pub fn connect(url: String, poll_interval: Duration) -> Self {
+        info!("Using CometBFT node at {} for total order broadcast", url);
+        Self::new(HttpCometRpc::new(url), poll_interval)
+    }
+}
+
+impl<C: CometRpc> CometBftTOB<C> {
+    pub fn new(rpc: C, poll_interval: Duration) -> Self {
+        Self {
+            rpc: Arc::new(rpc),
+            poll_interval,
+            state: tokio::sync::Mutex::new(DeliveryState {
+                next_height: None,
+                queue: VecDeque::new(),
+            }),
+        }
+    }
+
+    /// Starts delivery at block `height` instead of the block after the latest one, e.g. to
+    /// replay the messages a node missed while it was down.
+    pub fn start_at(mut self, height: u64) -> Self {
+        self.state.get_mut().next_height = Some(height);
+        self
+    }
+
+    // Fetches the next block if it has been committed. Returns false if there is no new block.
+    async fn poll(&self, state: &mut DeliveryState) -> Result<bool, String> {
+        let latest = self.rpc.latest_height().await?;
+        let height = *state.next_height.get_or_insert(latest + 1);
+        if height > latest {
+            return Ok(false);
+        }
+
+        let txs = self.rpc.block_txs(height).await?;
+        trace!("Block {} contains {} transactions", height, txs.len());
+        for tx in txs {
+            match NetMessage::try_from(tx) {
+                Ok(message) => state.queue.push_back(message),
+                Err(e) => trace!("Skipping transaction in block {}: {}", height, e),
+            }
+        }
+        // the queue and the height change together, a cancelled poll fetches the block again
+        state.next_height = Some(height + 1);
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<C: CometRpc + 'static> TOB for CometBftTOB<C> {
+    type T = NetMessage;
+
+    fn broadcast(&mut self, message: NetMessage) {
+        let rpc = self.rpc.clone();
+        let instance_id = message.get_instace_id().clone();
+        tokio::spawn(async move {
+            match rpc.broadcast_tx(Vec::from(message)).await {
+                Ok(_) => trace!("Submitted message of instance {} to the mempool", instance_id),
+                Err(e) => error!("Error submitting message of instance {}: {}", instance_id, e),
+            }
+        });
+    }
+
+    async fn deliver(&self) -> NetMessage {
+        let mut state = self.state.lock().await;
+        loop {
+            if let Some(message) = state.queue.pop_front() {
+                return message;
+            }
+            match self.poll(&mut state).await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(e) => warn!("Error fetching blocks: {}", e),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+}
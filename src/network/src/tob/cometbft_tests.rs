@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tonic::async_trait;
+
+use crate::interface::TOB;
+use crate::types::message::{Channel, NetMessage, NetMessageMetadata};
+
+use super::cometbft::{CometBftTOB, CometRpc};
+
+// A chain shared by the mock clients of all nodes. Blocks are committed by the test.
+#[derive(Clone, Default)]
+struct MockChain {
+    mempool: Arc<Mutex<Vec<Vec<u8>>>>,
+    // block at height h is stored at index h - 1
+    blocks: Arc<Mutex<Vec<Vec<Vec<u8>>>>>,
+}
+
+impl MockChain {
+    fn commit(&self) {
+        let txs = std::mem::take(&mut *self.mempool.lock().unwrap());
+        self.blocks.lock().unwrap().push(txs);
+    }
+}
+
+#[async_trait]
+impl CometRpc for MockChain {
+    async fn broadcast_tx(&self, tx: Vec<u8>) -> Result<(), String> {
+        self.mempool.lock().unwrap().push(tx);
+        Ok(())
+    }
+
+    async fn latest_height(&self) -> Result<u64, String> {
+        Ok(self.blocks.lock().unwrap().len() as u64)
+    }
+
+    async fn block_txs(&self, height: u64) -> Result<Vec<Vec<u8>>, String> {
+        self.blocks
+            .lock()
+            .unwrap()
+            .get(height as usize - 1)
+            .cloned()
+            .ok_or(format!("Block {} has not been committed", height))
+    }
+}
+
+fn message(instance_id: &str) -> NetMessage {
+    NetMessage::new(
+        String::from(instance_id),
+        NetMessageMetadata::new(Channel::TOB),
+        instance_id.as_bytes().to_vec(),
+    )
+}
+
+async fn try_deliver(tob: &CometBftTOB<MockChain>) -> Option<NetMessage> {
+    tokio::time::timeout(Duration::from_secs(5), tob.deliver())
+        .await
+        .ok()
+}
+
+// lets the tasks spawned by broadcast reach the mempool
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_nodes_deliver_in_block_order() {
+    let chain = MockChain::default();
+    let mut tob1 = CometBftTOB::new(chain.clone(), Duration::from_millis(100));
+    let mut tob2 = CometBftTOB::new(chain.clone(), Duration::from_millis(100));
+    // the first poll fixes the start height
+    assert_eq!(try_deliver(&tob1).await, None);
+    assert_eq!(try_deliver(&tob2).await, None);
+
+    tob2.broadcast(message("m1"));
+    settle().await;
+    tob1.broadcast(message("m2"));
+    settle().await;
+    chain.commit();
+    tob1.broadcast(message("m3"));
+    settle().await;
+    chain.commit();
+
+    for tob in [&tob1, &tob2] {
+        assert_eq!(try_deliver(tob).await, Some(message("m1")));
+        assert_eq!(try_deliver(tob).await, Some(message("m2")));
+        assert_eq!(try_deliver(tob).await, Some(message("m3")));
+        assert_eq!(try_deliver(tob).await, None);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_foreign_transactions_are_skipped() {
+    let chain = MockChain::default();
+    let mut tob = CometBftTOB::new(chain.clone(), Duration::from_millis(100)).start_at(1);
+
+    chain.broadcast_tx(b"not a thetacrypt message".to_vec()).await.unwrap();
+    tob.broadcast(message("m1"));
+    settle().await;
+    chain.commit();
+
+    assert_eq!(try_deliver(&tob).await, Some(message("m1")));
+    assert_eq!(try_deliver(&tob).await, None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_start_height() {
+    let chain = MockChain::default();
+    chain.broadcast_tx(Vec::from(message("old"))).await.unwrap();
+    chain.commit();
+    chain.broadcast_tx(Vec::from(message("new"))).await.unwrap();
+    chain.commit();
+
+    // a node that joins later only delivers blocks committed after it started
+    let late = CometBftTOB::new(chain.clone(), Duration::from_millis(100));
+    assert_eq!(try_deliver(&late).await, None);
+
+    // a node that replays the chain delivers from the given height
+    let replaying = CometBftTOB::new(chain.clone(), Duration::from_millis(100)).start_at(2);
+    assert_eq!(try_deliver(&replaying).await, Some(message("new")));
+    assert_eq!(try_deliver(&replaying).await, None);
+}
@@ -0,0 +1,3 @@
+pub mod cometbft;
+#[cfg(test)]
+mod cometbft_tests;
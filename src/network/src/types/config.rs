@@ -1,4 +1,7 @@
-use libp2p::multiaddr::Multiaddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use libp2p::{multiaddr::Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
 use utils::server::types::{Peer, ProxyNode, ServerConfig};
 
@@ -7,6 +10,9 @@ pub struct NetworkPeer{
     pub id: u32, 
     pub ip: String,
     pub port: u16,
+    // libp2p peer id of the node's identity key. If set, only a node with this identity is
+    // accepted as the peer.
+    pub identity: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -21,6 +27,9 @@ pub struct NetworkConfig {
     pub peers: Option<Vec<NetworkPeer>>,
     pub proxy: Option<NetworkProxy>,
     pub base_listen_address: String,
+    // File with the identity key of this node. If not set, a new identity is generated on every
+    // start.
+    pub identity_file: Option<PathBuf>,
 }
 
 impl NetworkPeer{
@@ -28,7 +37,17 @@ impl NetworkPeer{
         return NetworkPeer{
             id: peer.id,
             ip: peer.ip.clone(),
-            port: peer.p2p_port
+            port: peer.p2p_port,
+            identity: peer.identity.clone(),
+        }
+    }
+
+    pub fn get_identity(&self) -> Result<Option<PeerId>, String> {
+        match &self.identity {
+            Some(identity) => PeerId::from_str(identity)
+                .map(Some)
+                .map_err(|e| format!("Invalid identity for peer {}: {}", self.id, e)),
+            None => Ok(None),
         }
     }
 }
@@ -65,6 +84,10 @@ impl NetworkConfig {
             )
             .collect();
 
+        for peer in server_peers.iter() {
+            peer.get_identity()?;
+        }
+
         if server_peers.len() > 1 {
             network_peers = Some(server_peers);
         }
@@ -80,6 +103,7 @@ impl NetworkConfig {
             peers: network_peers,
             proxy: proxy_peer,
             base_listen_address: server_config.listen_address.clone(),
+            identity_file: server_config.identity_file.clone(),
         })
     }
 
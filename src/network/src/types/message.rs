@@ -1,4 +1,6 @@
+use prost::Message;
 use serde::{Serialize, Deserialize};
+use theta_proto::network_types::{self as proto, ChannelType};
 
 
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
@@ -13,27 +15,45 @@ pub enum Channel{
 
 
 /// NetMessageMetadata incapsulates the information for handling the transmission of the message.
-/// Each message needs to specify the sender_id so that at the protocol layer it can be checked if 
-/// the sender_id matched the share_id of the piece of information received.
+/// The sender_id is set by the network layer of the receiving node to the id of the node that
+/// authenticated itself as the sender, or to None if the sender is unknown. The value set by the
+/// sending node is not trusted.
+/// The topic is the committee a message belongs to. Nodes only receive the messages of the
+/// committees they subscribed to, messages without a topic reach every node.
 #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 pub struct NetMessageMetadata{
-    sender_id: u16,
+    sender_id: Option<u32>,
     channel: Channel,
+    topic: Option<String>,
+}
+
+/// Returns the topic of the committee holding the key with the given id.
+pub fn committee_topic(key_id: &str) -> String {
+    format!("committee-{}", key_id)
 }
 
 impl NetMessageMetadata{
     pub fn new(channel: Channel) -> Self {
         NetMessageMetadata {
-            sender_id: 0,
+            sender_id: None,
             channel: channel,
+            topic: None,
         }
     }
 
-    pub fn set_sender(&mut self, sender_id: u16){
+    pub fn set_topic(&mut self, topic: String){
+        self.topic = Some(topic);
+    }
+
+    pub fn get_topic(&self) -> Option<&String> {
+        return self.topic.as_ref()
+    }
+
+    pub fn set_sender(&mut self, sender_id: Option<u32>){
         self.sender_id = sender_id;
     }
 
-    pub fn get_sender(&self) -> u16 {
+    pub fn get_sender(&self) -> Option<u32> {
         return self.sender_id
     }
 
@@ -82,16 +102,104 @@ impl NetMessage {
         return &self.message_data
     }
 
+    pub fn set_topic(&mut self, topic: String){
+        self.metadata.set_topic(topic);
+    }
+
+    pub fn set_sender(&mut self, sender_id: Option<u32>){
+        self.metadata.set_sender(sender_id);
+    }
+
 
 }
+/// Version of the wire format produced by `From<NetMessage> for Vec<u8>`.
+pub const WIRE_FORMAT_VERSION: u8 = 1;
+
+/// Size of the header preceding an encoded message: the format version and the length of the
+/// protobuf encoded message (u32, big endian).
+const HEADER_LEN: usize = 5;
+
 impl From<NetMessage> for Vec<u8> {
     fn from(net_message: NetMessage) -> Self {
-        // serde_json::to_string(&p2p_message).unwrap().as_bytes().to_vec()
-        serde_json::to_string(&net_message).expect("Error in From<NetMessage> for Vec<u8>").into_bytes()
+        let (channel, receiver_ids) = match net_message.metadata.channel {
+            Channel::Gossip => (ChannelType::Gossip, Vec::new()),
+            Channel::TOB => (ChannelType::Tob, Vec::new()),
+            Channel::PointToPoint { receiver_id } => (ChannelType::PointToPoint, receiver_id),
+        };
+
+        let proto_message = proto::NetMessage {
+            instance_id: net_message.instance_id,
+            metadata: Some(proto::NetMessageMetadata {
+                // 0 stands for an unknown sender on the wire
+                sender_id: net_message.metadata.sender_id.unwrap_or(0),
+                channel: channel.into(),
+                receiver_ids,
+                topic: net_message.metadata.topic,
+            }),
+            message_data: net_message.message_data,
+        };
+
+        let len = proto_message.encoded_len();
+        let mut bytes = Vec::with_capacity(HEADER_LEN + len);
+        bytes.push(WIRE_FORMAT_VERSION);
+        bytes.extend_from_slice(&(len as u32).to_be_bytes());
+        proto_message
+            .encode(&mut bytes)
+            .expect("Vec<u8> has enough capacity for the encoded message");
+        bytes
     }
 }
-impl From<Vec<u8>> for NetMessage {
-    fn from(vec: Vec<u8>) -> Self {
-        serde_json::from_str::<NetMessage>(&String::from_utf8(vec).expect("Error in From<Vec<u8>> for NetMessage")).unwrap()
+
+impl TryFrom<Vec<u8>> for NetMessage {
+    type Error = String;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        if bytes.len() < HEADER_LEN {
+            return Err(format!("Message too short: {} bytes", bytes.len()));
+        }
+
+        if bytes[0] != WIRE_FORMAT_VERSION {
+            return Err(format!("Unsupported wire format version {}", bytes[0]));
+        }
+
+        let len = u32::from_be_bytes([bytes[1], bytes[2], bytes[3], bytes[4]]) as usize;
+        if bytes.len() - HEADER_LEN != len {
+            return Err(format!(
+                "Length mismatch: header announces {} bytes, got {}",
+                len,
+                bytes.len() - HEADER_LEN
+            ));
+        }
+
+        let proto_message = proto::NetMessage::decode(&bytes[HEADER_LEN..])
+            .map_err(|e| format!("Malformed message: {}", e))?;
+
+        let metadata = proto_message
+            .metadata
+            .ok_or_else(|| String::from("Message without metadata"))?;
+
+        let sender_id = match metadata.sender_id {
+            0 => None,
+            id => Some(id),
+        };
+
+        let channel = match ChannelType::from_i32(metadata.channel) {
+            Some(ChannelType::Gossip) => Channel::Gossip,
+            Some(ChannelType::Tob) => Channel::TOB,
+            Some(ChannelType::PointToPoint) => Channel::PointToPoint {
+                receiver_id: metadata.receiver_ids,
+            },
+            None => return Err(format!("Unknown channel {}", metadata.channel)),
+        };
+
+        Ok(NetMessage {
+            instance_id: proto_message.instance_id,
+            metadata: NetMessageMetadata {
+                sender_id,
+                channel,
+                topic: metadata.topic,
+            },
+            message_data: proto_message.message_data,
+        })
     }
-}
\ No newline at end of file
+}
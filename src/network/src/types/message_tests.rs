@@ -0,0 +1,92 @@
+use super::message::{committee_topic, Channel, NetMessage, NetMessageMetadata, WIRE_FORMAT_VERSION};
+
+fn test_message(channel: Channel) -> NetMessage {
+    let mut metadata = NetMessageMetadata::new(channel);
+    metadata.set_sender(Some(3));
+    NetMessage::new(String::from("instance"), metadata, vec![0, 1, 2, 255, 128])
+}
+
+#[test]
+fn test_roundtrip() {
+    for channel in [
+        Channel::Gossip,
+        Channel::TOB,
+        Channel::PointToPoint {
+            receiver_id: vec![1, 4],
+        },
+    ] {
+        let message = test_message(channel);
+        let bytes: Vec<u8> = message.clone().into();
+        assert_eq!(bytes[0], WIRE_FORMAT_VERSION);
+
+        let decoded = NetMessage::try_from(bytes).expect("NetMessage::try_from returned Err");
+        assert_eq!(decoded, message);
+    }
+}
+
+#[test]
+fn test_roundtrip_with_topic() {
+    let mut message = test_message(Channel::Gossip);
+    message.set_topic(committee_topic("key"));
+    let bytes: Vec<u8> = message.clone().into();
+
+    let decoded = NetMessage::try_from(bytes).expect("NetMessage::try_from returned Err");
+    assert_eq!(decoded.get_metadata().get_topic(), Some(&String::from("committee-key")));
+    assert_eq!(decoded, message);
+}
+
+#[test]
+fn test_binary_payload_is_not_inflated() {
+    let payload = vec![200u8; 1000];
+    let message = NetMessage::new(
+        String::from("instance"),
+        NetMessageMetadata::new(Channel::Gossip),
+        payload.clone(),
+    );
+    let bytes: Vec<u8> = message.into();
+    assert!(bytes.len() < payload.len() + 32);
+}
+
+#[test]
+fn test_malformed_messages_are_rejected() {
+    let bytes: Vec<u8> = test_message(Channel::Gossip).into();
+
+    // empty and truncated input
+    assert!(NetMessage::try_from(Vec::new()).is_err());
+    assert!(NetMessage::try_from(bytes[..3].to_vec()).is_err());
+    assert!(NetMessage::try_from(bytes[..bytes.len() - 1].to_vec()).is_err());
+
+    // unknown version
+    let mut wrong_version = bytes.clone();
+    wrong_version[0] = WIRE_FORMAT_VERSION + 1;
+    assert!(NetMessage::try_from(wrong_version).is_err());
+
+    // trailing garbage
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert!(NetMessage::try_from(trailing).is_err());
+
+    // legacy JSON encoding
+    let json = serde_json::to_vec(&test_message(Channel::Gossip)).unwrap();
+    assert!(NetMessage::try_from(json).is_err());
+
+    // garbage with a valid header
+    let mut garbage = vec![WIRE_FORMAT_VERSION, 0, 0, 0, 4];
+    garbage.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+    assert!(NetMessage::try_from(garbage).is_err());
+}
+
+#[test]
+fn test_sender_roundtrip() {
+    // node ids are not limited to the range of share ids
+    let mut message = test_message(Channel::Gossip);
+    message.set_sender(Some(70000));
+    let bytes: Vec<u8> = message.clone().into();
+    let decoded = NetMessage::try_from(bytes).unwrap();
+    assert_eq!(decoded.get_metadata().get_sender(), Some(70000));
+
+    message.set_sender(None);
+    let bytes: Vec<u8> = message.clone().into();
+    let decoded = NetMessage::try_from(bytes).unwrap();
+    assert_eq!(decoded.get_metadata().get_sender(), None);
+}
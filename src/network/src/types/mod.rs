@@ -1,2 +1,7 @@
 pub mod message;
-pub mod config;
\ No newline at end of file
+#[cfg(test)]
+mod message_tests;
+pub mod config;
+pub mod peer_table;
+#[cfg(test)]
+mod peer_table_tests;
@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::types::config::NetworkPeer;
+
+/// Delay before the first redial of a peer that disconnected.
+pub const INITIAL_REDIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Upper bound on the delay between two redials of the same peer.
+pub const MAX_REDIAL_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Health of a configured peer, as seen by this node.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerStatus {
+    pub id: u32,
+    pub address: String,
+    pub connected: bool,
+    /// Last time a connection, message or ping from the peer was observed.
+    pub last_seen: Option<SystemTime>,
+    /// Most recent round-trip time measured to the peer.
+    pub latency: Option<Duration>,
+    /// Number of redials since the peer was last connected.
+    pub redial_attempts: u32,
+}
+
+struct PeerEntry {
+    status: PeerStatus,
+    next_dial: Option<Instant>,
+}
+
+/// PeerTable keeps the health of all configured peers (except the local one) and decides when a
+/// disconnected peer is dialed again. Redials back off exponentially, starting at
+/// `initial_backoff` and doubling up to `max_backoff`.
+pub struct PeerTable {
+    peers: BTreeMap<u32, PeerEntry>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl PeerTable {
+    pub fn new(
+        peers: &[NetworkPeer],
+        local_id: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        let peers = peers
+            .iter()
+            .filter(|peer| peer.id != local_id)
+            .map(|peer| {
+                let entry = PeerEntry {
+                    status: PeerStatus {
+                        id: peer.id,
+                        address: format!("{}:{}", peer.ip, peer.port),
+                        connected: false,
+                        last_seen: None,
+                        latency: None,
+                        redial_attempts: 0,
+                    },
+                    next_dial: None,
+                };
+                (peer.id, entry)
+            })
+            .collect();
+
+        Self {
+            peers,
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    pub fn contains(&self, id: u32) -> bool {
+        self.peers.contains_key(&id)
+    }
+
+    /// Adds a peer, or replaces it if its address changed, and schedules an immediate dial.
+    pub fn add_peer(&mut self, peer: &NetworkPeer, now: Instant) {
+        let entry = PeerEntry {
+            status: PeerStatus {
+                id: peer.id,
+                address: format!("{}:{}", peer.ip, peer.port),
+                connected: false,
+                last_seen: None,
+                latency: None,
+                redial_attempts: 0,
+            },
+            next_dial: Some(now),
+        };
+        self.peers.insert(peer.id, entry);
+    }
+
+    /// Stops tracking a peer. Returns whether the peer was known.
+    pub fn remove_peer(&mut self, id: u32) -> bool {
+        self.peers.remove(&id).is_some()
+    }
+
+    pub fn on_connected(&mut self, id: u32) {
+        if let Some(entry) = self.peers.get_mut(&id) {
+            entry.status.connected = true;
+            entry.status.last_seen = Some(SystemTime::now());
+            entry.status.redial_attempts = 0;
+            entry.next_dial = None;
+        }
+    }
+
+    /// Marks the peer as disconnected and schedules an immediate redial.
+    pub fn on_disconnected(&mut self, id: u32, now: Instant) {
+        if let Some(entry) = self.peers.get_mut(&id) {
+            if entry.status.connected {
+                entry.status.connected = false;
+                entry.next_dial = Some(now);
+            }
+        }
+    }
+
+    pub fn on_seen(&mut self, id: u32) {
+        if let Some(entry) = self.peers.get_mut(&id) {
+            entry.status.last_seen = Some(SystemTime::now());
+        }
+    }
+
+    pub fn on_latency(&mut self, id: u32, latency: Duration) {
+        if let Some(entry) = self.peers.get_mut(&id) {
+            entry.status.latency = Some(latency);
+            entry.status.last_seen = Some(SystemTime::now());
+        }
+    }
+
+    /// Returns the peers to dial now. Every returned peer counts as one redial attempt, and its
+    /// next redial is scheduled after the backoff, in case the attempt fails.
+    pub fn due_for_redial(&mut self, now: Instant) -> Vec<u32> {
+        let mut due = Vec::new();
+        for (id, entry) in self.peers.iter_mut() {
+            if entry.status.connected {
+                continue;
+            }
+            match entry.next_dial {
+                Some(next_dial) if next_dial <= now => {}
+                _ => continue,
+            }
+
+            entry.status.redial_attempts += 1;
+            entry.next_dial = Some(now + self.backoff(entry.status.redial_attempts));
+            due.push(*id);
+        }
+        due
+    }
+
+    /// Schedules the first dial of every peer that is neither connected nor scheduled yet, e.g.,
+    /// peers that could not be reached at startup.
+    pub fn schedule_unconnected(&mut self, now: Instant) {
+        for entry in self.peers.values_mut() {
+            if !entry.status.connected && entry.next_dial.is_none() {
+                entry.next_dial = Some(now + self.initial_backoff);
+            }
+        }
+    }
+
+    fn backoff(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    pub fn status(&self) -> Vec<PeerStatus> {
+        self.peers.values().map(|entry| entry.status.clone()).collect()
+    }
+}
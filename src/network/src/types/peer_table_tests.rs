@@ -0,0 +1,112 @@
+use std::time::{Duration, Instant};
+
+use super::config::NetworkPeer;
+use super::peer_table::PeerTable;
+
+const INITIAL: Duration = Duration::from_secs(1);
+const MAX: Duration = Duration::from_secs(8);
+
+fn peers() -> Vec<NetworkPeer> {
+    (1..=3)
+        .map(|id| NetworkPeer {
+            id,
+            ip: String::from("127.0.0.1"),
+            port: 8000 + id as u16,
+            identity: None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_local_peer_is_excluded() {
+    let table = PeerTable::new(&peers(), 1, INITIAL, MAX);
+    let ids: Vec<u32> = table.status().iter().map(|s| s.id).collect();
+    assert_eq!(ids, vec![2, 3]);
+    assert!(table.status().iter().all(|s| !s.connected));
+}
+
+#[test]
+fn test_redial_backs_off_exponentially() {
+    let mut table = PeerTable::new(&peers(), 1, INITIAL, MAX);
+    let start = Instant::now();
+    table.on_connected(2);
+    table.on_disconnected(2, start);
+
+    // the first redial happens right away, the following ones after 1, 2, 4, 8, 8 seconds
+    let mut now = start;
+    for delay in [0, 1, 2, 4, 8, 8] {
+        now += Duration::from_secs(delay);
+        if delay > 0 {
+            assert!(table.due_for_redial(now - Duration::from_millis(1)).is_empty());
+        }
+        assert_eq!(table.due_for_redial(now), vec![2]);
+    }
+    assert_eq!(table.status()[0].redial_attempts, 6);
+
+    table.on_connected(2);
+    let status = &table.status()[0];
+    assert!(status.connected);
+    assert_eq!(status.redial_attempts, 0);
+    assert!(status.last_seen.is_some());
+    assert!(table.due_for_redial(now + MAX).is_empty());
+}
+
+#[test]
+fn test_unconnected_peers_are_scheduled() {
+    let mut table = PeerTable::new(&peers(), 1, INITIAL, MAX);
+    let now = Instant::now();
+    table.on_connected(3);
+
+    // peers are only dialed by the table once they are scheduled
+    assert!(table.due_for_redial(now + MAX).is_empty());
+    table.schedule_unconnected(now);
+    assert!(table.due_for_redial(now).is_empty());
+    assert_eq!(table.due_for_redial(now + INITIAL), vec![2]);
+}
+
+#[test]
+fn test_latency() {
+    let mut table = PeerTable::new(&peers(), 1, INITIAL, MAX);
+    table.on_latency(3, Duration::from_millis(12));
+    table.on_latency(7, Duration::from_millis(12));
+
+    let status = table.status();
+    assert_eq!(status[1].latency, Some(Duration::from_millis(12)));
+    assert!(status[1].last_seen.is_some());
+    assert_eq!(status[0].latency, None);
+}
+
+#[test]
+fn test_add_and_remove_peers() {
+    let mut table = PeerTable::new(&peers(), 1, INITIAL, MAX);
+    let now = Instant::now();
+    table.on_connected(2);
+
+    let new_peer = NetworkPeer {
+        id: 4,
+        ip: String::from("127.0.0.1"),
+        port: 8004,
+        identity: None,
+    };
+    table.add_peer(&new_peer, now);
+    assert!(table.remove_peer(3));
+    assert!(!table.remove_peer(3));
+
+    let ids: Vec<u32> = table.status().iter().map(|s| s.id).collect();
+    assert_eq!(ids, vec![2, 4]);
+    // added peers are dialed right away
+    assert_eq!(table.due_for_redial(now), vec![4]);
+
+    // a peer with a new address starts over as disconnected
+    let moved_peer = NetworkPeer {
+        id: 2,
+        ip: String::from("127.0.0.2"),
+        port: 8002,
+        identity: None,
+    };
+    table.add_peer(&moved_peer, now);
+    let status = &table.status()[0];
+    assert!(!status.connected);
+    assert_eq!(status.address, "127.0.0.2:8002");
+    assert_eq!(table.due_for_redial(now), vec![2]);
+}
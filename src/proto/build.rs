@@ -4,7 +4,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     .out_dir("./src")
     .type_attribute("ThresholdScheme", "#[derive(serde::Serialize, serde::Deserialize)]")
     .type_attribute("Group", "#[derive(serde::Serialize, serde::Deserialize)]")
+    .type_attribute("KeyStatus", "#[derive(serde::Serialize, serde::Deserialize)]")
     .protoc_arg("--experimental_allow_proto3_optional")
-    .compile(&["./src/protocol_types.proto","./src/scheme_types.proto", "./src/proxy_api.proto"], &["./src"])?;
+    .compile(&["./src/protocol_types.proto","./src/scheme_types.proto", "./src/proxy_api.proto", "./src/network_types.proto"], &["./src"])?;
     Ok(())
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
 pub mod protocol_types;
 pub mod scheme_types;
-pub mod proxy_api;
\ No newline at end of file
+pub mod proxy_api;
+pub mod network_types;
\ No newline at end of file
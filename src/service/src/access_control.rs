@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use theta_proto::scheme_types::ThresholdOperation;
+use tonic::{Request, Status};
+
+/// Wildcard that grants access to every key.
+pub const ANY_KEY: &str = "*";
+
+/// Operations a client can be authorized for.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Operation {
+    Decrypt,
+    Sign,
+    Coin,
+    /// Key management. Listing keys requires admin rights for all keys ("*").
+    Admin,
+    /// Reading the state of the node, i.e., its metrics, peers and the reputation of the peers.
+    /// Requires rights for all keys ("*").
+    Monitor,
+}
+
+impl From<ThresholdOperation> for Operation {
+    fn from(operation: ThresholdOperation) -> Self {
+        match operation {
+            ThresholdOperation::Encryption => Operation::Decrypt,
+            ThresholdOperation::Signature => Operation::Sign,
+            ThresholdOperation::Coin => Operation::Coin,
+        }
+    }
+}
+
+/// Access rights of a single client. A client is identified by the SHA-256 fingerprint of the
+/// certificate it presents during the mutual TLS handshake.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientPolicy {
+    /// Human readable name, only used for logging.
+    pub name: String,
+    /// Hex encoded SHA-256 fingerprint of the client's DER encoded certificate.
+    pub cert_fingerprint: String,
+    /// Operations the client may request.
+    pub operations: Vec<Operation>,
+    /// Key ids the client may use. The entry "*" grants access to all keys.
+    pub key_ids: Vec<String>,
+}
+
+impl ClientPolicy {
+    fn allows(&self, operation: Operation, key_id: &str) -> bool {
+        self.operations.contains(&operation)
+            && self.key_ids.iter().any(|k| k == ANY_KEY || k == key_id)
+    }
+}
+
+/// Client identity policy of the RPC endpoint. Every client not listed in the policy is denied.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AccessPolicy {
+    pub clients: Vec<ClientPolicy>,
+}
+
+impl AccessPolicy {
+    /// Read an access policy from a JSON encoding on disk.
+    pub fn from_file(file: &PathBuf) -> Result<AccessPolicy, String> {
+        let data = match fs::read_to_string(file) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Error reading access policy file: {}", e)),
+        };
+
+        match AccessPolicy::from_json(&data) {
+            Ok(policy) => Ok(policy),
+            Err(e) => Err(format!("Error parsing access policy file: {}", e)),
+        }
+    }
+
+    /// Build an access policy based on a JSON serialization.
+    pub fn from_json(data: &str) -> Result<AccessPolicy, String> {
+        let mut policy: AccessPolicy = match serde_json::from_str(data) {
+            Ok(policy) => policy,
+            Err(e) => return Err(format!("Invalid JSON: {}", e)),
+        };
+
+        for client in policy.clients.iter_mut() {
+            client.cert_fingerprint = normalize_fingerprint(&client.cert_fingerprint);
+            if hex::decode(&client.cert_fingerprint).map_or(true, |f| f.len() != 32) {
+                return Err(format!(
+                    "Invalid certificate fingerprint for client {}",
+                    client.name
+                ));
+            }
+        }
+
+        Ok(policy)
+    }
+
+    /// Checks whether the client with the given identity may perform `operation` with the key
+    /// `key_id`. Returns `Status::permission_denied` otherwise.
+    pub fn authorize(
+        &self,
+        identity: Option<&str>,
+        operation: Operation,
+        key_id: &str,
+    ) -> Result<(), Status> {
+        let identity = match identity {
+            Some(identity) => identity,
+            None => {
+                warn!("Rejected {:?} request without client certificate", operation);
+                return Err(Status::permission_denied(
+                    "A client certificate is required",
+                ));
+            }
+        };
+
+        let client = self
+            .clients
+            .iter()
+            .find(|c| c.cert_fingerprint == identity);
+
+        match client {
+            Some(client) if client.allows(operation, key_id) => Ok(()),
+            Some(client) => {
+                warn!(
+                    "Client {} is not allowed to {:?} with key {}",
+                    client.name, operation, key_id
+                );
+                Err(Status::permission_denied(format!(
+                    "Client is not allowed to {:?} with key {}",
+                    operation, key_id
+                )))
+            }
+            None => {
+                warn!("Rejected {:?} request from unknown client {}", operation, identity);
+                Err(Status::permission_denied("Unknown client"))
+            }
+        }
+    }
+}
+
+/// Returns the identity of the client that sent `request`, i.e., the hex encoded SHA-256
+/// fingerprint of the first certificate it presented. Returns None if the connection is not
+/// authenticated with a client certificate.
+pub fn client_identity<T>(request: &Request<T>) -> Option<String> {
+    let certs = request.peer_certs()?;
+    let cert = certs.first()?;
+    Some(hex::encode(Sha256::digest(cert.get_ref())))
+}
+
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint.replace(':', "").to_lowercase()
+}
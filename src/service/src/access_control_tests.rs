@@ -0,0 +1,193 @@
+use std::fs;
+
+use theta_proto::scheme_types::ThresholdOperation;
+use tonic::Code;
+
+use crate::access_control::{AccessPolicy, Operation};
+
+const ALICE: &str = "0000000000000000000000000000000000000000000000000000000000000a11";
+const BOB: &str = "0000000000000000000000000000000000000000000000000000000000000b0b";
+
+fn policy() -> AccessPolicy {
+    let data = format!(
+        r#"{{
+            "clients": [
+                {{
+                    "name": "alice",
+                    "cert_fingerprint": "{}",
+                    "operations": ["decrypt", "sign"],
+                    "key_ids": ["key-1"]
+                }},
+                {{
+                    "name": "bob",
+                    "cert_fingerprint": "{}",
+                    "operations": ["coin", "admin", "monitor"],
+                    "key_ids": ["*"]
+                }}
+            ]
+        }}"#,
+        ALICE, BOB
+    );
+    AccessPolicy::from_json(&data).unwrap()
+}
+
+fn client_json(fingerprint: &str) -> String {
+    format!(
+        r#"{{ "clients": [{{ "name": "carol", "cert_fingerprint": "{}", "operations": ["sign"], "key_ids": ["*"] }}] }}"#,
+        fingerprint
+    )
+}
+
+#[test]
+fn test_parse_policy() {
+    let policy = policy();
+    assert_eq!(policy.clients.len(), 2);
+    assert_eq!(policy.clients[0].name, "alice");
+    assert_eq!(
+        policy.clients[0].operations,
+        vec![Operation::Decrypt, Operation::Sign]
+    );
+    assert_eq!(policy.clients[1].key_ids, vec![String::from("*")]);
+}
+
+#[test]
+fn test_parse_normalizes_fingerprint() {
+    // fingerprints as printed by openssl, i.e., upper case and colon separated
+    let fingerprint = ALICE
+        .to_uppercase()
+        .as_bytes()
+        .chunks(2)
+        .map(|c| std::str::from_utf8(c).unwrap())
+        .collect::<Vec<&str>>()
+        .join(":");
+    let policy = AccessPolicy::from_json(&client_json(&fingerprint)).unwrap();
+    assert_eq!(policy.clients[0].cert_fingerprint, ALICE);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(AccessPolicy::from_json("{")
+        .unwrap_err()
+        .starts_with("Invalid JSON"));
+
+    // unknown operation
+    let data = client_json(ALICE).replace("sign", "encrypt");
+    assert!(AccessPolicy::from_json(&data)
+        .unwrap_err()
+        .starts_with("Invalid JSON"));
+
+    for fingerprint in ["not-hex", "0a11"] {
+        assert_eq!(
+            AccessPolicy::from_json(&client_json(fingerprint)).unwrap_err(),
+            "Invalid certificate fingerprint for client carol"
+        );
+    }
+}
+
+#[test]
+fn test_from_file() {
+    let path = std::env::temp_dir().join(format!(
+        "thetacrypt-access-policy-{}.json",
+        std::process::id()
+    ));
+    fs::write(&path, client_json(ALICE)).unwrap();
+    let policy = AccessPolicy::from_file(&path).unwrap();
+    assert_eq!(policy.clients[0].name, "carol");
+
+    fs::write(&path, "{").unwrap();
+    assert!(AccessPolicy::from_file(&path)
+        .unwrap_err()
+        .starts_with("Error parsing access policy file"));
+    fs::remove_file(&path).unwrap();
+
+    assert!(AccessPolicy::from_file(&path)
+        .unwrap_err()
+        .starts_with("Error reading access policy file"));
+}
+
+#[test]
+fn test_authorize_listed_key() {
+    let policy = policy();
+    assert!(policy
+        .authorize(Some(ALICE), Operation::Decrypt, "key-1")
+        .is_ok());
+    assert!(policy
+        .authorize(Some(ALICE), Operation::Sign, "key-1")
+        .is_ok());
+
+    let status = policy
+        .authorize(Some(ALICE), Operation::Sign, "key-2")
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(
+        status.message(),
+        "Client is not allowed to Sign with key key-2"
+    );
+}
+
+#[test]
+fn test_authorize_wildcard() {
+    let policy = policy();
+    for key_id in ["key-1", "key-2", "*"] {
+        assert!(policy.authorize(Some(BOB), Operation::Coin, key_id).is_ok());
+        assert!(policy
+            .authorize(Some(BOB), Operation::Admin, key_id)
+            .is_ok());
+        assert!(policy
+            .authorize(Some(BOB), Operation::Monitor, key_id)
+            .is_ok());
+    }
+    // the wildcard grants all keys, but not all operations
+    assert!(policy
+        .authorize(Some(BOB), Operation::Decrypt, "key-1")
+        .is_err());
+}
+
+#[test]
+fn test_operation_of_scheme() {
+    assert_eq!(
+        Operation::from(ThresholdOperation::Encryption),
+        Operation::Decrypt
+    );
+    assert_eq!(
+        Operation::from(ThresholdOperation::Signature),
+        Operation::Sign
+    );
+    assert_eq!(Operation::from(ThresholdOperation::Coin), Operation::Coin);
+}
+
+#[test]
+fn test_authorize_denied_operation() {
+    let policy = policy();
+    let status = policy
+        .authorize(Some(ALICE), Operation::Admin, "key-1")
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert!(policy
+        .authorize(Some(ALICE), Operation::Coin, "key-1")
+        .is_err());
+    assert!(policy
+        .authorize(Some(ALICE), Operation::Monitor, "*")
+        .is_err());
+}
+
+#[test]
+fn test_authorize_unknown_client() {
+    let policy = policy();
+    let unknown = "0000000000000000000000000000000000000000000000000000000000000bad";
+    let status = policy
+        .authorize(Some(unknown), Operation::Coin, "key-1")
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(status.message(), "Unknown client");
+}
+
+#[test]
+fn test_authorize_missing_identity() {
+    let policy = policy();
+    let status = policy
+        .authorize(None, Operation::Coin, "key-1")
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(status.message(), "A client certificate is required");
+}
@@ -1 +1,9 @@
+pub mod access_control;
+pub mod membership;
 pub mod rpc_request_handler;
+pub mod tls;
+
+#[cfg(test)]
+mod access_control_tests;
+#[cfg(test)]
+mod rpc_request_handler_tests;
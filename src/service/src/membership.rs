@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use log::{info, warn};
+use theta_network::network_manager::network_manager::NetworkCommand;
+use theta_network::types::config::{NetworkPeer, NetworkProxy};
+use theta_orchestration::key_manager::key_manager::KeyManagerCommand;
+use theta_schemes::keys::key_store::KeyEntry;
+use tokio::sync::{mpsc::Sender, oneshot};
+
+/// Checks that `peers` fit the committees of the keys in the keystore: every peer must hold a
+/// share of at least one key, and every key must keep at least `threshold` of its share holders.
+/// Share holders that are missing are only reported, as a committee may shrink on purpose.
+pub fn validate_committee(peers: &[NetworkPeer], keys: &[Arc<KeyEntry>]) -> Result<(), String> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    for peer in peers {
+        let member = keys
+            .iter()
+            .any(|key| peer.id >= 1 && peer.id <= key.pk.get_n() as u32);
+        if !member {
+            return Err(format!(
+                "Peer {} does not hold a share of any key in the keystore",
+                peer.id
+            ));
+        }
+    }
+
+    for key in keys {
+        let n = key.pk.get_n() as u32;
+        let threshold = key.pk.get_threshold() as usize;
+        let members = peers.iter().filter(|peer| peer.id <= n).count();
+        if members < threshold {
+            return Err(format!(
+                "Key {} needs {} of its {} share holders, but only {} are configured",
+                key.id, threshold, n, members
+            ));
+        }
+        if members < n as usize {
+            warn!(
+                "Only {} of the {} share holders of key {} are configured",
+                members, n, key.id
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Validates `peers` against the keystore and hands them to the network manager. If `proxy` is
+/// `None`, the current proxy is kept.
+pub async fn update_peers(
+    key_manager_command_sender: &Sender<KeyManagerCommand>,
+    network_command_sender: &Sender<NetworkCommand>,
+    peers: Vec<NetworkPeer>,
+    proxy: Option<NetworkProxy>,
+) -> Result<(), String> {
+    let (key_sender, key_receiver) = oneshot::channel::<Vec<Arc<KeyEntry>>>();
+    key_manager_command_sender
+        .send(KeyManagerCommand::ListKeys {
+            responder: key_sender,
+        })
+        .await
+        .map_err(|_| "Could not contact key manager".to_string())?;
+    let keys = key_receiver
+        .await
+        .map_err(|_| "Got no response from key manager".to_string())?;
+
+    validate_committee(&peers, &keys)?;
+
+    let peer_count = peers.len();
+    let (network_sender, network_receiver) = oneshot::channel::<Result<(), String>>();
+    network_command_sender
+        .send(NetworkCommand::UpdatePeers {
+            peers,
+            proxy,
+            responder: network_sender,
+        })
+        .await
+        .map_err(|_| "Could not contact network manager".to_string())?;
+    network_receiver
+        .await
+        .map_err(|_| "Got no response from network manager".to_string())??;
+
+    info!("Updated the peers of this node, now {} peers are configured", peer_count);
+    Ok(())
+}
@@ -8,12 +8,29 @@ use theta_orchestration::instance_manager::instance_manager::{
     InstanceManagerCommand, InstanceStatus, StartInstanceRequest,
 };
 use theta_orchestration::key_manager::key_manager::KeyManagerCommand;
+use theta_orchestration::key_manager::nonce_pool::NoncePoolStatus;
+use theta_network::network_manager::network_manager::NetworkCommand;
+use theta_network::types::config::{NetworkPeer, NetworkProxy};
+use theta_network::types::peer_table::PeerStatus;
+use theta_schemes::keys::key_store::{KeyEntry, KeyMetadata};
+use theta_schemes::keys::keys::PrivateKeyShare;
+use theta_schemes::scheme_types_impl::SchemeDetails;
+use theta_orchestration::instance_manager::admission::AdmissionMetrics;
+use theta_orchestration::instance_manager::reputation::PeerReputation;
+use theta_orchestration::instance_manager::share::NodeShare;
 use theta_proto::protocol_types::{
-    CoinRequest, CoinResponse, KeyRequest, KeyResponse, StatusRequest, StatusResponse,
+    BlameEntry, CoinRequest, CoinResponse, DeleteKeyRequest, DeleteKeyResponse,
+    ExportPublicKeyRequest, ExportPublicKeyResponse, FrostPoolCount, FrostPoolKeyStatus,
+    FrostPoolStatusRequest, FrostPoolStatusResponse, ImportKeyRequest, ImportKeyResponse,
+    InstanceMetricsRequest, InstanceMetricsResponse, KeyInfo, KeyRequest, KeyResponse,
+    ListKeysRequest, ListKeysResponse, NetworkStatusRequest, NetworkStatusResponse, PeerHealth,
+    ReputationRequest, ReputationResponse,
+    SetDefaultKeyRequest, SetDefaultKeyResponse, SetKeyMetadataRequest, SetKeyMetadataResponse,
+    SetKeyStatusRequest, SetKeyStatusResponse, ShareResponse, StatusRequest, StatusResponse, UpdatePeersRequest, UpdatePeersResponse,
 };
-use theta_proto::scheme_types::{Group, PublicKeyEntry};
+use theta_proto::scheme_types::{Group, KeyStatus, PublicKeyEntry};
 use tokio::sync::{oneshot, Notify};
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{transport::{Server, ServerTlsConfig}, Request, Response, Status};
 
 use log::{self, debug, error, info, warn};
 
@@ -21,15 +38,21 @@ use theta_proto::protocol_types::{
     threshold_crypto_library_server::{ThresholdCryptoLibrary, ThresholdCryptoLibraryServer},
     DecryptRequest, DecryptResponse, SignRequest, SignResponse,
 };
-use theta_schemes::interface::{Ciphertext, SchemeError, Serializable, ThresholdScheme};
+use theta_schemes::interface::{Ciphertext, Serializable, ThresholdScheme};
 
 use theta_events::event::Event;
+use theta_protocols::interface::ProtocolError;
+
+use crate::access_control::{client_identity, AccessPolicy, Operation, ANY_KEY};
+use crate::membership;
 
 #[derive(Clone)]
 pub struct RpcRequestHandler {
     key_manager_command_sender: tokio::sync::mpsc::Sender<KeyManagerCommand>,
     instance_manager_command_sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
+    network_command_sender: tokio::sync::mpsc::Sender<NetworkCommand>,
     event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+    access_policy: Option<Arc<AccessPolicy>>,
 }
 
 #[tonic::async_trait]
@@ -62,11 +85,14 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
 
         println!("User wants to use key {}", ciphertext.get_key_id());
 
+        self.authorize(&request, Operation::Decrypt, ciphertext.get_key_id())?;
+
         let (response_sender, response_receiver) =
-            oneshot::channel::<Result<String, SchemeError>>();
+            oneshot::channel::<Result<String, ProtocolError>>();
         self.instance_manager_command_sender
             .send(InstanceManagerCommand::CreateInstance {
                 request: StartInstanceRequest::Decryption { ciphertext },
+                client_id: client_identity(&request),
                 responder: response_sender,
             })
             .await
@@ -91,7 +117,7 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
                 "Error creating instance: {}",
                 result.as_ref().unwrap_err().to_string()
             );
-            return Err(Status::aborted(result.unwrap_err().to_string()));
+            return Err(instance_creation_status(result.unwrap_err()));
         }
 
         Ok(Response::new(DecryptResponse {
@@ -120,8 +146,14 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
         }
         let group = group.unwrap();
 
+        let key_id = self.canonical_key_id(&req.key_id).await?;
+        if self.access_policy.is_some() {
+            let key_id = self.resolve_key_id(&key_id, scheme, group).await?;
+            self.authorize(&request, Operation::Sign, &key_id)?;
+        }
+
         let (response_sender, response_receiver) =
-            oneshot::channel::<Result<String, SchemeError>>();
+            oneshot::channel::<Result<String, ProtocolError>>();
         if let Err(e) = self.instance_manager_command_sender
             .send(InstanceManagerCommand::CreateInstance {
                 request: StartInstanceRequest::Signature {
@@ -129,8 +161,9 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
                     label: req.label.clone(),
                     group,
                     scheme,
-                    key_id: req.key_id.clone(),
+                    key_id,
                 },
+                client_id: client_identity(&request),
                 responder: response_sender,
             })
             .await{
@@ -151,7 +184,7 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
                         "Error creating instance: {}",
                         result.as_ref().unwrap_err().to_string()
                     );
-                    return Err(Status::aborted(result.unwrap_err().to_string()));
+                    return Err(instance_creation_status(result.unwrap_err()));
                 }
                 debug!("Instance for id {}, created", result.clone().unwrap());
                 Ok(Response::new(SignResponse {
@@ -193,16 +226,23 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
         }
         let group = group.unwrap();
 
+        let key_id = self.canonical_key_id(&req.key_id).await?;
+        if self.access_policy.is_some() {
+            let key_id = self.resolve_key_id(&key_id, scheme, group).await?;
+            self.authorize(&request, Operation::Coin, &key_id)?;
+        }
+
         let (response_sender, response_receiver) =
-            oneshot::channel::<Result<String, SchemeError>>();
+            oneshot::channel::<Result<String, ProtocolError>>();
         self.instance_manager_command_sender
             .send(InstanceManagerCommand::CreateInstance {
                 request: StartInstanceRequest::Coin {
                     name: req.name.clone(),
                     scheme,
                     group,
-                    key_id: req.key_id.clone(),
+                    key_id,
                 },
+                client_id: client_identity(&request),
                 responder: response_sender,
             })
             .await
@@ -217,7 +257,7 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
                 "Error creating instance: {}",
                 result.as_ref().unwrap_err().to_string()
             );
-            return Err(Status::aborted(result.unwrap_err().to_string()));
+            return Err(instance_creation_status(result.unwrap_err()));
         }
 
         Ok(Response::new(CoinResponse {
@@ -225,6 +265,90 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
         }))
     }
 
+    async fn get_decryption_share(
+        &self,
+        request: Request<DecryptRequest>,
+    ) -> Result<Response<ShareResponse>, Status> {
+        info!("Received a decryption share request.");
+
+        let ciphertext = match Ciphertext::from_bytes(&request.get_ref().ciphertext) {
+            Ok(ctxt) => ctxt,
+            Err(e) => {
+                error!("Invalid ciphertext: {}", e);
+                return Err(Status::invalid_argument("Invalid ciphertext"));
+            }
+        };
+
+        self.authorize(&request, Operation::Decrypt, ciphertext.get_key_id())?;
+
+        self.create_share(
+            StartInstanceRequest::Decryption { ciphertext },
+            client_identity(&request),
+        )
+        .await
+    }
+
+    async fn get_signature_share(
+        &self,
+        request: Request<SignRequest>,
+    ) -> Result<Response<ShareResponse>, Status> {
+        info!("Received a signature share request.");
+        let req: &SignRequest = request.get_ref();
+
+        let scheme = ThresholdScheme::from_i32(req.scheme)
+            .ok_or_else(|| Status::invalid_argument("Invalid scheme"))?;
+        let group = Group::from_i32(req.group)
+            .ok_or_else(|| Status::invalid_argument("Invalid group"))?;
+
+        let key_id = self.canonical_key_id(&req.key_id).await?;
+        if self.access_policy.is_some() {
+            let key_id = self.resolve_key_id(&key_id, scheme, group).await?;
+            self.authorize(&request, Operation::Sign, &key_id)?;
+        }
+
+        self.create_share(
+            StartInstanceRequest::Signature {
+                message: req.message.clone(),
+                label: req.label.clone(),
+                scheme,
+                group,
+                key_id,
+            },
+            client_identity(&request),
+        )
+        .await
+    }
+
+    async fn get_coin_share(
+        &self,
+        request: Request<CoinRequest>,
+    ) -> Result<Response<ShareResponse>, Status> {
+        info!("Received a coin share request.");
+        let req: &CoinRequest = request.get_ref();
+
+        let scheme = ThresholdScheme::from_i32(req.scheme)
+            .ok_or_else(|| Status::invalid_argument("Invalid scheme"))?;
+        let group = Group::from_i32(req.group)
+            .ok_or_else(|| Status::invalid_argument("Invalid group"))?;
+
+        let key_id = self.canonical_key_id(&req.key_id).await?;
+        if self.access_policy.is_some() {
+            let key_id = self.resolve_key_id(&key_id, scheme, group).await?;
+            self.authorize(&request, Operation::Coin, &key_id)?;
+        }
+
+        self.create_share(
+            StartInstanceRequest::Coin {
+                name: req.name.clone(),
+                scheme,
+                group,
+                key_id,
+            },
+            client_identity(&request),
+        )
+        .await
+    }
+
     async fn get_public_keys(
         &self,
         _request: Request<KeyRequest>,
@@ -279,61 +403,461 @@ impl ThresholdCryptoLibrary for RpcRequestHandler {
         }
 
         let status = status.unwrap();
+        // Instance ids are derived from the request, so anyone could ask for the result of an
+        // instance. Only clients that may run the instance get to see it.
+        self.authorize(&request, status.scheme.get_operation().into(), &status.key_id)?;
 
-        let result = match status.result {
-            Some(r) => Some(r.unwrap()),
-            None => None,
+        // A failed instance is finished, but has no result.
+        let (result, error) = match status.result {
+            Some(Ok(r)) => (Some(r), None),
+            Some(Err(e)) => (None, Some(e.to_string())),
+            None => (None, None),
         };
 
+        let blame = status
+            .blame
+            .iter()
+            .map(|m| BlameEntry {
+                share_id: m.share_id as u32,
+                reason: format!("{:?}", m.kind),
+                sender_id: m.sender,
+            })
+            .collect();
+
         let response = StatusResponse {
             instance_id: req.instance_id.clone(),
             scheme: status.scheme.into(),
             group: status.group.into(),
             is_finished: status.finished,
             result,
-            key_id: None,
+            key_id: Some(status.key_id),
+            blame,
+            error,
+        };
+        Ok(Response::new(response))
+    }
+
+    async fn get_peer_reputation(
+        &self,
+        request: Request<ReputationRequest>,
+    ) -> Result<Response<ReputationResponse>, Status> {
+        debug!("Received a get_peer_reputation request.");
+        self.authorize(&request, Operation::Monitor, ANY_KEY)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<PeerReputation>>();
+        let cmd = InstanceManagerCommand::GetPeerReputation {
+            responder: response_sender,
+        };
+        self.instance_manager_command_sender
+            .send(cmd)
+            .await
+            .expect("Receiver for state_command_sender closed.");
+        let peers = response_receiver
+            .await
+            .expect("response_receiver.await returned Err");
+
+        let peers = peers
+            .into_iter()
+            .map(|p| theta_proto::protocol_types::PeerReputation {
+                peer_id: p.peer_id,
+                invalid_shares: p.invalid_shares,
+                duplicate_shares: p.duplicate_shares,
+                last_instance_id: p.last_instance_id,
+                equivocations: p.equivocations,
+            })
+            .collect();
+
+        Ok(Response::new(ReputationResponse { peers }))
+    }
+
+    async fn get_instance_metrics(
+        &self,
+        request: Request<InstanceMetricsRequest>,
+    ) -> Result<Response<InstanceMetricsResponse>, Status> {
+        debug!("Received a get_instance_metrics request.");
+        self.authorize(&request, Operation::Monitor, ANY_KEY)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<AdmissionMetrics>();
+        let cmd = InstanceManagerCommand::GetAdmissionMetrics {
+            responder: response_sender,
+        };
+        self.instance_manager_command_sender
+            .send(cmd)
+            .await
+            .expect("Receiver for state_command_sender closed.");
+        let metrics = response_receiver
+            .await
+            .expect("response_receiver.await returned Err");
+
+        Ok(Response::new(InstanceMetricsResponse {
+            running_instances: metrics.running_instances as u64,
+            queued_requests: metrics.queued_requests as u64,
+            admitted_requests: metrics.admitted_requests,
+            rejected_requests: metrics.rejected_requests,
+            max_running_instances: metrics.max_running_instances.map(|m| m as u64),
+            max_queued_requests: metrics.max_queued_requests as u64,
+        }))
+    }
+
+    async fn get_frost_pool_status(
+        &self,
+        request: Request<FrostPoolStatusRequest>,
+    ) -> Result<Response<FrostPoolStatusResponse>, Status> {
+        debug!("Received a get_frost_pool_status request.");
+        self.authorize(&request, Operation::Monitor, ANY_KEY)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<NoncePoolStatus>>();
+        self.send_key_manager_command(KeyManagerCommand::GetFrostPoolStatus {
+            responder: response_sender,
+        })
+        .await?;
+        let pools = response_receiver
+            .await
+            .map_err(|_| Status::internal("Got no response from key manager"))?;
+
+        let counts = |counts: &std::collections::BTreeMap<u16, usize>| -> Vec<FrostPoolCount> {
+            counts
+                .iter()
+                .map(|(id, available)| FrostPoolCount {
+                    party_id: *id as u32,
+                    available: *available as u64,
+                })
+                .collect()
         };
+
+        let mut response = FrostPoolStatusResponse::default();
+        for pool in pools {
+            response.target_size = pool.target_size as u64;
+            response.low_watermark = pool.low_watermark as u64;
+            response.keys.push(FrostPoolKeyStatus {
+                key_id: pool.key_id.clone(),
+                nonces: counts(&pool.nonces),
+                commitments: counts(&pool.commitments),
+            });
+        }
+
         Ok(Response::new(response))
     }
+
+    async fn get_network_status(
+        &self,
+        request: Request<NetworkStatusRequest>,
+    ) -> Result<Response<NetworkStatusResponse>, Status> {
+        debug!("Received a get_network_status request.");
+        self.authorize(&request, Operation::Monitor, ANY_KEY)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<PeerStatus>>();
+        self.network_command_sender
+            .send(NetworkCommand::GetPeerStatus {
+                responder: response_sender,
+            })
+            .await
+            .map_err(|_| Status::internal("Could not contact network manager"))?;
+        let status = response_receiver
+            .await
+            .map_err(|_| Status::internal("Got no response from network manager"))?;
+
+        let peers = status
+            .into_iter()
+            .map(|peer| PeerHealth {
+                peer_id: peer.id,
+                address: peer.address,
+                connected: peer.connected,
+                last_seen: peer
+                    .last_seen
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs()),
+                latency_ms: peer.latency.map(|l| l.as_millis() as u64),
+                redial_attempts: peer.redial_attempts,
+            })
+            .collect();
+
+        Ok(Response::new(NetworkStatusResponse { peers }))
+    }
+
+    async fn list_keys(
+        &self,
+        request: Request<ListKeysRequest>,
+    ) -> Result<Response<ListKeysResponse>, Status> {
+        info!("Received a list_keys request.");
+        self.authorize(&request, Operation::Admin, ANY_KEY)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Vec<Arc<KeyEntry>>>();
+        self.send_key_manager_command(KeyManagerCommand::ListKeys {
+            responder: response_sender,
+        })
+        .await?;
+        let key_entries = response_receiver
+            .await
+            .map_err(|_| Status::internal("Got no response from key manager"))?;
+
+        let keys = key_entries
+            .iter()
+            .map(|entry| KeyInfo {
+                id: entry.id.clone(),
+                operation: entry.pk.get_operation().into(),
+                scheme: entry.pk.get_scheme().into(),
+                group: (*entry.pk.get_group()).into(),
+                is_default: entry.is_default(),
+                has_private_key: entry.sk.is_some(),
+                threshold: entry.pk.get_threshold() as u32,
+                n: entry.pk.get_n() as u32,
+                alias: entry.metadata.alias.clone(),
+                description: entry.metadata.description.clone(),
+                tags: entry.metadata.tags.clone(),
+                created_at: entry.metadata.created_at,
+                version: entry.metadata.version,
+                lineage: entry.metadata.lineage.clone(),
+                status: entry.status().into(),
+            })
+            .collect();
+
+        Ok(Response::new(ListKeysResponse { keys }))
+    }
+
+    async fn import_key(
+        &self,
+        request: Request<ImportKeyRequest>,
+    ) -> Result<Response<ImportKeyResponse>, Status> {
+        info!("Received an import_key request.");
+        let req: &ImportKeyRequest = request.get_ref();
+
+        let key = match PrivateKeyShare::from_pem(&req.pem) {
+            Ok(key) => key,
+            Err(e) => {
+                error!("Invalid private key share: {}", e);
+                return Err(Status::invalid_argument("Invalid private key share"));
+            }
+        };
+
+        self.authorize(&request, Operation::Admin, key.get_key_id())?;
+        let previous = match &req.previous_key_id {
+            Some(previous) => {
+                let previous = self.lookup_key_id(previous).await?;
+                self.authorize(&request, Operation::Admin, &previous)?;
+                Some(previous)
+            }
+            None => None,
+        };
+
+        let metadata = KeyMetadata {
+            alias: req.alias.clone().filter(|alias| !alias.is_empty()),
+            description: req.description.clone(),
+            tags: req.tags.clone(),
+            ..Default::default()
+        };
+
+        let (response_sender, response_receiver) = oneshot::channel::<Result<String, String>>();
+        self.send_key_manager_command(KeyManagerCommand::ImportKey {
+            key,
+            set_default: req.set_default,
+            metadata,
+            previous,
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(key_id)) => Ok(Response::new(ImportKeyResponse { key_id })),
+            Ok(Err(e)) => Err(Status::aborted(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn export_public_key(
+        &self,
+        request: Request<ExportPublicKeyRequest>,
+    ) -> Result<Response<ExportPublicKeyResponse>, Status> {
+        info!("Received an export_public_key request.");
+        let req: &ExportPublicKeyRequest = request.get_ref();
+        let entry = self.get_key_entry(&req.key_id).await?;
+        self.authorize(&request, Operation::Admin, &entry.id)?;
+
+        let (bytes, pem) = match (entry.pk.to_bytes(), entry.pk.pem()) {
+            (Ok(bytes), Ok(pem)) => (bytes, pem),
+            _ => return Err(Status::internal("Could not serialize public key")),
+        };
+
+        Ok(Response::new(ExportPublicKeyResponse {
+            key: Some(PublicKeyEntry {
+                id: entry.id.clone(),
+                operation: entry.pk.get_operation().into(),
+                scheme: entry.pk.get_scheme().into(),
+                group: (*entry.pk.get_group()).into(),
+                key: bytes,
+                version: entry.metadata.version,
+                lineage: entry.metadata.lineage.clone(),
+                status: entry.status().into(),
+            }),
+            pem,
+        }))
+    }
+
+    async fn delete_key(
+        &self,
+        request: Request<DeleteKeyRequest>,
+    ) -> Result<Response<DeleteKeyResponse>, Status> {
+        info!("Received a delete_key request.");
+        let req: &DeleteKeyRequest = request.get_ref();
+        let key_id = self.lookup_key_id(&req.key_id).await?;
+        self.authorize(&request, Operation::Admin, &key_id)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Result<(), String>>();
+        self.send_key_manager_command(KeyManagerCommand::DeleteKey {
+            id: key_id,
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(())) => Ok(Response::new(DeleteKeyResponse {})),
+            Ok(Err(e)) => Err(Status::aborted(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn set_default_key(
+        &self,
+        request: Request<SetDefaultKeyRequest>,
+    ) -> Result<Response<SetDefaultKeyResponse>, Status> {
+        info!("Received a set_default_key request.");
+        let req: &SetDefaultKeyRequest = request.get_ref();
+        let key_id = self.lookup_key_id(&req.key_id).await?;
+        self.authorize(&request, Operation::Admin, &key_id)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Result<(), String>>();
+        self.send_key_manager_command(KeyManagerCommand::SetDefaultKey {
+            id: key_id,
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(())) => Ok(Response::new(SetDefaultKeyResponse {})),
+            Ok(Err(e)) => Err(Status::aborted(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn set_key_metadata(
+        &self,
+        request: Request<SetKeyMetadataRequest>,
+    ) -> Result<Response<SetKeyMetadataResponse>, Status> {
+        info!("Received a set_key_metadata request.");
+        let req: &SetKeyMetadataRequest = request.get_ref();
+        let key_id = self.lookup_key_id(&req.key_id).await?;
+        self.authorize(&request, Operation::Admin, &key_id)?;
+
+        let metadata = KeyMetadata {
+            alias: req.alias.clone().filter(|alias| !alias.is_empty()),
+            description: req.description.clone(),
+            tags: req.tags.clone(),
+            ..Default::default()
+        };
+
+        let (response_sender, response_receiver) = oneshot::channel::<Result<(), String>>();
+        self.send_key_manager_command(KeyManagerCommand::SetKeyMetadata {
+            id: key_id,
+            metadata,
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(())) => Ok(Response::new(SetKeyMetadataResponse {})),
+            Ok(Err(e)) => Err(Status::invalid_argument(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn set_key_status(
+        &self,
+        request: Request<SetKeyStatusRequest>,
+    ) -> Result<Response<SetKeyStatusResponse>, Status> {
+        info!("Received a set_key_status request.");
+        let req: &SetKeyStatusRequest = request.get_ref();
+        let status = KeyStatus::from_i32(req.status)
+            .ok_or_else(|| Status::invalid_argument("Invalid key status"))?;
+        let key_id = self.lookup_key_id(&req.key_id).await?;
+        self.authorize(&request, Operation::Admin, &key_id)?;
+
+        let (response_sender, response_receiver) = oneshot::channel::<Result<(), String>>();
+        self.send_key_manager_command(KeyManagerCommand::SetKeyStatus {
+            id: key_id,
+            status,
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(())) => Ok(Response::new(SetKeyStatusResponse {})),
+            Ok(Err(e)) => Err(Status::aborted(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn update_peers(
+        &self,
+        request: Request<UpdatePeersRequest>,
+    ) -> Result<Response<UpdatePeersResponse>, Status> {
+        info!("Received an update_peers request.");
+        self.authorize(&request, Operation::Admin, ANY_KEY)?;
+        let req: &UpdatePeersRequest = request.get_ref();
+
+        let mut peers = Vec::new();
+        for peer in req.peers.iter() {
+            let port = u16::try_from(peer.port)
+                .map_err(|_| Status::invalid_argument(format!("Invalid port for peer {}", peer.peer_id)))?;
+            peers.push(NetworkPeer {
+                id: peer.peer_id,
+                ip: peer.ip.clone(),
+                port,
+                identity: peer.identity.clone(),
+            });
+        }
+        let proxy = match &req.proxy {
+            Some(proxy) => Some(NetworkProxy {
+                ip: proxy.ip.clone(),
+                port: u16::try_from(proxy.port)
+                    .map_err(|_| Status::invalid_argument("Invalid port for proxy"))?,
+            }),
+            None => None,
+        };
+
+        membership::update_peers(
+            &self.key_manager_command_sender,
+            &self.network_command_sender,
+            peers,
+            proxy,
+        )
+        .await
+        .map_err(Status::aborted)?;
+
+        Ok(Response::new(UpdatePeersResponse {}))
+    }
 }
 
-pub async fn init(
-    rpc_listen_address: String,
-    rpc_listen_port: u16,
-    instance_manager_command_sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
-    key_manager_command_sender: tokio::sync::mpsc::Sender<KeyManagerCommand>,
-    event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
-    shutdown_notify: Arc<Notify>
-) -> Result<(), String>{
-    // Start server
-    let rpc_addr = format!("{}:{}", rpc_listen_address, rpc_listen_port);
-    let service = RpcRequestHandler {
-        key_manager_command_sender: key_manager_command_sender,
-        instance_manager_command_sender: instance_manager_command_sender,
-        event_emitter_sender,
-    };
-
-    tokio::select! {
-        _ = shutdown_notify.notified() => {
-            info!("Shutting down RPC server.");
-            //TODO: Gracefully shut down the server
-            return Ok(());
-        },
-        // result = _start_rpc_server(rpc_addr, service) => {
-        //     match result {
-        //         Ok(_) => {
-        //             info!("RPC server shut down.");
-        //             return Ok(());
-        //         },
-        //         Err(e) => {
-        //             error!("Error at gRPC server: {}", e);
-        //             return Err(e.to_string());
-        //         }
-        //     }
-        // }
-    }
-
-    Ok(())
+/// Maps an error returned by the instance manager when creating an instance to an RPC status.
+fn instance_creation_status(error: ProtocolError) -> Status {
+    match error {
+        ProtocolError::PolicyViolation(reason) => Status::permission_denied(reason),
+        ProtocolError::ResourceExhausted => {
+            Status::resource_exhausted("Too many concurrent requests, try again later")
+        }
+        e => Status::aborted(e.to_string()),
+    }
+}
+
+fn share_status(error: ProtocolError) -> Status {
+    match error {
+        ProtocolError::PolicyViolation(reason) => Status::permission_denied(reason),
+        ProtocolError::InvalidCiphertext => Status::invalid_argument("Invalid ciphertext"),
+        ProtocolError::ResourceExhausted => {
+            Status::resource_exhausted("Too many concurrent requests, try again later")
+        }
+        ProtocolError::SchemeError(e) => Status::failed_precondition(e.to_string()),
+        e => Status::internal(e.to_string()),
+    }
 }
 
 impl RpcRequestHandler {
@@ -341,24 +865,155 @@ impl RpcRequestHandler {
     pub fn new(
         key_manager_command_sender: tokio::sync::mpsc::Sender<KeyManagerCommand>,
         instance_manager_command_sender: tokio::sync::mpsc::Sender<InstanceManagerCommand>,
+        network_command_sender: tokio::sync::mpsc::Sender<NetworkCommand>,
         event_emitter_sender: tokio::sync::mpsc::Sender<Event>,
+        access_policy: Option<AccessPolicy>,
     ) -> Self {
+        if access_policy.is_none() {
+            warn!("No access policy configured, every client may use every key and key management is disabled.");
+        }
+
         return Self {
             key_manager_command_sender,
             instance_manager_command_sender,
+            network_command_sender,
             event_emitter_sender,
+            access_policy: access_policy.map(Arc::new),
         };
     }
 
+    /// Checks the access policy for the client that sent `request`. Without a policy, every client
+    /// may use every key, but key management is denied, as it requires an authenticated client.
+    fn authorize<T>(
+        &self,
+        request: &Request<T>,
+        operation: Operation,
+        key_id: &str,
+    ) -> Result<(), Status> {
+        match &self.access_policy {
+            Some(policy) => {
+                let identity = client_identity(request);
+                policy.authorize(identity.as_deref(), operation, key_id)
+            }
+            None if operation == Operation::Admin => {
+                warn!("Rejected admin request, no access policy configured");
+                Err(Status::permission_denied(
+                    "Key management requires mutual TLS and an access policy that grants admin rights",
+                ))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the id of the key that will be used for a request, which is either the explicitly
+    /// requested key or the default key for the given scheme and group.
+    async fn resolve_key_id(
+        &self,
+        key_id: &Option<String>,
+        scheme: ThresholdScheme,
+        group: Group,
+    ) -> Result<String, Status> {
+        if let Some(id) = key_id {
+            return Ok(id.clone());
+        }
+
+        let (response_sender, response_receiver) =
+            oneshot::channel::<Result<Arc<KeyEntry>, String>>();
+        self.key_manager_command_sender
+            .send(KeyManagerCommand::GetKeyBySchemeAndGroup {
+                scheme,
+                group,
+                responder: response_sender,
+            })
+            .await
+            .map_err(|_| Status::internal("Could not contact key manager"))?;
+
+        match response_receiver.await {
+            Ok(Ok(key)) => Ok(key.id.clone()),
+            Ok(Err(e)) => Err(Status::invalid_argument(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
 
-    pub async fn run(rpc_addr: String, shutdown_notify: Arc<Notify>, service: RpcRequestHandler) -> Result<(), String>{
+    /// Replaces an alias in the key id of a request with the id of the key. Instance ids are
+    /// derived from the key id, so they must not depend on aliases, which are local to a node.
+    async fn canonical_key_id(&self, key_id: &Option<String>) -> Result<Option<String>, Status> {
+        match key_id {
+            Some(id) => Ok(Some(self.lookup_key_id(id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the id of the key with the given id or alias.
+    async fn lookup_key_id(&self, id: &str) -> Result<String, Status> {
+        Ok(self.get_key_entry(id).await?.id.clone())
+    }
+
+    async fn get_key_entry(&self, id: &str) -> Result<Arc<KeyEntry>, Status> {
+        let (response_sender, response_receiver) =
+            oneshot::channel::<Result<Arc<KeyEntry>, String>>();
+        self.send_key_manager_command(KeyManagerCommand::GetKeyById {
+            id: String::from(id),
+            responder: response_sender,
+        })
+        .await?;
+
+        match response_receiver.await {
+            Ok(Ok(entry)) => Ok(entry),
+            Ok(Err(e)) => Err(Status::not_found(e)),
+            Err(_) => Err(Status::internal("Got no response from key manager")),
+        }
+    }
+
+    async fn create_share(
+        &self,
+        request: StartInstanceRequest,
+        client_id: Option<String>,
+    ) -> Result<Response<ShareResponse>, Status> {
+        let (response_sender, response_receiver) =
+            oneshot::channel::<Result<NodeShare, ProtocolError>>();
+        self.instance_manager_command_sender
+            .send(InstanceManagerCommand::CreateShare {
+                request,
+                client_id,
+                responder: response_sender,
+            })
+            .await
+            .map_err(|_| Status::internal("Could not contact instance manager"))?;
+
+        match response_receiver.await {
+            Ok(Ok(share)) => Ok(Response::new(ShareResponse {
+                key_id: share.key_id,
+                share_id: share.share_id as u32,
+                share: share.share,
+            })),
+            Ok(Err(e)) => {
+                warn!("Error creating share: {}", e);
+                Err(share_status(e))
+            }
+            Err(_) => Err(Status::internal("Got no response from instance manager")),
+        }
+    }
+
+    async fn send_key_manager_command(&self, command: KeyManagerCommand) -> Result<(), Status> {
+        self.key_manager_command_sender
+            .send(command)
+            .await
+            .map_err(|_| Status::internal("Could not contact key manager"))
+    }
+
+    pub async fn run(rpc_addr: String, shutdown_notify: Arc<Notify>, service: RpcRequestHandler, tls_config: Option<ServerTlsConfig>) -> Result<(), String>{
         info!("Starting RPC server.");
 
-        
+        let mut builder = Server::builder();
+        if let Some(tls_config) = tls_config {
+            info!("Serving RPC endpoint over TLS.");
+            builder = builder.tls_config(tls_config).map_err(|e| e.to_string())?;
+        }
 
         // Start server
         let rpc_handle = tokio::spawn( async move {
-            Server::builder()
+            builder
             .add_service(ThresholdCryptoLibraryServer::new(service))
             .serve(rpc_addr.parse().unwrap())
             .await
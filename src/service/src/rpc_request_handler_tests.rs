@@ -0,0 +1,73 @@
+use theta_orchestration::instance_manager::instance_manager::{
+    InstanceManagerCommand, InstanceStatus,
+};
+use theta_proto::protocol_types::{
+    threshold_crypto_library_server::ThresholdCryptoLibrary, StatusRequest,
+};
+use theta_proto::scheme_types::{Group, ThresholdScheme};
+use theta_protocols::interface::ProtocolError;
+use tokio::sync::mpsc;
+use tonic::Request;
+
+use crate::rpc_request_handler::RpcRequestHandler;
+
+// A handler whose instance manager reports every instance with the given result.
+fn handler_with_result(result: Option<Result<Vec<u8>, ProtocolError>>) -> RpcRequestHandler {
+    let (instance_manager, mut commands) = mpsc::channel::<InstanceManagerCommand>(8);
+    tokio::spawn(async move {
+        while let Some(command) = commands.recv().await {
+            if let InstanceManagerCommand::GetInstanceStatus { responder, .. } = command {
+                let status = InstanceStatus {
+                    scheme: ThresholdScheme::Cks05,
+                    group: Group::Bls12381,
+                    key_id: String::from("key-1"),
+                    finished: result.is_some(),
+                    result: result.clone(),
+                    blame: Vec::new(),
+                };
+                responder.send(Some(status)).unwrap();
+            }
+        }
+    });
+
+    let (key_manager, _) = mpsc::channel(1);
+    let (network, _) = mpsc::channel(1);
+    let (events, _) = mpsc::channel(1);
+    RpcRequestHandler::new(key_manager, instance_manager, network, events, None)
+}
+
+fn status_request() -> Request<StatusRequest> {
+    Request::new(StatusRequest {
+        instance_id: String::from("instance"),
+    })
+}
+
+#[tokio::test]
+async fn test_status_of_timed_out_instance() {
+    let handler = handler_with_result(Some(Err(ProtocolError::Timeout)));
+
+    // polling a failed instance returns its error instead of a result
+    for _ in 0..2 {
+        let status = handler.get_status(status_request()).await.unwrap();
+        let status = status.get_ref();
+        assert!(status.is_finished);
+        assert_eq!(status.result, None);
+        assert_eq!(status.error.as_deref(), Some("Timeout"));
+        assert_eq!(status.key_id.as_deref(), Some("key-1"));
+    }
+}
+
+#[tokio::test]
+async fn test_status_of_finished_instance() {
+    let handler = handler_with_result(Some(Ok(vec![1])));
+    let status = handler.get_status(status_request()).await.unwrap();
+    assert!(status.get_ref().is_finished);
+    assert_eq!(status.get_ref().result, Some(vec![1]));
+    assert_eq!(status.get_ref().error, None);
+
+    let handler = handler_with_result(None);
+    let status = handler.get_status(status_request()).await.unwrap();
+    assert!(!status.get_ref().is_finished);
+    assert_eq!(status.get_ref().result, None);
+    assert_eq!(status.get_ref().error, None);
+}
@@ -0,0 +1,27 @@
+use std::fs;
+use std::path::PathBuf;
+
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+/// Builds the TLS configuration of the RPC endpoint from PEM files on disk. If `client_ca_file`
+/// is given, clients are required to authenticate with a certificate signed by that CA.
+pub fn server_tls_config(
+    cert_file: &PathBuf,
+    key_file: &PathBuf,
+    client_ca_file: Option<&PathBuf>,
+) -> Result<ServerTlsConfig, String> {
+    let cert = fs::read(cert_file)
+        .map_err(|e| format!("Error reading TLS certificate {}: {}", cert_file.display(), e))?;
+    let key = fs::read(key_file)
+        .map_err(|e| format!("Error reading TLS key {}: {}", key_file.display(), e))?;
+
+    let mut tls_config = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+
+    if let Some(ca_file) = client_ca_file {
+        let ca = fs::read(ca_file)
+            .map_err(|e| format!("Error reading client CA {}: {}", ca_file.display(), e))?;
+        tls_config = tls_config.client_ca_root(Certificate::from_pem(ca));
+    }
+
+    Ok(tls_config)
+}
@@ -1,67 +1,102 @@
 // Tokio
-use log::{error, info};
+use log::{error, info, warn};
 use theta_proto::proxy_api::proxy_api_client::ProxyApiClient;
-use std::collections::{HashSet, VecDeque};
-use std::{io, result};
-use std::io::Write;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::process::exit;
 use std::str::FromStr;
-use tokio::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
 
 use theta_proto::proxy_api::proxy_api_server::{ProxyApi, ProxyApiServer};
 use theta_proto::proxy_api::{
-    AtomicBroadcastRequest, AtomicBroadcastResponse, ForwardShareRequest, ForwardShareResponse,
+    AtomicBroadcastRequest, AtomicBroadcastResponse, DeliverBlockRequest, DeliverBlockResponse,
+    ForwardShareRequest, ForwardShareResponse, GetBlocksRequest, GetBlocksResponse,
 };
-use tonic::{transport::Server, Request, Response, Status};
+use tonic::{transport::{Channel, Server}, Request, Response, Status};
 
 use clap::Parser;
 use thetacrypt_blockchain_stub::cli::cli::P2PCli;
 use thetacrypt_blockchain_stub::cli::types::{P2PConfig, PeerP2PInfo};
+use thetacrypt_blockchain_stub::ledger::Ledger;
+
+// Delay before a failed block delivery to a node is retried.
+const DELIVERY_RETRY_INTERVAL: Duration = Duration::from_secs(1);
 
-const MAX_BLOCKCHAIN_CAPACITY: usize = 100;
 #[derive(Clone)]
 struct ThetacryptBlockchainStub {
     peers: Vec<PeerP2PInfo>,
-    broadcast_channel_sender: mpsc::Sender<(String, Vec<u8>)>,
+    ledger: Arc<Mutex<Ledger>>,
 }
 
-/// Blockchain represents the storage abstraction of a chian
-struct Blockchain {
-    chain: VecDeque<Vec<u8>>,
-    registry: HashSet<String>,
-    broadcast_channel_receiver: mpsc::Receiver<(String, Vec<u8>)>,
+/// Cuts a block from the pending requests in every interval and announces the height of the next
+/// block to the delivery tasks.
+async fn produce_blocks(ledger: Arc<Mutex<Ledger>>, interval: Duration, height_sender: watch::Sender<u64>) {
+    let mut block_timer = tokio::time::interval(interval);
+    loop {
+        block_timer.tick().await;
+        let result = ledger.lock().unwrap().cut_block();
+        match result {
+            Ok(Some(block)) => {
+                info!("Ordered block {} with {} requests", block.height, block.entries.len());
+                let _ = height_sender.send(block.height + 1);
+            }
+            Ok(None) => {}
+            Err(e) => error!("{}", e),
+        }
+    }
 }
 
-impl Blockchain {
-    pub fn new(channel_receiver: mpsc::Receiver<(String, Vec<u8>)>) -> Self {
-        return Self {
-            chain: VecDeque::new(),
-            registry: HashSet::new(),
-            broadcast_channel_receiver: channel_receiver,
+/// Delivers the blocks of the ledger to a thetacrypt node in order, starting at `next_height`.
+/// A block is only delivered after the node acknowledged the previous one, failed deliveries are
+/// retried, so every node sees the same sequence of blocks.
+async fn deliver_blocks(
+    peer: PeerP2PInfo,
+    ledger: Arc<Mutex<Ledger>>,
+    mut next_height: u64,
+    mut height_receiver: watch::Receiver<u64>,
+) {
+    let address = format!("http://{}:{}", peer.ip, peer.p2p_port);
+    let mut client: Option<ProxyApiClient<Channel>> = None;
+
+    loop {
+        let block = ledger
+            .lock()
+            .unwrap()
+            .get_block(next_height)
+            .map(|block| block.to_proto());
+        let block = match block {
+            Some(block) => block,
+            None => {
+                // wait for the next block
+                if height_receiver.changed().await.is_err() {
+                    return;
+                }
+                continue;
+            }
         };
-    }
-    pub async fn start_and_run(&mut self, config: P2PConfig) {
-        loop {
-            tokio::select! {
-                Some(incoming_block) = self.broadcast_channel_receiver.recv() => {
-                    let (id, msg) = incoming_block;
-                    if !(self.registry.contains(&id)){ //The id for now is the instance id, but this can be the same for multiple messages of the same protocol instance
-                        println!("Id of the msg {}",id);
-                        self.registry.insert(id);
-                        self.chain.push_back(msg.clone());
-                        println!("New message added to the chain. Current length: {}", self.chain.len());
-
-                        let result = forward_to_all(config.clone(), msg.as_slice()); //To review, maybe TOB case?
-
-                        if result.is_err(){
-                            error!("{}", result.err().unwrap());
-                        }
-                          
-                    }        
+
+        if client.is_none() {
+            match ProxyApiClient::connect(address.clone()).await {
+                Ok(connected) => client = Some(connected),
+                Err(e) => {
+                    warn!("Could not connect to node {} at {}: {}", peer.id, address, e);
+                    tokio::time::sleep(DELIVERY_RETRY_INTERVAL).await;
+                    continue;
                 }
             }
         }
+
+        let request = DeliverBlockRequest { block: Some(block) };
+        match client.as_mut().unwrap().deliver_block(request).await {
+            Ok(_) => next_height += 1,
+            Err(e) => {
+                warn!("Delivering block {} to node {} failed: {}", next_height, peer.id, e);
+                client = None;
+                tokio::time::sleep(DELIVERY_RETRY_INTERVAL).await;
+            }
+        }
     }
 }
 
@@ -114,6 +149,8 @@ impl ProxyApi for ThetacryptBlockchainStub {
         info!("Message received from peer...");
         let peers_config = P2PConfig {
             peers: self.peers.clone(),
+            block_interval_ms: None,
+            ledger_file: None,
         };
 
         let binding = request.into_inner();
@@ -134,15 +171,39 @@ impl ProxyApi for ThetacryptBlockchainStub {
     ) -> Result<Response<AtomicBroadcastResponse>, Status> {
         // extracting the message from the response
         let binding = request.into_inner();
-        let msg = binding.data.as_slice();
-        let id = binding.id;
 
-        //Adding the msg into to the queue (TODO: implement the solution with a second channel and a dedicated function for deliver the TOB msg)
-        if let Err(e) = self.broadcast_channel_sender.send((id, msg.to_vec())).await {
-            error!("Error occurred during send(): {}", e);
+        // The request is ordered into the next block and delivered to every node, the sender included
+        let id = binding.id.clone();
+        if !self.ledger.lock().unwrap().submit(binding.id, binding.data) {
+            info!("Ignoring repeated request of {}", id);
         }
         Ok(Response::new(AtomicBroadcastResponse {}))
     }
+
+    async fn deliver_block(
+        &self,
+        _request: Request<DeliverBlockRequest>,
+    ) -> Result<Response<DeliverBlockResponse>, Status> {
+        Err(Status::unimplemented("The blockchain stub produces blocks, it does not receive them"))
+    }
+
+    async fn get_blocks(
+        &self,
+        request: Request<GetBlocksRequest>,
+    ) -> Result<Response<GetBlocksResponse>, Status> {
+        let request = request.into_inner();
+        let ledger = self.ledger.lock().unwrap();
+        let blocks = ledger
+            .blocks_from(request.from_height, request.limit as usize)
+            .iter()
+            .map(|block| block.to_proto())
+            .collect();
+
+        Ok(Response::new(GetBlocksResponse {
+            blocks,
+            next_height: ledger.next_height(),
+        }))
+    }
 }
 
 impl ThetacryptBlockchainStub {
@@ -191,20 +252,47 @@ async fn main() -> io::Result<()> {
         }
     };
 
-    let (channel_sender, channel_receiver) = tokio::sync::mpsc::channel::<(String, Vec<u8>)>(100);
-    let mut blockchain = Blockchain::new(channel_receiver);
+    let ledger = match &config.ledger_file {
+        Some(path) => match Ledger::open(path) {
+            Ok(ledger) => {
+                println!("Replayed {} blocks from {}", ledger.next_height(), path.display());
+                ledger
+            }
+            Err(e) => {
+                error!("{}", e);
+                println!("Error {}", e);
+                exit(1);
+            }
+        },
+        None => Ledger::new(),
+    };
+    // Nodes receive the blocks produced from now on, older blocks can be fetched with get_blocks
+    let start_height = ledger.next_height();
+    let ledger = Arc::new(Mutex::new(ledger));
+    let (height_sender, height_receiver) = watch::channel(start_height);
 
     let service = ThetacryptBlockchainStub {
         peers: config.peers.clone(), //We now here pass the peers coming from the config file. TODO: remove the hard coded ones.
-        broadcast_channel_sender: channel_sender,
+        ledger: ledger.clone(),
     };
 
     //We need a separate process to handle the Blockchain
+    let block_ledger = ledger.clone();
+    let block_interval = config.block_interval();
     tokio::spawn(async move {
         println!("Blockchain is starting");
-        blockchain.start_and_run(config.clone()).await
+        produce_blocks(block_ledger, block_interval, height_sender).await
     });
 
+    for peer in config.peers.iter() {
+        let peer = peer.clone();
+        let peer_ledger = ledger.clone();
+        let peer_height_receiver = height_receiver.clone();
+        tokio::spawn(async move {
+            deliver_blocks(peer, peer_ledger, start_height, peer_height_receiver).await
+        });
+    }
+
     tokio::spawn(async move {
         println!("Server is starting");
         start_and_run(service, address).await
@@ -218,17 +306,4 @@ async fn main() -> io::Result<()> {
             Ok(())
         }
     }
-
-    // //Create the broadcast channel for communicating with the blockchain data structure
-    // let (b_sender, b_receiver) = mpsc::channel::<Vec<u8>>(32);
-
-    // Start the server
-    // Initialize the data structure for the blockchain
-    // Handle the listener so that every request is handled by modifying the state of the blockchain atomically
-    // All the messages sent to the blockchain must be totally ordered. So it doesn't matter the order in which they arrive, 
-    // but in the deliver phase (when we are triggered on the node about something decided in the blockchain 
-    // we need to ensure that every node will receive the same messages in the same order). 
-    
-    // How can we do this? We can keep a list of the connected endpoints and for each of them keeping a thread that notifies 
-    // and points to a particular position of the vector. Modifying it just if the reception of th message was ack(ed).
 }
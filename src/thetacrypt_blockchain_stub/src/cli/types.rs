@@ -17,8 +17,17 @@ pub struct PeerP2PInfo {
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct P2PConfig {
     pub peers: Vec<PeerP2PInfo>,
+    /// Interval in milliseconds in which pending requests are ordered into a block. Defaults to
+    /// DEFAULT_BLOCK_INTERVAL_MS.
+    pub block_interval_ms: Option<u64>,
+    /// Optional file the ledger is appended to and replayed from on startup. If not set, the
+    /// ledger is kept in memory only.
+    pub ledger_file: Option<PathBuf>,
 }
 
+/// Default interval in which blocks are produced.
+pub const DEFAULT_BLOCK_INTERVAL_MS: u64 = 500;
+
 impl P2PConfig {
     /// Read a server's configuration from a JSON encoding on disk.
     pub fn from_file(file: &PathBuf) -> Result<P2PConfig, String> {
@@ -40,7 +49,20 @@ impl P2PConfig {
             Err(e) => return Err(format!("Invalid JSON: {}", e)),
         };
 
-        P2PConfig::new(cfg.peers)
+        let mut config = P2PConfig::new(cfg.peers)?;
+        config.block_interval_ms = cfg.block_interval_ms;
+        config.ledger_file = cfg.ledger_file;
+
+        if config.block_interval_ms == Some(0) {
+            return Err("block_interval_ms must be positive".to_string());
+        }
+
+        Ok(config)
+    }
+
+    /// Interval in which blocks are produced.
+    pub fn block_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.block_interval_ms.unwrap_or(DEFAULT_BLOCK_INTERVAL_MS))
     }
 
     /// Initialize a new config struct. Performs a sanity check of passed values.
@@ -52,6 +74,10 @@ impl P2PConfig {
             }
         }
 
-        Ok(P2PConfig { peers })
+        Ok(P2PConfig {
+            peers,
+            block_interval_ms: None,
+            ledger_file: None,
+        })
     }
 }
\ No newline at end of file
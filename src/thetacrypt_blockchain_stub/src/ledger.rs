@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use theta_proto::proxy_api::{AtomicBroadcastRequest, Block};
+
+/// A request ordered by the ledger.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+    pub id: String,
+    pub data: Vec<u8>,
+}
+
+/// A block of requests. Heights start at 0 and increase by one per block.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerBlock {
+    pub height: u64,
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl LedgerBlock {
+    pub fn to_proto(&self) -> Block {
+        Block {
+            height: self.height,
+            entries: self
+                .entries
+                .iter()
+                .map(|entry| AtomicBroadcastRequest {
+                    id: entry.id.clone(),
+                    data: entry.data.clone(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Ledger is an append-only log of blocks, standing in for the consensus layer of a blockchain.
+/// Submitted requests are collected until the next block is cut. With a log file, every block is
+/// appended to the file (one JSON encoded block per line) before it is handed out, so the ledger
+/// is replayed after a restart.
+pub struct Ledger {
+    blocks: Vec<LedgerBlock>,
+    pending: Vec<LedgerEntry>,
+    // requests that were ordered or are pending, a request submitted twice is ordered once
+    seen: HashSet<(String, Vec<u8>)>,
+    log: Option<File>,
+}
+
+impl Ledger {
+    /// Creates an empty ledger that is kept in memory only.
+    pub fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            pending: Vec::new(),
+            seen: HashSet::new(),
+            log: None,
+        }
+    }
+
+    /// Replays the ledger from `path`, if the file exists, and appends new blocks to it. A last
+    /// line without a line break was cut short by a crash while the block was appended. That block
+    /// was never handed out, so the line is removed from the file.
+    pub fn open(path: &PathBuf) -> Result<Self, String> {
+        let mut ledger = Self::new();
+
+        if path.exists() {
+            let data = fs::read(path)
+                .map_err(|e| format!("Error reading ledger '{}': {}", path.display(), e))?;
+            let complete = match data.iter().rposition(|b| *b == b'\n') {
+                Some(i) => i + 1,
+                None => 0,
+            };
+            if complete < data.len() {
+                warn!(
+                    "Removing the incomplete last block of ledger '{}' ({} bytes)",
+                    path.display(),
+                    data.len() - complete
+                );
+                Self::truncate(path, complete as u64)?;
+            }
+
+            for (i, line) in data[..complete].split(|b| *b == b'\n').enumerate() {
+                if line.iter().all(|b| b.is_ascii_whitespace()) {
+                    continue;
+                }
+                let block: LedgerBlock = serde_json::from_slice(line).map_err(|e| {
+                    format!("Invalid block in line {} of ledger '{}': {}", i + 1, path.display(), e)
+                })?;
+                if block.height != ledger.next_height() {
+                    return Err(format!(
+                        "Ledger '{}' has block {} where block {} was expected",
+                        path.display(),
+                        block.height,
+                        ledger.next_height()
+                    ));
+                }
+                for entry in block.entries.iter() {
+                    ledger.seen.insert((entry.id.clone(), entry.data.clone()));
+                }
+                ledger.blocks.push(block);
+            }
+        }
+
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Error opening ledger '{}': {}", path.display(), e))?;
+        ledger.log = Some(log);
+
+        Ok(ledger)
+    }
+
+    fn truncate(path: &PathBuf, length: u64) -> Result<(), String> {
+        let file = OpenOptions::new()
+            .write(true)
+            .open(path)
+            .map_err(|e| format!("Error opening ledger '{}': {}", path.display(), e))?;
+        file.set_len(length)
+            .and_then(|_| file.sync_all())
+            .map_err(|e| format!("Error truncating ledger '{}': {}", path.display(), e))
+    }
+
+    /// Queues a request for the next block. Returns false if the request was submitted before.
+    pub fn submit(&mut self, id: String, data: Vec<u8>) -> bool {
+        if !self.seen.insert((id.clone(), data.clone())) {
+            return false;
+        }
+        self.pending.push(LedgerEntry { id, data });
+        true
+    }
+
+    /// Orders the pending requests into a new block and appends it to the log. Returns None if no
+    /// request is pending.
+    pub fn cut_block(&mut self) -> Result<Option<LedgerBlock>, String> {
+        if self.pending.is_empty() {
+            return Ok(None);
+        }
+
+        let block = LedgerBlock {
+            height: self.next_height(),
+            entries: std::mem::take(&mut self.pending),
+        };
+
+        if let Some(log) = self.log.as_mut() {
+            let mut line = serde_json::to_vec(&block)
+                .map_err(|e| format!("Error serializing block: {}", e))?;
+            line.push(b'\n');
+            if let Err(e) = log.write_all(&line).and_then(|_| log.sync_data()) {
+                // the requests stay pending, the block is cut again on the next attempt
+                self.pending = block.entries;
+                return Err(format!("Error appending block to the ledger: {}", e));
+            }
+        }
+
+        self.blocks.push(block.clone());
+        Ok(Some(block))
+    }
+
+    /// Height of the next block.
+    pub fn next_height(&self) -> u64 {
+        self.blocks.len() as u64
+    }
+
+    pub fn get_block(&self, height: u64) -> Option<&LedgerBlock> {
+        self.blocks.get(height as usize)
+    }
+
+    /// Returns up to `limit` blocks starting at `height`, or all of them if `limit` is 0.
+    pub fn blocks_from(&self, height: u64, limit: usize) -> &[LedgerBlock] {
+        let start = (height as usize).min(self.blocks.len());
+        let end = match limit {
+            0 => self.blocks.len(),
+            limit => start.saturating_add(limit).min(self.blocks.len()),
+        };
+        &self.blocks[start..end]
+    }
+}
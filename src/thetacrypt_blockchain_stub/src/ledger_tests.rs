@@ -0,0 +1,97 @@
+use std::path::PathBuf;
+
+use super::ledger::Ledger;
+
+fn ledger_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("thetacrypt-ledger-{}-{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_blocks_are_cut_in_order() {
+    let mut ledger = Ledger::new();
+    assert!(ledger.cut_block().unwrap().is_none());
+
+    assert!(ledger.submit(String::from("a"), vec![1]));
+    assert!(ledger.submit(String::from("a"), vec![2]));
+    // the same request is ordered once
+    assert!(!ledger.submit(String::from("a"), vec![1]));
+    let block = ledger.cut_block().unwrap().unwrap();
+    assert_eq!(block.height, 0);
+    assert_eq!(block.entries.len(), 2);
+
+    ledger.submit(String::from("b"), vec![3]);
+    assert_eq!(ledger.cut_block().unwrap().unwrap().height, 1);
+    assert_eq!(ledger.next_height(), 2);
+
+    assert_eq!(ledger.blocks_from(1, 0).len(), 1);
+    assert_eq!(ledger.blocks_from(0, 1)[0].height, 0);
+    assert!(ledger.blocks_from(5, 0).is_empty());
+}
+
+#[test]
+fn test_ledger_is_replayed_from_file() {
+    let path = ledger_path("replay");
+
+    let mut ledger = Ledger::open(&path).unwrap();
+    ledger.submit(String::from("a"), vec![1]);
+    ledger.cut_block().unwrap();
+    ledger.submit(String::from("b"), vec![2]);
+    ledger.cut_block().unwrap();
+    // pending requests are not persisted
+    ledger.submit(String::from("c"), vec![3]);
+    drop(ledger);
+
+    let mut replayed = Ledger::open(&path).unwrap();
+    assert_eq!(replayed.next_height(), 2);
+    assert_eq!(replayed.get_block(1).unwrap().entries[0].id, "b");
+    assert!(!replayed.submit(String::from("a"), vec![1]));
+
+    replayed.submit(String::from("c"), vec![3]);
+    assert_eq!(replayed.cut_block().unwrap().unwrap().height, 2);
+    drop(replayed);
+
+    assert_eq!(Ledger::open(&path).unwrap().next_height(), 3);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_inconsistent_ledger_is_rejected() {
+    let path = ledger_path("gap");
+    std::fs::write(&path, "{\"height\":1,\"entries\":[]}\n").unwrap();
+    assert!(Ledger::open(&path).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_incomplete_last_block_is_removed() {
+    let path = ledger_path("torn");
+
+    let mut ledger = Ledger::open(&path).unwrap();
+    ledger.submit(String::from("a"), vec![1]);
+    ledger.cut_block().unwrap();
+    drop(ledger);
+    let complete = std::fs::read(&path).unwrap();
+
+    // a crash while block 1 was appended
+    let mut torn = complete.clone();
+    torn.extend_from_slice(b"{\"height\":1,\"entries\":[{\"id\":\"b\"");
+    std::fs::write(&path, &torn).unwrap();
+
+    let mut ledger = Ledger::open(&path).unwrap();
+    assert_eq!(ledger.next_height(), 1);
+    assert_eq!(std::fs::read(&path).unwrap(), complete);
+
+    // the request of the lost block can be submitted again
+    assert!(ledger.submit(String::from("b"), vec![2]));
+    assert_eq!(ledger.cut_block().unwrap().unwrap().height, 1);
+    drop(ledger);
+    assert_eq!(Ledger::open(&path).unwrap().next_height(), 2);
+
+    // the first block, too
+    std::fs::write(&path, "{\"height\":0").unwrap();
+    assert_eq!(Ledger::open(&path).unwrap().next_height(), 0);
+    assert!(std::fs::read(&path).unwrap().is_empty());
+    std::fs::remove_file(&path).unwrap();
+}
@@ -1 +1,4 @@
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod ledger;
+#[cfg(test)]
+mod ledger_tests;
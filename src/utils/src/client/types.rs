@@ -13,10 +13,25 @@ pub struct PeerPublicInfo {
     pub rpc_port: u16,
 }
 
+/// TLS settings used by a client to connect to the servers' RPC endpoints.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientTlsConfig {
+    /// PEM encoded CA certificate used to authenticate the servers.
+    pub ca_file: PathBuf,
+    /// PEM encoded client certificate, presented to servers which require mutual TLS.
+    pub cert_file: Option<PathBuf>,
+    /// PEM encoded private key belonging to `cert_file`.
+    pub key_file: Option<PathBuf>,
+    /// Domain name expected in the servers' certificates. Defaults to the peer's IP.
+    pub domain_name: Option<String>,
+}
+
 /// Configuration of the server binary.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ClientConfig {
     pub peers: Vec<PeerPublicInfo>,
+    /// Optional TLS configuration. If not set, plaintext connections are used.
+    pub tls: Option<ClientTlsConfig>,
 }
 
 impl ClientConfig {
@@ -40,7 +55,10 @@ impl ClientConfig {
             Err(e) => return Err(format!("Invalid JSON: {}", e)),
         };
 
-        ClientConfig::new(cfg.peers)
+        let mut config = ClientConfig::new(cfg.peers)?;
+        config.tls = cfg.tls;
+
+        Ok(config)
     }
 
     /// Initialize a new config struct. Performs a sanity check of passed values.
@@ -52,7 +70,7 @@ impl ClientConfig {
             }
         }
 
-        Ok(ClientConfig { peers })
+        Ok(ClientConfig { peers, tls: None })
     }
 
     /// Get list of all peers' IDs, sorted by the order in which they appear in the config file.
@@ -1,6 +1,7 @@
 use std::{fmt::Display, path::PathBuf};
 
 use clap::{Parser, ValueEnum};
+use serde::{Deserialize, Serialize};
 
 #[derive(Parser, Debug)]
 pub struct ConfgenCli {
@@ -32,9 +33,17 @@ pub struct ConfgenCli {
 
     #[arg(
         long,
-        help = "Path to file containing IPs of servers, one per line. Required."
+        required_unless_present = "spec",
+        help = "Path to file containing IPs of servers, one per line. Required unless a deployment spec is given."
     )]
-    pub ip_file: PathBuf,
+    pub ip_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with = "ip_file",
+        help = "Path to a TOML or YAML deployment spec. Generates a complete deployment: server and client configs, node identities, TLS certificates, keystores and service definitions."
+    )]
+    pub spec: Option<PathBuf>,
 
     #[arg(
         long,
@@ -75,7 +84,8 @@ pub struct ConfgenCli {
 
 /// Enum representing how ports are assigned to servers. Static uses the same port for all servers,
 /// while consecutive uses incremental ports.
-#[derive(ValueEnum, Debug, Clone)]
+#[derive(ValueEnum, Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "lowercase")]
 pub enum PortStrategy {
     Consecutive,
     Static,
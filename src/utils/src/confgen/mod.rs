@@ -1 +1,4 @@
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+pub mod spec;
+#[cfg(test)]
+mod spec_tests;
@@ -0,0 +1,234 @@
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::cli::PortStrategy;
+
+/// Declarative description of a deployment, read by `confgen --spec` from a TOML or YAML file.
+/// confgen turns it into the configuration files, identities, certificates and keystores of all
+/// nodes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentSpec {
+    /// Address which the servers bind to.
+    #[serde(default = "default_listen_address")]
+    pub listen_address: String,
+    /// Port of the RPC endpoint, or the first port if ports are assigned consecutively.
+    #[serde(default = "default_rpc_port")]
+    pub rpc_port: u16,
+    /// Port of the P2P layer, or the first port if ports are assigned consecutively.
+    #[serde(default = "default_p2p_port")]
+    pub p2p_port: u16,
+    #[serde(default = "default_port_strategy")]
+    pub port_strategy: PortStrategy,
+    /// Directory in which the servers find the generated files when they run. The paths in the
+    /// generated configuration files point into this directory.
+    #[serde(default = "default_config_dir")]
+    pub config_dir: PathBuf,
+    /// Optional file in which the servers store benchmarking events.
+    pub event_file: Option<PathBuf>,
+    /// The servers of the deployment. Their ids are assigned in this order, starting at 1.
+    pub nodes: Vec<NodeSpec>,
+    /// Optional threshold keys to generate. If not set, no keystores are written.
+    pub keys: Option<KeySpec>,
+    /// Optional TLS settings of the RPC endpoints. If not set, the endpoints are served in
+    /// plaintext.
+    pub tls: Option<TlsSpec>,
+    /// Optional integration with a target blockchain platform through proxy nodes.
+    pub integration: Option<IntegrationSpec>,
+    /// Service definitions to generate in addition to the configuration files.
+    #[serde(default)]
+    pub layout: LayoutSpec,
+}
+
+/// A single server of the deployment.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct NodeSpec {
+    pub ip: String,
+    /// Overrides the RPC port assigned by the port strategy.
+    pub rpc_port: Option<u16>,
+    /// Overrides the P2P port assigned by the port strategy.
+    pub p2p_port: Option<u16>,
+}
+
+/// Threshold keys shared among all servers.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct KeySpec {
+    /// Number of servers needed to use a key.
+    pub threshold: u16,
+    /// Keys to generate, in the format of `thetacli keygen --subjects`, i.e., `scheme-group` with
+    /// an optional `:alias`.
+    pub subjects: Vec<String>,
+}
+
+/// Certificates for the RPC endpoints, issued by a CA that confgen creates.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TlsSpec {
+    /// Whether clients have to authenticate with a certificate. If set, a client certificate and
+    /// an access policy that grants it decryption, signing and coins with all keys are generated
+    /// as well.
+    #[serde(default = "default_mutual")]
+    pub mutual: bool,
+    /// Whether the generated client may also manage the keys of the servers through the admin
+    /// RPCs. Requires `mutual`.
+    #[serde(default)]
+    pub client_admin: bool,
+    /// DNS names added to the certificate of every server, in addition to its IP and `localhost`.
+    #[serde(default)]
+    pub dns_names: Vec<String>,
+    /// Domain name the client expects in the servers' certificates. Defaults to the peer's IP.
+    pub domain_name: Option<String>,
+}
+
+/// Proxy nodes of a target blockchain platform, one per server.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IntegrationSpec {
+    pub proxy_ips: Vec<String>,
+    pub proxy_port: u16,
+    /// Whether to write the configuration of the blockchain stub (stub.json).
+    #[serde(default)]
+    pub stub: bool,
+}
+
+/// Service definitions that start the servers.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct LayoutSpec {
+    /// Writes a docker-compose.yml with one service per server.
+    #[serde(default)]
+    pub docker_compose: bool,
+    /// Docker image of the services. Defaults to `rust-threshold-library`, as built from the
+    /// repository's Dockerfile.
+    pub image: Option<String>,
+    /// Writes a systemd unit per server.
+    #[serde(default)]
+    pub systemd: bool,
+    /// Working directory of the systemd units, in which relative paths such as `config_dir` are
+    /// resolved. Defaults to `/opt/thetacrypt`.
+    pub working_dir: Option<PathBuf>,
+}
+
+impl DeploymentSpec {
+    /// Read a deployment spec from disk. Files ending in `.yaml` or `.yml` are parsed as YAML,
+    /// all others as TOML.
+    pub fn from_file(file: &PathBuf) -> Result<DeploymentSpec, String> {
+        let data = match fs::read_to_string(file) {
+            Ok(s) => s,
+            Err(e) => return Err(format!("Error reading deployment spec: {}", e)),
+        };
+
+        let spec = match file.extension().and_then(|e| e.to_str()) {
+            Some("yaml") | Some("yml") => DeploymentSpec::from_yaml(&data),
+            _ => DeploymentSpec::from_toml(&data),
+        };
+        match spec {
+            Ok(spec) => Ok(spec),
+            Err(e) => Err(format!("Error parsing deployment spec: {}", e)),
+        }
+    }
+
+    /// Build a deployment spec from a TOML encoding and check it.
+    pub fn from_toml(data: &str) -> Result<DeploymentSpec, String> {
+        let spec: DeploymentSpec = match toml::from_str(data) {
+            Ok(spec) => spec,
+            Err(e) => return Err(format!("Invalid TOML: {}", e)),
+        };
+
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Build a deployment spec from a YAML encoding and check it.
+    pub fn from_yaml(data: &str) -> Result<DeploymentSpec, String> {
+        let spec: DeploymentSpec = match serde_yaml::from_str(data) {
+            Ok(spec) => spec,
+            Err(e) => return Err(format!("Invalid YAML: {}", e)),
+        };
+
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Check that the spec describes a consistent deployment.
+    pub fn validate(&self) -> Result<(), String> {
+        if let Err(e) = IpAddr::from_str(&self.listen_address) {
+            return Err(format!("Invalid value for listen_address: {}", e));
+        }
+
+        if self.nodes.is_empty() {
+            return Err(String::from("The deployment must have at least one node"));
+        }
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Err(e) = IpAddr::from_str(&node.ip) {
+                return Err(format!("Invalid IP for node {}: {}", i + 1, e));
+            }
+        }
+
+        if let Some(keys) = &self.keys {
+            if keys.threshold == 0 || keys.threshold as usize > self.nodes.len() {
+                return Err(format!(
+                    "The threshold must be between 1 and the number of nodes ({})",
+                    self.nodes.len()
+                ));
+            }
+            if keys.subjects.is_empty() {
+                return Err(String::from("No keys to generate in keys.subjects"));
+            }
+        }
+
+        if let Some(tls) = &self.tls {
+            if tls.client_admin && !tls.mutual {
+                return Err(String::from(
+                    "tls.client_admin requires mutual TLS, the client has no certificate otherwise",
+                ));
+            }
+        }
+
+        if let Some(integration) = &self.integration {
+            if integration.proxy_ips.len() != self.nodes.len() {
+                return Err(format!(
+                    "There must be one proxy for each of the {} nodes",
+                    self.nodes.len()
+                ));
+            }
+            for ip in integration.proxy_ips.iter() {
+                if let Err(e) = IpAddr::from_str(ip) {
+                    return Err(format!("Invalid IP for proxy {}: {}", ip, e));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn default_listen_address() -> String {
+    String::from("0.0.0.0")
+}
+
+fn default_rpc_port() -> u16 {
+    51000
+}
+
+fn default_p2p_port() -> u16 {
+    50000
+}
+
+fn default_port_strategy() -> PortStrategy {
+    PortStrategy::Static
+}
+
+fn default_config_dir() -> PathBuf {
+    PathBuf::from("conf")
+}
+
+fn default_mutual() -> bool {
+    true
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use super::cli::PortStrategy;
+use super::spec::DeploymentSpec;
+
+const MINIMAL: &str = r#"
+[[nodes]]
+ip = "127.0.0.1"
+[[nodes]]
+ip = "127.0.0.2"
+"#;
+
+fn with_nodes(section: &str) -> String {
+    format!("{}\n{}", MINIMAL, section)
+}
+
+#[test]
+fn test_defaults() {
+    let spec = DeploymentSpec::from_toml(MINIMAL).unwrap();
+
+    assert_eq!(spec.listen_address, "0.0.0.0");
+    assert_eq!(spec.rpc_port, 51000);
+    assert_eq!(spec.p2p_port, 50000);
+    assert!(matches!(spec.port_strategy, PortStrategy::Static));
+    assert_eq!(spec.config_dir, PathBuf::from("conf"));
+    assert_eq!(spec.nodes.len(), 2);
+    assert!(spec.keys.is_none() && spec.tls.is_none() && spec.integration.is_none());
+    assert!(!spec.layout.docker_compose && !spec.layout.systemd);
+}
+
+#[test]
+fn test_full_toml() {
+    let data = r#"
+port_strategy = "consecutive"
+config_dir = "/etc/thetacrypt"
+
+[[nodes]]
+ip = "10.0.0.1"
+rpc_port = 52000
+[[nodes]]
+ip = "10.0.0.2"
+[[nodes]]
+ip = "10.0.0.3"
+
+[keys]
+threshold = 2
+subjects = ["Sg02-Bls12381", "Bls04-Bls12381:payments"]
+
+[tls]
+dns_names = ["thetacrypt.example"]
+
+[layout]
+docker_compose = true
+"#;
+    let spec = DeploymentSpec::from_toml(data).unwrap();
+
+    assert!(matches!(spec.port_strategy, PortStrategy::Consecutive));
+    assert_eq!(spec.config_dir, PathBuf::from("/etc/thetacrypt"));
+    assert_eq!(spec.nodes[0].rpc_port, Some(52000));
+    assert_eq!(spec.nodes[1].rpc_port, None);
+    assert_eq!(spec.keys.as_ref().unwrap().subjects.len(), 2);
+
+    // mutual TLS is the default, admin rights for the client are not
+    let tls = spec.tls.unwrap();
+    assert!(tls.mutual);
+    assert!(!tls.client_admin);
+    assert_eq!(tls.dns_names, vec![String::from("thetacrypt.example")]);
+    assert!(spec.layout.docker_compose);
+}
+
+#[test]
+fn test_yaml() {
+    let data = r#"
+nodes:
+  - ip: 127.0.0.1
+  - ip: 127.0.0.1
+tls:
+  mutual: true
+  client_admin: true
+integration:
+  proxy_ips: [127.0.0.1, 127.0.0.1]
+  proxy_port: 30000
+  stub: true
+"#;
+    let spec = DeploymentSpec::from_yaml(data).unwrap();
+
+    assert!(spec.tls.unwrap().client_admin);
+    let integration = spec.integration.unwrap();
+    assert_eq!(integration.proxy_port, 30000);
+    assert!(integration.stub);
+}
+
+#[test]
+fn test_unknown_fields_are_rejected() {
+    let result = DeploymentSpec::from_toml(&with_nodes("[tls]\nmutal = true"));
+    assert!(result.unwrap_err().starts_with("Invalid TOML"));
+}
+
+#[test]
+fn test_validation() {
+    let error = |data: &str| DeploymentSpec::from_toml(data).unwrap_err();
+
+    assert_eq!(
+        error("nodes = []"),
+        "The deployment must have at least one node"
+    );
+    assert!(error("[[nodes]]\nip = \"localhost\"").starts_with("Invalid IP for node 1"));
+    assert!(error(&format!("listen_address = \"any\"\n{}", MINIMAL))
+        .starts_with("Invalid value for listen_address"));
+    assert_eq!(
+        error(&with_nodes("[keys]\nthreshold = 3\nsubjects = [\"Sg02-Bls12381\"]")),
+        "The threshold must be between 1 and the number of nodes (2)"
+    );
+    assert_eq!(
+        error(&with_nodes("[keys]\nthreshold = 2\nsubjects = []")),
+        "No keys to generate in keys.subjects"
+    );
+    assert_eq!(
+        error(&with_nodes("[integration]\nproxy_ips = [\"127.0.0.1\"]\nproxy_port = 30000")),
+        "There must be one proxy for each of the 2 nodes"
+    );
+    assert!(error(&with_nodes("[tls]\nmutual = false\nclient_admin = true"))
+        .starts_with("tls.client_admin requires mutual TLS"));
+}
@@ -6,7 +6,7 @@ use clap::Parser;
 #[derive(Parser, Debug)]
 #[command(about = format!("Server application {}", env!("CARGO_PKG_VERSION")))]
 pub struct ServerCli {
-    #[arg(long, help = "Path to JSON-encoded configuration.")]
+    #[arg(long, help = "Path to the configuration file (JSON, or TOML/YAML with a .toml/.yaml extension).")]
     pub config_file: PathBuf,
 
     #[arg(
@@ -18,4 +18,11 @@ pub struct ServerCli {
 
     #[arg(long, help = "Path to JSON-encoded keystore.")]
     pub key_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Check the configuration and the keystore, report all problems found and exit.",
+        default_value_t = false
+    )]
+    pub check_config: bool,
 }
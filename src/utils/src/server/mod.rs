@@ -1,3 +1,5 @@
 pub mod types;
 pub mod dirutil;
-pub mod cli;
\ No newline at end of file
+pub mod cli;
+#[cfg(test)]
+mod types_tests;
@@ -1,6 +1,8 @@
+use std::collections::HashSet;
+use std::env;
 use std::fs;
 use std::net::IpAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use serde::{Deserialize, Serialize};
@@ -11,6 +13,9 @@ pub struct Peer {
     pub id: u32,
     pub ip: String,
     pub p2p_port: u16,
+    /// Optional libp2p peer id of the peer's identity key. Once identities are configured for the
+    /// peers, connections of nodes with other identities are closed.
+    pub identity: Option<String>,
 }
 
 /// A proxy node for remote delegation of networking task
@@ -20,6 +25,105 @@ pub struct ProxyNode {
     pub port: u16,
 }
 
+/// TLS settings of the RPC endpoint.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RpcTlsConfig {
+    /// PEM encoded certificate (chain) presented by the server.
+    pub cert_file: PathBuf,
+    /// PEM encoded private key belonging to `cert_file`.
+    pub key_file: PathBuf,
+    /// PEM encoded CA certificate used to authenticate clients. If set, every client must present
+    /// a certificate signed by this CA (mutual TLS).
+    pub client_ca_file: Option<PathBuf>,
+}
+
+/// Limits on the protocol instances a server runs concurrently. Unset limits are not enforced.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AdmissionConfig {
+    /// Maximum number of instances running at the same time.
+    pub max_running_instances: Option<usize>,
+    /// Maximum number of running instances requested by the same (authenticated) client.
+    pub max_running_per_client: Option<usize>,
+    /// Maximum number of running instances using the same key.
+    pub max_running_per_key: Option<usize>,
+    /// Maximum number of requests waiting for a free slot. Further requests are rejected.
+    #[serde(default)]
+    pub max_queued_requests: usize,
+    /// Seconds after which a running instance is aborted and its slot freed.
+    #[serde(default = "default_instance_timeout_secs")]
+    pub instance_timeout_secs: u64,
+}
+
+impl Default for AdmissionConfig {
+    fn default() -> Self {
+        Self {
+            max_running_instances: None,
+            max_running_per_client: None,
+            max_running_per_key: None,
+            max_queued_requests: 0,
+            instance_timeout_secs: default_instance_timeout_secs(),
+        }
+    }
+}
+
+fn default_instance_timeout_secs() -> u64 {
+    300
+}
+
+fn default_robust_frost() -> bool {
+    true
+}
+
+/// Settings of the pool of precomputed FROST nonces, which lets FROST signing finish in a single
+/// round in steady state.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FrostNoncePoolConfig {
+    /// File in which unused nonces are stored. It contains secret material and is created with
+    /// permissions 0600. Without a file, the pool is kept in memory only and starts empty.
+    pub file: Option<PathBuf>,
+    /// Number of nonces kept available per key and coordinator.
+    pub target_size: usize,
+    /// The pool of a key is refilled once fewer nonces are left for one of its coordinators.
+    pub low_watermark: usize,
+    /// Seconds between two checks of the pool.
+    pub check_interval_secs: u64,
+}
+
+impl Default for FrostNoncePoolConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            target_size: 20,
+            low_watermark: 5,
+            check_interval_secs: 10,
+        }
+    }
+}
+
+/// Settings of the CometBFT (Tendermint) node used to order messages on the TOB channel.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CometBftConfig {
+    /// URL of the JSON-RPC endpoint of the node, e.g. `http://127.0.0.1:26657`.
+    pub rpc_url: String,
+    /// Milliseconds between two checks for new blocks. If not set, new blocks are checked for
+    /// every 500 ms.
+    pub poll_interval_ms: Option<u64>,
+}
+
+/// Environment variables that override settings of the configuration file, and the settings
+/// they override. An empty value unsets an optional setting.
+pub const ENV_OVERRIDES: [(&str, &str); 9] = [
+    ("THETA_ID", "id"),
+    ("THETA_LISTEN_ADDRESS", "listen_address"),
+    ("THETA_RPC_PORT", "rpc_port"),
+    ("THETA_EVENT_FILE", "event_file"),
+    ("THETA_ACCESS_POLICY_FILE", "access_policy_file"),
+    ("THETA_SIGNING_POLICY_FILE", "signing_policy_file"),
+    ("THETA_IDENTITY_FILE", "identity_file"),
+    ("THETA_PEER_WATCH_INTERVAL_SECS", "peer_watch_interval_secs"),
+    ("THETA_COMETBFT_RPC_URL", "cometbft.rpc_url"),
+];
+
 /// Configuration of the server binary.
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ServerConfig {
@@ -36,30 +140,324 @@ pub struct ServerConfig {
     /// Path to file in which to store benchmarking events.
     /// If not set, benchmarking events will be discarded.
     pub event_file: Option<PathBuf>,
+    /// Optional TLS configuration of the RPC endpoint. If not set, the endpoint is served in plaintext.
+    pub rpc_tls: Option<RpcTlsConfig>,
+    /// Optional path to the access policy file, which specifies which client may use which keys
+    /// and operations. If not set, every client may use every key.
+    pub access_policy_file: Option<PathBuf>,
+    /// Optional path to the signing policy file, which specifies the rules a message must satisfy
+    /// before this node signs it with a given key. If not set, every message is signed.
+    pub signing_policy_file: Option<PathBuf>,
+    /// Optional limits on concurrently running protocol instances. If not set, there is no limit.
+    pub admission: Option<AdmissionConfig>,
+    /// Optional settings of the FROST nonce pool. If not set, the defaults of FrostNoncePoolConfig
+    /// apply and the pool is kept in memory only.
+    pub frost_nonce_pool: Option<FrostNoncePoolConfig>,
+    /// Whether FROST signatures are produced by the robust protocol, which keeps selecting new
+    /// signers until a signature is produced. Otherwise, the plain two-round protocol is used,
+    /// which fails if a selected signer does not answer. Defaults to true.
+    #[serde(default = "default_robust_frost")]
+    pub robust_frost: bool,
+    /// Optional interval in seconds in which the config file is checked for changes. Changed
+    /// peers and proxy settings are applied without a restart. If not set, the file is read once.
+    pub peer_watch_interval_secs: Option<u64>,
+    /// Optional CometBFT node that orders the messages of the TOB channel. If not set, these
+    /// messages are handed to the gossip network (or the proxy node).
+    pub cometbft: Option<CometBftConfig>,
+    /// Optional path to the file with the P2P identity key of this server, as written by confgen.
+    /// If not set, a new identity is generated on every start.
+    pub identity_file: Option<PathBuf>,
 }
 
 impl ServerConfig {
-    /// Read a server's configuration from a JSON encoding on disk.
+    /// Read a server's configuration from disk, apply the environment overrides (see
+    /// `ENV_OVERRIDES`) and validate it. Files ending in `.toml` are parsed as TOML, files ending
+    /// in `.yaml` or `.yml` as YAML, all others as JSON.
     pub fn from_file(file: &PathBuf) -> Result<ServerConfig, String> {
+        let config = ServerConfig::load(file)?;
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Read a server's configuration from disk and apply the environment overrides, without
+    /// validating it. Use `validate` to find all problems of the configuration.
+    pub fn load(file: &PathBuf) -> Result<ServerConfig, String> {
         let data = match fs::read_to_string(file) {
             Ok(s) => s,
             Err(e) => return Err(format!("Error reading config file: {}", e)),
         };
 
-        match ServerConfig::from_json(&data) {
-            Ok(cfg) => return Ok(cfg),
+        let parsed: Result<ServerConfig, String> = match file.extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&data).map_err(|e| format!("Invalid TOML: {}", e)),
+            Some("yaml") | Some("yml") => {
+                serde_yaml::from_str(&data).map_err(|e| format!("Invalid YAML: {}", e))
+            }
+            _ => serde_json::from_str(&data).map_err(|e| format!("Invalid JSON: {}", e)),
+        };
+        let mut config = match parsed {
+            Ok(cfg) => cfg,
             Err(e) => return Err(format!("Error parsing config file: {}", e)),
-        }
+        };
+
+        config.apply_env_overrides()?;
+        Ok(config)
     }
 
     /// Build a server's configuration based on a JSON serialization.
     pub fn from_json(data: &str) -> Result<ServerConfig, String> {
-        let cfg: ServerConfig = match serde_json::from_str(data) {
+        let config: ServerConfig = match serde_json::from_str(data) {
             Ok(cfg) => cfg,
             Err(e) => return Err(format!("Invalid JSON: {}", e)),
         };
 
-        ServerConfig::new(cfg.id, cfg.listen_address, cfg.rpc_port, cfg.peers, cfg.proxy_node, cfg.event_file)
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Build a server's configuration based on a TOML serialization.
+    pub fn from_toml(data: &str) -> Result<ServerConfig, String> {
+        let config: ServerConfig = match toml::from_str(data) {
+            Ok(cfg) => cfg,
+            Err(e) => return Err(format!("Invalid TOML: {}", e)),
+        };
+
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Build a server's configuration based on a YAML serialization.
+    pub fn from_yaml(data: &str) -> Result<ServerConfig, String> {
+        let config: ServerConfig = match serde_yaml::from_str(data) {
+            Ok(cfg) => cfg,
+            Err(e) => return Err(format!("Invalid YAML: {}", e)),
+        };
+
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Override settings of the configuration with the environment variables in
+    /// `ENV_OVERRIDES` that are set.
+    pub fn apply_env_overrides(&mut self) -> Result<(), String> {
+        self.apply_overrides(|name| env::var(name).ok())
+    }
+
+    /// Override settings of the configuration with the values `var` returns for the names in
+    /// `ENV_OVERRIDES`. An empty value unsets an optional setting.
+    pub fn apply_overrides<F: Fn(&str) -> Option<String>>(&mut self, var: F) -> Result<(), String> {
+        fn parse<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid value for {}: {}", name, value))
+        }
+        fn path(value: String) -> Option<PathBuf> {
+            Some(value).filter(|v| !v.is_empty()).map(PathBuf::from)
+        }
+
+        if let Some(value) = var("THETA_ID") {
+            self.id = parse("THETA_ID", &value)?;
+        }
+        if let Some(value) = var("THETA_LISTEN_ADDRESS") {
+            self.listen_address = value;
+        }
+        if let Some(value) = var("THETA_RPC_PORT") {
+            self.rpc_port = parse("THETA_RPC_PORT", &value)?;
+        }
+        if let Some(value) = var("THETA_EVENT_FILE") {
+            self.event_file = path(value);
+        }
+        if let Some(value) = var("THETA_ACCESS_POLICY_FILE") {
+            self.access_policy_file = path(value);
+        }
+        if let Some(value) = var("THETA_SIGNING_POLICY_FILE") {
+            self.signing_policy_file = path(value);
+        }
+        if let Some(value) = var("THETA_IDENTITY_FILE") {
+            self.identity_file = path(value);
+        }
+        if let Some(value) = var("THETA_PEER_WATCH_INTERVAL_SECS") {
+            self.peer_watch_interval_secs = if value.is_empty() {
+                None
+            } else {
+                Some(parse("THETA_PEER_WATCH_INTERVAL_SECS", &value)?)
+            };
+        }
+        if let Some(value) = var("THETA_COMETBFT_RPC_URL") {
+            self.cometbft = if value.is_empty() {
+                None
+            } else {
+                Some(CometBftConfig {
+                    rpc_url: value,
+                    poll_interval_ms: self.cometbft.as_ref().and_then(|c| c.poll_interval_ms),
+                })
+            };
+        }
+
+        Ok(())
+    }
+
+    /// Validate the configuration, returning an error that lists all problems found.
+    pub fn check(&self) -> Result<(), String> {
+        let problems = self.validate();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        Err(format!("Invalid configuration: {}", problems.join("; ")))
+    }
+
+    /// Validate the configuration and return all problems found. Besides the values themselves,
+    /// this checks that the files the configuration refers to exist.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut require_file = |name: &str, file: &PathBuf| {
+            if !file.is_file() {
+                problems.push(format!("The {} {} does not exist", name, file.display()));
+            }
+        };
+
+        if let Some(tls) = &self.rpc_tls {
+            require_file("TLS certificate", &tls.cert_file);
+            require_file("TLS key", &tls.key_file);
+            if let Some(ca_file) = &tls.client_ca_file {
+                require_file("client CA", ca_file);
+            }
+        }
+        if let Some(file) = &self.access_policy_file {
+            require_file("access policy file", file);
+        }
+        if let Some(file) = &self.signing_policy_file {
+            require_file("signing policy file", file);
+        }
+        if let Some(file) = &self.identity_file {
+            require_file("identity file", file);
+        }
+
+        if let Err(e) = IpAddr::from_str(&self.listen_address) {
+            problems.push(format!("Invalid listen_address {}: {}", self.listen_address, e));
+        }
+        if self.rpc_port == 0 {
+            problems.push(String::from("rpc_port must not be 0"));
+        }
+
+        // peers
+        if self.peers.is_empty() {
+            problems.push(String::from("No peers configured"));
+        }
+        let mut ids = HashSet::new();
+        let mut addresses = HashSet::new();
+        for peer in &self.peers {
+            if let Err(e) = IpAddr::from_str(&peer.ip) {
+                problems.push(format!("Invalid IP for peer {}: {}", peer.id, e));
+            }
+            if peer.p2p_port == 0 {
+                problems.push(format!("The p2p_port of peer {} must not be 0", peer.id));
+            }
+            if !ids.insert(peer.id) {
+                problems.push(format!("Peer {} is configured more than once", peer.id));
+            }
+            if !addresses.insert((peer.ip.as_str(), peer.p2p_port)) {
+                problems.push(format!("Peers share the P2P address {}:{}", peer.ip, peer.p2p_port));
+            }
+        }
+        match self.self_peer() {
+            Some(me) => {
+                // the RPC endpoint and the P2P layers of the peers on the same host need distinct ports
+                for peer in self.peers.iter().filter(|p| p.ip == me.ip && p.p2p_port == self.rpc_port) {
+                    problems.push(format!(
+                        "The rpc_port {} is also the P2P port of peer {}",
+                        self.rpc_port, peer.id
+                    ));
+                }
+            }
+            None => problems.push(format!(
+                "This server (id {}) is not in the list of peers",
+                self.id
+            )),
+        }
+
+        if let Some(proxy) = &self.proxy_node {
+            match IpAddr::from_str(&proxy.ip) {
+                Ok(ip) if ip.is_unspecified() => problems.push(format!(
+                    "The proxy node address {} is not reachable, it must be the address of the proxy",
+                    proxy.ip
+                )),
+                Ok(_) => {}
+                Err(e) => problems.push(format!("Invalid IP for the proxy node: {}", e)),
+            }
+            if proxy.port == 0 {
+                problems.push(String::from("The port of the proxy node must not be 0"));
+            }
+        }
+
+        if let Some(cometbft) = &self.cometbft {
+            if !cometbft.rpc_url.starts_with("http://") && !cometbft.rpc_url.starts_with("https://") {
+                problems.push(format!("cometbft.rpc_url {} is not an HTTP URL", cometbft.rpc_url));
+            }
+            if cometbft.poll_interval_ms == Some(0) {
+                problems.push(String::from("cometbft.poll_interval_ms must not be 0"));
+            }
+        }
+
+        if let Some(event_file) = &self.event_file {
+            if !parent_dir(event_file).is_dir() {
+                problems.push(format!(
+                    "The directory of the event_file {} does not exist",
+                    event_file.display()
+                ));
+            }
+        }
+
+        if self.access_policy_file.is_some()
+            && self.rpc_tls.as_ref().map_or(true, |tls| tls.client_ca_file.is_none())
+        {
+            problems.push(String::from(
+                "An access policy requires mutual TLS on the RPC endpoint (rpc_tls.client_ca_file)",
+            ));
+        }
+
+        if let Some(admission) = &self.admission {
+            let limits = [
+                ("max_running_instances", admission.max_running_instances),
+                ("max_running_per_client", admission.max_running_per_client),
+                ("max_running_per_key", admission.max_running_per_key),
+            ];
+            for (name, limit) in limits {
+                if limit == Some(0) {
+                    problems.push(format!("admission.{} must be at least 1", name));
+                }
+            }
+            if admission.instance_timeout_secs == 0 {
+                problems.push(String::from("admission.instance_timeout_secs must not be 0"));
+            }
+        }
+
+        if let Some(pool) = &self.frost_nonce_pool {
+            if pool.target_size == 0 {
+                problems.push(String::from("frost_nonce_pool.target_size must be at least 1"));
+            }
+            if pool.low_watermark > pool.target_size {
+                problems.push(String::from(
+                    "frost_nonce_pool.low_watermark must not exceed frost_nonce_pool.target_size",
+                ));
+            }
+            if pool.check_interval_secs == 0 {
+                problems.push(String::from("frost_nonce_pool.check_interval_secs must not be 0"));
+            }
+            if let Some(file) = &pool.file {
+                if !parent_dir(file).is_dir() {
+                    problems.push(format!(
+                        "The directory of the FROST nonce pool file {} does not exist",
+                        file.display()
+                    ));
+                }
+            }
+        }
+
+        if self.peer_watch_interval_secs == Some(0) {
+            problems.push(String::from("peer_watch_interval_secs must not be 0"));
+        }
+
+        problems
     }
 
     /// Initialize a new config struct. Performs a sanity check of passed values.
@@ -90,6 +488,15 @@ impl ServerConfig {
             peers,
             proxy_node,
             event_file,
+            rpc_tls: None,
+            access_policy_file: None,
+            signing_policy_file: None,
+            admission: None,
+            frost_nonce_pool: None,
+            robust_frost: default_robust_frost(),
+            peer_watch_interval_secs: None,
+            cometbft: None,
+            identity_file: None,
         })
     }
 
@@ -136,6 +543,14 @@ impl ServerConfig {
     }
 }
 
+// Directory a file is created in. A bare file name is created in the working directory.
+fn parent_dir(file: &Path) -> &Path {
+    match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
 // /// Configuration of the server binary.
 // #[derive(Serialize, Deserialize, Debug)]
 // pub struct ServerProxyConfig {
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use super::types::{ServerConfig, ENV_OVERRIDES};
+
+const JSON: &str = r#"{
+    "id": 1,
+    "listen_address": "0.0.0.0",
+    "rpc_port": 51000,
+    "peers": [
+        { "id": 1, "ip": "127.0.0.1", "p2p_port": 50000 },
+        { "id": 2, "ip": "127.0.0.1", "p2p_port": 50001 }
+    ]
+}"#;
+
+const TOML: &str = r#"
+id = 2
+listen_address = "0.0.0.0"
+rpc_port = 51001
+
+[[peers]]
+id = 1
+ip = "127.0.0.1"
+p2p_port = 50000
+
+[[peers]]
+id = 2
+ip = "127.0.0.1"
+p2p_port = 50001
+
+[admission]
+max_running_instances = 10
+"#;
+
+const YAML: &str = r#"
+id: 1
+listen_address: "0.0.0.0"
+rpc_port: 51000
+peers:
+  - id: 1
+    ip: "127.0.0.1"
+    p2p_port: 50000
+  - id: 2
+    ip: "127.0.0.1"
+    p2p_port: 50001
+cometbft:
+  rpc_url: "http://127.0.0.1:26657"
+"#;
+
+fn temp_file(name: &str, data: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("thetacrypt-{}-{}", std::process::id(), name));
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn config() -> ServerConfig {
+    ServerConfig::from_json(JSON).unwrap()
+}
+
+fn overrides(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars: HashMap<String, String> = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn test_load_by_extension() {
+    for (name, data) in [
+        ("config.json", JSON),
+        ("config.toml", TOML),
+        ("config.yml", YAML),
+    ] {
+        let path = temp_file(name, data);
+        let config = ServerConfig::load(&path).unwrap();
+        assert_eq!(config.peers.len(), 2);
+        assert!(
+            config.validate().is_empty(),
+            "{}: {:?}",
+            name,
+            config.validate()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    let config = ServerConfig::from_toml(TOML).unwrap();
+    assert_eq!(config.id, 2);
+    let admission = config.admission.unwrap();
+    assert_eq!(admission.max_running_instances, Some(10));
+    assert_eq!(admission.instance_timeout_secs, 300);
+
+    let config = ServerConfig::from_yaml(YAML).unwrap();
+    let cometbft = config.cometbft.unwrap();
+    assert_eq!(cometbft.rpc_url, "http://127.0.0.1:26657");
+    assert_eq!(cometbft.poll_interval_ms, None);
+}
+
+#[test]
+fn test_load_errors() {
+    let missing = std::env::temp_dir().join("thetacrypt-no-such-config.json");
+    assert!(ServerConfig::load(&missing)
+        .unwrap_err()
+        .starts_with("Error reading config file"));
+
+    // a TOML file is not parsed as JSON
+    let path = temp_file("misnamed.json", TOML);
+    assert!(ServerConfig::load(&path)
+        .unwrap_err()
+        .contains("Invalid JSON"));
+    fs::remove_file(&path).unwrap();
+
+    assert!(ServerConfig::from_toml("id = ")
+        .unwrap_err()
+        .starts_with("Invalid TOML"));
+    assert!(ServerConfig::from_yaml("id: [")
+        .unwrap_err()
+        .starts_with("Invalid YAML"));
+}
+
+#[test]
+fn test_validate_lists_all_problems() {
+    let mut config = config();
+    config.listen_address = String::from("localhost");
+    config.id = 3;
+    config.peers[1].p2p_port = 50000;
+    config.peer_watch_interval_secs = Some(0);
+    config.access_policy_file = Some(PathBuf::from("/nonexistent/policy.json"));
+
+    let problems = config.validate();
+    let expected = [
+        "The access policy file /nonexistent/policy.json does not exist",
+        "Invalid listen_address localhost: invalid IP address syntax",
+        "Peers share the P2P address 127.0.0.1:50000",
+        "This server (id 3) is not in the list of peers",
+        "An access policy requires mutual TLS on the RPC endpoint (rpc_tls.client_ca_file)",
+        "peer_watch_interval_secs must not be 0",
+    ];
+    assert_eq!(problems, expected);
+
+    let error = config.check().unwrap_err();
+    assert!(error.starts_with("Invalid configuration: The access policy file"));
+}
+
+#[test]
+fn test_validate_rpc_port_collision() {
+    let mut config = config();
+    config.rpc_port = 50001;
+
+    assert_eq!(
+        config.validate(),
+        vec!["The rpc_port 50001 is also the P2P port of peer 2"]
+    );
+}
+
+#[test]
+fn test_overrides() {
+    let mut config = config();
+    config
+        .apply_overrides(overrides(&[
+            ("THETA_ID", "2"),
+            ("THETA_RPC_PORT", "52000"),
+            ("THETA_EVENT_FILE", "/tmp/events.log"),
+            ("THETA_PEER_WATCH_INTERVAL_SECS", "30"),
+            ("THETA_COMETBFT_RPC_URL", "http://127.0.0.1:26657"),
+        ]))
+        .unwrap();
+
+    assert_eq!(config.id, 2);
+    assert_eq!(config.rpc_port, 52000);
+    assert_eq!(config.listen_address, "0.0.0.0");
+    assert_eq!(config.event_file, Some(PathBuf::from("/tmp/events.log")));
+    assert_eq!(config.peer_watch_interval_secs, Some(30));
+    assert_eq!(
+        config.cometbft.as_ref().unwrap().rpc_url,
+        "http://127.0.0.1:26657"
+    );
+
+    // empty values unset optional settings
+    config
+        .apply_overrides(overrides(&[
+            ("THETA_EVENT_FILE", ""),
+            ("THETA_PEER_WATCH_INTERVAL_SECS", ""),
+            ("THETA_COMETBFT_RPC_URL", ""),
+        ]))
+        .unwrap();
+    assert_eq!(config.event_file, None);
+    assert_eq!(config.peer_watch_interval_secs, None);
+    assert!(config.cometbft.is_none());
+}
+
+#[test]
+fn test_invalid_override() {
+    let mut config = config();
+    let error = config
+        .apply_overrides(overrides(&[("THETA_RPC_PORT", "not-a-port")]))
+        .unwrap_err();
+    assert_eq!(error, "Invalid value for THETA_RPC_PORT: not-a-port");
+    assert_eq!(config.rpc_port, 51000);
+}
+
+#[test]
+fn test_every_override_is_applied() {
+    // every variable in ENV_OVERRIDES changes the configuration
+    for (name, setting) in ENV_OVERRIDES {
+        let value = match name {
+            "THETA_ID" => "2",
+            "THETA_RPC_PORT" => "52000",
+            "THETA_PEER_WATCH_INTERVAL_SECS" => "30",
+            "THETA_LISTEN_ADDRESS" => "127.0.0.1",
+            "THETA_COMETBFT_RPC_URL" => "http://127.0.0.1:26657",
+            _ => "/tmp/thetacrypt-file",
+        };
+        let mut config = config();
+        let before = format!("{:?}", config);
+        config.apply_overrides(overrides(&[(name, value)])).unwrap();
+        assert_ne!(before, format!("{:?}", config), "{} ({})", name, setting);
+    }
+}
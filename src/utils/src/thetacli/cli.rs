@@ -18,6 +18,14 @@ pub enum Commands {
     Verify(VerifyArgs),
     /// Manipulate keystores
     Keystore(KeystoreArgs),
+    /// Decrypt a ciphertext with a running committee
+    Decrypt(DecryptArgs),
+    /// Sign a message with a running committee
+    Sign(SignArgs),
+    /// Flip a threshold coin with a running committee
+    Coin(CoinArgs),
+    /// Generate the next version of a key and make the previous version decrypt-only
+    Rotate(RotateArgs),
 }
 
 #[derive(Args, Debug)]
@@ -32,7 +40,7 @@ pub struct KeyGenArgs {
     #[arg(
         short,
         long,
-        help = "A list of comma separated elements of the format 'scheme-group', where 'scheme' is one of the following:\n\t encryption schemes: sg02, bz03\n\t signature schemes: bls04, frost, sh00\n\t coin schemes: cks05\nand 'group' is one of\n\t 'bls12381', 'bn254', 'ed25519', 'rsa512', 'rsa1024', 'rsa2048', 'rsa4096'.\nexample: sg02-bls12381,bz03-ed25519. \nA single string 'all' should be used to create all possible keys."
+        help = "A list of comma separated elements of the format 'scheme-group', where 'scheme' is one of the following:\n\t encryption schemes: sg02, bz03\n\t signature schemes: bls04, frost, sh00\n\t coin schemes: cks05\nand 'group' is one of\n\t 'bls12381', 'bn254', 'ed25519', 'rsa512', 'rsa1024', 'rsa2048', 'rsa4096'.\nexample: sg02-bls12381,bz03-ed25519. \nAn alias can be given to a key with 'scheme-group:alias', e.g. sg02-bls12381:backups.\nA single string 'all' should be used to create all possible keys."
     )]
     pub subjects: String,
     #[arg(short, long, help = "Directory to store the generated keys in")]
@@ -43,6 +51,23 @@ pub struct KeyGenArgs {
         default_value_t = false
     )]
     pub new: bool,
+    #[arg(
+        long,
+        help = "Key ceremony mode: write one bundle per node and a signed manifest of the public keys instead of the keystores",
+        default_value_t = false
+    )]
+    pub ceremony: bool,
+    #[arg(
+        long,
+        help = "The ed25519 key that signs the manifest (created if the file does not exist)"
+    )]
+    pub signing_key: Option<String>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated paths to the bundle keys of the nodes (as created by 'keystore bundle-key'), in the order of the node ids. The bundles are encrypted to these keys"
+    )]
+    pub recipients: Vec<String>,
 }
 #[derive(Args, Debug)]
 pub struct EncArgs {
@@ -58,6 +83,12 @@ pub struct EncArgs {
     pub label: String,
     #[arg(short, long, help = "The output file (use - for stdout)")]
     pub output: String,
+    #[arg(
+        long,
+        help = "Encrypt the input in segments without loading it into memory (for large files, sg02 and bz03 only)",
+        default_value_t = false
+    )]
+    pub stream: bool,
 }
 
 #[derive(Args, Debug)]
@@ -91,6 +122,114 @@ pub struct KeystoreArgs {
         default_value_t = false
     )]
     pub new: bool,
-    #[arg(long, help = "Path to input key file")]
+    #[arg(long, help = "Path to input key file (or bundle for 'unpack')")]
     pub input: Option<String>,
+    #[arg(long, help = "Path to the manifest of a key ceremony ('verify', 'unpack')")]
+    pub manifest: Option<String>,
+    #[arg(long, help = "The ed25519 key expected to have signed the manifest")]
+    pub signer: Option<String>,
+    #[arg(long, help = "Path to the bundle key of the node, if its bundle is encrypted ('unpack')")]
+    pub bundle_key: Option<String>,
+    #[arg(long, help = "The id of the node the keystore belongs to ('verify')")]
+    pub node_id: Option<u16>,
+    #[arg(long, help = "The id or alias of the key to label ('label')")]
+    pub key_id: Option<String>,
+    #[arg(long, help = "A unique name for the key that can be used in place of its id, empty to remove it ('label')")]
+    pub alias: Option<String>,
+    #[arg(long, help = "A description of the key ('label')")]
+    pub description: Option<String>,
+    #[arg(long, value_delimiter = ',', help = "Comma separated tags of the key ('label')")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct ServerArgs {
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Comma separated addresses of the servers of the committee (example http://127.0.0.1:51000,http://127.0.0.1:51001)"
+    )]
+    pub servers: Vec<String>,
+    #[arg(
+        long,
+        help = "The path to a client config file listing the servers (alternative to --servers)"
+    )]
+    pub config: Option<String>,
+    #[arg(
+        long,
+        help = "The path to a keystore with the public keys to use, instead of fetching them from the servers"
+    )]
+    pub keystore: Option<String>,
+    #[arg(
+        long,
+        help = "Seconds to wait for the result",
+        default_value_t = 60
+    )]
+    pub timeout: u64,
+    #[arg(
+        long,
+        help = "Collect the shares of the servers and combine them locally (non-interactive schemes only)"
+    )]
+    pub combine_shares: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct DecryptArgs {
+    #[command(flatten)]
+    pub servers: ServerArgs,
+    #[arg(short, long, help = "The path to the ciphertext file, as written by 'enc' (defaults to stdin)")]
+    pub infile: Option<String>,
+    #[arg(short, long, help = "The key id (defaults to the key the ciphertext was encrypted with)")]
+    pub key_id: Option<String>,
+    #[arg(short, long, help = "The output file for the plaintext (use - for stdout)")]
+    pub output: String,
+    #[arg(
+        long,
+        help = "The input was encrypted with 'enc --stream'",
+        default_value_t = false
+    )]
+    pub stream: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct SignArgs {
+    #[command(flatten)]
+    pub servers: ServerArgs,
+    #[arg(short, long, help = "The path to the file containing the message (defaults to stdin)")]
+    pub infile: Option<String>,
+    #[arg(short, long, help = "The key id")]
+    pub key_id: String,
+    #[arg(short, long, help = "The signature label (defaults to a random label)")]
+    pub label: Option<String>,
+    #[arg(short, long, help = "The output file for the hex encoded signature (use - for stdout)")]
+    pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct CoinArgs {
+    #[command(flatten)]
+    pub servers: ServerArgs,
+    #[arg(short, long, help = "The name of the coin")]
+    pub name: String,
+    #[arg(short, long, help = "The key id")]
+    pub key_id: String,
+    #[arg(short, long, help = "The output file for the hex encoded coin value (use - for stdout)")]
+    pub output: String,
+}
+
+#[derive(Args, Debug)]
+pub struct RotateArgs {
+    #[arg(long, help = "The id or alias of the key to rotate")]
+    pub key_id: String,
+    #[arg(
+        short,
+        long,
+        help = "Directory with the keystores of the nodes (node<i>.keystore), as written by 'keygen'"
+    )]
+    pub output: String,
+    #[arg(
+        short,
+        help = "Threshold of the new version (defaults to the threshold of the previous version)"
+    )]
+    pub k: Option<u16>,
 }